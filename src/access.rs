@@ -0,0 +1,118 @@
+pub const ACC_PUBLIC: u16 = 0x0001;
+pub const ACC_PRIVATE: u16 = 0x0002;
+pub const ACC_PROTECTED: u16 = 0x0004;
+pub const ACC_STATIC: u16 = 0x0008;
+pub const ACC_FINAL: u16 = 0x0010;
+pub const ACC_SUPER: u16 = 0x0020;
+pub const ACC_SYNCHRONIZED: u16 = 0x0020;
+pub const ACC_VOLATILE: u16 = 0x0040;
+pub const ACC_BRIDGE: u16 = 0x0040;
+pub const ACC_TRANSIENT: u16 = 0x0080;
+pub const ACC_VARARGS: u16 = 0x0080;
+pub const ACC_NATIVE: u16 = 0x0100;
+pub const ACC_INTERFACE: u16 = 0x0200;
+pub const ACC_ABSTRACT: u16 = 0x0400;
+pub const ACC_STRICT: u16 = 0x0800;
+pub const ACC_SYNTHETIC: u16 = 0x1000;
+pub const ACC_ANNOTATION: u16 = 0x2000;
+pub const ACC_ENUM: u16 = 0x4000;
+pub const ACC_MODULE: u16 = 0x8000;
+
+/// What an access flags value belongs to, as some bits mean different things for each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlagTarget {
+    Class,
+    Field,
+    Method,
+}
+
+const CLASS_FLAGS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "ACC_PUBLIC"),
+    (ACC_FINAL, "ACC_FINAL"),
+    (ACC_SUPER, "ACC_SUPER"),
+    (ACC_INTERFACE, "ACC_INTERFACE"),
+    (ACC_ABSTRACT, "ACC_ABSTRACT"),
+    (ACC_SYNTHETIC, "ACC_SYNTHETIC"),
+    (ACC_ANNOTATION, "ACC_ANNOTATION"),
+    (ACC_ENUM, "ACC_ENUM"),
+    (ACC_MODULE, "ACC_MODULE"),
+];
+
+const FIELD_FLAGS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "ACC_PUBLIC"),
+    (ACC_PRIVATE, "ACC_PRIVATE"),
+    (ACC_PROTECTED, "ACC_PROTECTED"),
+    (ACC_STATIC, "ACC_STATIC"),
+    (ACC_FINAL, "ACC_FINAL"),
+    (ACC_VOLATILE, "ACC_VOLATILE"),
+    (ACC_TRANSIENT, "ACC_TRANSIENT"),
+    (ACC_SYNTHETIC, "ACC_SYNTHETIC"),
+    (ACC_ENUM, "ACC_ENUM"),
+];
+
+const METHOD_FLAGS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "ACC_PUBLIC"),
+    (ACC_PRIVATE, "ACC_PRIVATE"),
+    (ACC_PROTECTED, "ACC_PROTECTED"),
+    (ACC_STATIC, "ACC_STATIC"),
+    (ACC_FINAL, "ACC_FINAL"),
+    (ACC_SYNCHRONIZED, "ACC_SYNCHRONIZED"),
+    (ACC_BRIDGE, "ACC_BRIDGE"),
+    (ACC_VARARGS, "ACC_VARARGS"),
+    (ACC_NATIVE, "ACC_NATIVE"),
+    (ACC_ABSTRACT, "ACC_ABSTRACT"),
+    (ACC_STRICT, "ACC_STRICT"),
+    (ACC_SYNTHETIC, "ACC_SYNTHETIC"),
+];
+
+/// The names of the flags set in `flags`, e.g. `["ACC_PUBLIC", "ACC_STATIC"]`.
+pub fn flag_names(flags: u16, target: FlagTarget) -> Vec<&'static str> {
+    let table = match target {
+        FlagTarget::Class => CLASS_FLAGS,
+        FlagTarget::Field => FIELD_FLAGS,
+        FlagTarget::Method => METHOD_FLAGS,
+    };
+
+    table
+        .iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// The Java source modifiers for `flags`, e.g. `"public static"`.
+pub fn modifiers(flags: u16, target: FlagTarget) -> String {
+    let mut modifiers = vec![];
+    let mut add = |flag: u16, name: &'static str| {
+        if flags & flag != 0 {
+            modifiers.push(name);
+        }
+    };
+
+    add(ACC_PUBLIC, "public");
+    add(ACC_PRIVATE, "private");
+    add(ACC_PROTECTED, "protected");
+    add(ACC_STATIC, "static");
+    match target {
+        FlagTarget::Class => {
+            if flags & ACC_INTERFACE == 0 {
+                add(ACC_ABSTRACT, "abstract");
+            }
+            add(ACC_FINAL, "final");
+        }
+        FlagTarget::Field => {
+            add(ACC_FINAL, "final");
+            add(ACC_VOLATILE, "volatile");
+            add(ACC_TRANSIENT, "transient");
+        }
+        FlagTarget::Method => {
+            add(ACC_ABSTRACT, "abstract");
+            add(ACC_FINAL, "final");
+            add(ACC_SYNCHRONIZED, "synchronized");
+            add(ACC_NATIVE, "native");
+            add(ACC_STRICT, "strictfp");
+        }
+    }
+
+    modifiers.join(" ")
+}
@@ -0,0 +1,198 @@
+use crate::{
+    constant_pool::ConstantPoolIndex,
+    descriptor::{FieldType, MethodDescriptor},
+    error::JomResult,
+    method::code::{
+        instruction::{Instruction, Wide},
+        Exception,
+    },
+};
+
+use super::{
+    frame::Value,
+    interpreter::{InstructionContext, Interpreter},
+};
+
+/// The verification types of the JVM without class information.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BasicValue {
+    Uninitialized,
+    Int,
+    Float,
+    Long,
+    Double,
+    Reference,
+    ReturnAddress,
+}
+
+impl BasicValue {
+    pub fn from_type(ty: &FieldType) -> Self {
+        match ty {
+            FieldType::Byte
+            | FieldType::Char
+            | FieldType::Int
+            | FieldType::Short
+            | FieldType::Boolean => Self::Int,
+            FieldType::Float => Self::Float,
+            FieldType::Long => Self::Long,
+            FieldType::Double => Self::Double,
+            FieldType::Object(_) | FieldType::Array(_) => Self::Reference,
+        }
+    }
+}
+
+impl Value for BasicValue {
+    fn size(&self) -> usize {
+        match self {
+            Self::Long | Self::Double => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Tracks the [`BasicValue`] of every local and stack entry.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BasicInterpreter;
+
+impl Interpreter for BasicInterpreter {
+    type Value = BasicValue;
+
+    fn new_value(&self, ty: Option<&FieldType>) -> BasicValue {
+        ty.map_or(BasicValue::Uninitialized, BasicValue::from_type)
+    }
+
+    fn new_exception_value(&self, _: &Exception) -> BasicValue {
+        BasicValue::Reference
+    }
+
+    fn new_operation(&self, insn: &InstructionContext) -> JomResult<BasicValue> {
+        use Instruction::*;
+
+        Ok(match insn.instruction {
+            AConstNull | New(_) => BasicValue::Reference,
+            IConstM1 | IConst0 | IConst1 | IConst2 | IConst3 | IConst4 | IConst5 | BiPush(_)
+            | Sipush(_) => BasicValue::Int,
+            LConst0 | LConst1 => BasicValue::Long,
+            FConst0 | FConst1 | FConst2 => BasicValue::Float,
+            DConst0 | DConst1 => BasicValue::Double,
+            Ldc(index) => self.constant(insn, *index as u16)?,
+            LdcW(index) | Ldc2W(index) => self.constant(insn, *index)?,
+            GetStatic(index) => BasicValue::from_type(&insn.field_type(*index)?),
+            _ => return Err(insn.error("not a new operation")),
+        })
+    }
+
+    fn copy_operation(&self, _: &InstructionContext, value: &BasicValue) -> JomResult<BasicValue> {
+        Ok(*value)
+    }
+
+    fn unary_operation(
+        &self,
+        insn: &InstructionContext,
+        _: &BasicValue,
+    ) -> JomResult<Option<BasicValue>> {
+        use Instruction::*;
+
+        Ok(match insn.instruction {
+            INeg
+            | IInc(_, _)
+            | Wide(self::Wide::IInc(_, _))
+            | L2I
+            | F2I
+            | D2I
+            | I2B
+            | I2C
+            | I2S
+            | ArrayLength
+            | InstanceOf(_) => Some(BasicValue::Int),
+            FNeg | I2F | L2F | D2F => Some(BasicValue::Float),
+            LNeg | I2L | F2L | D2L => Some(BasicValue::Long),
+            DNeg | I2D | L2D | F2D => Some(BasicValue::Double),
+            GetField(index) => Some(BasicValue::from_type(&insn.field_type(*index)?)),
+            NewArray(_) | ANewArray(_) | CheckCast(_) => Some(BasicValue::Reference),
+            _ => None,
+        })
+    }
+
+    fn binary_operation(
+        &self,
+        insn: &InstructionContext,
+        _: &BasicValue,
+        _: &BasicValue,
+    ) -> JomResult<Option<BasicValue>> {
+        use Instruction::*;
+
+        Ok(match insn.instruction {
+            IALoad | BALoad | CALoad | SALoad | IAdd | ISub | IMul | IDiv | IRem | IShl | IShr
+            | IUShr | IAnd | IOr | IXor | LCmp | FCmpL | FCmpG | DCmpL | DCmpG => {
+                Some(BasicValue::Int)
+            }
+            FALoad | FAdd | FSub | FMul | FDiv | FRem => Some(BasicValue::Float),
+            LALoad | LAdd | LSub | LMul | LDiv | LRem | LShl | LShr | LUShr | LAnd | LOr | LXor => {
+                Some(BasicValue::Long)
+            }
+            DALoad | DAdd | DSub | DMul | DDiv | DRem => Some(BasicValue::Double),
+            AALoad => Some(BasicValue::Reference),
+            _ => None,
+        })
+    }
+
+    fn ternary_operation(
+        &self,
+        _: &InstructionContext,
+        _: &BasicValue,
+        _: &BasicValue,
+        _: &BasicValue,
+    ) -> JomResult<Option<BasicValue>> {
+        Ok(None)
+    }
+
+    fn nary_operation(
+        &self,
+        insn: &InstructionContext,
+        _: &[BasicValue],
+    ) -> JomResult<Option<BasicValue>> {
+        use Instruction::*;
+
+        Ok(match insn.instruction {
+            MultiANewArray(_, _) => Some(BasicValue::Reference),
+            InvokeVirtual(index)
+            | InvokeSpecial(index)
+            | InvokeStatic(index)
+            | InvokeInterface(index, _)
+            | InvokeDynamic(index) => {
+                let MethodDescriptor { return_type, .. } = insn.method_descriptor(*index)?;
+                return_type.as_ref().map(BasicValue::from_type)
+            }
+            _ => None,
+        })
+    }
+
+    fn merge(&self, value1: &BasicValue, value2: &BasicValue) -> BasicValue {
+        if value1 == value2 {
+            *value1
+        } else {
+            BasicValue::Uninitialized
+        }
+    }
+}
+
+impl BasicInterpreter {
+    fn constant(&self, insn: &InstructionContext, index: u16) -> JomResult<BasicValue> {
+        Ok(match insn.constant_pool.get(index)? {
+            ConstantPoolIndex::Integer(_) => BasicValue::Int,
+            ConstantPoolIndex::Float(_) => BasicValue::Float,
+            ConstantPoolIndex::Long(_) => BasicValue::Long,
+            ConstantPoolIndex::Double(_) => BasicValue::Double,
+            ConstantPoolIndex::String(_)
+            | ConstantPoolIndex::StringUnits(_)
+            | ConstantPoolIndex::Class(_)
+            | ConstantPoolIndex::MethodType(_)
+            | ConstantPoolIndex::MethodHandle { .. } => BasicValue::Reference,
+            ConstantPoolIndex::Dynamic { descriptor, .. } => {
+                BasicValue::from_type(&FieldType::parse(&descriptor)?)
+            }
+            x => return Err(insn.error(format!("cannot load a {} constant", x.name()))),
+        })
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    error::{JomError, JomResult},
+    method::code::Code,
+};
+
+/// An instruction-level control flow graph of a method body.
+///
+/// Nodes are indices into [`Code::code`].
+pub struct ControlFlowGraph {
+    pcs: Vec<u32>,
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+    handlers: Vec<Vec<usize>>,
+    handler_starts: Vec<usize>,
+}
+
+impl ControlFlowGraph {
+    pub fn new(code: &Code) -> JomResult<Self> {
+        let mut pcs = Vec::with_capacity(code.code.len());
+        let mut pc = 0;
+        for instruction in &code.code {
+            pcs.push(pc);
+            pc += instruction.size(pc);
+        }
+
+        let index_of = |index: usize, target: i64| -> JomResult<usize> {
+            u32::try_from(target)
+                .ok()
+                .and_then(|target| pcs.binary_search(&target).ok())
+                .ok_or_else(|| JomError::analysis(index, format!("invalid jump target {target}")))
+        };
+
+        let mut successors = vec![vec![]; code.code.len()];
+        for (i, instruction) in code.code.iter().enumerate() {
+            let pc = pcs[i] as i64;
+
+            for offset in instruction.branch_offsets() {
+                let target = index_of(i, pc + offset as i64)?;
+                if !successors[i].contains(&target) {
+                    successors[i].push(target);
+                }
+            }
+
+            if instruction.falls_through() {
+                if i + 1 >= code.code.len() {
+                    return Err(JomError::analysis(
+                        i,
+                        "execution falls off the end of the code",
+                    ));
+                }
+                if !successors[i].contains(&(i + 1)) {
+                    successors[i].push(i + 1);
+                }
+            }
+        }
+
+        let mut handlers = vec![vec![]; code.code.len()];
+        let mut handler_starts = Vec::with_capacity(code.exception_table.len());
+        for (n, exception) in code.exception_table.iter().enumerate() {
+            let handler = index_of(n, exception.handler_pc as i64)?;
+            handler_starts.push(handler);
+
+            for (i, &pc) in pcs.iter().enumerate() {
+                if pc >= exception.start_pc as u32 && pc < exception.end_pc as u32 {
+                    handlers[i].push(n);
+                }
+            }
+        }
+
+        let mut predecessors = vec![vec![]; code.code.len()];
+        for (i, successors) in successors.iter().enumerate() {
+            for &s in successors {
+                predecessors[s].push(i);
+            }
+        }
+        for (i, handlers) in handlers.iter().enumerate() {
+            for &n in handlers {
+                if !predecessors[handler_starts[n]].contains(&i) {
+                    predecessors[handler_starts[n]].push(i);
+                }
+            }
+        }
+
+        Ok(Self {
+            pcs,
+            successors,
+            predecessors,
+            handlers,
+            handler_starts,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.pcs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pcs.is_empty()
+    }
+
+    pub fn pcs(&self) -> &[u32] {
+        &self.pcs
+    }
+
+    pub fn pc(&self, index: usize) -> u32 {
+        self.pcs[index]
+    }
+
+    pub fn index_of(&self, pc: u32) -> Option<usize> {
+        self.pcs.binary_search(&pc).ok()
+    }
+
+    /// Normal control flow successors, excluding exception handlers.
+    pub fn successors(&self, index: usize) -> &[usize] {
+        &self.successors[index]
+    }
+
+    /// Normal and exceptional control flow predecessors.
+    pub fn predecessors(&self, index: usize) -> &[usize] {
+        &self.predecessors[index]
+    }
+
+    /// Indices into [`Code::exception_table`] of the handlers covering the instruction.
+    pub fn handlers(&self, index: usize) -> &[usize] {
+        &self.handlers[index]
+    }
+
+    /// The instruction index of the handler of the exception table entry `n`.
+    pub fn handler_start(&self, n: usize) -> usize {
+        self.handler_starts[n]
+    }
+
+    /// Normal successors followed by the entry points of the covering exception handlers.
+    pub fn all_successors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.successors[index]
+            .iter()
+            .copied()
+            .chain(self.handlers[index].iter().map(|&n| self.handler_starts[n]))
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{
+    constant_pool::ConstantPoolIndex,
+    descriptor::FieldType,
+    error::JomResult,
+    method::code::{
+        instruction::{Instruction, Wide},
+        Exception,
+    },
+};
+
+use super::{
+    basic::{BasicInterpreter, BasicValue},
+    frame::Value,
+    interpreter::{InstructionContext, Interpreter},
+};
+
+#[derive(Clone, Debug)]
+pub enum Constant {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Null,
+}
+
+/// Floats and doubles are compared by their bits, so `0.0` and `-0.0` differ and NaN equals
+/// itself.
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::Long(a), Self::Long(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a.to_bits() == b.to_bits(),
+            (Self::Double(a), Self::Double(b)) => a.to_bits() == b.to_bits(),
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Null, Self::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A [`BasicValue`] together with its value, if it is the same on every path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstValue {
+    pub basic: BasicValue,
+    pub constant: Option<Constant>,
+}
+
+impl ConstValue {
+    fn unknown(basic: BasicValue) -> Self {
+        Self {
+            basic,
+            constant: None,
+        }
+    }
+}
+
+impl Value for ConstValue {
+    fn size(&self) -> usize {
+        self.basic.size()
+    }
+}
+
+/// Propagates primitive and string constants through arithmetic, conversions and locals.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConstantInterpreter;
+
+impl Interpreter for ConstantInterpreter {
+    type Value = ConstValue;
+
+    fn new_value(&self, ty: Option<&FieldType>) -> ConstValue {
+        ConstValue::unknown(BasicInterpreter.new_value(ty))
+    }
+
+    fn new_exception_value(&self, exception: &Exception) -> ConstValue {
+        ConstValue::unknown(BasicInterpreter.new_exception_value(exception))
+    }
+
+    fn new_operation(&self, insn: &InstructionContext) -> JomResult<ConstValue> {
+        use Instruction::*;
+
+        let basic = BasicInterpreter.new_operation(insn)?;
+        let constant = match insn.instruction {
+            AConstNull => Some(Constant::Null),
+            IConstM1 => Some(Constant::Int(-1)),
+            IConst0 => Some(Constant::Int(0)),
+            IConst1 => Some(Constant::Int(1)),
+            IConst2 => Some(Constant::Int(2)),
+            IConst3 => Some(Constant::Int(3)),
+            IConst4 => Some(Constant::Int(4)),
+            IConst5 => Some(Constant::Int(5)),
+            LConst0 => Some(Constant::Long(0)),
+            LConst1 => Some(Constant::Long(1)),
+            FConst0 => Some(Constant::Float(0.0)),
+            FConst1 => Some(Constant::Float(1.0)),
+            FConst2 => Some(Constant::Float(2.0)),
+            DConst0 => Some(Constant::Double(0.0)),
+            DConst1 => Some(Constant::Double(1.0)),
+            BiPush(x) => Some(Constant::Int(*x as i8 as i32)),
+            Sipush(x) => Some(Constant::Int(*x as i16 as i32)),
+            Ldc(index) => Self::constant(insn, *index as u16)?,
+            LdcW(index) | Ldc2W(index) => Self::constant(insn, *index)?,
+            _ => None,
+        };
+
+        Ok(ConstValue { basic, constant })
+    }
+
+    fn copy_operation(&self, _: &InstructionContext, value: &ConstValue) -> JomResult<ConstValue> {
+        Ok(value.clone())
+    }
+
+    fn unary_operation(
+        &self,
+        insn: &InstructionContext,
+        value: &ConstValue,
+    ) -> JomResult<Option<ConstValue>> {
+        use Constant::*;
+
+        let Some(basic) = BasicInterpreter.unary_operation(insn, &value.basic)? else {
+            return Ok(None);
+        };
+
+        let constant = match (insn.instruction, &value.constant) {
+            (Instruction::INeg, Some(Int(x))) => Some(Int(x.wrapping_neg())),
+            (Instruction::LNeg, Some(Long(x))) => Some(Long(x.wrapping_neg())),
+            (Instruction::FNeg, Some(Float(x))) => Some(Float(-x)),
+            (Instruction::DNeg, Some(Double(x))) => Some(Double(-x)),
+            (Instruction::IInc(_, c), Some(Int(x))) => Some(Int(x.wrapping_add(*c as i8 as i32))),
+            (Instruction::Wide(Wide::IInc(_, c)), Some(Int(x))) => {
+                Some(Int(x.wrapping_add(*c as i32)))
+            }
+            (Instruction::I2L, Some(Int(x))) => Some(Long(*x as i64)),
+            (Instruction::I2F, Some(Int(x))) => Some(Float(*x as f32)),
+            (Instruction::I2D, Some(Int(x))) => Some(Double(*x as f64)),
+            (Instruction::L2I, Some(Long(x))) => Some(Int(*x as i32)),
+            (Instruction::L2F, Some(Long(x))) => Some(Float(*x as f32)),
+            (Instruction::L2D, Some(Long(x))) => Some(Double(*x as f64)),
+            (Instruction::F2I, Some(Float(x))) => Some(Int(*x as i32)),
+            (Instruction::F2L, Some(Float(x))) => Some(Long(*x as i64)),
+            (Instruction::F2D, Some(Float(x))) => Some(Double(*x as f64)),
+            (Instruction::D2I, Some(Double(x))) => Some(Int(*x as i32)),
+            (Instruction::D2L, Some(Double(x))) => Some(Long(*x as i64)),
+            (Instruction::D2F, Some(Double(x))) => Some(Float(*x as f32)),
+            (Instruction::I2B, Some(Int(x))) => Some(Int(*x as i8 as i32)),
+            (Instruction::I2C, Some(Int(x))) => Some(Int(*x as u16 as i32)),
+            (Instruction::I2S, Some(Int(x))) => Some(Int(*x as i16 as i32)),
+            (Instruction::CheckCast(_), constant) => constant.clone(),
+            _ => None,
+        };
+
+        Ok(Some(ConstValue { basic, constant }))
+    }
+
+    fn binary_operation(
+        &self,
+        insn: &InstructionContext,
+        value1: &ConstValue,
+        value2: &ConstValue,
+    ) -> JomResult<Option<ConstValue>> {
+        use Constant::*;
+        use Instruction::*;
+
+        let Some(basic) = BasicInterpreter.binary_operation(insn, &value1.basic, &value2.basic)?
+        else {
+            return Ok(None);
+        };
+
+        let constant = match (insn.instruction, &value1.constant, &value2.constant) {
+            (IAdd, Some(Int(a)), Some(Int(b))) => Some(Int(a.wrapping_add(*b))),
+            (ISub, Some(Int(a)), Some(Int(b))) => Some(Int(a.wrapping_sub(*b))),
+            (IMul, Some(Int(a)), Some(Int(b))) => Some(Int(a.wrapping_mul(*b))),
+            (IDiv, Some(Int(a)), Some(Int(b))) if *b != 0 => Some(Int(a.wrapping_div(*b))),
+            (IRem, Some(Int(a)), Some(Int(b))) if *b != 0 => Some(Int(a.wrapping_rem(*b))),
+            (IShl, Some(Int(a)), Some(Int(b))) => Some(Int(a.wrapping_shl(*b as u32))),
+            (IShr, Some(Int(a)), Some(Int(b))) => Some(Int(a.wrapping_shr(*b as u32))),
+            (IUShr, Some(Int(a)), Some(Int(b))) => {
+                Some(Int((*a as u32).wrapping_shr(*b as u32) as i32))
+            }
+            (IAnd, Some(Int(a)), Some(Int(b))) => Some(Int(a & b)),
+            (IOr, Some(Int(a)), Some(Int(b))) => Some(Int(a | b)),
+            (IXor, Some(Int(a)), Some(Int(b))) => Some(Int(a ^ b)),
+            (LAdd, Some(Long(a)), Some(Long(b))) => Some(Long(a.wrapping_add(*b))),
+            (LSub, Some(Long(a)), Some(Long(b))) => Some(Long(a.wrapping_sub(*b))),
+            (LMul, Some(Long(a)), Some(Long(b))) => Some(Long(a.wrapping_mul(*b))),
+            (LDiv, Some(Long(a)), Some(Long(b))) if *b != 0 => Some(Long(a.wrapping_div(*b))),
+            (LRem, Some(Long(a)), Some(Long(b))) if *b != 0 => Some(Long(a.wrapping_rem(*b))),
+            (LShl, Some(Long(a)), Some(Int(b))) => Some(Long(a.wrapping_shl(*b as u32))),
+            (LShr, Some(Long(a)), Some(Int(b))) => Some(Long(a.wrapping_shr(*b as u32))),
+            (LUShr, Some(Long(a)), Some(Int(b))) => {
+                Some(Long((*a as u64).wrapping_shr(*b as u32) as i64))
+            }
+            (LAnd, Some(Long(a)), Some(Long(b))) => Some(Long(a & b)),
+            (LOr, Some(Long(a)), Some(Long(b))) => Some(Long(a | b)),
+            (LXor, Some(Long(a)), Some(Long(b))) => Some(Long(a ^ b)),
+            (FAdd, Some(Float(a)), Some(Float(b))) => Some(Float(a + b)),
+            (FSub, Some(Float(a)), Some(Float(b))) => Some(Float(a - b)),
+            (FMul, Some(Float(a)), Some(Float(b))) => Some(Float(a * b)),
+            (FDiv, Some(Float(a)), Some(Float(b))) => Some(Float(a / b)),
+            (FRem, Some(Float(a)), Some(Float(b))) => Some(Float(a % b)),
+            (DAdd, Some(Double(a)), Some(Double(b))) => Some(Double(a + b)),
+            (DSub, Some(Double(a)), Some(Double(b))) => Some(Double(a - b)),
+            (DMul, Some(Double(a)), Some(Double(b))) => Some(Double(a * b)),
+            (DDiv, Some(Double(a)), Some(Double(b))) => Some(Double(a / b)),
+            (DRem, Some(Double(a)), Some(Double(b))) => Some(Double(a % b)),
+            (LCmp, Some(Long(a)), Some(Long(b))) => Some(Int(a.cmp(b) as i32)),
+            (FCmpL | FCmpG, Some(Float(a)), Some(Float(b))) => Some(Int(Self::compare(
+                *a as f64,
+                *b as f64,
+                insn.instruction == &FCmpG,
+            ))),
+            (DCmpL | DCmpG, Some(Double(a)), Some(Double(b))) => {
+                Some(Int(Self::compare(*a, *b, insn.instruction == &DCmpG)))
+            }
+            _ => None,
+        };
+
+        Ok(Some(ConstValue { basic, constant }))
+    }
+
+    fn ternary_operation(
+        &self,
+        _: &InstructionContext,
+        _: &ConstValue,
+        _: &ConstValue,
+        _: &ConstValue,
+    ) -> JomResult<Option<ConstValue>> {
+        Ok(None)
+    }
+
+    fn nary_operation(
+        &self,
+        insn: &InstructionContext,
+        _: &[ConstValue],
+    ) -> JomResult<Option<ConstValue>> {
+        Ok(BasicInterpreter
+            .nary_operation(insn, &[])?
+            .map(ConstValue::unknown))
+    }
+
+    fn merge(&self, value1: &ConstValue, value2: &ConstValue) -> ConstValue {
+        let basic = BasicInterpreter.merge(&value1.basic, &value2.basic);
+        let constant = if value1.constant == value2.constant {
+            value1.constant.clone()
+        } else {
+            None
+        };
+
+        ConstValue { basic, constant }
+    }
+}
+
+impl ConstantInterpreter {
+    fn constant(insn: &InstructionContext, index: u16) -> JomResult<Option<Constant>> {
+        Ok(match insn.constant_pool.get(index)? {
+            ConstantPoolIndex::Integer(x) => Some(Constant::Int(x)),
+            ConstantPoolIndex::Float(x) => Some(Constant::Float(x)),
+            ConstantPoolIndex::Long(x) => Some(Constant::Long(x)),
+            ConstantPoolIndex::Double(x) => Some(Constant::Double(x)),
+            ConstantPoolIndex::String(x) => Some(Constant::String(x)),
+            _ => None,
+        })
+    }
+
+    /// `fcmpg`/`dcmpg` push 1 and `fcmpl`/`dcmpl` push -1 if either value is NaN.
+    fn compare(a: f64, b: f64, nan_greater: bool) -> i32 {
+        match a.partial_cmp(&b) {
+            Some(ordering) => ordering as i32,
+            None if nan_greater => 1,
+            None => -1,
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::method::code::{
+    instruction::{Instruction, Wide},
+    Code,
+};
+
+use super::cfg::ControlFlowGraph;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+/// A monotone dataflow problem solved by [`solve`].
+pub trait DataflowProblem {
+    type Fact: Clone + PartialEq;
+
+    fn direction(&self) -> Direction;
+
+    /// The fact at the method entry for forward problems, or after every exit
+    /// (return or throw) for backward problems.
+    fn boundary(&self) -> Self::Fact;
+
+    /// The initial fact everywhere else, the identity of [`join`](Self::join).
+    fn bottom(&self) -> Self::Fact;
+
+    fn join(&self, fact: &mut Self::Fact, other: &Self::Fact);
+
+    /// Computes the fact on the other side of an instruction in the direction of the analysis.
+    fn transfer(&self, index: usize, instruction: &Instruction, fact: &Self::Fact) -> Self::Fact;
+}
+
+/// The facts before and after every instruction, in program order regardless of direction.
+pub struct DataflowResult<F> {
+    pub before: Vec<F>,
+    pub after: Vec<F>,
+}
+
+/// Iterates a [`DataflowProblem`] over the control flow graph until a fixpoint is reached.
+///
+/// Exception handlers receive the fact from before a covered instruction, as the
+/// instruction might throw before taking effect.
+pub fn solve<P: DataflowProblem>(
+    code: &Code,
+    cfg: &ControlFlowGraph,
+    problem: &P,
+) -> DataflowResult<P::Fact> {
+    let len = code.code.len();
+    let mut before = vec![problem.bottom(); len];
+    let mut after = vec![problem.bottom(); len];
+
+    let mut queued = vec![true; len];
+    let mut queue: VecDeque<usize> = match problem.direction() {
+        Direction::Forward => (0..len).collect(),
+        Direction::Backward => (0..len).rev().collect(),
+    };
+
+    match problem.direction() {
+        Direction::Forward => {
+            if len > 0 {
+                before[0] = problem.boundary();
+            }
+
+            while let Some(i) = queue.pop_front() {
+                queued[i] = false;
+                after[i] = problem.transfer(i, &code.code[i], &before[i]);
+
+                let normal = cfg.successors(i).iter().map(|&s| (s, after[i].clone()));
+                let exceptional = cfg
+                    .handlers(i)
+                    .iter()
+                    .map(|&n| (cfg.handler_start(n), before[i].clone()));
+
+                for (successor, fact) in normal.chain(exceptional).collect::<Vec<_>>() {
+                    let old = before[successor].clone();
+                    problem.join(&mut before[successor], &fact);
+
+                    if before[successor] != old && !queued[successor] {
+                        queued[successor] = true;
+                        queue.push_back(successor);
+                    }
+                }
+            }
+        }
+        Direction::Backward => {
+            while let Some(i) = queue.pop_front() {
+                queued[i] = false;
+
+                let mut fact = problem.bottom();
+                let mut exits = true;
+                for successor in cfg.all_successors(i) {
+                    problem.join(&mut fact, &before[successor]);
+                    exits = false;
+                }
+                if exits {
+                    problem.join(&mut fact, &problem.boundary());
+                }
+                after[i] = fact;
+
+                let fact = problem.transfer(i, &code.code[i], &after[i]);
+                if fact != before[i] {
+                    before[i] = fact;
+
+                    for &predecessor in cfg.predecessors(i) {
+                        if !queued[predecessor] {
+                            queued[predecessor] = true;
+                            queue.push_back(predecessor);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    DataflowResult { before, after }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LocalAccess {
+    Load(u16),
+    Store(u16),
+    Increment(u16),
+}
+
+fn local_access(instruction: &Instruction) -> Option<LocalAccess> {
+    use Instruction::*;
+
+    Some(match instruction {
+        ILoad(n) | LLoad(n) | FLoad(n) | DLoad(n) | ALoad(n) | Ret(n) => {
+            LocalAccess::Load(*n as u16)
+        }
+        ILoad0 | LLoad0 | FLoad0 | DLoad0 | ALoad0 => LocalAccess::Load(0),
+        ILoad1 | LLoad1 | FLoad1 | DLoad1 | ALoad1 => LocalAccess::Load(1),
+        ILoad2 | LLoad2 | FLoad2 | DLoad2 | ALoad2 => LocalAccess::Load(2),
+        ILoad3 | LLoad3 | FLoad3 | DLoad3 | ALoad3 => LocalAccess::Load(3),
+        IStore(n) | LStore(n) | FStore(n) | DStore(n) | AStore(n) => LocalAccess::Store(*n as u16),
+        IStore0 | LStore0 | FStore0 | DStore0 | AStore0 => LocalAccess::Store(0),
+        IStore1 | LStore1 | FStore1 | DStore1 | AStore1 => LocalAccess::Store(1),
+        IStore2 | LStore2 | FStore2 | DStore2 | AStore2 => LocalAccess::Store(2),
+        IStore3 | LStore3 | FStore3 | DStore3 | AStore3 => LocalAccess::Store(3),
+        IInc(n, _) => LocalAccess::Increment(*n as u16),
+        Wide(self::Wide::ILoad(n))
+        | Wide(self::Wide::LLoad(n))
+        | Wide(self::Wide::FLoad(n))
+        | Wide(self::Wide::DLoad(n))
+        | Wide(self::Wide::ALoad(n))
+        | Wide(self::Wide::Ret(n)) => LocalAccess::Load(*n),
+        Wide(self::Wide::IStore(n))
+        | Wide(self::Wide::LStore(n))
+        | Wide(self::Wide::FStore(n))
+        | Wide(self::Wide::DStore(n))
+        | Wide(self::Wide::AStore(n)) => LocalAccess::Store(*n),
+        Wide(self::Wide::IInc(n, _)) => LocalAccess::Increment(*n),
+        _ => return None,
+    })
+}
+
+/// Backward analysis computing the local variable indices whose current value may still be read.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LiveVariables;
+
+impl DataflowProblem for LiveVariables {
+    type Fact = BTreeSet<u16>;
+
+    fn direction(&self) -> Direction {
+        Direction::Backward
+    }
+
+    fn boundary(&self) -> Self::Fact {
+        BTreeSet::new()
+    }
+
+    fn bottom(&self) -> Self::Fact {
+        BTreeSet::new()
+    }
+
+    fn join(&self, fact: &mut Self::Fact, other: &Self::Fact) {
+        fact.extend(other);
+    }
+
+    fn transfer(&self, _: usize, instruction: &Instruction, fact: &Self::Fact) -> Self::Fact {
+        let mut fact = fact.clone();
+
+        match local_access(instruction) {
+            Some(LocalAccess::Load(n) | LocalAccess::Increment(n)) => {
+                fact.insert(n);
+            }
+            Some(LocalAccess::Store(n)) => {
+                fact.remove(&n);
+            }
+            None => {}
+        }
+
+        fact
+    }
+}
+
+impl LiveVariables {
+    /// Returns the indices of the store instructions whose value is never read.
+    pub fn dead_stores(code: &Code, cfg: &ControlFlowGraph) -> Vec<usize> {
+        let result = solve(code, cfg, &LiveVariables);
+
+        code.code
+            .iter()
+            .enumerate()
+            .filter(|(i, instruction)| match local_access(instruction) {
+                Some(LocalAccess::Store(n) | LocalAccess::Increment(n)) => {
+                    !result.after[*i].contains(&n)
+                }
+                _ => false,
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
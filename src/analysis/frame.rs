@@ -0,0 +1,477 @@
+use crate::{
+    constant_pool::{ConstantPool, ConstantPoolIndex},
+    error::{JomError, JomResult},
+    method::code::instruction::{Instruction, Wide},
+};
+
+use super::interpreter::{InstructionContext, Interpreter};
+
+/// A value tracked in the locals and on the operand stack of a [`Frame`].
+pub trait Value: Clone + PartialEq {
+    /// The number of slots taken by the value, 2 for `long` and `double`, 1 otherwise.
+    fn size(&self) -> usize;
+}
+
+/// The state of the local variables and the operand stack before an instruction.
+///
+/// A `long` or `double` is a single operand stack entry that takes up two slots of the
+/// maximum stack size, and two local variable slots.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame<V> {
+    locals: Vec<V>,
+    stack: Vec<V>,
+    max_stack: usize,
+}
+
+impl<V: Value> Frame<V> {
+    pub fn new(locals: Vec<V>, max_stack: usize) -> Self {
+        Self {
+            locals,
+            stack: Vec::with_capacity(max_stack),
+            max_stack,
+        }
+    }
+
+    pub fn locals(&self) -> &[V] {
+        &self.locals
+    }
+
+    pub fn stack(&self) -> &[V] {
+        &self.stack
+    }
+
+    pub fn local(&self, index: usize) -> Option<&V> {
+        self.locals.get(index)
+    }
+
+    /// The `n`th value from the top of the stack, `0` being the top.
+    pub fn peek(&self, n: usize) -> Option<&V> {
+        self.stack.iter().rev().nth(n)
+    }
+
+    pub fn set_local(&mut self, index: usize, value: V) -> JomResult<()> {
+        let len = self.locals.len();
+        let local = self
+            .locals
+            .get_mut(index)
+            .ok_or_else(|| JomError::analysis(0, format!("local {index} out of bounds ({len})")))?;
+        *local = value;
+
+        Ok(())
+    }
+
+    pub fn push(&mut self, value: V) -> JomResult<()> {
+        let slots = self.stack.iter().map(Value::size).sum::<usize>();
+        if slots + value.size() > self.max_stack {
+            return Err(JomError::analysis(0, "operand stack overflow"));
+        }
+
+        self.stack.push(value);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> JomResult<V> {
+        self.stack
+            .pop()
+            .ok_or_else(|| JomError::analysis(0, "operand stack underflow"))
+    }
+
+    pub fn clear_stack(&mut self) {
+        self.stack.clear();
+    }
+
+    /// Merges `other` into this frame and returns whether this frame changed.
+    pub fn merge<I>(&mut self, other: &Self, interpreter: &I) -> JomResult<bool>
+    where
+        I: Interpreter<Value = V>,
+    {
+        if self.stack.len() != other.stack.len() {
+            return Err(JomError::analysis(
+                0,
+                format!(
+                    "incompatible stack heights {} and {}",
+                    self.stack.len(),
+                    other.stack.len()
+                ),
+            ));
+        }
+
+        let mut changed = false;
+        for (a, b) in self
+            .locals
+            .iter_mut()
+            .chain(self.stack.iter_mut())
+            .zip(other.locals.iter().chain(other.stack.iter()))
+        {
+            let merged = interpreter.merge(a, b);
+            if merged != *a {
+                *a = merged;
+                changed = true;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Simulates the execution of an instruction on this frame.
+    pub fn execute<I>(
+        &mut self,
+        index: usize,
+        instruction: &Instruction,
+        constant_pool: &ConstantPool,
+        interpreter: &I,
+    ) -> JomResult<()>
+    where
+        I: Interpreter<Value = V>,
+    {
+        let insn = InstructionContext {
+            index,
+            instruction,
+            constant_pool,
+        };
+
+        self.execute_inner(&insn, interpreter).map_err(|e| match e {
+            JomError::AnalysisError(_, message) => JomError::analysis(index, message),
+            e => e,
+        })
+    }
+
+    fn execute_inner<I>(&mut self, insn: &InstructionContext, interpreter: &I) -> JomResult<()>
+    where
+        I: Interpreter<Value = V>,
+    {
+        use Instruction::*;
+
+        match insn.instruction {
+            Nop | GoTo(_) | GotoW(_) | Return => {}
+            AConstNull | IConstM1 | IConst0 | IConst1 | IConst2 | IConst3 | IConst4 | IConst5
+            | LConst0 | LConst1 | FConst0 | FConst1 | FConst2 | DConst0 | DConst1 | BiPush(_)
+            | Sipush(_) | Ldc(_) | LdcW(_) | Ldc2W(_) | GetStatic(_) | New(_) => {
+                let value = interpreter.new_operation(insn)?;
+                self.push(value)?;
+            }
+            ILoad(n) | LLoad(n) | FLoad(n) | DLoad(n) | ALoad(n) => {
+                self.load(insn, interpreter, *n as usize)?
+            }
+            Wide(
+                self::Wide::ILoad(n)
+                | self::Wide::LLoad(n)
+                | self::Wide::FLoad(n)
+                | self::Wide::DLoad(n)
+                | self::Wide::ALoad(n),
+            ) => self.load(insn, interpreter, *n as usize)?,
+            ILoad0 | LLoad0 | FLoad0 | DLoad0 | ALoad0 => self.load(insn, interpreter, 0)?,
+            ILoad1 | LLoad1 | FLoad1 | DLoad1 | ALoad1 => self.load(insn, interpreter, 1)?,
+            ILoad2 | LLoad2 | FLoad2 | DLoad2 | ALoad2 => self.load(insn, interpreter, 2)?,
+            ILoad3 | LLoad3 | FLoad3 | DLoad3 | ALoad3 => self.load(insn, interpreter, 3)?,
+            IStore(n) | LStore(n) | FStore(n) | DStore(n) | AStore(n) => {
+                self.store(insn, interpreter, *n as usize)?
+            }
+            Wide(
+                self::Wide::IStore(n)
+                | self::Wide::LStore(n)
+                | self::Wide::FStore(n)
+                | self::Wide::DStore(n)
+                | self::Wide::AStore(n),
+            ) => self.store(insn, interpreter, *n as usize)?,
+            IStore0 | LStore0 | FStore0 | DStore0 | AStore0 => self.store(insn, interpreter, 0)?,
+            IStore1 | LStore1 | FStore1 | DStore1 | AStore1 => self.store(insn, interpreter, 1)?,
+            IStore2 | LStore2 | FStore2 | DStore2 | AStore2 => self.store(insn, interpreter, 2)?,
+            IStore3 | LStore3 | FStore3 | DStore3 | AStore3 => self.store(insn, interpreter, 3)?,
+            IALoad | LALoad | FALoad | DALoad | AALoad | BALoad | CALoad | SALoad | IAdd | LAdd
+            | FAdd | DAdd | ISub | LSub | FSub | DSub | IMul | LMul | FMul | DMul | IDiv | LDiv
+            | FDiv | DDiv | IRem | LRem | FRem | DRem | IShl | LShl | IShr | LShr | IUShr
+            | LUShr | IAnd | LAnd | IOr | LOr | IXor | LXor | LCmp | FCmpL | FCmpG | DCmpL
+            | DCmpG => {
+                let value2 = self.pop()?;
+                let value1 = self.pop()?;
+                let value = interpreter
+                    .binary_operation(insn, &value1, &value2)?
+                    .ok_or_else(|| insn.error("binary operation produced no value"))?;
+                self.push(value)?;
+            }
+            IAStore | LAStore | FAStore | DAStore | AAStore | BAStore | CAStore | SAStore => {
+                let value3 = self.pop()?;
+                let value2 = self.pop()?;
+                let value1 = self.pop()?;
+                interpreter.ternary_operation(insn, &value1, &value2, &value3)?;
+            }
+            Pop => {
+                self.pop_sized(1)?;
+            }
+            Pop2 => {
+                if self.pop()?.size() == 1 {
+                    self.pop_sized(1)?;
+                }
+            }
+            Dup => {
+                let value1 = self.pop_sized(1)?;
+                self.push(value1.clone())?;
+                self.push(interpreter.copy_operation(insn, &value1)?)?;
+            }
+            DupX1 => {
+                let value1 = self.pop_sized(1)?;
+                let value2 = self.pop_sized(1)?;
+                self.push(interpreter.copy_operation(insn, &value1)?)?;
+                self.push(value2)?;
+                self.push(value1)?;
+            }
+            DupX2 => {
+                let value1 = self.pop_sized(1)?;
+                let value2 = self.pop()?;
+                if value2.size() == 1 {
+                    let value3 = self.pop_sized(1)?;
+                    self.push(interpreter.copy_operation(insn, &value1)?)?;
+                    self.push(value3)?;
+                } else {
+                    self.push(interpreter.copy_operation(insn, &value1)?)?;
+                }
+                self.push(value2)?;
+                self.push(value1)?;
+            }
+            Dup2 => {
+                let value1 = self.pop()?;
+                if value1.size() == 1 {
+                    let value2 = self.pop_sized(1)?;
+                    self.push(value2.clone())?;
+                    self.push(value1.clone())?;
+                    self.push(interpreter.copy_operation(insn, &value2)?)?;
+                } else {
+                    self.push(value1.clone())?;
+                }
+                self.push(interpreter.copy_operation(insn, &value1)?)?;
+            }
+            Dup2X1 => {
+                let value1 = self.pop()?;
+                if value1.size() == 1 {
+                    let value2 = self.pop_sized(1)?;
+                    let value3 = self.pop_sized(1)?;
+                    self.push(interpreter.copy_operation(insn, &value2)?)?;
+                    self.push(interpreter.copy_operation(insn, &value1)?)?;
+                    self.push(value3)?;
+                    self.push(value2)?;
+                } else {
+                    let value2 = self.pop_sized(1)?;
+                    self.push(interpreter.copy_operation(insn, &value1)?)?;
+                    self.push(value2)?;
+                }
+                self.push(value1)?;
+            }
+            Dup2X2 => {
+                let value1 = self.pop()?;
+                if value1.size() == 1 {
+                    let value2 = self.pop_sized(1)?;
+                    let value3 = self.pop()?;
+                    if value3.size() == 1 {
+                        let value4 = self.pop_sized(1)?;
+                        self.push(interpreter.copy_operation(insn, &value2)?)?;
+                        self.push(interpreter.copy_operation(insn, &value1)?)?;
+                        self.push(value4)?;
+                    } else {
+                        self.push(interpreter.copy_operation(insn, &value2)?)?;
+                        self.push(interpreter.copy_operation(insn, &value1)?)?;
+                    }
+                    self.push(value3)?;
+                    self.push(value2)?;
+                } else {
+                    let value2 = self.pop()?;
+                    if value2.size() == 1 {
+                        let value3 = self.pop_sized(1)?;
+                        self.push(interpreter.copy_operation(insn, &value1)?)?;
+                        self.push(value3)?;
+                    } else {
+                        self.push(interpreter.copy_operation(insn, &value1)?)?;
+                    }
+                    self.push(value2)?;
+                }
+                self.push(value1)?;
+            }
+            Swap => {
+                let value2 = self.pop_sized(1)?;
+                let value1 = self.pop_sized(1)?;
+                self.push(interpreter.copy_operation(insn, &value2)?)?;
+                self.push(interpreter.copy_operation(insn, &value1)?)?;
+            }
+            INeg | LNeg | FNeg | DNeg | I2L | I2F | I2D | L2I | L2F | L2D | F2I | F2L | F2D
+            | D2I | D2L | D2F | I2B | I2C | I2S | GetField(_) | NewArray(_) | ANewArray(_)
+            | ArrayLength | CheckCast(_) | InstanceOf(_) => {
+                let value = self.pop()?;
+                let value = interpreter
+                    .unary_operation(insn, &value)?
+                    .ok_or_else(|| insn.error("unary operation produced no value"))?;
+                self.push(value)?;
+            }
+            IInc(n, _) => self.increment(insn, interpreter, *n as usize)?,
+            Wide(self::Wide::IInc(n, _)) => self.increment(insn, interpreter, *n as usize)?,
+            IfEq(_)
+            | IfNe(_)
+            | IfLt(_)
+            | IfGe(_)
+            | IfGt(_)
+            | IfLe(_)
+            | IfNull(_)
+            | IfNonNull(_)
+            | TableSwitch { .. }
+            | LookupSwitch { .. }
+            | PutStatic(_)
+            | AThrow
+            | MonitorEnter
+            | MonitorExit => {
+                let value = self.pop()?;
+                interpreter.unary_operation(insn, &value)?;
+            }
+            IfICmpEq(_) | IfICmpNe(_) | IfICmpLt(_) | IfICmpGe(_) | IfICmpGt(_) | IfICmpLe(_)
+            | IfACmpEq(_) | IfACmpNe(_) | PutField(_) => {
+                let value2 = self.pop()?;
+                let value1 = self.pop()?;
+                interpreter.binary_operation(insn, &value1, &value2)?;
+            }
+            IReturn | LReturn | FReturn | DReturn | AReturn => {
+                let value = self.pop()?;
+                interpreter.unary_operation(insn, &value)?;
+            }
+            Jsr(_) | JsrW(_) | Ret(_) | Wide(self::Wide::Ret(_)) => {
+                return Err(insn.error("subroutines are not supported"));
+            }
+            InvokeVirtual(index) | InvokeSpecial(index) | InvokeInterface(index, _) => {
+                self.invoke(insn, interpreter, *index, true)?
+            }
+            InvokeStatic(index) | InvokeDynamic(index) => {
+                self.invoke(insn, interpreter, *index, false)?
+            }
+            MultiANewArray(_, dimensions) => {
+                let mut values = (0..*dimensions)
+                    .map(|_| self.pop())
+                    .collect::<JomResult<Vec<_>>>()?;
+                values.reverse();
+                let value = interpreter
+                    .nary_operation(insn, &values)?
+                    .ok_or_else(|| insn.error("multianewarray produced no value"))?;
+                self.push(value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn pop_sized(&mut self, size: usize) -> JomResult<V> {
+        let value = self.pop()?;
+        if value.size() != size {
+            return Err(JomError::analysis(
+                0,
+                format!("expected a value of size {size}, found {}", value.size()),
+            ));
+        }
+
+        Ok(value)
+    }
+
+    fn load<I>(&mut self, insn: &InstructionContext, interpreter: &I, n: usize) -> JomResult<()>
+    where
+        I: Interpreter<Value = V>,
+    {
+        let value = self
+            .local(n)
+            .ok_or_else(|| insn.error(format!("local {n} out of bounds")))?;
+        let value = interpreter.copy_operation(insn, value)?;
+        self.push(value)
+    }
+
+    fn store<I>(&mut self, insn: &InstructionContext, interpreter: &I, n: usize) -> JomResult<()>
+    where
+        I: Interpreter<Value = V>,
+    {
+        let value = self.pop()?;
+        let value = interpreter.copy_operation(insn, &value)?;
+        let size = value.size();
+
+        self.set_local(n, value)?;
+        if size == 2 {
+            self.set_local(n + 1, interpreter.new_value(None))?;
+        }
+
+        // Overwriting the second half of a long or double invalidates the first half
+        if n > 0 && self.locals[n - 1].size() == 2 {
+            self.set_local(n - 1, interpreter.new_value(None))?;
+        }
+
+        Ok(())
+    }
+
+    fn increment<I>(
+        &mut self,
+        insn: &InstructionContext,
+        interpreter: &I,
+        n: usize,
+    ) -> JomResult<()>
+    where
+        I: Interpreter<Value = V>,
+    {
+        let value = self
+            .local(n)
+            .ok_or_else(|| insn.error(format!("local {n} out of bounds")))?;
+        let value = interpreter
+            .unary_operation(insn, value)?
+            .ok_or_else(|| insn.error("iinc produced no value"))?;
+        self.set_local(n, value)
+    }
+
+    fn invoke<I>(
+        &mut self,
+        insn: &InstructionContext,
+        interpreter: &I,
+        index: u16,
+        has_receiver: bool,
+    ) -> JomResult<()>
+    where
+        I: Interpreter<Value = V>,
+    {
+        let descriptor = insn.method_descriptor(index)?;
+
+        let count = descriptor.parameters.len() + has_receiver as usize;
+        let mut values = (0..count)
+            .map(|_| self.pop())
+            .collect::<JomResult<Vec<_>>>()?;
+        values.reverse();
+
+        if let Instruction::InvokeSpecial(_) = insn.instruction {
+            if let ConstantPoolIndex::Methodref { name, .. }
+            | ConstantPoolIndex::InterfaceMethodref { name, .. } =
+                insn.constant_pool.get(index)?
+            {
+                if name == "<init>" {
+                    self.initialize(insn, interpreter, &values[0])?;
+                }
+            }
+        }
+
+        let value = interpreter.nary_operation(insn, &values)?;
+        match (value, &descriptor.return_type) {
+            (Some(value), Some(_)) => self.push(value),
+            (None, None) => Ok(()),
+            _ => Err(insn.error("invocation result does not match its descriptor")),
+        }
+    }
+
+    /// Replaces every copy of a receiver whose constructor was called.
+    fn initialize<I>(
+        &mut self,
+        insn: &InstructionContext,
+        interpreter: &I,
+        value: &V,
+    ) -> JomResult<()>
+    where
+        I: Interpreter<Value = V>,
+    {
+        let initialized = interpreter.init_operation(insn, value)?;
+        if initialized != *value {
+            for x in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+                if x == value {
+                    *x = initialized.clone();
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    constant_pool::{ConstantPool, ConstantPoolIndex},
+    descriptor::{FieldType, MethodDescriptor},
+    error::{JomError, JomResult},
+    method::code::{instruction::Instruction, Exception},
+};
+
+use super::frame::Value;
+
+/// The instruction being simulated, passed to every [`Interpreter`] operation.
+pub struct InstructionContext<'a> {
+    pub index: usize,
+    pub instruction: &'a Instruction,
+    pub constant_pool: &'a ConstantPool,
+}
+
+impl InstructionContext<'_> {
+    /// Resolves the type of the field referenced by the instruction's constant pool operand.
+    pub fn field_type(&self, index: u16) -> JomResult<FieldType> {
+        let descriptor = self.constant_pool.get(index)?.into_fieldref()?.descriptor;
+
+        FieldType::parse(&descriptor)
+    }
+
+    /// Resolves the descriptor of the method referenced by the instruction's constant pool operand.
+    pub fn method_descriptor(&self, index: u16) -> JomResult<MethodDescriptor> {
+        let descriptor = match self.constant_pool.get(index)? {
+            ConstantPoolIndex::Methodref { descriptor, .. }
+            | ConstantPoolIndex::InterfaceMethodref { descriptor, .. }
+            | ConstantPoolIndex::InvokeDynamic { descriptor, .. } => descriptor,
+            x => {
+                return Err(JomError::new_cp_index(
+                    "Methodref, InterfaceMethodref or InvokeDynamic",
+                    x.name(),
+                ))
+            }
+        };
+
+        MethodDescriptor::parse(&descriptor)
+    }
+
+    pub(crate) fn error(&self, message: impl Into<String>) -> JomError {
+        JomError::analysis(self.index, message)
+    }
+}
+
+/// The semantics of the values tracked by an [`Analyzer`](super::Analyzer).
+///
+/// Operations returning `None` produce no value on the operand stack.
+pub trait Interpreter {
+    type Value: Value;
+
+    /// Creates a value of the given type, or an uninitialized value for `None`.
+    fn new_value(&self, ty: Option<&FieldType>) -> Self::Value;
+
+    /// Creates the value of `this` at the start of an instance method of `class`, which is
+    /// not initialized yet in a constructor.
+    fn new_this_value(&self, class: &str, _constructor: bool) -> Self::Value {
+        self.new_value(Some(&FieldType::Object(class.to_owned())))
+    }
+
+    /// Creates the value of the exception on the stack at the start of a handler.
+    fn new_exception_value(&self, exception: &Exception) -> Self::Value {
+        let class = exception
+            .catch_type
+            .clone()
+            .unwrap_or_else(|| "java/lang/Throwable".to_owned());
+
+        self.new_value(Some(&FieldType::Object(class)))
+    }
+
+    /// Instructions without stack operands that push a value, like `iconst_0`, `ldc`,
+    /// `getstatic` and `new`.
+    fn new_operation(&self, insn: &InstructionContext) -> JomResult<Self::Value>;
+
+    /// Loads and stores between locals and the stack, and the `dup` family.
+    fn copy_operation(
+        &self,
+        insn: &InstructionContext,
+        value: &Self::Value,
+    ) -> JomResult<Self::Value>;
+
+    fn unary_operation(
+        &self,
+        insn: &InstructionContext,
+        value: &Self::Value,
+    ) -> JomResult<Option<Self::Value>>;
+
+    fn binary_operation(
+        &self,
+        insn: &InstructionContext,
+        value1: &Self::Value,
+        value2: &Self::Value,
+    ) -> JomResult<Option<Self::Value>>;
+
+    fn ternary_operation(
+        &self,
+        insn: &InstructionContext,
+        value1: &Self::Value,
+        value2: &Self::Value,
+        value3: &Self::Value,
+    ) -> JomResult<Option<Self::Value>>;
+
+    /// Method invocations and `multianewarray`.
+    fn nary_operation(
+        &self,
+        insn: &InstructionContext,
+        values: &[Self::Value],
+    ) -> JomResult<Option<Self::Value>>;
+
+    /// Called for the receiver of an `invokespecial` of `<init>`. Every copy of the value in
+    /// the frame is replaced by the returned one.
+    fn init_operation(
+        &self,
+        _insn: &InstructionContext,
+        value: &Self::Value,
+    ) -> JomResult<Self::Value> {
+        Ok(value.clone())
+    }
+
+    /// Called for the `xreturn` instructions with the returned value and the method's return type.
+    fn return_operation(
+        &self,
+        _insn: &InstructionContext,
+        _value: &Self::Value,
+        _expected: Option<&FieldType>,
+    ) -> JomResult<()> {
+        Ok(())
+    }
+
+    /// Joins two values reaching the same instruction from different paths.
+    fn merge(&self, value1: &Self::Value, value2: &Self::Value) -> Self::Value;
+}
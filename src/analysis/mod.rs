@@ -0,0 +1,157 @@
+pub mod basic;
+pub mod cfg;
+pub mod constant;
+pub mod dataflow;
+pub mod frame;
+pub mod interpreter;
+pub mod source;
+pub mod verifier;
+
+use std::collections::VecDeque;
+
+use crate::{
+    access::ACC_STATIC,
+    descriptor::MethodDescriptor,
+    error::{JomError, JomResult},
+    method::{code::instruction::Instruction, MethodInfo},
+    ClassFile,
+};
+
+use self::{
+    cfg::ControlFlowGraph,
+    frame::Frame,
+    interpreter::{InstructionContext, Interpreter},
+};
+
+/// Simulates the operand stack and local variables of a method with an [`Interpreter`],
+/// iterating over its control flow graph until a fixpoint is reached.
+pub struct Analyzer<I> {
+    interpreter: I,
+}
+
+impl<I: Interpreter> Analyzer<I> {
+    pub fn new(interpreter: I) -> Self {
+        Self { interpreter }
+    }
+
+    pub fn interpreter(&self) -> &I {
+        &self.interpreter
+    }
+
+    /// Returns the frame before every instruction of `method`, or `None` for unreachable
+    /// instructions. Methods without code have no frames.
+    pub fn analyze(
+        &self,
+        class: &ClassFile,
+        method: &MethodInfo,
+    ) -> JomResult<Vec<Option<Frame<I::Value>>>> {
+        let Some(code) = method.code() else {
+            return Ok(vec![]);
+        };
+
+        let cfg = ControlFlowGraph::new(code)?;
+        let descriptor = MethodDescriptor::parse(&method.descriptor)?;
+        let interpreter = &self.interpreter;
+
+        let mut frames: Vec<Option<Frame<I::Value>>> = vec![None; code.code.len()];
+        if frames.is_empty() {
+            return Ok(frames);
+        }
+
+        let mut locals = vec![];
+        if method.access_flags & ACC_STATIC == 0 {
+            locals.push(interpreter.new_this_value(class.this_class(), method.name == "<init>"));
+        }
+        for parameter in &descriptor.parameters {
+            locals.push(interpreter.new_value(Some(parameter)));
+            if parameter.size() == 2 {
+                locals.push(interpreter.new_value(None));
+            }
+        }
+        if locals.len() > code.max_locals as usize {
+            return Err(JomError::analysis(0, "parameters exceed max_locals"));
+        }
+        locals.resize_with(code.max_locals as usize, || interpreter.new_value(None));
+
+        frames[0] = Some(Frame::new(locals, code.max_stack as usize));
+
+        let mut queued = vec![false; frames.len()];
+        let mut queue = VecDeque::from([0]);
+        queued[0] = true;
+
+        while let Some(i) = queue.pop_front() {
+            queued[i] = false;
+
+            let frame = frames[i].clone().expect("queued instructions have a frame");
+            let instruction = &code.code[i];
+
+            if let Instruction::IReturn
+            | Instruction::LReturn
+            | Instruction::FReturn
+            | Instruction::DReturn
+            | Instruction::AReturn = instruction
+            {
+                let insn = InstructionContext {
+                    index: i,
+                    instruction,
+                    constant_pool: &class.constant_pool,
+                };
+                let value = frame
+                    .peek(0)
+                    .ok_or_else(|| JomError::analysis(i, "operand stack underflow"))?;
+                interpreter.return_operation(&insn, value, descriptor.return_type.as_ref())?;
+            }
+
+            let mut next = frame.clone();
+            next.execute(i, instruction, &class.constant_pool, interpreter)?;
+
+            for &successor in cfg.successors(i) {
+                self.merge_into(&mut frames, &mut queue, &mut queued, successor, &next)?;
+            }
+
+            for &n in cfg.handlers(i) {
+                let mut handler = frame.clone();
+                handler.clear_stack();
+                handler
+                    .push(interpreter.new_exception_value(&code.exception_table[n]))
+                    .map_err(|_| JomError::analysis(i, "operand stack overflow"))?;
+                self.merge_into(
+                    &mut frames,
+                    &mut queue,
+                    &mut queued,
+                    cfg.handler_start(n),
+                    &handler,
+                )?;
+            }
+        }
+
+        Ok(frames)
+    }
+
+    fn merge_into(
+        &self,
+        frames: &mut [Option<Frame<I::Value>>],
+        queue: &mut VecDeque<usize>,
+        queued: &mut [bool],
+        index: usize,
+        frame: &Frame<I::Value>,
+    ) -> JomResult<()> {
+        let changed = match &mut frames[index] {
+            Some(old) => old.merge(frame, &self.interpreter).map_err(|e| match e {
+                JomError::AnalysisError(_, message) => JomError::analysis(index, message),
+                e => e,
+            })?,
+            x @ None => {
+                *x = Some(frame.clone());
+                true
+            }
+        };
+
+        if changed && !queued[index] {
+            queued[index] = true;
+            queue.push_back(index);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeSet;
+
+use crate::{descriptor::FieldType, error::JomResult};
+
+use super::{
+    basic::{BasicInterpreter, BasicValue},
+    frame::Value,
+    interpreter::{InstructionContext, Interpreter},
+};
+
+/// A value annotated with the indices of the instructions that may have produced it.
+///
+/// Parameters, uninitialized locals and caught exceptions have no sources.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceValue {
+    pub size: usize,
+    pub sources: BTreeSet<usize>,
+}
+
+impl SourceValue {
+    fn produced_by(size: usize, index: usize) -> Self {
+        Self {
+            size,
+            sources: BTreeSet::from([index]),
+        }
+    }
+}
+
+impl Value for SourceValue {
+    fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Tracks which instructions produced each value.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SourceInterpreter;
+
+impl SourceInterpreter {
+    /// Wraps the result of a [`BasicInterpreter`] operation, which only depends on the instruction.
+    fn wrap(insn: &InstructionContext, value: Option<BasicValue>) -> Option<SourceValue> {
+        value.map(|x| SourceValue::produced_by(x.size(), insn.index))
+    }
+}
+
+impl Interpreter for SourceInterpreter {
+    type Value = SourceValue;
+
+    fn new_value(&self, ty: Option<&FieldType>) -> SourceValue {
+        SourceValue {
+            size: ty.map_or(1, FieldType::size),
+            sources: BTreeSet::new(),
+        }
+    }
+
+    fn new_operation(&self, insn: &InstructionContext) -> JomResult<SourceValue> {
+        let value = BasicInterpreter.new_operation(insn)?;
+
+        Ok(SourceValue::produced_by(value.size(), insn.index))
+    }
+
+    fn copy_operation(
+        &self,
+        insn: &InstructionContext,
+        value: &SourceValue,
+    ) -> JomResult<SourceValue> {
+        Ok(SourceValue::produced_by(value.size, insn.index))
+    }
+
+    fn unary_operation(
+        &self,
+        insn: &InstructionContext,
+        _: &SourceValue,
+    ) -> JomResult<Option<SourceValue>> {
+        let value = BasicInterpreter.unary_operation(insn, &BasicValue::Uninitialized)?;
+
+        Ok(Self::wrap(insn, value))
+    }
+
+    fn binary_operation(
+        &self,
+        insn: &InstructionContext,
+        _: &SourceValue,
+        _: &SourceValue,
+    ) -> JomResult<Option<SourceValue>> {
+        let value = BasicInterpreter.binary_operation(
+            insn,
+            &BasicValue::Uninitialized,
+            &BasicValue::Uninitialized,
+        )?;
+
+        Ok(Self::wrap(insn, value))
+    }
+
+    fn ternary_operation(
+        &self,
+        _: &InstructionContext,
+        _: &SourceValue,
+        _: &SourceValue,
+        _: &SourceValue,
+    ) -> JomResult<Option<SourceValue>> {
+        Ok(None)
+    }
+
+    fn nary_operation(
+        &self,
+        insn: &InstructionContext,
+        _: &[SourceValue],
+    ) -> JomResult<Option<SourceValue>> {
+        let value = BasicInterpreter.nary_operation(insn, &[])?;
+
+        Ok(Self::wrap(insn, value))
+    }
+
+    fn merge(&self, value1: &SourceValue, value2: &SourceValue) -> SourceValue {
+        SourceValue {
+            size: value1.size.min(value2.size),
+            sources: value1.sources.union(&value2.sources).copied().collect(),
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{
+    constant_pool::ConstantPoolIndex,
+    descriptor::{FieldType, MethodDescriptor},
+    error::JomResult,
+    hierarchy::ClassHierarchy,
+    method::code::{
+        instruction::{AType, Instruction},
+        Exception,
+    },
+};
+
+use super::{
+    basic::{BasicInterpreter, BasicValue},
+    frame::Value,
+    interpreter::{InstructionContext, Interpreter},
+};
+
+const OBJECT: &str = "java/lang/Object";
+
+/// The verification types of stack map frames.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum VerifierValue {
+    Top,
+    Int,
+    Float,
+    Long,
+    Double,
+    Null,
+    /// `this` in a constructor before the super constructor is called, of the class.
+    UninitializedThis(String),
+    /// An object created by the `new` at the instruction index, of the class.
+    Uninitialized(usize, String),
+    /// An initialized object, of a class name or an array descriptor.
+    Reference(String),
+}
+
+impl VerifierValue {
+    pub fn from_type(ty: &FieldType) -> Self {
+        match ty {
+            FieldType::Byte
+            | FieldType::Char
+            | FieldType::Int
+            | FieldType::Short
+            | FieldType::Boolean => Self::Int,
+            FieldType::Float => Self::Float,
+            FieldType::Long => Self::Long,
+            FieldType::Double => Self::Double,
+            FieldType::Object(class) => Self::Reference(class.clone()),
+            FieldType::Array(_) => Self::Reference(ty.to_string()),
+        }
+    }
+
+    fn from_basic(value: BasicValue) -> Self {
+        match value {
+            BasicValue::Int => Self::Int,
+            BasicValue::Float => Self::Float,
+            BasicValue::Long => Self::Long,
+            BasicValue::Double => Self::Double,
+            BasicValue::Reference => Self::Reference(OBJECT.to_owned()),
+            BasicValue::Uninitialized | BasicValue::ReturnAddress => Self::Top,
+        }
+    }
+
+    fn to_basic(&self) -> BasicValue {
+        match self {
+            Self::Top => BasicValue::Uninitialized,
+            Self::Int => BasicValue::Int,
+            Self::Float => BasicValue::Float,
+            Self::Long => BasicValue::Long,
+            Self::Double => BasicValue::Double,
+            Self::Null
+            | Self::UninitializedThis(_)
+            | Self::Uninitialized(..)
+            | Self::Reference(_) => BasicValue::Reference,
+        }
+    }
+}
+
+impl Value for VerifierValue {
+    fn size(&self) -> usize {
+        match self {
+            Self::Long | Self::Double => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Tracks the [`VerifierValue`] of every local and stack entry, merging classes to their
+/// common superclass in `hierarchy`, like the frames a compiler emits.
+pub struct VerifierInterpreter<'a> {
+    hierarchy: &'a ClassHierarchy,
+}
+
+impl<'a> VerifierInterpreter<'a> {
+    pub fn new(hierarchy: &'a ClassHierarchy) -> Self {
+        Self { hierarchy }
+    }
+
+    fn constant(&self, insn: &InstructionContext, index: u16) -> JomResult<VerifierValue> {
+        let class = match insn.constant_pool.get(index)? {
+            ConstantPoolIndex::String(_) | ConstantPoolIndex::StringUnits(_) => "java/lang/String",
+            ConstantPoolIndex::Class(_) => "java/lang/Class",
+            ConstantPoolIndex::MethodType(_) => "java/lang/invoke/MethodType",
+            ConstantPoolIndex::MethodHandle { .. } => "java/lang/invoke/MethodHandle",
+            ConstantPoolIndex::Dynamic { descriptor, .. } => {
+                return Ok(VerifierValue::from_type(&FieldType::parse(&descriptor)?))
+            }
+            _ => {
+                let value = BasicInterpreter.new_operation(insn)?;
+                return Ok(VerifierValue::from_basic(value));
+            }
+        };
+
+        Ok(VerifierValue::Reference(class.to_owned()))
+    }
+}
+
+/// The descriptor of an array of `class`, which is a class name or an array descriptor.
+fn array_of(class: &str) -> String {
+    match class.starts_with('[') {
+        true => format!("[{class}"),
+        false => format!("[L{class};"),
+    }
+}
+
+impl Interpreter for VerifierInterpreter<'_> {
+    type Value = VerifierValue;
+
+    fn new_value(&self, ty: Option<&FieldType>) -> VerifierValue {
+        ty.map_or(VerifierValue::Top, VerifierValue::from_type)
+    }
+
+    fn new_this_value(&self, class: &str, constructor: bool) -> VerifierValue {
+        match constructor && class != OBJECT {
+            true => VerifierValue::UninitializedThis(class.to_owned()),
+            false => VerifierValue::Reference(class.to_owned()),
+        }
+    }
+
+    fn new_exception_value(&self, exception: &Exception) -> VerifierValue {
+        let class = exception
+            .catch_type
+            .as_deref()
+            .unwrap_or("java/lang/Throwable");
+
+        VerifierValue::Reference(class.to_owned())
+    }
+
+    fn new_operation(&self, insn: &InstructionContext) -> JomResult<VerifierValue> {
+        use Instruction::*;
+
+        Ok(match insn.instruction {
+            AConstNull => VerifierValue::Null,
+            New(index) => {
+                VerifierValue::Uninitialized(insn.index, insn.constant_pool.get_class(*index)?)
+            }
+            Ldc(index) => self.constant(insn, *index as u16)?,
+            LdcW(index) | Ldc2W(index) => self.constant(insn, *index)?,
+            GetStatic(index) => VerifierValue::from_type(&insn.field_type(*index)?),
+            _ => VerifierValue::from_basic(BasicInterpreter.new_operation(insn)?),
+        })
+    }
+
+    fn copy_operation(
+        &self,
+        _: &InstructionContext,
+        value: &VerifierValue,
+    ) -> JomResult<VerifierValue> {
+        Ok(value.clone())
+    }
+
+    fn unary_operation(
+        &self,
+        insn: &InstructionContext,
+        value: &VerifierValue,
+    ) -> JomResult<Option<VerifierValue>> {
+        use Instruction::*;
+
+        let class = match insn.instruction {
+            GetField(index) => {
+                return Ok(Some(VerifierValue::from_type(&insn.field_type(*index)?)))
+            }
+            NewArray(ty) => match ty {
+                AType::Boolean => "[Z",
+                AType::Char => "[C",
+                AType::Float => "[F",
+                AType::Double => "[D",
+                AType::Byte => "[B",
+                AType::Short => "[S",
+                AType::Int => "[I",
+                AType::Long => "[J",
+            }
+            .to_owned(),
+            ANewArray(index) => array_of(&insn.constant_pool.get_class(*index)?),
+            CheckCast(index) => insn.constant_pool.get_class(*index)?,
+            _ => {
+                let value = BasicInterpreter.unary_operation(insn, &value.to_basic())?;
+                return Ok(value.map(VerifierValue::from_basic));
+            }
+        };
+
+        Ok(Some(VerifierValue::Reference(class)))
+    }
+
+    fn binary_operation(
+        &self,
+        insn: &InstructionContext,
+        value1: &VerifierValue,
+        value2: &VerifierValue,
+    ) -> JomResult<Option<VerifierValue>> {
+        if let Instruction::AALoad = insn.instruction {
+            let component = match value1 {
+                VerifierValue::Reference(array) => match array.strip_prefix('[') {
+                    Some(x) => match FieldType::parse(x)? {
+                        FieldType::Object(class) => VerifierValue::Reference(class),
+                        _ => VerifierValue::Reference(x.to_owned()),
+                    },
+                    None => VerifierValue::Reference(OBJECT.to_owned()),
+                },
+                _ => VerifierValue::Null,
+            };
+
+            return Ok(Some(component));
+        }
+
+        let value =
+            BasicInterpreter.binary_operation(insn, &value1.to_basic(), &value2.to_basic())?;
+
+        Ok(value.map(VerifierValue::from_basic))
+    }
+
+    fn ternary_operation(
+        &self,
+        _: &InstructionContext,
+        _: &VerifierValue,
+        _: &VerifierValue,
+        _: &VerifierValue,
+    ) -> JomResult<Option<VerifierValue>> {
+        Ok(None)
+    }
+
+    fn nary_operation(
+        &self,
+        insn: &InstructionContext,
+        _: &[VerifierValue],
+    ) -> JomResult<Option<VerifierValue>> {
+        use Instruction::*;
+
+        Ok(match insn.instruction {
+            MultiANewArray(index, _) => Some(VerifierValue::Reference(
+                insn.constant_pool.get_class(*index)?,
+            )),
+            InvokeVirtual(index)
+            | InvokeSpecial(index)
+            | InvokeStatic(index)
+            | InvokeInterface(index, _)
+            | InvokeDynamic(index) => {
+                let MethodDescriptor { return_type, .. } = insn.method_descriptor(*index)?;
+                return_type.as_ref().map(VerifierValue::from_type)
+            }
+            _ => None,
+        })
+    }
+
+    fn init_operation(
+        &self,
+        _: &InstructionContext,
+        value: &VerifierValue,
+    ) -> JomResult<VerifierValue> {
+        Ok(match value {
+            VerifierValue::UninitializedThis(class) | VerifierValue::Uninitialized(_, class) => {
+                VerifierValue::Reference(class.clone())
+            }
+            x => x.clone(),
+        })
+    }
+
+    fn merge(&self, value1: &VerifierValue, value2: &VerifierValue) -> VerifierValue {
+        match (value1, value2) {
+            _ if value1 == value2 => value1.clone(),
+            (VerifierValue::Null, x @ VerifierValue::Reference(_))
+            | (x @ VerifierValue::Reference(_), VerifierValue::Null) => x.clone(),
+            (VerifierValue::Reference(a), VerifierValue::Reference(b)) => {
+                VerifierValue::Reference(self.hierarchy.common_superclass(a, b))
+            }
+            _ => VerifierValue::Top,
+        }
+    }
+}
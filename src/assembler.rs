@@ -0,0 +1,1181 @@
+//! An assembler for a textual class file syntax modelled on Jasmin.
+//!
+//! ```text
+//! .version 61 0
+//! .class public super HelloWorld
+//! .super java/lang/Object
+//! .source HelloWorld.java
+//!
+//! .field private static final GREETING Ljava/lang/String; = "Hello World!"
+//!
+//! .method public static main([Ljava/lang/String;)V
+//!     .limit stack 2
+//!     .line 3
+//!     getstatic java/lang/System.out Ljava/io/PrintStream;
+//!     ldc "Hello World!"
+//!     invokevirtual java/io/PrintStream.println(Ljava/lang/String;)V
+//!     return
+//! .end method
+//! ```
+//!
+//! Comments start with a `;` at the beginning of a token and run to the end of the line.
+//!
+//! Field and method references are written as `owner.name descriptor` and `owner.name(args)ret`.
+//! `ldc` takes an integer, a float, a quoted string, `Class name` or `MethodType descriptor`, and
+//! `ldc2_w` a long or a double. Jumps take a label, defined by `name:` at the start of a line.
+//! Switches list their targets on the following lines and end with `default: label`:
+//!
+//! ```text
+//!     tableswitch 0
+//!         zero
+//!         one
+//!         default: other
+//!     lookupswitch
+//!         10: ten
+//!         default: other
+//! ```
+//!
+//! Inside a method, `.limit stack n` and `.limit locals n` set the maximums, which are computed
+//! when omitted. `.line n` and `.stack frame` apply to the next instruction, and
+//! `.catch class from start to end using handler` and `.var n is name descriptor from start to end`
+//! add exception handlers and local variables, with `any` catching every exception.
+//!
+//! Stack map frames are `same`, `same_locals_1_stack_item type`, `chop n`, `append types...` and
+//! `full locals types... stack types...`, where a type is `Top`, `Integer`, `Float`, `Double`,
+//! `Long`, `Null`, `UninitializedThis`, `Object class` or `Uninitialized label`.
+
+use std::{collections::HashMap, io::Cursor};
+
+use binrw::BinRead;
+
+use crate::{
+    access::*,
+    attribute::{
+        ClassAttribute, CodeAttribute, ConstantValue, FieldAttribute, LineNumberTableIndex,
+        LocalVariableTableIndex, MethodAttribute,
+    },
+    constant_pool::{ConstantPool, ConstantPoolIndex},
+    descriptor::{FieldType, MethodDescriptor},
+    error::{JomError, JomResult},
+    field::FieldInfo,
+    method::{
+        code::{
+            instruction::{AType, Instruction, Wide},
+            layout::{self, Catch, Item, Layout},
+            stack_map::{StackMapFrame, VerificationType},
+            Code,
+        },
+        MethodInfo,
+    },
+    ClassFile,
+};
+
+/// Assembles the source into a class file.
+pub fn assemble(source: &str) -> JomResult<ClassFile> {
+    let mut assembler = Assembler::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let tokens = Tokens::new(line, i + 1)?;
+        assembler.statement(tokens)?;
+    }
+
+    assembler.finish()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+}
+
+struct Tokens {
+    tokens: Vec<Token>,
+    position: usize,
+    line: usize,
+}
+
+impl Tokens {
+    fn new(source: &str, line: usize) -> JomResult<Self> {
+        let mut tokens = vec![];
+        let mut chars = source.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c == ';' {
+                break;
+            } else if c == '"' {
+                chars.next();
+                tokens.push(Token::Str(unescape(&mut chars, line)?));
+            } else {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+
+        Ok(Self {
+            tokens,
+            position: 0,
+            line,
+        })
+    }
+
+    fn error(&self, message: impl Into<String>) -> JomError {
+        JomError::assembly(self.line, message)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_word(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Word(w)) => Some(w),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn word(&mut self, expected: &str) -> JomResult<String> {
+        match self.next() {
+            Some(Token::Word(w)) => Ok(w),
+            _ => Err(self.error(format!("expected {expected}"))),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> JomResult<()> {
+        match self.next() {
+            Some(Token::Word(w)) if w == keyword => Ok(()),
+            _ => Err(self.error(format!("expected `{keyword}`"))),
+        }
+    }
+
+    fn string(&mut self, expected: &str) -> JomResult<String> {
+        match self.next() {
+            Some(Token::Word(s) | Token::Str(s)) => Ok(s),
+            None => Err(self.error(format!("expected {expected}"))),
+        }
+    }
+
+    fn int<T: TryFrom<i64>>(&mut self, expected: &str) -> JomResult<T> {
+        let word = self.word(expected)?;
+
+        parse_int(&word)
+            .and_then(|x| T::try_from(x).ok())
+            .ok_or_else(|| self.error(format!("invalid {expected} {word}")))
+    }
+
+    fn end(&self) -> JomResult<()> {
+        match self.peek() {
+            None => Ok(()),
+            Some(Token::Word(w) | Token::Str(w)) => {
+                Err(self.error(format!("unexpected token {w}")))
+            }
+        }
+    }
+
+    /// Removes the remaining tokens.
+    fn rest(&mut self) -> Vec<Token> {
+        let rest = self.tokens.split_off(self.position.min(self.tokens.len()));
+        self.position = self.tokens.len();
+        rest
+    }
+}
+
+fn unescape(chars: &mut impl Iterator<Item = char>, line: usize) -> JomResult<String> {
+    let mut s = String::new();
+
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(s),
+            Some('\\') => s.push(match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some('\\') => '\\',
+                Some('"') => '"',
+                Some('u') => {
+                    let hex = chars.by_ref().take(4).collect::<String>();
+                    u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| {
+                            JomError::assembly(line, format!("invalid escape \\u{hex}"))
+                        })?
+                }
+                c => {
+                    return Err(JomError::assembly(
+                        line,
+                        format!("invalid escape \\{}", c.unwrap_or(' ')),
+                    ))
+                }
+            }),
+            Some(c) => s.push(c),
+            None => return Err(JomError::assembly(line, "unterminated string")),
+        }
+    }
+}
+
+fn parse_int(s: &str) -> Option<i64> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(x) => (true, x),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i64>().ok()?,
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+/// Parses a floating point literal, allowing one of the given type suffixes.
+fn parse_float(s: &str, suffixes: &[char]) -> Option<f64> {
+    let s = s.strip_suffix(suffixes).unwrap_or(s);
+
+    s.parse().ok()
+}
+
+fn access_flag(word: &str) -> Option<u16> {
+    Some(match word {
+        "public" => ACC_PUBLIC,
+        "private" => ACC_PRIVATE,
+        "protected" => ACC_PROTECTED,
+        "static" => ACC_STATIC,
+        "final" => ACC_FINAL,
+        "super" => ACC_SUPER,
+        "synchronized" => ACC_SYNCHRONIZED,
+        "volatile" => ACC_VOLATILE,
+        "bridge" => ACC_BRIDGE,
+        "transient" => ACC_TRANSIENT,
+        "varargs" => ACC_VARARGS,
+        "native" => ACC_NATIVE,
+        "interface" => ACC_INTERFACE,
+        "abstract" => ACC_ABSTRACT,
+        "strict" => ACC_STRICT,
+        "synthetic" => ACC_SYNTHETIC,
+        "annotation" => ACC_ANNOTATION,
+        "enum" => ACC_ENUM,
+        "module" => ACC_MODULE,
+        _ => return None,
+    })
+}
+
+/// Parses the access flags in front of the last `names` words.
+fn access_flags(tokens: &mut Tokens, names: usize) -> JomResult<u16> {
+    let mut flags = 0;
+
+    while tokens.tokens.len() - tokens.position > names {
+        let word = tokens.word("access flag")?;
+        flags |= access_flag(&word).ok_or_else(|| tokens.error(format!("unknown flag {word}")))?;
+    }
+
+    Ok(flags)
+}
+
+/// Instructions without operands, looked up by decoding every opcode on its own.
+fn simple_instruction(mnemonic: &str) -> Option<Instruction> {
+    (0..=u8::MAX)
+        .filter_map(|opcode| Instruction::read(&mut Cursor::new([opcode])).ok())
+        .find(|x| x.mnemonic() == mnemonic)
+}
+
+fn array_type(name: &str) -> Option<AType> {
+    Some(match name {
+        "boolean" => AType::Boolean,
+        "char" => AType::Char,
+        "float" => AType::Float,
+        "double" => AType::Double,
+        "byte" => AType::Byte,
+        "short" => AType::Short,
+        "int" => AType::Int,
+        "long" => AType::Long,
+        _ => return None,
+    })
+}
+
+fn split_member(tokens: &Tokens, reference: &str) -> JomResult<(String, String)> {
+    reference
+        .rsplit_once('.')
+        .map(|(class, name)| (class.to_owned(), name.to_owned()))
+        .ok_or_else(|| tokens.error(format!("expected owner.name, found {reference}")))
+}
+
+/// Splits `name(args)ret` into the name and the descriptor.
+fn split_descriptor(tokens: &Tokens, s: &str) -> JomResult<(String, String)> {
+    let (name, descriptor) = s
+        .find('(')
+        .map(|i| s.split_at(i))
+        .ok_or_else(|| tokens.error(format!("expected a method descriptor in {s}")))?;
+    MethodDescriptor::parse(descriptor)
+        .map_err(|_| tokens.error(format!("invalid descriptor {descriptor}")))?;
+
+    Ok((name.to_owned(), descriptor.to_owned()))
+}
+
+fn field_type(tokens: &Tokens, descriptor: &str) -> JomResult<FieldType> {
+    FieldType::parse(descriptor)
+        .map_err(|_| tokens.error(format!("invalid descriptor {descriptor}")))
+}
+
+fn field_value(tokens: &mut Tokens, ty: &FieldType) -> JomResult<ConstantValue> {
+    if let FieldType::Object(class) = ty {
+        return match (class.as_str(), tokens.next()) {
+            ("java/lang/String", Some(Token::Str(s))) => Ok(ConstantValue::String(s)),
+            _ => Err(tokens.error("expected a string constant")),
+        };
+    }
+
+    let word = tokens.word("constant value")?;
+    let value = match ty {
+        FieldType::Long => parse_int(&word).map(ConstantValue::Long),
+        FieldType::Float => parse_float(&word, &['f', 'F']).map(|x| ConstantValue::Float(x as f32)),
+        FieldType::Double => parse_float(&word, &['d', 'D']).map(ConstantValue::Double),
+        FieldType::Array(_) => None,
+        _ => parse_int(&word)
+            .and_then(|x| i32::try_from(x).ok())
+            .map(ConstantValue::Integer),
+    };
+
+    value.ok_or_else(|| tokens.error(format!("invalid constant value {word}")))
+}
+
+struct Assembler {
+    constant_pool: ConstantPool,
+    minor: u16,
+    major: u16,
+    access_flags: u16,
+    this_class: Option<String>,
+    super_class: Option<String>,
+    interfaces: Vec<String>,
+    fields: Vec<FieldInfo>,
+    methods: Vec<MethodInfo>,
+    attributes: Vec<ClassAttribute>,
+    method: Option<MethodAssembler>,
+}
+
+impl Assembler {
+    fn new() -> Self {
+        Self {
+            constant_pool: ConstantPool(vec![ConstantPoolIndex::Unusable]),
+            minor: 0,
+            major: 63,
+            access_flags: 0,
+            this_class: None,
+            super_class: None,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+            method: None,
+        }
+    }
+
+    fn statement(&mut self, mut tokens: Tokens) -> JomResult<()> {
+        if let Some(method) = &mut self.method {
+            if method.switch.is_some() {
+                return method.switch_targets(tokens);
+            }
+        }
+
+        let Some(first) = tokens.peek_word().map(str::to_owned) else {
+            return match tokens.peek() {
+                None => Ok(()),
+                Some(_) => Err(tokens.error("expected a directive or an instruction")),
+            };
+        };
+
+        if first.starts_with('.') {
+            tokens.next();
+            return self.directive(&first, tokens);
+        }
+
+        match &mut self.method {
+            Some(method) => method.statement(tokens, &mut self.constant_pool),
+            None => Err(tokens.error("instruction outside of a method")),
+        }
+    }
+
+    fn directive(&mut self, directive: &str, mut tokens: Tokens) -> JomResult<()> {
+        if let Some(method) = &mut self.method {
+            return match directive {
+                ".end" => {
+                    tokens.keyword("method")?;
+                    tokens.end()?;
+                    let method = self.method.take().unwrap();
+                    let method = method.finish(&mut self.constant_pool)?;
+                    self.methods.push(method);
+                    Ok(())
+                }
+                _ => method.directive(directive, tokens, &mut self.constant_pool),
+            };
+        }
+
+        match directive {
+            ".version" => {
+                self.major = tokens.int("major version")?;
+                if !tokens.is_empty() {
+                    self.minor = tokens.int("minor version")?;
+                }
+            }
+            ".class" | ".interface" => {
+                if self.this_class.is_some() {
+                    return Err(tokens.error("duplicate class directive"));
+                }
+
+                self.access_flags = access_flags(&mut tokens, 1)?;
+                if directive == ".interface" {
+                    self.access_flags |= ACC_INTERFACE | ACC_ABSTRACT;
+                }
+
+                let name = tokens.word("class name")?;
+                self.constant_pool.insert_class(name.clone())?;
+                self.this_class = Some(name);
+            }
+            ".super" => {
+                let name = tokens.word("class name")?;
+                self.constant_pool.insert_class(name.clone())?;
+                self.super_class = Some(name);
+            }
+            ".implements" => {
+                let name = tokens.word("interface name")?;
+                self.constant_pool.insert_class(name.clone())?;
+                self.interfaces.push(name);
+            }
+            ".source" => {
+                let file = tokens.string("source file")?;
+                self.attributes.push(ClassAttribute::SourceFile(file));
+            }
+            ".field" => {
+                let names = tokens.tokens[tokens.position..]
+                    .iter()
+                    .position(|x| x == &Token::Word("=".to_owned()))
+                    .map_or(2, |i| tokens.tokens.len() - tokens.position - i + 2);
+                let access_flags = access_flags(&mut tokens, names)?;
+                let name = tokens.word("field name")?;
+                let descriptor = tokens.word("field descriptor")?;
+                let ty = field_type(&tokens, &descriptor)?;
+
+                let mut attributes = vec![];
+                if !tokens.is_empty() {
+                    tokens.keyword("=")?;
+                    attributes.push(FieldAttribute::ConstantValue(field_value(
+                        &mut tokens,
+                        &ty,
+                    )?));
+                }
+
+                self.fields.push(FieldInfo {
+                    access_flags,
+                    name,
+                    descriptor,
+                    attributes,
+                });
+            }
+            ".method" => {
+                let access_flags = access_flags(&mut tokens, 1)?;
+                let method = tokens.word("method name")?;
+                let (name, descriptor) = split_descriptor(&tokens, &method)?;
+
+                self.method = Some(MethodAssembler::new(
+                    tokens.line,
+                    access_flags,
+                    name,
+                    descriptor,
+                ));
+            }
+            ".end" => return Err(tokens.error(".end outside of a method")),
+            x => return Err(tokens.error(format!("unknown directive {x}"))),
+        }
+
+        tokens.end()
+    }
+
+    fn finish(self) -> JomResult<ClassFile> {
+        if let Some(method) = self.method {
+            return Err(JomError::assembly(method.line, "missing .end method"));
+        }
+
+        let Some(this_class) = self.this_class else {
+            return Err(JomError::assembly(0, "missing .class directive"));
+        };
+        let mut constant_pool = self.constant_pool;
+        let super_class = self
+            .super_class
+            .unwrap_or_else(|| "java/lang/Object".to_owned());
+        constant_pool.insert_class(super_class.clone())?;
+
+        Ok(ClassFile {
+            minor: self.minor,
+            major: self.major,
+            constant_pool,
+            access_flags: self.access_flags,
+            this_class,
+            super_class,
+            interfaces: self.interfaces,
+            fields: self.fields,
+            methods: self.methods,
+            attributes: self.attributes,
+        })
+    }
+}
+
+struct Var {
+    line: usize,
+    index: u16,
+    name: String,
+    descriptor: String,
+    start: String,
+    end: String,
+}
+
+struct MethodAssembler {
+    line: usize,
+    access_flags: u16,
+    name: String,
+    descriptor: String,
+    max_stack: Option<u16>,
+    max_locals: Option<u16>,
+    code: Vec<Item<String>>,
+    /// The line each item was defined on.
+    code_lines: Vec<usize>,
+    /// The index of the item following each label.
+    labels: HashMap<String, usize>,
+    line_numbers: Vec<(usize, u16)>,
+    frames: Vec<(usize, usize, StackMapFrame<String>)>,
+    /// The exception handlers with the line they were defined on.
+    catches: Vec<(usize, Catch<String>)>,
+    vars: Vec<Var>,
+    /// A switch waiting for its targets, with its line, mnemonic and the tokens read so far.
+    switch: Option<(usize, String, Vec<String>)>,
+}
+
+impl MethodAssembler {
+    fn new(line: usize, access_flags: u16, name: String, descriptor: String) -> Self {
+        Self {
+            line,
+            access_flags,
+            name,
+            descriptor,
+            max_stack: None,
+            max_locals: None,
+            code: vec![],
+            code_lines: vec![],
+            labels: HashMap::new(),
+            line_numbers: vec![],
+            frames: vec![],
+            catches: vec![],
+            vars: vec![],
+            switch: None,
+        }
+    }
+
+    fn directive(
+        &mut self,
+        directive: &str,
+        mut tokens: Tokens,
+        constant_pool: &mut ConstantPool,
+    ) -> JomResult<()> {
+        match directive {
+            ".limit" => match tokens.word("stack or locals")?.as_str() {
+                "stack" => self.max_stack = Some(tokens.int("stack size")?),
+                "locals" => self.max_locals = Some(tokens.int("locals size")?),
+                x => return Err(tokens.error(format!("unknown limit {x}"))),
+            },
+            ".line" => {
+                let line = tokens.int("line number")?;
+                self.line_numbers.push((self.code.len(), line));
+            }
+            ".catch" => {
+                let catch_type = match tokens.word("exception class")?.as_str() {
+                    "any" => None,
+                    x => Some(x.to_owned()),
+                };
+                tokens.keyword("from")?;
+                let start = tokens.word("label")?;
+                tokens.keyword("to")?;
+                let end = tokens.word("label")?;
+                tokens.keyword("using")?;
+                let handler = tokens.word("label")?;
+
+                self.catches.push((
+                    tokens.line,
+                    Catch {
+                        start,
+                        end,
+                        handler,
+                        catch_type,
+                    },
+                ));
+            }
+            ".var" => {
+                let index = tokens.int("local variable index")?;
+                tokens.keyword("is")?;
+                let name = tokens.word("local variable name")?;
+                let descriptor = tokens.word("local variable descriptor")?;
+                field_type(&tokens, &descriptor)?;
+                tokens.keyword("from")?;
+                let start = tokens.word("label")?;
+                tokens.keyword("to")?;
+                let end = tokens.word("label")?;
+
+                self.vars.push(Var {
+                    line: tokens.line,
+                    index,
+                    name,
+                    descriptor,
+                    start,
+                    end,
+                });
+            }
+            ".stack" => {
+                let frame = Self::frame(&mut tokens, constant_pool)?;
+                self.frames.push((tokens.line, self.code.len(), frame));
+            }
+            x => return Err(tokens.error(format!("unknown directive {x} in method"))),
+        }
+
+        tokens.end()
+    }
+
+    fn frame(
+        tokens: &mut Tokens,
+        constant_pool: &mut ConstantPool,
+    ) -> JomResult<StackMapFrame<String>> {
+        let types = |tokens: &mut Tokens,
+                     constant_pool: &mut ConstantPool,
+                     until: Option<&str>|
+         -> JomResult<Vec<VerificationType<String>>> {
+            let mut types = vec![];
+            while !tokens.is_empty() && tokens.peek_word() != until {
+                types.push(Self::verification_type(tokens, constant_pool)?);
+            }
+            Ok(types)
+        };
+
+        Ok(match tokens.word("frame type")?.as_str() {
+            "same" => StackMapFrame::Same,
+            "same_locals_1_stack_item" => {
+                StackMapFrame::SameLocals1StackItem(Self::verification_type(tokens, constant_pool)?)
+            }
+            "chop" => match tokens.int("chopped locals")? {
+                x @ 1..=3 => StackMapFrame::Chop(x),
+                x => return Err(tokens.error(format!("cannot chop {x} locals"))),
+            },
+            "append" => match types(tokens, constant_pool, None)? {
+                x if (1..=3).contains(&x.len()) => StackMapFrame::Append(x),
+                x => return Err(tokens.error(format!("cannot append {} locals", x.len()))),
+            },
+            "full" => {
+                let locals = match tokens.peek_word() {
+                    Some("locals") => {
+                        tokens.next();
+                        types(tokens, constant_pool, Some("stack"))?
+                    }
+                    _ => vec![],
+                };
+                let stack = match tokens.peek_word() {
+                    Some("stack") => {
+                        tokens.next();
+                        types(tokens, constant_pool, None)?
+                    }
+                    _ => vec![],
+                };
+                StackMapFrame::Full(locals, stack)
+            }
+            x => return Err(tokens.error(format!("unknown frame type {x}"))),
+        })
+    }
+
+    fn verification_type(
+        tokens: &mut Tokens,
+        constant_pool: &mut ConstantPool,
+    ) -> JomResult<VerificationType<String>> {
+        Ok(match tokens.word("verification type")?.as_str() {
+            "Top" => VerificationType::Top,
+            "Integer" => VerificationType::Integer,
+            "Float" => VerificationType::Float,
+            "Double" => VerificationType::Double,
+            "Long" => VerificationType::Long,
+            "Null" => VerificationType::Null,
+            "UninitializedThis" => VerificationType::UninitializedThis,
+            "Object" => {
+                VerificationType::Object(constant_pool.insert_class(tokens.word("class name")?)?)
+            }
+            "Uninitialized" => VerificationType::Uninitialized(tokens.word("label")?),
+            x => return Err(tokens.error(format!("unknown verification type {x}"))),
+        })
+    }
+
+    fn statement(&mut self, mut tokens: Tokens, constant_pool: &mut ConstantPool) -> JomResult<()> {
+        let mut mnemonic = tokens.word("instruction")?;
+
+        if let Some(label) = mnemonic.strip_suffix(':') {
+            if self
+                .labels
+                .insert(label.to_owned(), self.code.len())
+                .is_some()
+            {
+                return Err(tokens.error(format!("duplicate label {label}")));
+            }
+            if tokens.is_empty() {
+                return Ok(());
+            }
+            mnemonic = tokens.word("instruction")?;
+        }
+
+        let item = self.instruction(&mnemonic, &mut tokens, constant_pool)?;
+        if let Some(item) = item {
+            self.push(tokens.line, item);
+            tokens.end()
+        } else {
+            let rest = tokens.rest();
+            self.switch = Some((tokens.line, mnemonic, vec![]));
+            self.switch_targets(Tokens {
+                tokens: rest,
+                position: 0,
+                line: tokens.line,
+            })
+        }
+    }
+
+    /// Parses an instruction, or returns `None` for a switch whose targets follow.
+    fn instruction(
+        &mut self,
+        mnemonic: &str,
+        tokens: &mut Tokens,
+        constant_pool: &mut ConstantPool,
+    ) -> JomResult<Option<Item<String>>> {
+        use Instruction::*;
+
+        let local =
+            |tokens: &mut Tokens, narrow: fn(u8) -> Instruction, wide: fn(u16) -> self::Wide| {
+                let index: u16 = tokens.int("local variable index")?;
+                Ok::<_, JomError>(match u8::try_from(index) {
+                    Ok(x) => narrow(x),
+                    Err(_) => Wide(wide(index)),
+                })
+            };
+        let class = |tokens: &mut Tokens, constant_pool: &mut ConstantPool| {
+            constant_pool.insert_class(tokens.word("class name")?)
+        };
+
+        let instruction = match mnemonic {
+            "bipush" => BiPush(tokens.int::<i8>("byte")? as u8),
+            "sipush" => Sipush(tokens.int::<i16>("short")? as u16),
+            "ldc" | "ldc_w" => {
+                let index = Self::constant(tokens, constant_pool, false)?;
+                match u8::try_from(index) {
+                    Ok(x) if mnemonic == "ldc" => Ldc(x),
+                    _ => LdcW(index),
+                }
+            }
+            "ldc2_w" => Ldc2W(Self::constant(tokens, constant_pool, true)?),
+            "iload" => local(tokens, ILoad, self::Wide::ILoad)?,
+            "lload" => local(tokens, LLoad, self::Wide::LLoad)?,
+            "fload" => local(tokens, FLoad, self::Wide::FLoad)?,
+            "dload" => local(tokens, DLoad, self::Wide::DLoad)?,
+            "aload" => local(tokens, ALoad, self::Wide::ALoad)?,
+            "istore" => local(tokens, IStore, self::Wide::IStore)?,
+            "lstore" => local(tokens, LStore, self::Wide::LStore)?,
+            "fstore" => local(tokens, FStore, self::Wide::FStore)?,
+            "dstore" => local(tokens, DStore, self::Wide::DStore)?,
+            "astore" => local(tokens, AStore, self::Wide::AStore)?,
+            "ret" => local(tokens, Ret, self::Wide::Ret)?,
+            "iinc" => {
+                let index: u16 = tokens.int("local variable index")?;
+                let constant: i16 = tokens.int("increment")?;
+                match (u8::try_from(index), i8::try_from(constant)) {
+                    (Ok(index), Ok(constant)) => IInc(index, constant as u8),
+                    _ => Wide(self::Wide::IInc(index, constant)),
+                }
+            }
+            "ifeq" | "ifne" | "iflt" | "ifge" | "ifgt" | "ifle" | "if_icmpeq" | "if_icmpne"
+            | "if_icmplt" | "if_icmpge" | "if_icmpgt" | "if_icmple" | "if_acmpeq" | "if_acmpne"
+            | "goto" | "jsr" | "ifnull" | "ifnonnull" => {
+                let f: fn(u16) -> Instruction = match mnemonic {
+                    "ifeq" => IfEq,
+                    "ifne" => IfNe,
+                    "iflt" => IfLt,
+                    "ifge" => IfGe,
+                    "ifgt" => IfGt,
+                    "ifle" => IfLe,
+                    "if_icmpeq" => IfICmpEq,
+                    "if_icmpne" => IfICmpNe,
+                    "if_icmplt" => IfICmpLt,
+                    "if_icmpge" => IfICmpGe,
+                    "if_icmpgt" => IfICmpGt,
+                    "if_icmple" => IfICmpLe,
+                    "if_acmpeq" => IfACmpEq,
+                    "if_acmpne" => IfACmpNe,
+                    "goto" => GoTo,
+                    "jsr" => Jsr,
+                    "ifnull" => IfNull,
+                    _ => IfNonNull,
+                };
+                return Ok(Some(Item::Jump(f, tokens.word("label")?)));
+            }
+            "goto_w" => return Ok(Some(Item::JumpW(GotoW, tokens.word("label")?))),
+            "jsr_w" => return Ok(Some(Item::JumpW(JsrW, tokens.word("label")?))),
+            "tableswitch" | "lookupswitch" => return Ok(None),
+            "getstatic" | "putstatic" | "getfield" | "putfield" => {
+                let reference = tokens.word("field reference")?;
+                let (class, name) = split_member(tokens, &reference)?;
+                let descriptor = tokens.word("field descriptor")?;
+                field_type(tokens, &descriptor)?;
+                let index = constant_pool.insert(ConstantPoolIndex::Fieldref {
+                    class,
+                    name,
+                    descriptor,
+                })?;
+
+                match mnemonic {
+                    "getstatic" => GetStatic(index),
+                    "putstatic" => PutStatic(index),
+                    "getfield" => GetField(index),
+                    _ => PutField(index),
+                }
+            }
+            "invokevirtual" | "invokespecial" | "invokestatic" | "invokeinterface" => {
+                // `invokespecial` and `invokestatic` can call interface methods.
+                let interface = mnemonic == "invokeinterface"
+                    || (mnemonic != "invokevirtual" && tokens.peek_word() == Some("interface"));
+                if tokens.peek_word() == Some("interface") {
+                    tokens.next();
+                }
+
+                let reference = tokens.word("method reference")?;
+                let (member, descriptor) = split_descriptor(tokens, &reference)?;
+                let (class, name) = split_member(tokens, &member)?;
+                let parameters = MethodDescriptor::parse(&descriptor)?.parameters_size();
+                let entry = if interface {
+                    ConstantPoolIndex::InterfaceMethodref {
+                        class,
+                        name,
+                        descriptor,
+                    }
+                } else {
+                    ConstantPoolIndex::Methodref {
+                        class,
+                        name,
+                        descriptor,
+                    }
+                };
+                let index = constant_pool.insert(entry)?;
+
+                match mnemonic {
+                    "invokevirtual" => InvokeVirtual(index),
+                    "invokespecial" => InvokeSpecial(index),
+                    "invokestatic" => InvokeStatic(index),
+                    _ if tokens.is_empty() => InvokeInterface(index, parameters as u8 + 1),
+                    _ => InvokeInterface(index, tokens.int("argument count")?),
+                }
+            }
+            "invokedynamic" => {
+                let bootstrap_method_attr_index = tokens.int("bootstrap method index")?;
+                let call_site = tokens.word("call site")?;
+                let (name, descriptor) = split_descriptor(tokens, &call_site)?;
+
+                InvokeDynamic(constant_pool.insert(ConstantPoolIndex::InvokeDynamic {
+                    bootstrap_method_attr_index,
+                    name,
+                    descriptor,
+                })?)
+            }
+            "new" => New(class(tokens, constant_pool)?),
+            "anewarray" => ANewArray(class(tokens, constant_pool)?),
+            "checkcast" => CheckCast(class(tokens, constant_pool)?),
+            "instanceof" => InstanceOf(class(tokens, constant_pool)?),
+            "multianewarray" => {
+                MultiANewArray(class(tokens, constant_pool)?, tokens.int("dimensions")?)
+            }
+            "newarray" => {
+                let name = tokens.word("array type")?;
+                NewArray(
+                    array_type(&name)
+                        .ok_or_else(|| tokens.error(format!("unknown array type {name}")))?,
+                )
+            }
+            x => simple_instruction(x)
+                .ok_or_else(|| tokens.error(format!("unknown instruction {x}")))?,
+        };
+
+        Ok(Some(Item::Instruction(instruction)))
+    }
+
+    fn constant(
+        tokens: &mut Tokens,
+        constant_pool: &mut ConstantPool,
+        wide: bool,
+    ) -> JomResult<u16> {
+        let entry = match tokens.next() {
+            Some(Token::Str(s)) if !wide => ConstantPoolIndex::String(s),
+            Some(Token::Word(w)) if w == "Class" && !wide => {
+                ConstantPoolIndex::Class(tokens.word("class name")?)
+            }
+            Some(Token::Word(w)) if w == "MethodType" && !wide => {
+                let descriptor = tokens.word("method descriptor")?;
+                MethodDescriptor::parse(&descriptor)
+                    .map_err(|_| tokens.error(format!("invalid descriptor {descriptor}")))?;
+                ConstantPoolIndex::MethodType(descriptor)
+            }
+            Some(Token::Word(w)) if wide => {
+                let long = parse_int(w.strip_suffix(['l', 'L']).unwrap_or(&w));
+                match long {
+                    Some(x) => ConstantPoolIndex::Long(x),
+                    None => ConstantPoolIndex::Double(
+                        parse_float(&w, &['d', 'D'])
+                            .ok_or_else(|| tokens.error(format!("invalid constant {w}")))?,
+                    ),
+                }
+            }
+            Some(Token::Word(w)) => match parse_int(&w).and_then(|x| i32::try_from(x).ok()) {
+                Some(x) => ConstantPoolIndex::Integer(x),
+                None => ConstantPoolIndex::Float(
+                    parse_float(&w, &['f', 'F'])
+                        .ok_or_else(|| tokens.error(format!("invalid constant {w}")))?
+                        as f32,
+                ),
+            },
+            _ => return Err(tokens.error("expected a constant")),
+        };
+
+        constant_pool.insert(entry)
+    }
+
+    /// Collects the targets of a pending switch until its default target.
+    fn switch_targets(&mut self, tokens: Tokens) -> JomResult<()> {
+        let (line, mnemonic, mut words) = self.switch.take().unwrap();
+
+        for token in &tokens.tokens {
+            match token {
+                Token::Word(w) if w.starts_with('.') => {
+                    return Err(tokens.error(format!("expected the default target of {mnemonic}")))
+                }
+                Token::Word(w) => {
+                    words.extend(w.split(':').filter(|x| !x.is_empty()).map(str::to_owned))
+                }
+                Token::Str(s) => return Err(tokens.error(format!("unexpected string \"{s}\""))),
+            }
+        }
+
+        let Some(i) = words.iter().position(|x| x == "default") else {
+            self.switch = Some((line, mnemonic, words));
+            return Ok(());
+        };
+        match words.len() - i {
+            1 => {
+                self.switch = Some((line, mnemonic, words));
+                return Ok(());
+            }
+            2 => {}
+            _ => return Err(tokens.error(format!("unexpected token {}", words[i + 2]))),
+        }
+
+        let default = words[i + 1].clone();
+        let error = |message: String| JomError::assembly(line, message);
+        let int = |x: &str| {
+            parse_int(x)
+                .and_then(|x| i32::try_from(x).ok())
+                .ok_or_else(|| error(format!("invalid switch key {x}")))
+        };
+
+        let item = if mnemonic == "tableswitch" {
+            let Some((low, targets)) = words[..i].split_first() else {
+                return Err(error("expected the low key of tableswitch".to_owned()));
+            };
+            if targets.is_empty() {
+                return Err(error("tableswitch needs at least one target".to_owned()));
+            }
+
+            Item::TableSwitch {
+                low: int(low)?,
+                targets: targets.to_vec(),
+                default,
+            }
+        } else {
+            if i % 2 != 0 {
+                return Err(error("expected key: label pairs".to_owned()));
+            }
+
+            let mut pairs = words[..i]
+                .chunks(2)
+                .map(|x| Ok((int(&x[0])?, x[1].clone())))
+                .collect::<JomResult<Vec<_>>>()?;
+            pairs.sort_by_key(|(key, _)| *key);
+
+            Item::LookupSwitch { pairs, default }
+        };
+
+        self.push(line, item);
+        Ok(())
+    }
+
+    fn push(&mut self, line: usize, item: Item<String>) {
+        self.code.push(item);
+        self.code_lines.push(line);
+    }
+
+    fn finish(self, constant_pool: &mut ConstantPool) -> JomResult<MethodInfo> {
+        if let Some((line, mnemonic, _)) = self.switch {
+            return Err(JomError::assembly(
+                line,
+                format!("missing default target of {mnemonic}"),
+            ));
+        }
+
+        let Layout { code, pcs } = layout::lay_out(
+            &self.code,
+            |i, target| {
+                self.labels.get(target).copied().ok_or_else(|| {
+                    JomError::assembly(self.code_lines[i], format!("unknown label {target}"))
+                })
+            },
+            false,
+            |i, target| {
+                JomError::assembly(
+                    self.code_lines[i],
+                    format!("{target} is out of range, use goto_w"),
+                )
+            },
+        )?;
+        if pcs[self.code.len()] > u16::MAX as u32 {
+            return Err(JomError::assembly(self.line, "method is too large"));
+        }
+
+        let label = |line: usize, label: &str| {
+            self.labels
+                .get(label)
+                .map(|&i| pcs[i])
+                .ok_or_else(|| JomError::assembly(line, format!("unknown label {label}")))
+        };
+
+        let exception_table = self
+            .catches
+            .iter()
+            .map(|(line, x)| x.exception(|x| label(*line, x)))
+            .collect::<JomResult<Vec<_>>>()?;
+
+        let mut attributes = vec![];
+        if !self.line_numbers.is_empty() {
+            attributes.push(CodeAttribute::LineNumberTable(
+                self.line_numbers
+                    .iter()
+                    .map(|&(i, line_number)| LineNumberTableIndex {
+                        start_pc: pcs[i] as u16,
+                        line_number,
+                    })
+                    .collect(),
+            ));
+        }
+        if !self.vars.is_empty() {
+            attributes.push(CodeAttribute::LocalVariableTable(
+                self.vars
+                    .iter()
+                    .map(|x| {
+                        let start_pc = label(x.line, &x.start)?;
+                        let end_pc = label(x.line, &x.end)?;
+
+                        Ok(LocalVariableTableIndex {
+                            start_pc: start_pc as u16,
+                            length: end_pc.saturating_sub(start_pc) as u16,
+                            name: x.name.clone(),
+                            descriptor: x.descriptor.clone(),
+                            index: x.index,
+                        })
+                    })
+                    .collect::<JomResult<Vec<_>>>()?,
+            ));
+        }
+        if !self.frames.is_empty() {
+            attributes.push(CodeAttribute::Unknown(
+                "StackMapTable".to_owned(),
+                self.stack_map_table(&pcs, &label)?,
+            ));
+        }
+
+        let mut method_attributes = vec![];
+        if !code.is_empty() || self.max_stack.is_some() || self.max_locals.is_some() {
+            let mut code = Code {
+                max_stack: 0,
+                max_locals: 0,
+                code,
+                exception_table,
+                attributes,
+            };
+
+            code.max_stack = match self.max_stack {
+                Some(x) => x,
+                None => code
+                    .compute_max_stack(constant_pool)
+                    .map_err(|e| JomError::assembly(self.line, e.to_string()))?,
+            };
+            code.max_locals = match self.max_locals {
+                Some(x) => x,
+                None => code.compute_max_locals(
+                    &MethodDescriptor::parse(&self.descriptor)?,
+                    self.access_flags & ACC_STATIC != 0,
+                ),
+            };
+
+            method_attributes.push(MethodAttribute::Code(code));
+        }
+
+        Ok(MethodInfo {
+            access_flags: self.access_flags,
+            name: self.name,
+            descriptor: self.descriptor,
+            attributes: method_attributes,
+        })
+    }
+
+    fn stack_map_table(
+        &self,
+        pcs: &[u32],
+        label: &impl Fn(usize, &str) -> JomResult<u32>,
+    ) -> JomResult<Vec<u8>> {
+        let mut info = (self.frames.len() as u16).to_be_bytes().to_vec();
+        let mut previous: Option<u32> = None;
+
+        for (line, i, frame) in &self.frames {
+            if *i == self.code.len() {
+                return Err(JomError::assembly(
+                    *line,
+                    ".stack is not followed by an instruction",
+                ));
+            }
+
+            let pc = pcs[*i];
+            let delta = match previous {
+                None => pc,
+                Some(x) if pc > x => pc - x - 1,
+                Some(_) => return Err(JomError::assembly(*line, "duplicate stack map frame")),
+            } as u16;
+            previous = Some(pc);
+
+            frame.encode(delta, &mut info, &|x| Ok(label(*line, x)? as u16))?;
+        }
+
+        Ok(info)
+    }
+}
@@ -1,11 +1,15 @@
 use std::io::Cursor;
 
-use binrw::{binrw, BinRead, VecArgs};
+use binrw::{binrw, BinRead, BinResult, BinWrite, VecArgs};
 
-use crate::{constant_pool::{ConstantPool, ConstantPoolIndex}, error::{JomResult, JomError}, method::code::Code};
+use crate::{constant_pool::{ConstantPool, ConstantPoolIndex}, error::{ErrorPath, JomResult, JomError}, method::code::Code, Diagnostic, ReadState};
 
 #[binrw]
 pub(crate) struct RawAttribute {
+    /// The offset of the attribute in what it was read from, which is zero for new ones.
+    #[br(parse_with = position)]
+    #[bw(ignore)]
+    offset: u64,
     name: u16,
     #[br(temp)]
     #[bw(calc = info.len() as u32)]
@@ -14,115 +18,327 @@ pub(crate) struct RawAttribute {
     info: Vec<u8>,
 }
 
+/// Reads the position of the reader, so that diagnostics can point into the class file.
+#[binrw::parser(reader)]
+fn position() -> BinResult<u64> {
+    Ok(std::io::Seek::stream_position(reader)?)
+}
+
 impl RawAttribute {
-    pub fn into_field_attr(self, cp: &ConstantPool) -> JomResult<FieldAttribute> {
+    pub fn into_field_attr(
+        self,
+        cp: &ConstantPool,
+        state: &mut ReadState,
+    ) -> JomResult<FieldAttribute> {
         let name = cp.get_utf8(self.name)?;
 
-        match name.as_str() {
-            "ConstantValue" => {
-                let value_idx = <u16 as BinRead>::read_be(&mut Cursor::new(self.info))?;
-                let value = cp.get(value_idx)?;
+        let decoded = match name.as_str() {
+            "ConstantValue" => Self::read_index(&self.info)
+                .and_then(|x| cp.get(x))
+                .and_then(ConstantValue::from_cp_index)
+                .map(|x| Some(FieldAttribute::ConstantValue(x))),
+            "Synthetic" => Ok(Some(FieldAttribute::Synthetic)),
+            "Deprecated" => Ok(Some(FieldAttribute::Deprecated)),
+            "Signature" => Self::read_index(&self.info)
+                .and_then(|x| cp.get_utf8(x))
+                .map(|x| Some(FieldAttribute::Signature(x))),
+            _ => Ok(None),
+        };
+
+        Ok(match self.recover(decoded, &name, state)? {
+            Some(attribute) => attribute,
+            None => FieldAttribute::Unknown(name, self.info),
+        })
+    }
 
-                Ok(FieldAttribute::ConstantValue(ConstantValue::from_cp_index(
-                    value,
-                )?))
+    pub fn into_method_attr(
+        self,
+        cp: &ConstantPool,
+        state: &mut ReadState,
+    ) -> JomResult<MethodAttribute> {
+        let name = cp.get_utf8(self.name)?;
+
+        let decoded = match name.as_str() {
+            "Code" if !state.options.skip_code => Code::read(&self.info, cp, state, self.offset + 6)
+                .map(|x| Some(MethodAttribute::Code(x))),
+            "Synthetic" => Ok(Some(MethodAttribute::Synthetic)),
+            "Deprecated" => Ok(Some(MethodAttribute::Deprecated)),
+            "Signature" => Self::read_index(&self.info)
+                .and_then(|x| cp.get_utf8(x))
+                .map(|x| Some(MethodAttribute::Signature(x))),
+            _ => Ok(None),
+        };
+
+        Ok(match self.recover(decoded, &name, state)? {
+            Some(attribute) => attribute,
+            None => MethodAttribute::Unknown(name, self.info),
+        })
+    }
+
+    /// Decodes an attribute of `Code`, whose info starts at `base` in the class file.
+    pub fn into_code_attr(
+        mut self,
+        cp: &ConstantPool,
+        state: &mut ReadState,
+        base: u64,
+    ) -> JomResult<CodeAttribute> {
+        let name = cp.get_utf8(self.name)?;
+        self.offset += base;
+
+        let decoded = match name.as_str() {
+            _ if state.options.skip_code_attributes => Ok(None),
+            "LineNumberTable" => {
+                Self::read_table::<LineNumberTableIndex>(&self.info)
+                    .map(|x| Some(CodeAttribute::LineNumberTable(x)))
             }
-            "Synthetic" => Ok(FieldAttribute::Synthetic),
-            "Deprecated" => Ok(FieldAttribute::Deprecated),
-            "Signature" => {
-                let value_idx = <u16 as BinRead>::read_be(&mut Cursor::new(self.info))?;
-                let value = cp.get_utf8(value_idx)?;
+            "LocalVariableTable" => {
+                Self::read_table::<RawLocalVariableTableIndex>(&self.info).and_then(|table| {
+                    let table = table
+                        .into_iter()
+                        .map(|x| x.into_table_index(cp))
+                        .collect::<JomResult<Vec<_>>>()?;
+
+                    Ok(Some(CodeAttribute::LocalVariableTable(table)))
+                })
+            }
+            "LocalVariableTypeTable" => {
+                Self::read_table::<RawLocalVariableTypeTableIndex>(&self.info).and_then(|table| {
+                    let table = table
+                        .into_iter()
+                        .map(|x| x.into_table_index(cp))
+                        .collect::<JomResult<Vec<_>>>()?;
+
+                    Ok(Some(CodeAttribute::LocalVariableTypeTable(table)))
+                })
+            }
+            // "StackMapTable" => todo!(),
+            // "RuntimeVisibleTypeAnnotations" => todo!(),
+            // "RuntimeInvisibleTypeAnnotations" => todo!(),
+            _ => Ok(None),
+        };
 
-                Ok(FieldAttribute::Signature(value))
+        Ok(match self.recover(decoded, &name, state)? {
+            Some(attribute) => attribute,
+            None => CodeAttribute::Unknown(name, self.info),
+        })
+    }
+
+    /// In lenient mode, turns a failure to decode the attribute into a diagnostic, so that it
+    /// is kept as `Unknown`. Otherwise adds the attribute to the path of the error.
+    fn recover<T>(
+        &self,
+        decoded: JomResult<Option<T>>,
+        name: &str,
+        state: &mut ReadState,
+    ) -> JomResult<Option<T>> {
+        match decoded {
+            Err(error) if state.options.lenient => {
+                state.diagnostics.push(Diagnostic {
+                    offset: self.offset,
+                    attribute: name.to_owned(),
+                    constant: None,
+                    message: error.to_string(),
+                });
+                Ok(None)
             }
-            _ => Ok(FieldAttribute::Unknown(name, self.info)),
+            Err(error) => {
+                let error = error.within(ErrorPath::Attribute(name.to_owned()));
+                Err(match self.offset {
+                    0 => error,
+                    offset => error.at(offset),
+                })
+            }
+            x => x,
         }
     }
 
+    fn read_index(info: &[u8]) -> JomResult<u16> {
+        Ok(<u16 as BinRead>::read_be(&mut Cursor::new(info))?)
+    }
 
-    pub fn into_method_attr(self, cp: &ConstantPool) -> JomResult<MethodAttribute> {
-        let name = cp.get_utf8(self.name)?;
+    fn read_table<T>(info: &[u8]) -> JomResult<Vec<T>>
+    where
+        T: for<'a> BinRead<Args<'a> = ()> + 'static,
+    {
+        let mut cursor = Cursor::new(info);
+
+        let len = <u16 as BinRead>::read_be(&mut cursor)? as usize;
+        let table = <Vec<T> as BinRead>::read_be_args(
+            &mut cursor,
+            VecArgs {
+                count: len,
+                inner: (),
+            },
+        )?;
+
+        Ok(table)
+    }
 
+    pub fn into_class_attr(self, cp: &ConstantPool) -> JomResult<ClassAttribute> {
+        let name = cp.get_utf8(self.name)?;
+        
         match name.as_str() {
-            "Code" => Ok(MethodAttribute::Code(Code::read(&self.info, cp)?)),
-            "Synthetic" => Ok(MethodAttribute::Synthetic),
-            "Deprecated" => Ok(MethodAttribute::Deprecated),
-            "Signature" => {
-                let value_idx = <u16 as BinRead>::read_be(&mut Cursor::new(self.info))?;
-                let value = cp.get_utf8(value_idx)?;
+            _ => Ok(ClassAttribute::Unknown(name, self.info)),
+        }
+    }
+
+    fn new(name: &str, info: Vec<u8>, cp: &mut ConstantPool) -> JomResult<Self> {
+        Ok(Self {
+            offset: 0,
+            name: cp.insert_utf8(name.to_owned())?,
+            info,
+        })
+    }
 
-                Ok(MethodAttribute::Signature(value))
+    fn from_index(name: &str, index: u16, cp: &mut ConstantPool) -> JomResult<Self> {
+        Self::new(name, index.to_be_bytes().to_vec(), cp)
+    }
+
+    pub fn from_field_attr(attr: &FieldAttribute, cp: &mut ConstantPool) -> JomResult<Self> {
+        match attr {
+            FieldAttribute::ConstantValue(value) => {
+                let index = cp.insert(value.to_cp_index())?;
+                Self::from_index("ConstantValue", index, cp)
+            }
+            FieldAttribute::Synthetic => Self::new("Synthetic", vec![], cp),
+            FieldAttribute::Deprecated => Self::new("Deprecated", vec![], cp),
+            FieldAttribute::Signature(s) => {
+                let index = cp.insert_utf8(s.clone())?;
+                Self::from_index("Signature", index, cp)
+            }
+            FieldAttribute::RuntimeVisibleAnnotations => {
+                Err(JomError::unwritable("RuntimeVisibleAnnotations"))
+            }
+            FieldAttribute::RuntimeInvisibleAnnotations => {
+                Err(JomError::unwritable("RuntimeInvisibleAnnotations"))
+            }
+            FieldAttribute::RuntimeVisibleTypeAnnotations => {
+                Err(JomError::unwritable("RuntimeVisibleTypeAnnotations"))
+            }
+            FieldAttribute::RuntimeInvisibleTypeAnnotations => {
+                Err(JomError::unwritable("RuntimeInvisibleTypeAnnotations"))
             }
-            _ => Ok(MethodAttribute::Unknown(name, self.info)),
+            FieldAttribute::Unknown(name, info) => Self::new(name, info.clone(), cp),
         }
     }
 
-    pub fn into_code_attr(self, cp: &ConstantPool) -> JomResult<CodeAttribute> {
-        let name = cp.get_utf8(self.name)?;
+    pub fn from_method_attr(attr: &MethodAttribute, cp: &mut ConstantPool) -> JomResult<Self> {
+        match attr {
+            MethodAttribute::Code(code) => {
+                let info = code.write(cp)?;
+                Self::new("Code", info, cp)
+            }
+            MethodAttribute::Synthetic => Self::new("Synthetic", vec![], cp),
+            MethodAttribute::Deprecated => Self::new("Deprecated", vec![], cp),
+            MethodAttribute::Signature(s) => {
+                let index = cp.insert_utf8(s.clone())?;
+                Self::from_index("Signature", index, cp)
+            }
+            MethodAttribute::Unknown(name, info) => Self::new(name, info.clone(), cp),
+            MethodAttribute::Exceptions => Err(JomError::unwritable("Exceptions")),
+            MethodAttribute::RuntimeVisibleParameterAnnotations => {
+                Err(JomError::unwritable("RuntimeVisibleParameterAnnotations"))
+            }
+            MethodAttribute::RuntimeInvisibleParameterAnnotations => {
+                Err(JomError::unwritable("RuntimeInvisibleParameterAnnotations"))
+            }
+            MethodAttribute::AnnotationDefault => Err(JomError::unwritable("AnnotationDefault")),
+            MethodAttribute::MethodParameters => Err(JomError::unwritable("MethodParameters")),
+            MethodAttribute::RuntimeVisibleAnnotations => {
+                Err(JomError::unwritable("RuntimeVisibleAnnotations"))
+            }
+            MethodAttribute::RuntimeInvisibleAnnotations => {
+                Err(JomError::unwritable("RuntimeInvisibleAnnotations"))
+            }
+            MethodAttribute::RuntimeVisibleTypeAnnotations => {
+                Err(JomError::unwritable("RuntimeVisibleTypeAnnotations"))
+            }
+            MethodAttribute::RuntimeInvisibleTypeAnnotations => {
+                Err(JomError::unwritable("RuntimeInvisibleTypeAnnotations"))
+            }
+        }
+    }
 
-        match name.as_str() {
-            "LineNumberTable" => {
-                let mut cursor = Cursor::new(self.info);
+    pub fn from_code_attr(attr: &CodeAttribute, cp: &mut ConstantPool) -> JomResult<Self> {
+        match attr {
+            CodeAttribute::LineNumberTable(table) => {
+                let mut cursor = Cursor::new(vec![]);
 
-                let len = <u16 as BinRead>::read_be(&mut cursor)? as usize;
-                let table = <Vec<LineNumberTableIndex> as BinRead>::read_be_args(
-                    &mut cursor,
-                    VecArgs {
-                        count: len,
-                        inner: (),
-                    },
-                )?;
+                (table.len() as u16).write_be(&mut cursor)?;
+                table.write_be(&mut cursor)?;
 
-                Ok(CodeAttribute::LineNumberTable(table))
+                Self::new("LineNumberTable", cursor.into_inner(), cp)
             }
-            "LocalVariableTable" => {
-                let mut cursor = Cursor::new(self.info);
-
-                let len = <u16 as BinRead>::read_be(&mut cursor)? as usize;
-                let table = <Vec<RawLocalVariableTableIndex> as BinRead>::read_be_args(
-                    &mut cursor,
-                    VecArgs {
-                        count: len,
-                        inner: (),
-                    },
-                )?;
+            CodeAttribute::LocalVariableTable(table) => {
                 let table = table
-                    .into_iter()
-                    .map(|x| x.into_table_index(cp))
+                    .iter()
+                    .map(|x| RawLocalVariableTableIndex::from_table_index(x, cp))
                     .collect::<JomResult<Vec<_>>>()?;
+                let mut cursor = Cursor::new(vec![]);
+
+                (table.len() as u16).write_be(&mut cursor)?;
+                table.write_be(&mut cursor)?;
 
-                Ok(CodeAttribute::LocalVariableTable(table))
+                Self::new("LocalVariableTable", cursor.into_inner(), cp)
             }
-            "LocalVariableTypeTable" => {
-                let mut cursor = Cursor::new(self.info);
-
-                let len = <u16 as BinRead>::read_be(&mut cursor)? as usize;
-                let table = <Vec<RawLocalVariableTypeTableIndex> as BinRead>::read_be_args(
-                    &mut cursor,
-                    VecArgs {
-                        count: len,
-                        inner: (),
-                    },
-                )?;
+            CodeAttribute::LocalVariableTypeTable(table) => {
                 let table = table
-                    .into_iter()
-                    .map(|x| x.into_table_index(cp))
+                    .iter()
+                    .map(|x| RawLocalVariableTypeTableIndex::from_table_index(x, cp))
                     .collect::<JomResult<Vec<_>>>()?;
+                let mut cursor = Cursor::new(vec![]);
+
+                (table.len() as u16).write_be(&mut cursor)?;
+                table.write_be(&mut cursor)?;
 
-                Ok(CodeAttribute::LocalVariableTypeTable(table))
+                Self::new("LocalVariableTypeTable", cursor.into_inner(), cp)
             }
-            // "StackMapTable" => todo!(),
-            // "RuntimeVisibleTypeAnnotations" => todo!(),
-            // "RuntimeInvisibleTypeAnnotations" => todo!(),
-            _ => Ok(CodeAttribute::Unknown(name, self.info)),
+            CodeAttribute::StackMapTable => Err(JomError::unwritable("StackMapTable")),
+            CodeAttribute::RuntimeVisibleTypeAnnotations => {
+                Err(JomError::unwritable("RuntimeVisibleTypeAnnotations"))
+            }
+            CodeAttribute::RuntimeInvisibleTypeAnnotations => {
+                Err(JomError::unwritable("RuntimeInvisibleTypeAnnotations"))
+            }
+            CodeAttribute::Unknown(name, info) => Self::new(name, info.clone(), cp),
         }
     }
 
-    pub fn into_class_attr(self, cp: &ConstantPool) -> JomResult<ClassAttribute> {
-        let name = cp.get_utf8(self.name)?;
-        
-        match name.as_str() {
-            _ => Ok(ClassAttribute::Unknown(name, self.info)),
+    pub fn from_class_attr(attr: &ClassAttribute, cp: &mut ConstantPool) -> JomResult<Self> {
+        match attr {
+            ClassAttribute::SourceFile(s) => {
+                let index = cp.insert_utf8(s.clone())?;
+                Self::from_index("SourceFile", index, cp)
+            }
+            ClassAttribute::Synthetic => Self::new("Synthetic", vec![], cp),
+            ClassAttribute::Deprecated => Self::new("Deprecated", vec![], cp),
+            ClassAttribute::Unknown(name, info) => Self::new(name, info.clone(), cp),
+            ClassAttribute::InnerClasses => Err(JomError::unwritable("InnerClasses")),
+            ClassAttribute::EnclosingMethod => Err(JomError::unwritable("EnclosingMethod")),
+            ClassAttribute::SourceDebugExtension => {
+                Err(JomError::unwritable("SourceDebugExtension"))
+            }
+            ClassAttribute::BootstrapMethods => Err(JomError::unwritable("BootstrapMethods")),
+            ClassAttribute::Module => Err(JomError::unwritable("Module")),
+            ClassAttribute::ModulePackages => Err(JomError::unwritable("ModulePackages")),
+            ClassAttribute::ModuleMainClass => Err(JomError::unwritable("ModuleMainClass")),
+            ClassAttribute::NestHost => Err(JomError::unwritable("NestHost")),
+            ClassAttribute::NestMembers => Err(JomError::unwritable("NestMembers")),
+            ClassAttribute::Record => Err(JomError::unwritable("Record")),
+            ClassAttribute::PermittedSubclasses => Err(JomError::unwritable("PermittedSubclasses")),
+            ClassAttribute::Signature => Err(JomError::unwritable("Signature")),
+            ClassAttribute::RuntimeVisibleAnnotations => {
+                Err(JomError::unwritable("RuntimeVisibleAnnotations"))
+            }
+            ClassAttribute::RuntimeInvisibleAnnotations => {
+                Err(JomError::unwritable("RuntimeInvisibleAnnotations"))
+            }
+            ClassAttribute::RuntimeVisibleTypeAnnotations => {
+                Err(JomError::unwritable("RuntimeVisibleTypeAnnotations"))
+            }
+            ClassAttribute::RuntimeInvisibleTypeAnnotations => {
+                Err(JomError::unwritable("RuntimeInvisibleTypeAnnotations"))
+            }
         }
     }
 }
@@ -139,12 +355,31 @@ pub enum FieldAttribute {
     Unknown(String, Vec<u8>),
 }
 
+impl FieldAttribute {
+    /// The name the attribute is stored under.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::ConstantValue(..) => "ConstantValue",
+            Self::Synthetic => "Synthetic",
+            Self::Deprecated => "Deprecated",
+            Self::Signature(..) => "Signature",
+            Self::RuntimeVisibleAnnotations => "RuntimeVisibleAnnotations",
+            Self::RuntimeInvisibleAnnotations => "RuntimeInvisibleAnnotations",
+            Self::RuntimeVisibleTypeAnnotations => "RuntimeVisibleTypeAnnotations",
+            Self::RuntimeInvisibleTypeAnnotations => "RuntimeInvisibleTypeAnnotations",
+            Self::Unknown(name, _) => name,
+        }
+    }
+}
+
 pub enum ConstantValue {
     Integer(i32),
     Float(f32),
     Long(i64),
     Double(f64),
     String(String),
+    /// A string with unpaired surrogates, like [`ConstantPoolIndex::StringUnits`].
+    StringUnits(Vec<u16>),
 }
 
 impl ConstantValue {
@@ -155,12 +390,24 @@ impl ConstantValue {
             ConstantPoolIndex::Long(l) => Ok(Self::Long(l)),
             ConstantPoolIndex::Double(d) => Ok(Self::Double(d)),
             ConstantPoolIndex::String(s) => Ok(Self::String(s)),
+            ConstantPoolIndex::StringUnits(x) => Ok(Self::StringUnits(x)),
             x => Err(JomError::ConstantPoolIndexError(
                 "Integer, Float, Long, Double or String",
                 x.name(),
             )),
         }
     }
+
+    pub(crate) fn to_cp_index(&self) -> ConstantPoolIndex {
+        match self {
+            Self::Integer(i) => ConstantPoolIndex::Integer(*i),
+            Self::Float(f) => ConstantPoolIndex::Float(*f),
+            Self::Long(l) => ConstantPoolIndex::Long(*l),
+            Self::Double(d) => ConstantPoolIndex::Double(*d),
+            Self::String(s) => ConstantPoolIndex::String(s.clone()),
+            Self::StringUnits(x) => ConstantPoolIndex::StringUnits(x.clone()),
+        }
+    }
 }
 
 pub enum MethodAttribute {
@@ -190,10 +437,75 @@ pub enum CodeAttribute {
     Unknown(String, Vec<u8>),
 }
 
+impl MethodAttribute {
+    /// The name the attribute is stored under.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Code(..) => "Code",
+            Self::Exceptions => "Exceptions",
+            Self::RuntimeVisibleParameterAnnotations => "RuntimeVisibleParameterAnnotations",
+            Self::RuntimeInvisibleParameterAnnotations => "RuntimeInvisibleParameterAnnotations",
+            Self::AnnotationDefault => "AnnotationDefault",
+            Self::MethodParameters => "MethodParameters",
+            Self::Synthetic => "Synthetic",
+            Self::Deprecated => "Deprecated",
+            Self::Signature(..) => "Signature",
+            Self::RuntimeVisibleAnnotations => "RuntimeVisibleAnnotations",
+            Self::RuntimeInvisibleAnnotations => "RuntimeInvisibleAnnotations",
+            Self::RuntimeVisibleTypeAnnotations => "RuntimeVisibleTypeAnnotations",
+            Self::RuntimeInvisibleTypeAnnotations => "RuntimeInvisibleTypeAnnotations",
+            Self::Unknown(name, _) => name,
+        }
+    }
+
+    /// Decodes a `Code` attribute that was skipped when the class was read.
+    pub(crate) fn decode(&mut self, cp: &ConstantPool) -> JomResult<()> {
+        if let MethodAttribute::Unknown(name, info) = self {
+            if name == "Code" {
+                *self = MethodAttribute::Code(Code::read(info, cp, &mut ReadState::default(), 0)?);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CodeAttribute {
+    /// The name the attribute is stored under.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::LineNumberTable(..) => "LineNumberTable",
+            Self::LocalVariableTable(..) => "LocalVariableTable",
+            Self::LocalVariableTypeTable(..) => "LocalVariableTypeTable",
+            Self::StackMapTable => "StackMapTable",
+            Self::RuntimeVisibleTypeAnnotations => "RuntimeVisibleTypeAnnotations",
+            Self::RuntimeInvisibleTypeAnnotations => "RuntimeInvisibleTypeAnnotations",
+            Self::Unknown(name, _) => name,
+        }
+    }
+
+    /// Decodes an attribute that was skipped when the class was read, if it has a decoded
+    /// form.
+    pub(crate) fn decode(&mut self, cp: &ConstantPool) -> JomResult<()> {
+        if let CodeAttribute::Unknown(name, info) = self {
+            if let Ok(name) = cp.find_utf8(name.clone()) {
+                let attribute = RawAttribute {
+                    offset: 0,
+                    name,
+                    info: info.clone(),
+                };
+                *self = attribute.into_code_attr(cp, &mut ReadState::default(), 0)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[binrw]
 pub struct LineNumberTableIndex {
-    start_pc: u16,
-    line_number: u16,
+    pub start_pc: u16,
+    pub line_number: u16,
 }
 
 #[binrw]
@@ -226,6 +538,16 @@ impl RawLocalVariableTableIndex {
             index,
         })
     }
+
+    fn from_table_index(table_index: &LocalVariableTableIndex, constant_pool: &mut ConstantPool) -> JomResult<Self> {
+        Ok(Self {
+            start_pc: table_index.start_pc,
+            length: table_index.length,
+            name: constant_pool.insert_utf8(table_index.name.clone())?,
+            descriptor: constant_pool.insert_utf8(table_index.descriptor.clone())?,
+            index: table_index.index,
+        })
+    }
 }
 
 pub struct LocalVariableTableIndex {
@@ -269,6 +591,16 @@ impl RawLocalVariableTypeTableIndex {
             index,
         })
     }
+
+    fn from_table_index(table_index: &LocalVariableTypeTableIndex, constant_pool: &mut ConstantPool) -> JomResult<Self> {
+        Ok(Self {
+            start_pc: table_index.start_pc,
+            length: table_index.length,
+            name: constant_pool.insert_utf8(table_index.name.clone())?,
+            descriptor: constant_pool.insert_utf8(table_index.descriptor.clone())?,
+            index: table_index.index,
+        })
+    }
 }
 
 pub struct LocalVariableTypeTableIndex {
@@ -301,3 +633,56 @@ pub enum ClassAttribute {
     RuntimeInvisibleTypeAnnotations,
     Unknown(String, Vec<u8>),
 }
+
+impl ClassAttribute {
+    /// The name the attribute is stored under.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::SourceFile(..) => "SourceFile",
+            Self::InnerClasses => "InnerClasses",
+            Self::EnclosingMethod => "EnclosingMethod",
+            Self::SourceDebugExtension => "SourceDebugExtension",
+            Self::BootstrapMethods => "BootstrapMethods",
+            Self::Module => "Module",
+            Self::ModulePackages => "ModulePackages",
+            Self::ModuleMainClass => "ModuleMainClass",
+            Self::NestHost => "NestHost",
+            Self::NestMembers => "NestMembers",
+            Self::Record => "Record",
+            Self::PermittedSubclasses => "PermittedSubclasses",
+            Self::Synthetic => "Synthetic",
+            Self::Deprecated => "Deprecated",
+            Self::Signature => "Signature",
+            Self::RuntimeVisibleAnnotations => "RuntimeVisibleAnnotations",
+            Self::RuntimeInvisibleAnnotations => "RuntimeInvisibleAnnotations",
+            Self::RuntimeVisibleTypeAnnotations => "RuntimeVisibleTypeAnnotations",
+            Self::RuntimeInvisibleTypeAnnotations => "RuntimeInvisibleTypeAnnotations",
+            Self::Unknown(name, _) => name,
+        }
+    }
+}
+
+/// The bootstrap methods of a class with `attributes`, as the indices of the method handle
+/// and arguments of each.
+pub(crate) fn bootstrap_methods(attributes: &[ClassAttribute]) -> JomResult<Vec<(u16, Vec<u16>)>> {
+    let Some(info) = attributes.iter().find_map(|x| match x {
+        ClassAttribute::Unknown(name, info) if name == "BootstrapMethods" => Some(info),
+        _ => None,
+    }) else {
+        return Ok(vec![]);
+    };
+
+    let mut cursor = Cursor::new(info);
+    let mut bootstrap_methods = vec![];
+    for _ in 0..<u16 as BinRead>::read_be(&mut cursor)? {
+        let handle = <u16 as BinRead>::read_be(&mut cursor)?;
+        let count = <u16 as BinRead>::read_be(&mut cursor)?;
+        let mut arguments = vec![];
+        for _ in 0..count {
+            arguments.push(<u16 as BinRead>::read_be(&mut cursor)?);
+        }
+        bootstrap_methods.push((handle, arguments));
+    }
+
+    Ok(bootstrap_methods)
+}
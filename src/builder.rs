@@ -0,0 +1,165 @@
+//! Generating classes from fields, methods and code built with [`CodeBuilder`].
+
+use std::collections::HashSet;
+
+use crate::{
+    access::{ACC_ABSTRACT, ACC_INTERFACE, ACC_PUBLIC, ACC_STATIC, ACC_SUPER},
+    attribute::{ClassAttribute, MethodAttribute},
+    descriptor::MethodDescriptor,
+    error::{ErrorPath, JomError, JomResult},
+    field::FieldInfo,
+    hierarchy::ClassHierarchy,
+    method::{
+        code::{builder::CodeBuilder, instruction::Instruction},
+        MethodInfo,
+    },
+    version::{ClassVersion, Feature},
+    ClassFile,
+};
+
+/// Builds a class, which extends `java/lang/Object` and is `public` unless set otherwise.
+///
+/// The version is the same as that of [`ClassFile::new`].
+pub struct ClassBuilder {
+    class: ClassFile,
+    hierarchy: ClassHierarchy,
+}
+
+impl ClassBuilder {
+    pub fn new(this_class: impl Into<String>) -> Self {
+        let mut class = ClassFile::new(this_class.into());
+        class.access_flags = ACC_PUBLIC | ACC_SUPER;
+
+        Self {
+            class,
+            hierarchy: ClassHierarchy::new(),
+        }
+    }
+
+    pub fn set_version(&mut self, version: ClassVersion) {
+        self.class.set_version(version.major, version.minor);
+    }
+
+    pub fn set_access_flags(&mut self, access_flags: u16) {
+        self.class.access_flags = access_flags;
+    }
+
+    pub fn set_super_class(&mut self, super_class: impl Into<String>) {
+        self.class.super_class = super_class.into();
+    }
+
+    /// The classes the stack map frames are computed with, which should hold those whose
+    /// values the code merges and their supertypes. The class itself is added when finishing.
+    pub fn set_hierarchy(&mut self, hierarchy: ClassHierarchy) {
+        self.hierarchy = hierarchy;
+    }
+
+    pub fn add_interface(&mut self, interface: impl Into<String>) {
+        self.class.interfaces.push(interface.into());
+    }
+
+    pub fn add_attribute(&mut self, attribute: ClassAttribute) {
+        self.class.attributes.push(attribute);
+    }
+
+    pub fn add_field(&mut self, field: FieldInfo) {
+        self.class.fields.push(field);
+    }
+
+    pub fn add_method(&mut self, method: MethodInfo) {
+        self.class.methods.push(method);
+    }
+
+    /// Adds a method with the code, adding its constants to the constant pool. Further
+    /// attributes can be added with [`Self::add_method`] instead, from the code finished by
+    /// [`CodeBuilder::finish`] with [`Self::class_mut`].
+    pub fn add_code_method(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        code: CodeBuilder,
+    ) -> JomResult<()> {
+        let path = || ErrorPath::Method {
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+        };
+        let parsed = MethodDescriptor::parse(descriptor).map_err(|e| e.within(path()))?;
+        let code = code
+            .finish(
+                &mut self.class.constant_pool,
+                &parsed,
+                access_flags & ACC_STATIC != 0,
+            )
+            .map_err(|e| e.within(path()))?;
+
+        self.add_method(MethodInfo {
+            access_flags,
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+            attributes: vec![MethodAttribute::Code(code)],
+        });
+
+        Ok(())
+    }
+
+    /// Adds a constructor without parameters that calls that of the super class, like the one
+    /// `javac` generates for classes without constructors.
+    pub fn add_default_constructor(&mut self, access_flags: u16) -> JomResult<()> {
+        if self.class.access_flags & ACC_INTERFACE != 0 {
+            return Err(JomError::invalid_class("interfaces have no constructors"));
+        }
+
+        let mut code = CodeBuilder::new();
+        code.aload(0);
+        let super_class = self.class.super_class.clone();
+        code.invoke_special(&super_class, "<init>", "()V");
+        code.emit(Instruction::Return);
+
+        self.add_code_method(access_flags, "<init>", "()V", code)
+    }
+
+    /// The class built so far.
+    pub fn class_mut(&mut self) -> &mut ClassFile {
+        &mut self.class
+    }
+
+    /// Checks that no field or method is declared twice and that interfaces are abstract,
+    /// then computes the stack map frames of code that needs them for the version, with
+    /// [`ClassFile::compute_frames`].
+    pub fn finish(self) -> JomResult<ClassFile> {
+        let Self {
+            mut class,
+            mut hierarchy,
+        } = self;
+        let error = |message: String| Err(JomError::invalid_class(message));
+
+        let mut fields = HashSet::new();
+        for field in &class.fields {
+            if !fields.insert((&field.name, &field.descriptor)) {
+                return error(format!(
+                    "duplicate field {} {}",
+                    field.name, field.descriptor
+                ));
+            }
+        }
+        let mut methods = HashSet::new();
+        for method in &class.methods {
+            if !methods.insert((&method.name, &method.descriptor)) {
+                return error(format!(
+                    "duplicate method {}{}",
+                    method.name, method.descriptor
+                ));
+            }
+        }
+        if class.access_flags & ACC_INTERFACE != 0 && class.access_flags & ACC_ABSTRACT == 0 {
+            return error("interfaces must be abstract".to_owned());
+        }
+        if !class.version().allows(&Feature::MissingStackMapTable) {
+            hierarchy.add(&class);
+            class.compute_frames(&hierarchy)?;
+        }
+
+        Ok(class)
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::Cursor;
+
+use binrw::BinRead;
+
+use crate::error::{JomError, JomResult};
+
+/// Reads big-endian values and slices from the class file, without copying.
+pub(crate) struct Reader<'a> {
+    pub(crate) cursor: Cursor<&'a [u8]>,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8], position: usize) -> Self {
+        let mut cursor = Cursor::new(data);
+        cursor.set_position(position as u64);
+
+        Self { cursor }
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.cursor.position() as usize
+    }
+
+    pub(crate) fn u8(&mut self) -> JomResult<u8> {
+        Ok(<u8 as BinRead>::read_be(&mut self.cursor)?)
+    }
+
+    pub(crate) fn u16(&mut self) -> JomResult<u16> {
+        Ok(<u16 as BinRead>::read_be(&mut self.cursor)?)
+    }
+
+    pub(crate) fn u32(&mut self) -> JomResult<u32> {
+        Ok(<u32 as BinRead>::read_be(&mut self.cursor)?)
+    }
+
+    pub(crate) fn bytes(&mut self, length: usize) -> JomResult<&'a [u8]> {
+        let data = *self.cursor.get_ref();
+        let start = self.position();
+        let bytes = data
+            .get(start..start + length)
+            .ok_or(JomError::Truncated)?;
+        self.cursor.set_position((start + length) as u64);
+
+        Ok(bytes)
+    }
+
+    pub(crate) fn skip_attributes(&mut self) -> JomResult<()> {
+        for _ in 0..self.u16()? {
+            self.u16()?;
+            let length = self.u32()?;
+            self.bytes(length as usize)?;
+        }
+
+        Ok(())
+    }
+
+    /// Skips a field or method table.
+    pub(crate) fn skip_members(&mut self) -> JomResult<()> {
+        for _ in 0..self.u16()? {
+            self.bytes(6)?;
+            self.skip_attributes()?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,543 @@
+//! Whole-program call graphs, built from the invoke instructions reachable from a set of
+//! entry points.
+//!
+//! Virtual and interface calls are dispatched with class hierarchy analysis (CHA), to every
+//! concrete subtype of the receiver's static type, or rapid type analysis (RTA), which
+//! only considers the classes instantiated in reachable code.
+//!
+//! Methods outside the given classes are nodes of the graph but have no calls of their own.
+//! An `invokedynamic` calls its bootstrap method and the method handles among its bootstrap
+//! arguments, which covers lambdas and method references.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::{self, Write},
+};
+
+use crate::{
+    access::{ACC_ABSTRACT, ACC_PUBLIC, ACC_STATIC},
+    attribute::bootstrap_methods,
+    constant_pool::{ConstantPool, ConstantPoolIndex, MethodHandleReferenceKind},
+    error::{JomError, JomResult},
+    hierarchy::ClassHierarchy,
+    method::code::instruction::Instruction,
+    ClassFile,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MethodRef {
+    pub class: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+impl MethodRef {
+    pub fn new(
+        class: impl Into<String>,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+    ) -> Self {
+        Self {
+            class: class.into(),
+            name: name.into(),
+            descriptor: descriptor.into(),
+        }
+    }
+}
+
+impl fmt::Display for MethodRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}{}", self.class, self.name, self.descriptor)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CallKind {
+    Virtual,
+    Special,
+    Static,
+    Interface,
+    Dynamic,
+    /// The implicit call of a static initializer when a class is first used.
+    Initializer,
+}
+
+impl CallKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Virtual => "virtual",
+            Self::Special => "special",
+            Self::Static => "static",
+            Self::Interface => "interface",
+            Self::Dynamic => "dynamic",
+            Self::Initializer => "initializer",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    /// Class hierarchy analysis.
+    Cha,
+    /// Rapid type analysis.
+    Rta,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CallEdge {
+    pub caller: MethodRef,
+    pub callee: MethodRef,
+    pub kind: CallKind,
+}
+
+pub struct CallGraph {
+    reachable: BTreeSet<MethodRef>,
+    edges: BTreeSet<CallEdge>,
+}
+
+impl CallGraph {
+    pub fn build(
+        classes: &[ClassFile],
+        entry_points: &[MethodRef],
+        precision: Precision,
+    ) -> JomResult<Self> {
+        let mut builder = Builder {
+            hierarchy: ClassHierarchy::from_classes(classes),
+            classes: classes.iter().map(|x| (x.this_class(), x)).collect(),
+            precision,
+            graph: Self {
+                reachable: BTreeSet::new(),
+                edges: BTreeSet::new(),
+            },
+            worklist: VecDeque::new(),
+            instantiated: HashSet::new(),
+            new_types: vec![],
+            virtual_calls: HashMap::new(),
+        };
+
+        for entry_point in entry_points {
+            if builder
+                .hierarchy
+                .method_access_flags(
+                    &entry_point.class,
+                    &entry_point.name,
+                    &entry_point.descriptor,
+                )
+                .is_some_and(|x| x & ACC_STATIC == 0)
+            {
+                builder.instantiated.insert(entry_point.class.clone());
+            }
+            builder.reach(entry_point.clone());
+        }
+
+        loop {
+            while let Some(method) = builder.worklist.pop_front() {
+                builder.visit(&method)?;
+            }
+
+            // With RTA, classes instantiated later can add targets to earlier calls.
+            for class in std::mem::take(&mut builder.new_types) {
+                builder.dispatch_new(&class);
+            }
+            if builder.worklist.is_empty() {
+                break;
+            }
+        }
+
+        Ok(builder.graph)
+    }
+
+    /// The `public static void main(String[])` methods of the classes.
+    pub fn main_methods(classes: &[ClassFile]) -> Vec<MethodRef> {
+        classes
+            .iter()
+            .flat_map(|class| {
+                class
+                    .methods()
+                    .iter()
+                    .filter(|x| {
+                        x.name == "main"
+                            && x.descriptor == "([Ljava/lang/String;)V"
+                            && x.access_flags & (ACC_PUBLIC | ACC_STATIC) == ACC_PUBLIC | ACC_STATIC
+                    })
+                    .map(|x| MethodRef::new(class.this_class(), &x.name, &x.descriptor))
+            })
+            .collect()
+    }
+
+    /// The entry points and every method called from them.
+    pub fn reachable(&self) -> &BTreeSet<MethodRef> {
+        &self.reachable
+    }
+
+    pub fn is_reachable(&self, method: &MethodRef) -> bool {
+        self.reachable.contains(method)
+    }
+
+    /// The methods declared by the classes that aren't reachable.
+    pub fn unreachable(&self, classes: &[ClassFile]) -> Vec<MethodRef> {
+        classes
+            .iter()
+            .flat_map(|class| {
+                class
+                    .methods()
+                    .iter()
+                    .map(|x| MethodRef::new(class.this_class(), &x.name, &x.descriptor))
+            })
+            .filter(|x| !self.reachable.contains(x))
+            .collect()
+    }
+
+    pub fn edges(&self) -> &BTreeSet<CallEdge> {
+        &self.edges
+    }
+
+    pub fn callees<'a>(&'a self, method: &'a MethodRef) -> impl Iterator<Item = &'a CallEdge> {
+        self.edges.iter().filter(move |x| &x.caller == method)
+    }
+
+    pub fn callers<'a>(&'a self, method: &'a MethodRef) -> impl Iterator<Item = &'a CallEdge> {
+        self.edges.iter().filter(move |x| &x.callee == method)
+    }
+
+    /// Graphviz source with an edge per call, labelled with the kind of call.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph calls {\n");
+
+        for method in &self.reachable {
+            writeln!(dot, "    {};", quote(&method.to_string())).unwrap();
+        }
+        for edge in &self.edges {
+            writeln!(
+                dot,
+                "    {} -> {} [label={}];",
+                quote(&edge.caller.to_string()),
+                quote(&edge.callee.to_string()),
+                quote(edge.kind.name())
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    /// JSON of the form `{"methods": [...], "edges": [{"caller", "callee", "kind"}, ...]}`,
+    /// with methods formatted like `java/lang/Object.toString()Ljava/lang/String;`.
+    pub fn to_json(&self) -> String {
+        let methods = self
+            .reachable
+            .iter()
+            .map(|x| quote(&x.to_string()))
+            .collect::<Vec<_>>();
+        let edges = self
+            .edges
+            .iter()
+            .map(|x| {
+                format!(
+                    "{{\"caller\": {}, \"callee\": {}, \"kind\": {}}}",
+                    quote(&x.caller.to_string()),
+                    quote(&x.callee.to_string()),
+                    quote(x.kind.name())
+                )
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\"methods\": [{}], \"edges\": [{}]}}",
+            methods.join(", "),
+            edges.join(", ")
+        )
+    }
+}
+
+struct Builder<'a> {
+    hierarchy: ClassHierarchy,
+    classes: HashMap<&'a str, &'a ClassFile>,
+    precision: Precision,
+    graph: CallGraph,
+    worklist: VecDeque<MethodRef>,
+    instantiated: HashSet<String>,
+    /// With RTA, the classes instantiated since calls were last dispatched to them.
+    new_types: Vec<String>,
+    /// With RTA, the virtual and interface calls seen so far by the class of their target,
+    /// to dispatch to the classes instantiated later.
+    virtual_calls: HashMap<String, Vec<(MethodRef, MethodRef, CallKind)>>,
+}
+
+impl Builder<'_> {
+    fn reach(&mut self, method: MethodRef) {
+        if self.graph.reachable.insert(method.clone()) {
+            self.worklist.push_back(method);
+        }
+    }
+
+    fn call(&mut self, caller: &MethodRef, callee: MethodRef, kind: CallKind) {
+        self.graph.edges.insert(CallEdge {
+            caller: caller.clone(),
+            callee: callee.clone(),
+            kind,
+        });
+        self.reach(callee);
+    }
+
+    fn visit(&mut self, method: &MethodRef) -> JomResult<()> {
+        let Some(&class) = self.classes.get(method.class.as_str()) else {
+            return Ok(());
+        };
+        let Some(code) = class
+            .methods()
+            .iter()
+            .find(|x| x.name == method.name && x.descriptor == method.descriptor)
+            .and_then(|x| x.code())
+        else {
+            return Ok(());
+        };
+        let cp = class.constant_pool();
+        let bootstrap_methods = bootstrap_methods(class.attributes())?;
+
+        for instruction in &code.code {
+            match instruction {
+                Instruction::InvokeStatic(index) => {
+                    let target = method_ref(cp, *index)?;
+                    self.initialize(method, &target.class);
+                    let target = self.resolve(target);
+                    self.call(method, target, CallKind::Static);
+                }
+                Instruction::InvokeSpecial(index) => {
+                    let target = self.resolve(method_ref(cp, *index)?);
+                    self.call(method, target, CallKind::Special);
+                }
+                Instruction::InvokeVirtual(index) => {
+                    let target = method_ref(cp, *index)?;
+                    self.virtual_call(method, target, CallKind::Virtual);
+                }
+                Instruction::InvokeInterface(index, _) => {
+                    let target = method_ref(cp, *index)?;
+                    self.virtual_call(method, target, CallKind::Interface);
+                }
+                Instruction::InvokeDynamic(index) => {
+                    let index = cp
+                        .get(*index)?
+                        .into_invoke_dynamic()?
+                        .bootstrap_method_attr_index;
+                    let Some((handle, arguments)) = bootstrap_methods.get(index as usize) else {
+                        return Err(JomError::invalid_class(format!(
+                            "bootstrap method {index} does not exist"
+                        )));
+                    };
+                    for index in std::iter::once(handle).chain(arguments) {
+                        if let ConstantPoolIndex::MethodHandle {
+                            kind,
+                            class,
+                            name,
+                            descriptor,
+                        } = cp.get(*index)?
+                        {
+                            let target = MethodRef::new(class, name, descriptor);
+                            self.method_handle(method, kind, target);
+                        }
+                    }
+                }
+                Instruction::New(index) => {
+                    let class = cp.get_class(*index)?;
+                    self.instantiate(method, &class);
+                }
+                Instruction::GetStatic(index) | Instruction::PutStatic(index) => {
+                    let field = cp.get(*index)?.into_fieldref()?;
+                    self.initialize(method, &field.class);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The declaration a non-virtual call resolves to, or the target itself if it isn't
+    /// declared in the classes.
+    fn resolve(&self, target: MethodRef) -> MethodRef {
+        match self
+            .hierarchy
+            .resolve_method(&target.class, &target.name, &target.descriptor)
+        {
+            Some(class) => MethodRef::new(class, target.name, target.descriptor),
+            None => target,
+        }
+    }
+
+    /// The call made by invoking a method handle, which is resolved like the instruction of
+    /// its kind. Handles of fields make no calls.
+    fn method_handle(
+        &mut self,
+        caller: &MethodRef,
+        kind: MethodHandleReferenceKind,
+        target: MethodRef,
+    ) {
+        match kind {
+            MethodHandleReferenceKind::InvokeStatic => {
+                self.initialize(caller, &target.class);
+                let target = self.resolve(target);
+                self.call(caller, target, CallKind::Dynamic);
+            }
+            MethodHandleReferenceKind::InvokeSpecial => {
+                let target = self.resolve(target);
+                self.call(caller, target, CallKind::Dynamic);
+            }
+            MethodHandleReferenceKind::NewInvokeSpecial => {
+                self.instantiate(caller, &target.class);
+                let target = self.resolve(target);
+                self.call(caller, target, CallKind::Dynamic);
+            }
+            MethodHandleReferenceKind::InvokeVirtual
+            | MethodHandleReferenceKind::InvokeInterface => {
+                self.virtual_call(caller, target, CallKind::Dynamic);
+            }
+            _ => {}
+        }
+    }
+
+    fn virtual_call(&mut self, caller: &MethodRef, target: MethodRef, kind: CallKind) {
+        self.dispatch(caller, &target, kind);
+        if self.precision == Precision::Rta {
+            self.virtual_calls
+                .entry(target.class.clone())
+                .or_default()
+                .push((caller.clone(), target, kind));
+        }
+    }
+
+    fn dispatch(&mut self, caller: &MethodRef, target: &MethodRef, kind: CallKind) {
+        // Receivers outside the classes may run library code.
+        if !self.hierarchy.contains(&target.class) {
+            self.call(caller, target.clone(), kind);
+        }
+
+        let receivers = std::iter::once(target.class.as_str())
+            .filter(|x| self.hierarchy.contains(x))
+            .chain(self.hierarchy.subtypes(&target.class))
+            .filter(|x| !self.hierarchy.is_abstract(x))
+            .filter(|x| self.precision == Precision::Cha || self.instantiated.contains(*x))
+            .collect::<Vec<_>>();
+
+        let callees = receivers
+            .into_iter()
+            .filter_map(|x| self.callee(x, target))
+            .collect::<BTreeSet<_>>();
+        for callee in callees {
+            self.call(caller, callee, kind);
+        }
+    }
+
+    /// Dispatches the calls seen so far to a newly instantiated class, through the calls
+    /// on each of its supertypes.
+    fn dispatch_new(&mut self, class: &str) {
+        if !self.hierarchy.contains(class) || self.hierarchy.is_abstract(class) {
+            return;
+        }
+
+        let mut calls = vec![];
+        for supertype in std::iter::once(class).chain(self.hierarchy.supertypes(class)) {
+            calls.extend(
+                self.virtual_calls
+                    .get(supertype)
+                    .into_iter()
+                    .flatten()
+                    .cloned(),
+            );
+        }
+        for (caller, target, kind) in calls {
+            if let Some(callee) = self.callee(class, &target) {
+                self.call(&caller, callee, kind);
+            }
+        }
+    }
+
+    /// The method a call of `target` runs on an instance of `receiver`, or `None` if it is
+    /// abstract there.
+    fn callee(&self, receiver: &str, target: &MethodRef) -> Option<MethodRef> {
+        match self
+            .hierarchy
+            .resolve_method(receiver, &target.name, &target.descriptor)
+        {
+            Some(class) => {
+                let flags = self
+                    .hierarchy
+                    .method_access_flags(class, &target.name, &target.descriptor)
+                    .unwrap_or(0);
+                (flags & ACC_ABSTRACT == 0)
+                    .then(|| MethodRef::new(class, &target.name, &target.descriptor))
+            }
+            // Inherited from a class outside the hierarchy.
+            None => Some(target.clone()),
+        }
+    }
+
+    fn instantiate(&mut self, caller: &MethodRef, class: &str) {
+        self.initialize(caller, class);
+        if self.instantiated.insert(class.to_owned()) && self.precision == Precision::Rta {
+            self.new_types.push(class.to_owned());
+        }
+    }
+
+    /// Calls the static initializers run by the first use of a class, except those of the
+    /// caller's own class and superclasses, which have already run.
+    fn initialize(&mut self, caller: &MethodRef, class: &str) {
+        let initialized = self.hierarchy.superclasses(&caller.class);
+        let classes = std::iter::once(class)
+            .chain(self.hierarchy.superclasses(class))
+            .filter(|&x| x != caller.class && !initialized.contains(&x))
+            .filter(|x| {
+                self.hierarchy
+                    .method_access_flags(x, "<clinit>", "()V")
+                    .is_some()
+            })
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+
+        for class in classes {
+            self.call(
+                caller,
+                MethodRef::new(class, "<clinit>", "()V"),
+                CallKind::Initializer,
+            );
+        }
+    }
+}
+
+fn method_ref(cp: &ConstantPool, index: u16) -> JomResult<MethodRef> {
+    match cp.get(index)? {
+        ConstantPoolIndex::Methodref {
+            class,
+            name,
+            descriptor,
+        }
+        | ConstantPoolIndex::InterfaceMethodref {
+            class,
+            name,
+            descriptor,
+        } => Ok(MethodRef::new(class, name, descriptor)),
+        x => Err(JomError::new_cp_index(
+            "Methodref or InterfaceMethodref",
+            x.name(),
+        )),
+    }
+}
+
+/// A quoted string, escaped for both DOT and JSON.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
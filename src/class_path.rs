@@ -0,0 +1,205 @@
+//! Resolving classes by internal name from directories, JARs and JDK images.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    error::{JomError, JomResult},
+    jar::Jar,
+    jimage::JImage,
+    jmod::Jmod,
+    ClassFile,
+};
+
+/// A source of classes on a [`ClassPath`].
+pub enum ClassPathEntry {
+    /// A directory of class files laid out by package, like `javac -d` output.
+    Directory(PathBuf),
+    Jar(Jar),
+    Jmod(Jmod),
+    JImage(JImage),
+}
+
+impl ClassPathEntry {
+    /// Opens a directory, a `.jar`, `.zip` or `.jmod` file, a jimage `modules` file, or a
+    /// JDK home, which resolves to its `lib/modules` image.
+    pub fn open(path: impl AsRef<Path>) -> JomResult<Self> {
+        let path = path.as_ref();
+
+        if path.is_dir() {
+            let modules = path.join("lib").join("modules");
+            return match modules.is_file() {
+                true => Self::open(modules),
+                false => Ok(Self::Directory(path.to_owned())),
+            };
+        }
+
+        let read = || fs::read(path).map_err(|e| JomError::io(path, e));
+        let extension = path.extension().and_then(|x| x.to_str()).unwrap_or("");
+
+        match extension.to_ascii_lowercase().as_str() {
+            "jar" | "zip" => Ok(Self::Jar(Jar::read(&read()?)?)),
+            "jmod" => Ok(Self::Jmod(Jmod::read(&read()?)?)),
+            "" if path.file_name().is_some_and(|x| x == "modules") => {
+                Ok(Self::JImage(JImage::read(read()?)?))
+            }
+            _ => Err(JomError::invalid_archive(format!(
+                "{}: unsupported class path entry",
+                path.display()
+            ))),
+        }
+    }
+
+    fn find_class(&self, name: &str, release: Option<u16>) -> JomResult<Option<ClassFile>> {
+        match self {
+            Self::Directory(path) => {
+                let path = path.join(format!("{name}.class"));
+                match fs::read(&path) {
+                    Ok(bytes) => ClassFile::read(&bytes).map(Some),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => Err(JomError::io(path, e)),
+                }
+            }
+            Self::Jar(jar) => jar
+                .find_class(name, release)
+                .map(|x| x.class_file())
+                .transpose(),
+            Self::Jmod(jmod) => jmod.find_class(name).map(|x| x.class_file()).transpose(),
+            Self::JImage(image) => image.find_class(name),
+        }
+    }
+
+    fn class_names(&self, names: &mut BTreeSet<String>) -> JomResult<()> {
+        match self {
+            Self::Directory(path) => directory_class_names(path, "", names)?,
+            Self::Jar(jar) => names.extend(
+                jar.classes()
+                    .filter_map(|x| x.class_name())
+                    .filter(|&x| x != "module-info")
+                    .map(str::to_owned),
+            ),
+            Self::Jmod(jmod) => names.extend(jmod.class_names().map(str::to_owned)),
+            Self::JImage(image) => names.extend(image.class_names()?),
+        }
+
+        Ok(())
+    }
+}
+
+/// An ordered list of class sources, where the first entry containing a class wins.
+///
+/// Resolved classes are cached, including classes that weren't found, and shared between
+/// threads.
+#[derive(Default)]
+pub struct ClassPath {
+    entries: Vec<ClassPathEntry>,
+    /// The Java release used to select entries of multi-release JARs.
+    release: Option<u16>,
+    cache: RwLock<HashMap<String, Option<Arc<ClassFile>>>>,
+}
+
+impl ClassPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens every path with [`ClassPathEntry::open`].
+    pub fn from_paths<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> JomResult<Self> {
+        let mut class_path = Self::new();
+        for path in paths {
+            class_path.push(ClassPathEntry::open(path)?);
+        }
+
+        Ok(class_path)
+    }
+
+    /// Appends an entry, searched after the existing entries.
+    pub fn push(&mut self, entry: ClassPathEntry) {
+        self.entries.push(entry);
+        self.clear_cache();
+    }
+
+    pub fn entries(&self) -> &[ClassPathEntry] {
+        &self.entries
+    }
+
+    pub fn release(&self) -> Option<u16> {
+        self.release
+    }
+
+    pub fn set_release(&mut self, release: Option<u16>) {
+        self.release = release;
+        self.clear_cache();
+    }
+
+    /// Resolves a class by internal name, like `java/lang/Object`.
+    pub fn resolve(&self, name: &str) -> JomResult<Option<Arc<ClassFile>>> {
+        if let Some(class) = self.cache.read().unwrap().get(name) {
+            return Ok(class.clone());
+        }
+
+        let mut class = None;
+        for entry in &self.entries {
+            if let Some(x) = entry.find_class(name, self.release)? {
+                class = Some(Arc::new(x));
+                break;
+            }
+        }
+
+        // Another thread may have resolved the class meanwhile, in which case its result
+        // is kept so that every caller shares the same instance.
+        Ok(self
+            .cache
+            .write()
+            .unwrap()
+            .entry(name.to_owned())
+            .or_insert(class)
+            .clone())
+    }
+
+    pub fn contains(&self, name: &str) -> JomResult<bool> {
+        Ok(self.resolve(name)?.is_some())
+    }
+
+    /// The internal names of every class on the class path, sorted and without duplicates.
+    pub fn class_names(&self) -> JomResult<Vec<String>> {
+        let mut names = BTreeSet::new();
+        for entry in &self.entries {
+            entry.class_names(&mut names)?;
+        }
+
+        Ok(names.into_iter().collect())
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.write().unwrap().clear();
+    }
+}
+
+fn directory_class_names(root: &Path, prefix: &str, names: &mut BTreeSet<String>) -> JomResult<()> {
+    let path = root.join(prefix);
+    let entries = fs::read_dir(&path).map_err(|e| JomError::io(&path, e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| JomError::io(&path, e))?;
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        let name = format!("{prefix}{file_name}");
+
+        if entry.path().is_dir() {
+            directory_class_names(root, &format!("{name}/"), names)?;
+        } else if let Some(class) = name.strip_suffix(".class") {
+            if class != "module-info" {
+                names.insert(class.to_owned());
+            }
+        }
+    }
+
+    Ok(())
+}
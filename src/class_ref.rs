@@ -0,0 +1,735 @@
+//! A borrowed view of a class file, for reading parts of many classes cheaply.
+//!
+//! [`ClassFileRef::read`] checks the structure of a class without decoding it. Constant pool
+//! entries are resolved by index when they are asked for, and strings borrow from the input
+//! unless they have to be converted from modified UTF-8. Members, attributes and code are
+//! decoded on demand.
+
+use std::{borrow::Cow, io::Cursor};
+
+use binrw::BinRead;
+
+use crate::{
+    bytes::Reader,
+    check_header,
+    constant_pool::{ConstantPool, ConstantPoolIndex, MethodHandleReferenceKind},
+    error::{ErrorPath, JomError, JomResult},
+    method::code::{instruction::Instruction, instruction_error, Exception},
+    utf8::ModifiedUtf8,
+    ClassFile,
+};
+
+const UTF8: u8 = 1;
+const INTEGER: u8 = 3;
+const FLOAT: u8 = 4;
+const LONG: u8 = 5;
+const DOUBLE: u8 = 6;
+const CLASS: u8 = 7;
+const STRING: u8 = 8;
+const FIELDREF: u8 = 9;
+const METHODREF: u8 = 10;
+const INTERFACE_METHODREF: u8 = 11;
+const NAME_AND_TYPE: u8 = 12;
+const METHOD_HANDLE: u8 = 15;
+const METHOD_TYPE: u8 = 16;
+const DYNAMIC: u8 = 17;
+const INVOKE_DYNAMIC: u8 = 18;
+const MODULE: u8 = 19;
+const PACKAGE: u8 = 20;
+
+/// A class file that borrows the bytes it was read from.
+pub struct ClassFileRef<'a> {
+    data: &'a [u8],
+    minor: u16,
+    major: u16,
+    /// The offset of each constant pool entry's tag, with zero for the unusable slots.
+    constant_pool: Vec<u32>,
+    /// The offset of the access flags, right after the constant pool.
+    header: usize,
+    fields: usize,
+    methods: usize,
+    attributes: usize,
+}
+
+impl<'a> ClassFileRef<'a> {
+    /// Checks the structure of the class file and records where each part of it starts.
+    pub fn read(data: &'a [u8]) -> JomResult<Self> {
+        Self::read_parts(data).map_err(|e| e.truncated_at(data.len()))
+    }
+
+    fn read_parts(data: &'a [u8]) -> JomResult<Self> {
+        let (minor, major) = check_header(data)?;
+        let mut reader = Reader::new(data, 8);
+
+        let count = reader.u16()? as usize;
+        let mut constant_pool = Vec::with_capacity(count);
+        constant_pool.push(0);
+        while constant_pool.len() < count {
+            let position = reader.position();
+            constant_pool.push(position as u32);
+
+            let length = match reader.u8()? {
+                UTF8 => reader.u16()? as usize,
+                CLASS | STRING | METHOD_TYPE | MODULE | PACKAGE => 2,
+                METHOD_HANDLE => 3,
+                INTEGER | FLOAT | FIELDREF | METHODREF | INTERFACE_METHODREF | NAME_AND_TYPE
+                | DYNAMIC | INVOKE_DYNAMIC => 4,
+                LONG | DOUBLE => {
+                    constant_pool.push(0);
+                    8
+                }
+                _ => {
+                    return Err(binrw::Error::NoVariantMatch {
+                        pos: position as u64,
+                    }
+                    .into())
+                }
+            };
+            reader.bytes(length)?;
+        }
+
+        let header = reader.position();
+        reader.bytes(6)?;
+        let interfaces = reader.u16()?;
+        reader.bytes(2 * interfaces as usize)?;
+        let fields = reader.position();
+        reader.skip_members()?;
+        let methods = reader.position();
+        reader.skip_members()?;
+        let attributes = reader.position();
+        reader.skip_attributes()?;
+
+        Ok(Self {
+            data,
+            minor,
+            major,
+            constant_pool,
+            header,
+            fields,
+            methods,
+            attributes,
+        })
+    }
+
+    /// Decodes the whole class, as [`ClassFile::read`] does.
+    pub fn to_class_file(&self) -> JomResult<ClassFile> {
+        ClassFile::read(self.data)
+    }
+
+    pub fn minor(&self) -> u16 {
+        self.minor
+    }
+
+    pub fn major(&self) -> u16 {
+        self.major
+    }
+
+    pub fn access_flags(&self) -> u16 {
+        self.u16_at(self.header)
+    }
+
+    pub fn this_class(&self) -> JomResult<Cow<'a, str>> {
+        self.get_class(self.u16_at(self.header + 2))
+    }
+
+    /// Empty for `java/lang/Object` and modules.
+    pub fn super_class(&self) -> JomResult<Cow<'a, str>> {
+        match self.u16_at(self.header + 4) {
+            0 => Ok(Cow::Borrowed("")),
+            index => self.get_class(index),
+        }
+    }
+
+    pub fn interfaces(&self) -> impl ExactSizeIterator<Item = JomResult<Cow<'a, str>>> + '_ {
+        let count = self.u16_at(self.header + 6) as usize;
+
+        (0..count).map(move |i| self.get_class(self.u16_at(self.header + 8 + 2 * i)))
+    }
+
+    pub fn fields(&self) -> Members<'_> {
+        Members::new(self, self.fields)
+    }
+
+    pub fn methods(&self) -> Members<'_> {
+        Members::new(self, self.methods)
+    }
+
+    pub fn attributes(&self) -> Attributes<'_> {
+        Attributes::new(self, self.data, self.attributes)
+    }
+
+    /// The number of constant pool slots, including the unusable ones.
+    pub fn constant_pool_len(&self) -> usize {
+        self.constant_pool.len()
+    }
+
+    /// Resolves every constant pool entry, as [`ClassFile::read`] does.
+    pub fn constant_pool(&self) -> JomResult<ConstantPool> {
+        (0..self.constant_pool.len() as u16)
+            .map(|i| self.get(i).map(ConstantRef::into_owned))
+            .collect::<JomResult<_>>()
+            .map(ConstantPool)
+    }
+
+    /// Resolves a constant pool entry, following its references to other entries.
+    pub fn get(&self, index: u16) -> JomResult<ConstantRef<'a>> {
+        let (tag, mut reader) = self.entry(index)?;
+
+        Ok(match tag {
+            UTF8 => match self.utf8_units(index)? {
+                Ok(x) => ConstantRef::Utf8(x),
+                Err(x) => ConstantRef::Utf8Units(x),
+            },
+            INTEGER => ConstantRef::Integer(reader.u32()? as i32),
+            FLOAT => ConstantRef::Float(f32::from_bits(reader.u32()?)),
+            LONG => ConstantRef::Long(((reader.u32()? as u64) << 32 | reader.u32()? as u64) as i64),
+            DOUBLE => ConstantRef::Double(f64::from_bits(
+                (reader.u32()? as u64) << 32 | reader.u32()? as u64,
+            )),
+            CLASS => ConstantRef::Class(self.get_utf8(reader.u16()?)?),
+            STRING => match self.utf8_units(reader.u16()?)? {
+                Ok(x) => ConstantRef::String(x),
+                Err(x) => ConstantRef::StringUnits(x),
+            },
+            FIELDREF => {
+                let (class, name, descriptor) = self.reference(reader)?;
+                ConstantRef::Fieldref {
+                    class,
+                    name,
+                    descriptor,
+                }
+            }
+            METHODREF => {
+                let (class, name, descriptor) = self.reference(reader)?;
+                ConstantRef::Methodref {
+                    class,
+                    name,
+                    descriptor,
+                }
+            }
+            INTERFACE_METHODREF => {
+                let (class, name, descriptor) = self.reference(reader)?;
+                ConstantRef::InterfaceMethodref {
+                    class,
+                    name,
+                    descriptor,
+                }
+            }
+            NAME_AND_TYPE => {
+                let (name, descriptor) = self.name_and_type(index)?;
+                ConstantRef::NameAndType(name, descriptor)
+            }
+            METHOD_HANDLE => {
+                let kind = MethodHandleReferenceKind::read_be(&mut reader.cursor)?;
+                let expected: &[u8] = match kind {
+                    MethodHandleReferenceKind::GetField
+                    | MethodHandleReferenceKind::GetStatic
+                    | MethodHandleReferenceKind::PutField
+                    | MethodHandleReferenceKind::PutStatic => &[FIELDREF],
+                    MethodHandleReferenceKind::InvokeVirtual
+                    | MethodHandleReferenceKind::NewInvokeSpecial => &[METHODREF],
+                    MethodHandleReferenceKind::InvokeStatic
+                    | MethodHandleReferenceKind::InvokeSpecial => &[METHODREF, INTERFACE_METHODREF],
+                    MethodHandleReferenceKind::InvokeInterface => &[INTERFACE_METHODREF],
+                };
+
+                let (found, reference) = self.entry(reader.u16()?)?;
+                if !expected.contains(&found) {
+                    return Err(JomError::new_cp_index(
+                        tag_name(expected[expected.len() - 1]),
+                        tag_name(found),
+                    ));
+                }
+                let (class, name, descriptor) = self.reference(reference)?;
+                ConstantRef::MethodHandle {
+                    kind,
+                    class,
+                    name,
+                    descriptor,
+                }
+            }
+            METHOD_TYPE => ConstantRef::MethodType(self.get_utf8(reader.u16()?)?),
+            DYNAMIC => {
+                let bootstrap_method_attr_index = reader.u16()?;
+                let (name, descriptor) = self.name_and_type(reader.u16()?)?;
+                ConstantRef::Dynamic {
+                    bootstrap_method_attr_index,
+                    name,
+                    descriptor,
+                }
+            }
+            INVOKE_DYNAMIC => {
+                let bootstrap_method_attr_index = reader.u16()?;
+                let (name, descriptor) = self.name_and_type(reader.u16()?)?;
+                ConstantRef::InvokeDynamic {
+                    bootstrap_method_attr_index,
+                    name,
+                    descriptor,
+                }
+            }
+            MODULE => ConstantRef::Module(self.get_utf8(reader.u16()?)?),
+            PACKAGE => ConstantRef::Package(self.get_utf8(reader.u16()?)?),
+            _ => ConstantRef::Unusable,
+        })
+    }
+
+    /// Fails with [`JomError::InvalidUtf8`] for a string with unpaired surrogates, which
+    /// [`Self::get`] returns as a [`ConstantRef::Utf8Units`] entry.
+    pub fn get_utf8(&self, index: u16) -> JomResult<Cow<'a, str>> {
+        self.utf8_units(index)?.map_err(|_| {
+            // The position of the length, after the tag, like for invalid modified UTF-8.
+            JomError::InvalidUtf8.at(self.constant_pool[index as usize] as u64 + 1)
+        })
+    }
+
+    /// Decodes a `Utf8` entry, or returns its UTF-16 code units if it has unpaired surrogates.
+    fn utf8_units(&self, index: u16) -> JomResult<Result<Cow<'a, str>, Vec<u16>>> {
+        let mut reader = self.expect(index, UTF8)?;
+        let position = reader.position();
+        let length = reader.u16()?;
+
+        ModifiedUtf8::decode_borrowed(reader.bytes(length as usize)?)
+            .ok_or_else(|| JomError::InvalidUtf8.at(position as u64))
+    }
+
+    pub fn get_class(&self, index: u16) -> JomResult<Cow<'a, str>> {
+        let mut reader = self.expect(index, CLASS)?;
+        self.get_utf8(reader.u16()?)
+    }
+
+    fn name_and_type(&self, index: u16) -> JomResult<(Cow<'a, str>, Cow<'a, str>)> {
+        let mut reader = self.expect(index, NAME_AND_TYPE)?;
+        let name = self.get_utf8(reader.u16()?)?;
+        let descriptor = self.get_utf8(reader.u16()?)?;
+
+        Ok((name, descriptor))
+    }
+
+    /// Resolves the class, name and descriptor of a field or method reference.
+    fn reference(
+        &self,
+        mut reader: Reader<'a>,
+    ) -> JomResult<(Cow<'a, str>, Cow<'a, str>, Cow<'a, str>)> {
+        let class = self.get_class(reader.u16()?)?;
+        let (name, descriptor) = self.name_and_type(reader.u16()?)?;
+
+        Ok((class, name, descriptor))
+    }
+
+    /// Returns the tag of an entry, which is zero for unusable slots, and a reader after it.
+    fn entry(&self, index: u16) -> JomResult<(u8, Reader<'a>)> {
+        let position = *self
+            .constant_pool
+            .get(index as usize)
+            .ok_or(JomError::out_of_bounds(index))? as usize;
+
+        Ok(match position {
+            0 => (0, Reader::new(self.data, 0)),
+            _ => (self.data[position], Reader::new(self.data, position + 1)),
+        })
+    }
+
+    fn expect(&self, index: u16, tag: u8) -> JomResult<Reader<'a>> {
+        match self.entry(index)? {
+            (found, reader) if found == tag => Ok(reader),
+            (found, _) => Err(JomError::new_cp_index(tag_name(tag), tag_name(found))),
+        }
+    }
+
+    /// Reads a `u16` from a part of the class that [`ClassFileRef::read`] has checked.
+    fn u16_at(&self, position: usize) -> u16 {
+        u16::from_be_bytes([self.data[position], self.data[position + 1]])
+    }
+}
+
+fn tag_name(tag: u8) -> &'static str {
+    match tag {
+        UTF8 => "Utf8",
+        INTEGER => "Integer",
+        FLOAT => "Float",
+        LONG => "Long",
+        DOUBLE => "Double",
+        CLASS => "Class",
+        STRING => "String",
+        FIELDREF => "Fieldref",
+        METHODREF => "Methodref",
+        INTERFACE_METHODREF => "InterfaceMethodref",
+        NAME_AND_TYPE => "NameAndType",
+        METHOD_HANDLE => "MethodHandle",
+        METHOD_TYPE => "MethodType",
+        DYNAMIC => "Dynamic",
+        INVOKE_DYNAMIC => "InvokeDynamic",
+        MODULE => "Module",
+        PACKAGE => "Package",
+        _ => "Unusable",
+    }
+}
+
+/// A resolved constant pool entry, like [`ConstantPoolIndex`] but borrowing its strings.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstantRef<'a> {
+    Utf8(Cow<'a, str>),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(Cow<'a, str>),
+    String(Cow<'a, str>),
+    Fieldref {
+        class: Cow<'a, str>,
+        name: Cow<'a, str>,
+        descriptor: Cow<'a, str>,
+    },
+    Methodref {
+        class: Cow<'a, str>,
+        name: Cow<'a, str>,
+        descriptor: Cow<'a, str>,
+    },
+    InterfaceMethodref {
+        class: Cow<'a, str>,
+        name: Cow<'a, str>,
+        descriptor: Cow<'a, str>,
+    },
+    NameAndType(Cow<'a, str>, Cow<'a, str>),
+    MethodHandle {
+        kind: MethodHandleReferenceKind,
+        class: Cow<'a, str>,
+        name: Cow<'a, str>,
+        descriptor: Cow<'a, str>,
+    },
+    MethodType(Cow<'a, str>),
+    Dynamic {
+        bootstrap_method_attr_index: u16,
+        name: Cow<'a, str>,
+        descriptor: Cow<'a, str>,
+    },
+    InvokeDynamic {
+        bootstrap_method_attr_index: u16,
+        name: Cow<'a, str>,
+        descriptor: Cow<'a, str>,
+    },
+    Module(Cow<'a, str>),
+    Package(Cow<'a, str>),
+    /// A `Utf8` entry with unpaired surrogates, as its UTF-16 code units.
+    Utf8Units(Vec<u16>),
+    /// A `String` entry whose value has unpaired surrogates.
+    StringUnits(Vec<u16>),
+    Unusable,
+}
+
+impl ConstantRef<'_> {
+    pub fn into_owned(self) -> ConstantPoolIndex {
+        match self {
+            Self::Utf8(x) => ConstantPoolIndex::Utf8(x.into_owned()),
+            Self::Integer(x) => ConstantPoolIndex::Integer(x),
+            Self::Float(x) => ConstantPoolIndex::Float(x),
+            Self::Long(x) => ConstantPoolIndex::Long(x),
+            Self::Double(x) => ConstantPoolIndex::Double(x),
+            Self::Class(x) => ConstantPoolIndex::Class(x.into_owned()),
+            Self::String(x) => ConstantPoolIndex::String(x.into_owned()),
+            Self::Fieldref {
+                class,
+                name,
+                descriptor,
+            } => ConstantPoolIndex::Fieldref {
+                class: class.into_owned(),
+                name: name.into_owned(),
+                descriptor: descriptor.into_owned(),
+            },
+            Self::Methodref {
+                class,
+                name,
+                descriptor,
+            } => ConstantPoolIndex::Methodref {
+                class: class.into_owned(),
+                name: name.into_owned(),
+                descriptor: descriptor.into_owned(),
+            },
+            Self::InterfaceMethodref {
+                class,
+                name,
+                descriptor,
+            } => ConstantPoolIndex::InterfaceMethodref {
+                class: class.into_owned(),
+                name: name.into_owned(),
+                descriptor: descriptor.into_owned(),
+            },
+            Self::NameAndType(name, descriptor) => {
+                ConstantPoolIndex::NameAndType(name.into_owned(), descriptor.into_owned())
+            }
+            Self::MethodHandle {
+                kind,
+                class,
+                name,
+                descriptor,
+            } => ConstantPoolIndex::MethodHandle {
+                kind,
+                class: class.into_owned(),
+                name: name.into_owned(),
+                descriptor: descriptor.into_owned(),
+            },
+            Self::MethodType(x) => ConstantPoolIndex::MethodType(x.into_owned()),
+            Self::Dynamic {
+                bootstrap_method_attr_index,
+                name,
+                descriptor,
+            } => ConstantPoolIndex::Dynamic {
+                bootstrap_method_attr_index,
+                name: name.into_owned(),
+                descriptor: descriptor.into_owned(),
+            },
+            Self::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name,
+                descriptor,
+            } => ConstantPoolIndex::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name: name.into_owned(),
+                descriptor: descriptor.into_owned(),
+            },
+            Self::Module(x) => ConstantPoolIndex::Module(x.into_owned()),
+            Self::Package(x) => ConstantPoolIndex::Package(x.into_owned()),
+            Self::Utf8Units(x) => ConstantPoolIndex::Utf8Units(x),
+            Self::StringUnits(x) => ConstantPoolIndex::StringUnits(x),
+            Self::Unusable => ConstantPoolIndex::Unusable,
+        }
+    }
+}
+
+/// The fields or methods of a class.
+pub struct Members<'a> {
+    class: &'a ClassFileRef<'a>,
+    reader: Reader<'a>,
+    remaining: u16,
+}
+
+impl<'a> Members<'a> {
+    fn new(class: &'a ClassFileRef<'a>, position: usize) -> Self {
+        Self {
+            class,
+            reader: Reader::new(class.data, position + 2),
+            remaining: class.u16_at(position),
+        }
+    }
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = MemberRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let member = MemberRef {
+            class: self.class,
+            position: self.reader.position(),
+        };
+        self.reader
+            .bytes(6)
+            .and_then(|_| self.reader.skip_attributes())
+            .expect("members are checked when the class is read");
+
+        Some(member)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl ExactSizeIterator for Members<'_> {}
+
+/// A field or method.
+#[derive(Clone, Copy)]
+pub struct MemberRef<'a> {
+    class: &'a ClassFileRef<'a>,
+    /// The offset of the access flags.
+    position: usize,
+}
+
+impl<'a> MemberRef<'a> {
+    pub fn access_flags(&self) -> u16 {
+        self.class.u16_at(self.position)
+    }
+
+    pub fn name(&self) -> JomResult<Cow<'a, str>> {
+        self.class.get_utf8(self.class.u16_at(self.position + 2))
+    }
+
+    pub fn descriptor(&self) -> JomResult<Cow<'a, str>> {
+        self.class.get_utf8(self.class.u16_at(self.position + 4))
+    }
+
+    pub fn attributes(&self) -> Attributes<'a> {
+        Attributes::new(self.class, self.class.data, self.position + 6)
+    }
+
+    /// Reads the `Code` attribute of a method, leaving the instructions undecoded.
+    pub fn code(&self) -> JomResult<Option<CodeRef<'a>>> {
+        for attribute in self.attributes() {
+            let attribute = attribute?;
+            if attribute.name == "Code" {
+                return CodeRef::read(self.class, attribute.info).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// An attribute's name and its undecoded contents, whose constant pool indices refer to the
+/// class's constant pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeRef<'a> {
+    pub name: Cow<'a, str>,
+    pub info: &'a [u8],
+}
+
+/// An attribute table of a class, member or code.
+pub struct Attributes<'a> {
+    class: &'a ClassFileRef<'a>,
+    reader: Reader<'a>,
+    remaining: u16,
+}
+
+impl<'a> Attributes<'a> {
+    /// Reads the table at `position` in `data`, which must have been checked already.
+    fn new(class: &'a ClassFileRef<'a>, data: &'a [u8], position: usize) -> Self {
+        Self {
+            class,
+            reader: Reader::new(data, position + 2),
+            remaining: u16::from_be_bytes([data[position], data[position + 1]]),
+        }
+    }
+
+    fn read(&mut self) -> JomResult<AttributeRef<'a>> {
+        let name = self.reader.u16()?;
+        let length = self.reader.u32()?;
+        let info = self.reader.bytes(length as usize)?;
+
+        Ok(AttributeRef {
+            name: self.class.get_utf8(name)?,
+            info,
+        })
+    }
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = JomResult<AttributeRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        Some(self.read())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl ExactSizeIterator for Attributes<'_> {}
+
+/// The `Code` attribute of a method.
+pub struct CodeRef<'a> {
+    class: &'a ClassFileRef<'a>,
+    pub max_stack: u16,
+    pub max_locals: u16,
+    code: &'a [u8],
+    exception_table: &'a [u8],
+    info: &'a [u8],
+    /// The offset of the attribute table in `info`.
+    attributes: usize,
+}
+
+impl<'a> CodeRef<'a> {
+    fn read(class: &'a ClassFileRef<'a>, info: &'a [u8]) -> JomResult<Self> {
+        let mut reader = Reader::new(info, 0);
+
+        let max_stack = reader.u16()?;
+        let max_locals = reader.u16()?;
+        let length = reader.u32()?;
+        let code = reader.bytes(length as usize)?;
+        let exceptions = reader.u16()?;
+        let exception_table = reader.bytes(8 * exceptions as usize)?;
+        let attributes = reader.position();
+        reader.skip_attributes()?;
+
+        Ok(Self {
+            class,
+            max_stack,
+            max_locals,
+            code,
+            exception_table,
+            info,
+            attributes,
+        })
+    }
+
+    /// The undecoded bytecode.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.code
+    }
+
+    pub fn instructions(&self) -> Instructions<'a> {
+        Instructions {
+            code: Cursor::new(self.code),
+        }
+    }
+
+    pub fn exception_table(&self) -> impl ExactSizeIterator<Item = JomResult<Exception>> + 'a {
+        let class = self.class;
+
+        self.exception_table.chunks(8).map(move |x| {
+            let at = |i: usize| u16::from_be_bytes([x[i], x[i + 1]]);
+            // A `catch_type` of zero catches every exception and is used to implement `finally`.
+            let catch_type = match at(6) {
+                0 => None,
+                index => Some(class.get_class(index)?.into_owned()),
+            };
+
+            Ok(Exception {
+                start_pc: at(0),
+                end_pc: at(2),
+                handler_pc: at(4),
+                catch_type,
+            })
+        })
+    }
+
+    pub fn attributes(&self) -> Attributes<'a> {
+        Attributes::new(self.class, self.info, self.attributes)
+    }
+}
+
+/// Decodes instructions one at a time, with their offsets in the code. Decoding stops at the
+/// first error.
+pub struct Instructions<'a> {
+    code: Cursor<&'a [u8]>,
+}
+
+impl Iterator for Instructions<'_> {
+    type Item = JomResult<(u32, Instruction)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let length = self.code.get_ref().len() as u64;
+        let offset = self.code.position();
+        if offset >= length {
+            return None;
+        }
+
+        Some(match Instruction::read(&mut self.code) {
+            Ok(instruction) => Ok((offset as u32, instruction)),
+            Err(error) => {
+                let code = &self.code.get_ref()[offset as usize..];
+                self.code.set_position(length);
+                Err(instruction_error(error, code).within(ErrorPath::Pc(offset as u32)))
+            }
+        })
+    }
+}
@@ -0,0 +1,311 @@
+//! Removing unused constant pool entries and renumbering the rest.
+//!
+//! The decoded parts of a class, like member names or `Signature` attributes, hold their
+//! constants by value, and [`ClassFile::write`] adds the entries they need. Only the entries
+//! referred to by index, from instructions and from attributes kept as raw bytes, have to
+//! stay in the pool.
+
+use std::cmp::Reverse;
+
+use crate::{
+    attribute::{ClassAttribute, CodeAttribute, FieldAttribute, MethodAttribute},
+    constant_pool::{ConstantPool, ConstantPoolIndex},
+    error::{JomError, JomResult},
+    method::code::{instruction::Instruction, Code, Exception},
+    walker::{self, Reference, Visitor},
+    ClassFile,
+};
+
+impl ClassFile {
+    /// Removes the constant pool entries that nothing refers to by index and renumbers the
+    /// rest. Code skipped when the class was read is decoded first.
+    ///
+    /// Entries loaded by `ldc` come first, then those loaded by `ldc_w`, most loaded first,
+    /// so that they get one byte indices. The other entries keep their order. `ldc_w`
+    /// instructions whose index fits in a byte become `ldc`, unless their code has an
+    /// attribute that cannot be moved.
+    ///
+    /// Returns `false`, leaving the pool unchanged, if the class has an attribute whose
+    /// layout is unknown, as it may refer to any entry.
+    pub fn compact_constant_pool(&mut self) -> JomResult<bool> {
+        for i in 0..self.methods.len() {
+            self.decode_method(i)?;
+        }
+
+        let len = self.constant_pool.len();
+        let mut used = vec![false; len];
+        let known = self.map_indices(&mut |index| {
+            *used
+                .get_mut(index as usize)
+                .ok_or(JomError::OutOfBounds(index))? = true;
+            Ok(index)
+        })?;
+        if !known {
+            return Ok(false);
+        }
+
+        // There are at most 255 entries loaded by `ldc`, as their indices fit in a byte.
+        let mut narrow = vec![false; len];
+        let mut wide = vec![0; len];
+        for code in self.methods.iter().filter_map(|x| x.code()) {
+            for instruction in &code.code {
+                match *instruction {
+                    Instruction::Ldc(index) => narrow[index as usize] = true,
+                    Instruction::LdcW(index) => wide[index as usize] += 1,
+                    _ => {}
+                }
+            }
+        }
+        let mut order = (1..len).filter(|&i| used[i]).collect::<Vec<_>>();
+        order.sort_by_key(|&i| (!narrow[i], Reverse(wide[i])));
+
+        let mut indices = vec![0; len];
+        let mut constant_pool = ConstantPool(vec![ConstantPoolIndex::Unusable]);
+        for i in order {
+            let entry = &self.constant_pool[i];
+            indices[i] = constant_pool.len() as u16;
+            constant_pool.0.push(entry.clone());
+            if let ConstantPoolIndex::Long(_) | ConstantPoolIndex::Double(_) = entry {
+                constant_pool.0.push(ConstantPoolIndex::Unusable);
+            }
+        }
+
+        self.map_indices(&mut |index| Ok(indices[index as usize]))?;
+        self.constant_pool = constant_pool;
+
+        for code in self.methods.iter_mut().filter_map(|x| x.code_mut()) {
+            shorten_loads(code, &self.constant_pool)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Replaces every constant pool index in code and raw attributes by what `map` returns
+    /// for it. Returns `false` if an attribute has an unknown layout, whose indices are left
+    /// alone.
+    fn map_indices(&mut self, map: &mut impl FnMut(u16) -> JomResult<u16>) -> JomResult<bool> {
+        let cp = &self.constant_pool;
+        let mut known = true;
+        let mut walk = |name: &str, info: &mut Vec<u8>| -> JomResult<()> {
+            let mut visitor = IndexMap {
+                constant_pool: cp,
+                map: &mut *map,
+                pcs: None,
+            };
+            known &= walker::walk(name, info, &mut visitor)?;
+
+            Ok(())
+        };
+
+        for attribute in &mut self.attributes {
+            if let ClassAttribute::Unknown(name, info) = attribute {
+                walk(name, info)?;
+            }
+        }
+        for field in &mut self.fields {
+            for attribute in &mut field.attributes {
+                if let FieldAttribute::Unknown(name, info) = attribute {
+                    walk(name, info)?;
+                }
+            }
+        }
+        for method in &mut self.methods {
+            for attribute in &mut method.attributes {
+                match attribute {
+                    MethodAttribute::Unknown(name, info) => walk(name, info)?,
+                    MethodAttribute::Code(code) => {
+                        for attribute in &mut code.attributes {
+                            if let CodeAttribute::Unknown(name, info) = attribute {
+                                walk(name, info)?;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if !known {
+            return Ok(false);
+        }
+
+        for code in self.methods.iter_mut().filter_map(|x| x.code_mut()) {
+            for instruction in &mut code.code {
+                if let Some(index) = instruction.constant_pool_index() {
+                    instruction.set_constant_pool_index(map(index)?)?;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// The offsets of the instructions, and of the end of the code.
+fn pcs(code: &[Instruction]) -> Vec<u32> {
+    let mut pcs = Vec::with_capacity(code.len() + 1);
+    let mut pc = 0;
+    for instruction in code {
+        pcs.push(pc);
+        pc += instruction.size(pc);
+    }
+    pcs.push(pc);
+
+    pcs
+}
+
+/// The new contents of a code attribute after moving the code.
+enum Moved {
+    StartPcs(Vec<u16>),
+    Ranges(Vec<(u16, u16)>),
+    Info(Vec<u8>),
+}
+
+/// Turns `ldc_w` instructions whose index fits in a byte into `ldc`, moving the code after
+/// them and every offset into it. The code is left unchanged if an offset cannot be moved,
+/// like one in an attribute of unknown layout or a branch that no longer fits.
+fn shorten_loads(code: &mut Code, constant_pool: &ConstantPool) -> JomResult<()> {
+    if !code
+        .code
+        .iter()
+        .any(|x| matches!(x, Instruction::LdcW(0..=0xff)))
+    {
+        return Ok(());
+    }
+
+    let mut instructions = code.code.clone();
+    for instruction in &mut instructions {
+        if let Instruction::LdcW(i @ 0..=0xff) = *instruction {
+            *instruction = Instruction::Ldc(i as u8);
+        }
+    }
+    let old = pcs(&code.code);
+    let new = pcs(&instructions);
+    // Offsets in the middle of an instruction cannot be moved.
+    let pc = |pc: u32| old.binary_search(&pc).ok().map(|i| new[i]);
+    let range = |start: u16, length: u16| {
+        let start_pc = pc(start as u32)?;
+        let end_pc = pc(start as u32 + length as u32)?;
+        Some((start_pc as u16, (end_pc - start_pc) as u16))
+    };
+
+    // Everything is moved before changing anything, so the code can be left unchanged.
+    for (i, instruction) in instructions.iter_mut().enumerate() {
+        let offsets = instruction.branch_offsets();
+        if offsets.is_empty() {
+            continue;
+        }
+        let offsets = offsets
+            .iter()
+            .map(|&x| {
+                let target = pc(u32::try_from(old[i] as i64 + x as i64).ok()?)?;
+                Some(target as i32 - new[i] as i32)
+            })
+            .collect::<Option<Vec<_>>>();
+        match offsets {
+            Some(x) if instruction.set_branch_offsets(&x) => {}
+            _ => return Ok(()),
+        }
+    }
+
+    let exception_table = code
+        .exception_table
+        .iter()
+        .map(|x| {
+            Some(Exception {
+                start_pc: pc(x.start_pc as u32)? as u16,
+                end_pc: pc(x.end_pc as u32)? as u16,
+                handler_pc: pc(x.handler_pc as u32)? as u16,
+                catch_type: x.catch_type.clone(),
+            })
+        })
+        .collect::<Option<Vec<_>>>();
+    let Some(exception_table) = exception_table else {
+        return Ok(());
+    };
+
+    let mut moved = vec![];
+    for attribute in &code.attributes {
+        let attribute = match attribute {
+            CodeAttribute::LineNumberTable(table) => table
+                .iter()
+                .map(|x| Some(pc(x.start_pc as u32)? as u16))
+                .collect::<Option<_>>()
+                .map(Moved::StartPcs),
+            CodeAttribute::LocalVariableTable(table) => table
+                .iter()
+                .map(|x| range(x.start_pc, x.length))
+                .collect::<Option<_>>()
+                .map(Moved::Ranges),
+            CodeAttribute::LocalVariableTypeTable(table) => table
+                .iter()
+                .map(|x| range(x.start_pc, x.length))
+                .collect::<Option<_>>()
+                .map(Moved::Ranges),
+            CodeAttribute::Unknown(name, info) => {
+                let mut info = info.clone();
+                let mut visitor = IndexMap {
+                    constant_pool,
+                    map: &mut Ok,
+                    pcs: Some(&pc),
+                };
+                walker::walk(name, &mut info, &mut visitor)?.then_some(Moved::Info(info))
+            }
+            _ => None,
+        };
+        match attribute {
+            Some(x) => moved.push(x),
+            None => return Ok(()),
+        }
+    }
+
+    code.code = instructions;
+    code.exception_table = exception_table;
+    for (attribute, moved) in code.attributes.iter_mut().zip(moved) {
+        match (attribute, moved) {
+            (CodeAttribute::LineNumberTable(table), Moved::StartPcs(pcs)) => {
+                for (x, pc) in table.iter_mut().zip(pcs) {
+                    x.start_pc = pc;
+                }
+            }
+            (CodeAttribute::LocalVariableTable(table), Moved::Ranges(ranges)) => {
+                for (x, (start_pc, length)) in table.iter_mut().zip(ranges) {
+                    x.start_pc = start_pc;
+                    x.length = length;
+                }
+            }
+            (CodeAttribute::LocalVariableTypeTable(table), Moved::Ranges(ranges)) => {
+                for (x, (start_pc, length)) in table.iter_mut().zip(ranges) {
+                    x.start_pc = start_pc;
+                    x.length = length;
+                }
+            }
+            (CodeAttribute::Unknown(_, info), Moved::Info(moved)) => *info = moved,
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(())
+}
+
+/// Maps the constant pool indices in raw attributes, moving their code offsets too when
+/// the code is being moved.
+struct IndexMap<'a, F> {
+    constant_pool: &'a ConstantPool,
+    map: &'a mut F,
+    /// Where the instructions move to, by their old offsets.
+    pcs: Option<&'a dyn Fn(u32) -> Option<u32>>,
+}
+
+impl<F: FnMut(u16) -> JomResult<u16>> Visitor for IndexMap<'_, F> {
+    fn constant_pool(&self) -> &ConstantPool {
+        self.constant_pool
+    }
+
+    fn index(&mut self, index: u16, _: Reference) -> JomResult<u16> {
+        (self.map)(index)
+    }
+
+    fn pc(&self, pc: u32) -> Option<u32> {
+        self.pcs.map_or(Some(pc), |x| x(pc))
+    }
+}
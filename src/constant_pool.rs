@@ -1,3 +1,8 @@
+use std::{
+    io::{Read, Seek},
+    ops::{Deref, DerefMut},
+};
+
 use binrw::{binrw, BinRead, BinResult};
 
 use crate::{
@@ -7,13 +12,21 @@ use crate::{
 
 pub struct ConstantPool(pub(crate) Vec<ConstantPoolIndex>);
 
-#[binrw::parser(reader: r, endian: e)]
+#[binrw::parser(reader: r)]
 pub(crate) fn constant_pool_parser(count: (u16,)) -> BinResult<Vec<RawConstantPoolIndex>> {
-    let count = count.0;
+    read_constant_pool(r, count.0)
+}
+
+/// Reads the entries of a constant pool with `count` slots, including the unusable first one
+/// and those after `Long` and `Double` entries.
+pub(crate) fn read_constant_pool<R: Read + Seek>(
+    r: &mut R,
+    count: u16,
+) -> BinResult<Vec<RawConstantPoolIndex>> {
     let mut raw_cp = vec![RawConstantPoolIndex::Unusable];
     let mut i = 1;
     while i < count {
-        let index = RawConstantPoolIndex::read_options(r, e, ())?;
+        let index = RawConstantPoolIndex::read_be(r)?;
 
         if let RawConstantPoolIndex::Long(_) | RawConstantPoolIndex::Double(_) = index {
             raw_cp.push(index);
@@ -28,6 +41,20 @@ pub(crate) fn constant_pool_parser(count: (u16,)) -> BinResult<Vec<RawConstantPo
     Ok(raw_cp)
 }
 
+impl Deref for ConstantPool {
+    type Target = [ConstantPoolIndex];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for ConstantPool {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 impl ConstantPool {
     // As the constant pool length is the first index in the constant pool it will never be empty
     #[allow(clippy::len_without_is_empty)]
@@ -67,6 +94,171 @@ impl ConstantPool {
     pub fn find_class(&self, class: String) -> JomResult<u16> {
         self.find(ConstantPoolIndex::Class(class))
     }
+
+    /// Returns the index of an equal entry, appending the entry if there is none.
+    pub fn insert(&mut self, index: ConstantPoolIndex) -> JomResult<u16> {
+        if let Ok(i) = self.find(index.clone()) {
+            return Ok(i);
+        }
+
+        let wide = matches!(index, ConstantPoolIndex::Long(_) | ConstantPoolIndex::Double(_));
+        let i = self.0.len();
+        if i + 1 + wide as usize > u16::MAX as usize {
+            return Err(JomError::ConstantPoolFull);
+        }
+
+        self.0.push(index);
+        if wide {
+            self.0.push(ConstantPoolIndex::Unusable);
+        }
+
+        Ok(i as u16)
+    }
+
+    pub fn insert_utf8(&mut self, s: String) -> JomResult<u16> {
+        self.insert(ConstantPoolIndex::Utf8(s))
+    }
+
+    pub fn insert_class(&mut self, class: String) -> JomResult<u16> {
+        self.insert(ConstantPoolIndex::Class(class))
+    }
+
+    /// Encodes the entries for writing, appending any referenced entries that are missing.
+    pub(crate) fn encode(&mut self) -> JomResult<Vec<RawConstantPoolIndex>> {
+        let mut raw_cp = vec![];
+        let mut i = 0;
+
+        // Encoding an entry can append new ones, which are encoded in turn.
+        while i < self.0.len() {
+            let raw = match self.0[i].clone() {
+                ConstantPoolIndex::Utf8(s) => {
+                    RawConstantPoolIndex::Utf8(s.encode_utf16().collect())
+                }
+                ConstantPoolIndex::Utf8Units(x) => RawConstantPoolIndex::Utf8(x),
+                ConstantPoolIndex::Integer(i) => RawConstantPoolIndex::Integer(i),
+                ConstantPoolIndex::Float(f) => RawConstantPoolIndex::Float(f),
+                ConstantPoolIndex::Long(l) => RawConstantPoolIndex::Long(l),
+                ConstantPoolIndex::Double(d) => RawConstantPoolIndex::Double(d),
+                ConstantPoolIndex::Class(s) => RawConstantPoolIndex::Class(self.insert_utf8(s)?),
+                ConstantPoolIndex::String(s) => RawConstantPoolIndex::String(self.insert_utf8(s)?),
+                ConstantPoolIndex::StringUnits(x) => {
+                    RawConstantPoolIndex::String(self.insert(ConstantPoolIndex::Utf8Units(x))?)
+                }
+                ConstantPoolIndex::Fieldref {
+                    class,
+                    name,
+                    descriptor,
+                } => RawConstantPoolIndex::Fieldref(
+                    self.insert_class(class)?,
+                    self.insert(ConstantPoolIndex::NameAndType(name, descriptor))?,
+                ),
+                ConstantPoolIndex::Methodref {
+                    class,
+                    name,
+                    descriptor,
+                } => RawConstantPoolIndex::Methodref(
+                    self.insert_class(class)?,
+                    self.insert(ConstantPoolIndex::NameAndType(name, descriptor))?,
+                ),
+                ConstantPoolIndex::InterfaceMethodref {
+                    class,
+                    name,
+                    descriptor,
+                } => RawConstantPoolIndex::InterfaceMethodref(
+                    self.insert_class(class)?,
+                    self.insert(ConstantPoolIndex::NameAndType(name, descriptor))?,
+                ),
+                ConstantPoolIndex::NameAndType(name, descriptor) => {
+                    RawConstantPoolIndex::NameAndType(
+                        self.insert_utf8(name)?,
+                        self.insert_utf8(descriptor)?,
+                    )
+                }
+                ConstantPoolIndex::MethodHandle {
+                    kind,
+                    class,
+                    name,
+                    descriptor,
+                } => {
+                    let reference = self.method_handle_reference(&kind, class, name, descriptor)?;
+                    RawConstantPoolIndex::MethodHandle(kind, reference)
+                }
+                ConstantPoolIndex::MethodType(s) => {
+                    RawConstantPoolIndex::MethodType(self.insert_utf8(s)?)
+                }
+                ConstantPoolIndex::Dynamic {
+                    bootstrap_method_attr_index,
+                    name,
+                    descriptor,
+                } => RawConstantPoolIndex::Dynamic(
+                    bootstrap_method_attr_index,
+                    self.insert(ConstantPoolIndex::NameAndType(name, descriptor))?,
+                ),
+                ConstantPoolIndex::InvokeDynamic {
+                    bootstrap_method_attr_index,
+                    name,
+                    descriptor,
+                } => RawConstantPoolIndex::InvokeDynamic(
+                    bootstrap_method_attr_index,
+                    self.insert(ConstantPoolIndex::NameAndType(name, descriptor))?,
+                ),
+                ConstantPoolIndex::Module(s) => RawConstantPoolIndex::Module(self.insert_utf8(s)?),
+                ConstantPoolIndex::Package(s) => {
+                    RawConstantPoolIndex::Package(self.insert_utf8(s)?)
+                }
+                ConstantPoolIndex::Unusable => RawConstantPoolIndex::Unusable,
+            };
+
+            raw_cp.push(raw);
+            i += 1;
+        }
+
+        Ok(raw_cp)
+    }
+
+    fn method_handle_reference(
+        &mut self,
+        kind: &MethodHandleReferenceKind,
+        class: String,
+        name: String,
+        descriptor: String,
+    ) -> JomResult<u16> {
+        match kind {
+            MethodHandleReferenceKind::GetField
+            | MethodHandleReferenceKind::GetStatic
+            | MethodHandleReferenceKind::PutField
+            | MethodHandleReferenceKind::PutStatic => self.insert(ConstantPoolIndex::Fieldref {
+                class,
+                name,
+                descriptor,
+            }),
+            MethodHandleReferenceKind::InvokeInterface => {
+                self.insert(ConstantPoolIndex::InterfaceMethodref {
+                    class,
+                    name,
+                    descriptor,
+                })
+            }
+            _ => {
+                let methodref = ConstantPoolIndex::Methodref {
+                    class: class.clone(),
+                    name: name.clone(),
+                    descriptor: descriptor.clone(),
+                };
+
+                // `invokestatic` and `invokespecial` handles may refer to interface methods.
+                self.find(methodref.clone())
+                    .or_else(|_| {
+                        self.find(ConstantPoolIndex::InterfaceMethodref {
+                            class,
+                            name,
+                            descriptor,
+                        })
+                    })
+                    .or_else(|_| self.insert(methodref))
+            }
+        }
+    }
 }
 
 #[binrw]
@@ -74,10 +266,11 @@ impl ConstantPool {
 #[derive(Clone, Debug)]
 pub enum RawConstantPoolIndex {
     #[brw(magic(1u8))]
+    /// The UTF-16 code units of the string.
     Utf8(
-        #[br(map = |s: ModifiedUtf8| s.to_string())]
-        #[bw(try_map = |s| ModifiedUtf8::try_from(s.clone()))]
-        String,
+        #[br(map = |s: ModifiedUtf8| s.0)]
+        #[bw(map = |s| ModifiedUtf8(s.clone()))]
+        Vec<u16>,
     ),
     #[brw(magic(3u8))]
     Integer(i32),
@@ -111,6 +304,8 @@ pub enum RawConstantPoolIndex {
     Module(u16),
     #[brw(magic(20u8))]
     Package(u16),
+    // Never read, so that an unknown tag is an error.
+    #[br(pre_assert(false))]
     Unusable,
 }
 
@@ -129,7 +324,7 @@ pub enum MethodHandleReferenceKind {
     InvokeInterface = 9,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum ConstantPoolIndex {
     Utf8(String),
     Integer(i32),
@@ -173,9 +368,114 @@ pub enum ConstantPoolIndex {
     },
     Module(String),
     Package(String),
+    /// A `Utf8` entry with unpaired surrogates, which Java strings allow but a `String`
+    /// cannot hold, as its UTF-16 code units.
+    Utf8Units(Vec<u16>),
+    /// A `String` entry whose value has unpaired surrogates, like [`Self::Utf8Units`].
+    StringUnits(Vec<u16>),
     Unusable,
 }
 
+/// Float and double entries are compared by their bits, so that NaN constants equal themselves
+/// and `0.0` differs from `-0.0`, like the entries the JVM resolves.
+impl PartialEq for ConstantPoolIndex {
+    fn eq(&self, other: &Self) -> bool {
+        use ConstantPoolIndex::*;
+
+        match (self, other) {
+            (Utf8(a), Utf8(b)) => a == b,
+            (Integer(a), Integer(b)) => a == b,
+            (Float(a), Float(b)) => a.to_bits() == b.to_bits(),
+            (Long(a), Long(b)) => a == b,
+            (Double(a), Double(b)) => a.to_bits() == b.to_bits(),
+            (Class(a), Class(b)) => a == b,
+            (String(a), String(b)) => a == b,
+            (
+                Fieldref {
+                    class,
+                    name,
+                    descriptor,
+                },
+                Fieldref {
+                    class: class2,
+                    name: name2,
+                    descriptor: descriptor2,
+                },
+            )
+            | (
+                Methodref {
+                    class,
+                    name,
+                    descriptor,
+                },
+                Methodref {
+                    class: class2,
+                    name: name2,
+                    descriptor: descriptor2,
+                },
+            )
+            | (
+                InterfaceMethodref {
+                    class,
+                    name,
+                    descriptor,
+                },
+                InterfaceMethodref {
+                    class: class2,
+                    name: name2,
+                    descriptor: descriptor2,
+                },
+            ) => (class, name, descriptor) == (class2, name2, descriptor2),
+            (NameAndType(a, b), NameAndType(c, d)) => (a, b) == (c, d),
+            (
+                MethodHandle {
+                    kind,
+                    class,
+                    name,
+                    descriptor,
+                },
+                MethodHandle {
+                    kind: kind2,
+                    class: class2,
+                    name: name2,
+                    descriptor: descriptor2,
+                },
+            ) => (kind, class, name, descriptor) == (kind2, class2, name2, descriptor2),
+            (MethodType(a), MethodType(b)) => a == b,
+            (
+                Dynamic {
+                    bootstrap_method_attr_index,
+                    name,
+                    descriptor,
+                },
+                Dynamic {
+                    bootstrap_method_attr_index: index2,
+                    name: name2,
+                    descriptor: descriptor2,
+                },
+            )
+            | (
+                InvokeDynamic {
+                    bootstrap_method_attr_index,
+                    name,
+                    descriptor,
+                },
+                InvokeDynamic {
+                    bootstrap_method_attr_index: index2,
+                    name: name2,
+                    descriptor: descriptor2,
+                },
+            ) => (bootstrap_method_attr_index, name, descriptor) == (index2, name2, descriptor2),
+            (Module(a), Module(b)) => a == b,
+            (Package(a), Package(b)) => a == b,
+            (Utf8Units(a), Utf8Units(b)) => a == b,
+            (StringUnits(a), StringUnits(b)) => a == b,
+            (Unusable, Unusable) => true,
+            _ => false,
+        }
+    }
+}
+
 pub(crate) fn process_cp(raw_cp: Vec<RawConstantPoolIndex>) -> JomResult<ConstantPool> {
     let mut cp = vec![None; raw_cp.len()];
 
@@ -194,7 +494,10 @@ fn resolve_index<'a>(
     if cp[i].is_none() {
         let raw = raw_cp[i].clone();
         cp[i] = match raw {
-            RawConstantPoolIndex::Utf8(s) => Some(ConstantPoolIndex::Utf8(s)),
+            RawConstantPoolIndex::Utf8(x) => Some(match String::from_utf16(&x) {
+                Ok(s) => ConstantPoolIndex::Utf8(s),
+                Err(_) => ConstantPoolIndex::Utf8Units(x),
+            }),
             RawConstantPoolIndex::Integer(i) => Some(ConstantPoolIndex::Integer(i)),
             RawConstantPoolIndex::Float(f) => Some(ConstantPoolIndex::Float(f)),
             RawConstantPoolIndex::Long(l) => Some(ConstantPoolIndex::Long(l)),
@@ -202,9 +505,10 @@ fn resolve_index<'a>(
             RawConstantPoolIndex::Class(i) => Some(ConstantPoolIndex::Class(
                 resolve_index(i as usize, raw_cp, cp)?.clone().into_utf8()?,
             )),
-            RawConstantPoolIndex::String(i) => Some(ConstantPoolIndex::String(
-                resolve_index(i as usize, raw_cp, cp)?.clone().into_utf8()?,
-            )),
+            RawConstantPoolIndex::String(i) => match resolve_index(i as usize, raw_cp, cp)? {
+                ConstantPoolIndex::Utf8Units(x) => Some(ConstantPoolIndex::StringUnits(x.clone())),
+                x => Some(ConstantPoolIndex::String(x.clone().into_utf8()?)),
+            },
             RawConstantPoolIndex::Fieldref(c, nty) => {
                 let class = resolve_index(c as usize, raw_cp, cp)?
                     .clone()
@@ -414,13 +718,18 @@ impl ConstantPoolIndex {
             ConstantPoolIndex::InvokeDynamic { .. } => "InvokeDynamic",
             ConstantPoolIndex::Module(_) => "Module",
             ConstantPoolIndex::Package(_) => "Package",
+            ConstantPoolIndex::Utf8Units(_) => "Utf8",
+            ConstantPoolIndex::StringUnits(_) => "String",
             ConstantPoolIndex::Unusable => "Unusable",
         }
     }
 
+    /// Fails with [`JomError::InvalidUtf8`] for a [`Self::Utf8Units`] entry, which is only
+    /// valid as the value of a `String`.
     pub fn into_utf8(self) -> JomResult<String> {
         match self {
             Self::Utf8(x) => Ok(x),
+            Self::Utf8Units(_) => Err(JomError::InvalidUtf8),
             x => Err(JomError::new_cp_index("Utf8", x.name())),
         }
     }
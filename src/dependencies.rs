@@ -0,0 +1,260 @@
+//! Everything a class refers to, for checking module boundaries or relocating packages.
+//!
+//! Attributes that are kept as raw bytes, like annotations or `InnerClasses`, are parsed
+//! against the class's constant pool.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    attribute::{ClassAttribute, CodeAttribute, FieldAttribute, MethodAttribute},
+    call_graph::MethodRef,
+    constant_pool::{ConstantPool, ConstantPoolIndex, MethodHandleReferenceKind},
+    error::JomResult,
+    signature,
+    walker::{self, Reference, Visitor},
+    ClassFile,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FieldRef {
+    pub class: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+impl FieldRef {
+    pub fn new(
+        class: impl Into<String>,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+    ) -> Self {
+        Self {
+            class: class.into(),
+            name: name.into(),
+            descriptor: descriptor.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Dependencies {
+    /// The internal names of the referenced classes, without the class itself. Array
+    /// types are replaced by their element types.
+    pub classes: BTreeSet<String>,
+    /// The fields accessed by instructions or method handles.
+    pub fields: BTreeSet<FieldRef>,
+    /// The methods invoked by instructions or method handles, and the enclosing method of
+    /// a local or anonymous class.
+    pub methods: BTreeSet<MethodRef>,
+}
+
+impl Dependencies {
+    fn add_class(&mut self, name: &str) -> JomResult<()> {
+        match name.starts_with('[') {
+            true => self.add_signature(name),
+            false => {
+                self.classes.insert(name.to_owned());
+                Ok(())
+            }
+        }
+    }
+
+    /// Adds the classes in a descriptor or generic signature.
+    fn add_signature(&mut self, signature: &str) -> JomResult<()> {
+        signature::map_classes(signature, |class| {
+            self.classes.insert(class.to_owned());
+            class.to_owned()
+        })
+        .map(|_| ())
+    }
+
+    fn add_cp_index(&mut self, index: &ConstantPoolIndex) -> JomResult<()> {
+        match index {
+            ConstantPoolIndex::Class(class) => self.add_class(class)?,
+            ConstantPoolIndex::Fieldref {
+                class,
+                name,
+                descriptor,
+            } => {
+                self.add_class(class)?;
+                self.add_signature(descriptor)?;
+                self.fields.insert(FieldRef::new(class, name, descriptor));
+            }
+            ConstantPoolIndex::Methodref {
+                class,
+                name,
+                descriptor,
+            }
+            | ConstantPoolIndex::InterfaceMethodref {
+                class,
+                name,
+                descriptor,
+            } => {
+                self.add_class(class)?;
+                self.add_signature(descriptor)?;
+                self.methods.insert(MethodRef::new(class, name, descriptor));
+            }
+            ConstantPoolIndex::MethodHandle {
+                kind,
+                class,
+                name,
+                descriptor,
+            } => {
+                self.add_class(class)?;
+                self.add_signature(descriptor)?;
+                match kind {
+                    MethodHandleReferenceKind::GetField
+                    | MethodHandleReferenceKind::GetStatic
+                    | MethodHandleReferenceKind::PutField
+                    | MethodHandleReferenceKind::PutStatic => {
+                        self.fields.insert(FieldRef::new(class, name, descriptor));
+                    }
+                    _ => {
+                        self.methods.insert(MethodRef::new(class, name, descriptor));
+                    }
+                }
+            }
+            ConstantPoolIndex::MethodType(descriptor)
+            | ConstantPoolIndex::Dynamic { descriptor, .. }
+            | ConstantPoolIndex::InvokeDynamic { descriptor, .. } => {
+                self.add_signature(descriptor)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn add_attribute(&mut self, name: &str, info: &[u8], cp: &ConstantPool) -> JomResult<()> {
+        let mut reader = AttributeReader {
+            cp,
+            dependencies: self,
+        };
+        // The walker writes the indices it is given back, so it is given a copy.
+        walker::walk(name, &mut info.to_vec(), &mut reader)?;
+
+        Ok(())
+    }
+}
+
+impl ClassFile {
+    /// Collects the classes and members referenced anywhere in the class file, including
+    /// classes that only appear in descriptors, signatures or annotations.
+    pub fn dependencies(&self) -> JomResult<Dependencies> {
+        let cp = &self.constant_pool;
+        let mut dependencies = Dependencies::default();
+
+        for index in cp.iter() {
+            dependencies.add_cp_index(index)?;
+        }
+
+        if !self.super_class.is_empty() {
+            dependencies.add_class(&self.super_class)?;
+        }
+        for interface in &self.interfaces {
+            dependencies.add_class(interface)?;
+        }
+
+        for field in &self.fields {
+            dependencies.add_signature(&field.descriptor)?;
+            for attribute in &field.attributes {
+                match attribute {
+                    FieldAttribute::Signature(signature) => {
+                        dependencies.add_signature(signature)?
+                    }
+                    FieldAttribute::Unknown(name, info) => {
+                        dependencies.add_attribute(name, info, cp)?
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for method in &self.methods {
+            dependencies.add_signature(&method.descriptor)?;
+            for attribute in &method.attributes {
+                match attribute {
+                    MethodAttribute::Signature(signature) => {
+                        dependencies.add_signature(signature)?
+                    }
+                    MethodAttribute::Unknown(name, info) => {
+                        dependencies.add_attribute(name, info, cp)?
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(code) = method.code() else {
+                continue;
+            };
+            for exception in &code.exception_table {
+                if let Some(catch_type) = &exception.catch_type {
+                    dependencies.add_class(catch_type)?;
+                }
+            }
+            for attribute in &code.attributes {
+                match attribute {
+                    CodeAttribute::LocalVariableTable(table) => {
+                        for variable in table {
+                            dependencies.add_signature(&variable.descriptor)?;
+                        }
+                    }
+                    CodeAttribute::LocalVariableTypeTable(table) => {
+                        for variable in table {
+                            dependencies.add_signature(&variable.descriptor)?;
+                        }
+                    }
+                    CodeAttribute::Unknown(name, info) => {
+                        dependencies.add_attribute(name, info, cp)?
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for attribute in &self.attributes {
+            if let ClassAttribute::Unknown(name, info) = attribute {
+                dependencies.add_attribute(name, info, cp)?;
+            }
+        }
+
+        dependencies.classes.remove(&self.this_class);
+
+        Ok(dependencies)
+    }
+}
+
+/// Reads the parts of raw attributes that refer to classes.
+struct AttributeReader<'a> {
+    cp: &'a ConstantPool,
+    dependencies: &'a mut Dependencies,
+}
+
+impl Visitor for AttributeReader<'_> {
+    fn constant_pool(&self) -> &ConstantPool {
+        self.cp
+    }
+
+    fn index(&mut self, index: u16, reference: Reference) -> JomResult<u16> {
+        match reference {
+            Reference::Constant | Reference::Class | Reference::BootstrapArgument { .. } => {
+                let entry = self.cp.get(index)?;
+                self.dependencies.add_cp_index(&entry)?;
+            }
+            Reference::Signature => {
+                let signature = self.cp.get_utf8(index)?;
+                self.dependencies.add_signature(&signature)?;
+            }
+            Reference::EnclosingMethod { class } => {
+                let (name, descriptor) = self.cp.get(index)?.into_name_and_type()?;
+                self.dependencies.add_signature(&descriptor)?;
+                self.dependencies
+                    .methods
+                    .insert(MethodRef::new(class, name, descriptor));
+            }
+            _ => {}
+        }
+
+        Ok(index)
+    }
+}
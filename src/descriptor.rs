@@ -0,0 +1,185 @@
+use std::{fmt, str::Chars};
+
+use crate::error::{JomError, JomResult};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+    Object(String),
+    Array(Box<FieldType>),
+}
+
+impl FieldType {
+    pub fn parse(descriptor: &str) -> JomResult<Self> {
+        let mut chars = descriptor.chars();
+        let ty =
+            Self::parse_from(&mut chars).ok_or_else(|| JomError::invalid_descriptor(descriptor))?;
+
+        if chars.next().is_some() {
+            return Err(JomError::invalid_descriptor(descriptor));
+        }
+
+        Ok(ty)
+    }
+
+    fn parse_from(chars: &mut Chars) -> Option<Self> {
+        Some(match chars.next()? {
+            'B' => Self::Byte,
+            'C' => Self::Char,
+            'D' => Self::Double,
+            'F' => Self::Float,
+            'I' => Self::Int,
+            'J' => Self::Long,
+            'S' => Self::Short,
+            'Z' => Self::Boolean,
+            'L' => {
+                let mut class = String::new();
+                loop {
+                    match chars.next()? {
+                        ';' => break,
+                        c => class.push(c),
+                    }
+                }
+
+                if class.is_empty() {
+                    return None;
+                }
+
+                Self::Object(class)
+            }
+            '[' => Self::Array(Box::new(Self::parse_from(chars)?)),
+            _ => return None,
+        })
+    }
+
+    /// The number of local variable or operand stack slots a value of this type occupies.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Long | Self::Double => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn is_reference(&self) -> bool {
+        matches!(self, Self::Object(_) | Self::Array(_))
+    }
+
+    /// The type as written in Java source, e.g. `java.lang.String[]`.
+    pub fn java_name(&self) -> String {
+        match self {
+            Self::Byte => "byte".to_owned(),
+            Self::Char => "char".to_owned(),
+            Self::Double => "double".to_owned(),
+            Self::Float => "float".to_owned(),
+            Self::Int => "int".to_owned(),
+            Self::Long => "long".to_owned(),
+            Self::Short => "short".to_owned(),
+            Self::Boolean => "boolean".to_owned(),
+            Self::Object(class) => class.replace('/', "."),
+            Self::Array(component) => format!("{}[]", component.java_name()),
+        }
+    }
+
+    /// Parses a type as written in Java source, e.g. `java.lang.String[]`.
+    pub fn from_java_name(name: &str) -> JomResult<Self> {
+        if let Some(component) = name.strip_suffix("[]") {
+            return Ok(Self::Array(Box::new(Self::from_java_name(component)?)));
+        }
+
+        Ok(match name {
+            "byte" => Self::Byte,
+            "char" => Self::Char,
+            "double" => Self::Double,
+            "float" => Self::Float,
+            "int" => Self::Int,
+            "long" => Self::Long,
+            "short" => Self::Short,
+            "boolean" => Self::Boolean,
+            "" | "void" => return Err(JomError::invalid_descriptor(name)),
+            _ => Self::Object(name.replace('.', "/")),
+        })
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Byte => write!(f, "B"),
+            Self::Char => write!(f, "C"),
+            Self::Double => write!(f, "D"),
+            Self::Float => write!(f, "F"),
+            Self::Int => write!(f, "I"),
+            Self::Long => write!(f, "J"),
+            Self::Short => write!(f, "S"),
+            Self::Boolean => write!(f, "Z"),
+            Self::Object(class) => write!(f, "L{class};"),
+            Self::Array(component) => write!(f, "[{component}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MethodDescriptor {
+    pub parameters: Vec<FieldType>,
+    /// `None` for `void` methods.
+    pub return_type: Option<FieldType>,
+}
+
+impl MethodDescriptor {
+    pub fn parse(descriptor: &str) -> JomResult<Self> {
+        let invalid = || JomError::invalid_descriptor(descriptor);
+
+        let mut chars = descriptor.chars();
+        if chars.next() != Some('(') {
+            return Err(invalid());
+        }
+
+        let mut parameters = vec![];
+        loop {
+            if chars.as_str().starts_with(')') {
+                chars.next();
+                break;
+            }
+
+            parameters.push(FieldType::parse_from(&mut chars).ok_or_else(invalid)?);
+        }
+
+        let return_type = if chars.as_str() == "V" {
+            None
+        } else {
+            Some(FieldType::parse(chars.as_str()).map_err(|_| invalid())?)
+        };
+
+        Ok(Self {
+            parameters,
+            return_type,
+        })
+    }
+
+    /// The number of local variable slots taken by the parameters, excluding `this`.
+    pub fn parameters_size(&self) -> usize {
+        self.parameters.iter().map(FieldType::size).sum()
+    }
+}
+
+impl fmt::Display for MethodDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        for parameter in &self.parameters {
+            write!(f, "{parameter}")?;
+        }
+        write!(f, ")")?;
+
+        match &self.return_type {
+            Some(ty) => write!(f, "{ty}"),
+            None => write!(f, "V"),
+        }
+    }
+}
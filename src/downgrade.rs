@@ -0,0 +1,680 @@
+//! Lowering classes to an older class file version, like Retrolambda or Jabel do for code
+//! compiled by a newer `javac`.
+//!
+//! Instructions are only replaced by ones of the same size, padded with `nop`, so branch
+//! offsets, exception tables and the other tables of the code, including `StackMapTable`,
+//! stay valid.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io::Cursor,
+};
+
+use binrw::BinRead;
+
+use crate::{
+    access::{ACC_INTERFACE, ACC_PRIVATE, ACC_PUBLIC, ACC_STATIC, ACC_SYNTHETIC},
+    attribute::{bootstrap_methods, ClassAttribute, MethodAttribute},
+    constant_pool::{ConstantPool, ConstantPoolIndex},
+    descriptor::{FieldType, MethodDescriptor},
+    error::{ErrorPath, JomResult},
+    method::{
+        code::{instruction::Instruction, Code},
+        MethodInfo,
+    },
+    version::{ClassVersion, Feature, VersionIssue},
+    ClassFile,
+};
+
+const STRING_CONCAT_FACTORY: &str = "java/lang/invoke/StringConcatFactory";
+const STRING_BUILDER: &str = "java/lang/StringBuilder";
+
+/// A use of a member of another class.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Access {
+    owner: String,
+    kind: AccessKind,
+    name: String,
+    descriptor: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum AccessKind {
+    GetField,
+    PutField,
+    GetStatic,
+    PutStatic,
+    /// A call of an instance method or constructor.
+    Invoke,
+    InvokeStatic,
+}
+
+/// A part of a string concatenation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Part {
+    Literal(String),
+    /// The parameter at this index.
+    Argument(usize),
+}
+
+/// Lowers classes to an older version.
+///
+/// Nestmates, which can use each other's private members from Java 11 on, get synthetic
+/// `access$` methods for them. This needs every class of a nest to be passed to
+/// [`Downgrader::scan`] before any of them is downgraded. Private constructors used by
+/// nestmates are made package-private instead.
+///
+/// String concatenation with `invokedynamic`, from Java 9 on, is replaced by calls of
+/// synthetic `concat$` methods that use a `StringBuilder`, except in interfaces below Java 8,
+/// which cannot have them. Attributes the target does not
+/// allow, like `Record` and `PermittedSubclasses`, are dropped, as is `StackMapTable` below
+/// Java 6.
+pub struct Downgrader {
+    target: ClassVersion,
+    /// The nest host of each scanned class that is in a nest.
+    nest_hosts: HashMap<String, String>,
+    interfaces: HashSet<String>,
+    /// The private members of scanned classes, by class, name and descriptor.
+    private_members: HashSet<(String, String, String)>,
+    /// The members of other classes used by scanned classes, with the class using them.
+    uses: BTreeSet<(Access, String)>,
+}
+
+impl Downgrader {
+    pub fn new(target: ClassVersion) -> Self {
+        Self {
+            target,
+            nest_hosts: HashMap::new(),
+            interfaces: HashSet::new(),
+            private_members: HashSet::new(),
+            uses: BTreeSet::new(),
+        }
+    }
+
+    /// Records the nest, private members and uses of a class. Methods whose code was
+    /// skipped when the class was read are ignored.
+    pub fn scan(&mut self, class: &ClassFile) -> JomResult<()> {
+        let this = class.this_class();
+        if let Some(host) = nest_host(class)? {
+            self.nest_hosts.insert(this.to_owned(), host);
+        }
+        if class.access_flags() & ACC_INTERFACE != 0 {
+            self.interfaces.insert(this.to_owned());
+        }
+
+        let fields = class
+            .fields()
+            .iter()
+            .map(|x| (x.access_flags, &x.name, &x.descriptor));
+        let methods = class
+            .methods()
+            .iter()
+            .map(|x| (x.access_flags, &x.name, &x.descriptor));
+        for (access_flags, name, descriptor) in fields.chain(methods) {
+            if access_flags & ACC_PRIVATE != 0 {
+                self.private_members
+                    .insert((this.to_owned(), name.clone(), descriptor.clone()));
+            }
+        }
+
+        for code in class.methods().iter().filter_map(MethodInfo::code) {
+            for instruction in &code.code {
+                if let Some(access) = access(instruction, class.constant_pool())? {
+                    if access.owner != this {
+                        self.uses.insert((access, this.to_owned()));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lowers a class to the target version, returning what is left that the target does
+    /// not allow, like lambdas below Java 7. Classes that are not newer than the target are
+    /// left unchanged.
+    pub fn downgrade(&self, class: &mut ClassFile) -> JomResult<Vec<VersionIssue>> {
+        if class.version() <= self.target {
+            return Ok(class.retarget_issues(self.target));
+        }
+        for i in 0..class.methods.len() {
+            class.decode_method(i)?;
+        }
+
+        if self.target.major < 55 {
+            self.replace_nest_access(class)?;
+        }
+        let mut issues = match self.target.major < 53 {
+            true => replace_string_concat(class, self.target)?,
+            false => vec![],
+        };
+
+        let allows = |name: &str| self.target.allows(&Feature::Attribute(name.to_owned()));
+        class.attributes.retain(|x| allows(x.name()));
+        for field in &mut class.fields {
+            field.attributes.retain(|x| allows(x.name()));
+        }
+        for method in &mut class.methods {
+            method.attributes.retain(|x| allows(x.name()));
+            if let Some(code) = method.code_mut() {
+                code.attributes.retain(|x| allows(x.name()));
+            }
+        }
+
+        remove_unused_constants(class, self.target)?;
+        class.set_version(self.target.major, self.target.minor);
+
+        issues.extend(class.retarget_issues(self.target));
+        Ok(issues)
+    }
+
+    /// Whether `access` of a private member of a nestmate needs an accessor.
+    fn needs_accessor(&self, access: &Access, user: &str) -> bool {
+        let host = self.nest_hosts.get(&access.owner);
+        let member = (
+            access.owner.clone(),
+            access.name.clone(),
+            access.descriptor.clone(),
+        );
+
+        host.is_some()
+            && host == self.nest_hosts.get(user)
+            && access.owner != user
+            && !self.interfaces.contains(&access.owner)
+            && self.private_members.contains(&member)
+    }
+
+    /// The names of the accessors of each class, which are numbered in order of the members
+    /// they access.
+    fn accessors(&self) -> BTreeMap<&Access, String> {
+        let mut accessors = BTreeMap::new();
+        let mut counts = HashMap::new();
+        for (access, user) in &self.uses {
+            if access.name == "<init>" || !self.needs_accessor(access, user) {
+                continue;
+            }
+            if accessors.contains_key(access) {
+                continue;
+            }
+            let count = counts.entry(&access.owner).or_insert(0);
+            accessors.insert(access, format!("access${count:03}"));
+            *count += 1;
+        }
+
+        accessors
+    }
+
+    /// Adds the accessors the nestmates of the class use, and makes its calls of private
+    /// methods of nestmates and of itself valid without nests.
+    fn replace_nest_access(&self, class: &mut ClassFile) -> JomResult<()> {
+        let this = class.this_class.clone();
+        let accessors = self.accessors();
+
+        for (access, user) in &self.uses {
+            if access.owner == this && access.name == "<init>" && self.needs_accessor(access, user)
+            {
+                let constructor = class
+                    .methods
+                    .iter_mut()
+                    .find(|x| x.name == "<init>" && x.descriptor == access.descriptor);
+                if let Some(constructor) = constructor {
+                    constructor.access_flags &= !ACC_PRIVATE;
+                }
+            }
+        }
+        for (access, name) in &accessors {
+            if access.owner == this {
+                let method = accessor(access, name, &mut class.constant_pool)?;
+                class.methods.push(method);
+            }
+        }
+
+        let private_methods = class
+            .methods
+            .iter()
+            .filter(|x| x.access_flags & ACC_PRIVATE != 0)
+            .map(|x| (x.name.clone(), x.descriptor.clone()))
+            .collect::<HashSet<_>>();
+        let cp = &mut class.constant_pool;
+        for method in &mut class.methods {
+            let Some(code) = method.code_mut() else {
+                continue;
+            };
+
+            let mut i = 0;
+            while i < code.code.len() {
+                let instruction = &code.code[i];
+                let Some(access) = access(instruction, cp)? else {
+                    i += 1;
+                    continue;
+                };
+
+                // Private methods are called with `invokespecial` before nests.
+                let own = access.owner == this
+                    && access.kind == AccessKind::Invoke
+                    && private_methods.contains(&(access.name.clone(), access.descriptor.clone()));
+                match (instruction, accessors.get(&access)) {
+                    (Instruction::InvokeVirtual(x), _) if own => {
+                        code.code[i] = Instruction::InvokeSpecial(*x);
+                    }
+                    (Instruction::InvokeInterface(x, _), _) if own => {
+                        code.code[i] = Instruction::InvokeSpecial(*x);
+                        code.code
+                            .splice(i + 1..i + 1, [Instruction::Nop, Instruction::Nop]);
+                    }
+                    (_, Some(name)) if self.needs_accessor(&access, &this) => {
+                        let descriptor = accessor_descriptor(&access)?.to_string();
+                        let index = cp.insert(ConstantPoolIndex::Methodref {
+                            class: access.owner.clone(),
+                            name: name.clone(),
+                            descriptor,
+                        })?;
+                        code.code[i] = Instruction::InvokeStatic(index);
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The nest host of a class, which is the class itself for hosts.
+fn nest_host(class: &ClassFile) -> JomResult<Option<String>> {
+    for attribute in class.attributes() {
+        match attribute {
+            ClassAttribute::Unknown(name, info) if name == "NestHost" => {
+                let index = <u16 as BinRead>::read_be(&mut Cursor::new(info))?;
+                return Ok(Some(class.constant_pool().get_class(index)?));
+            }
+            ClassAttribute::Unknown(name, _) if name == "NestMembers" => {
+                return Ok(Some(class.this_class().to_owned()));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(None)
+}
+
+/// The member an instruction uses, if it uses one.
+fn access(instruction: &Instruction, cp: &ConstantPool) -> JomResult<Option<Access>> {
+    let (kind, index) = match *instruction {
+        Instruction::GetField(x) => (AccessKind::GetField, x),
+        Instruction::PutField(x) => (AccessKind::PutField, x),
+        Instruction::GetStatic(x) => (AccessKind::GetStatic, x),
+        Instruction::PutStatic(x) => (AccessKind::PutStatic, x),
+        Instruction::InvokeVirtual(x)
+        | Instruction::InvokeSpecial(x)
+        | Instruction::InvokeInterface(x, _) => (AccessKind::Invoke, x),
+        Instruction::InvokeStatic(x) => (AccessKind::InvokeStatic, x),
+        _ => return Ok(None),
+    };
+
+    let (owner, name, descriptor) = match cp.get(index)? {
+        ConstantPoolIndex::Fieldref {
+            class,
+            name,
+            descriptor,
+        }
+        | ConstantPoolIndex::Methodref {
+            class,
+            name,
+            descriptor,
+        }
+        | ConstantPoolIndex::InterfaceMethodref {
+            class,
+            name,
+            descriptor,
+        } => (class, name, descriptor),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(Access {
+        owner,
+        kind,
+        name,
+        descriptor,
+    }))
+}
+
+/// The descriptor of the static method that does `access`.
+fn accessor_descriptor(access: &Access) -> JomResult<MethodDescriptor> {
+    let owner = FieldType::Object(access.owner.clone());
+    let descriptor = match access.kind {
+        AccessKind::Invoke | AccessKind::InvokeStatic => {
+            let mut descriptor = MethodDescriptor::parse(&access.descriptor)?;
+            if access.kind == AccessKind::Invoke {
+                descriptor.parameters.insert(0, owner);
+            }
+            return Ok(descriptor);
+        }
+        _ => FieldType::parse(&access.descriptor)?,
+    };
+
+    let (parameters, return_type) = match access.kind {
+        AccessKind::GetField => (vec![owner], Some(descriptor)),
+        AccessKind::PutField => (vec![owner, descriptor], None),
+        AccessKind::GetStatic => (vec![], Some(descriptor)),
+        _ => (vec![descriptor], None),
+    };
+
+    Ok(MethodDescriptor {
+        parameters,
+        return_type,
+    })
+}
+
+/// A synthetic method that does `access` in the class that owns the member.
+fn accessor(access: &Access, name: &str, cp: &mut ConstantPool) -> JomResult<MethodInfo> {
+    let descriptor = accessor_descriptor(access)?;
+
+    let mut code = vec![];
+    let mut slot = 0;
+    for parameter in &descriptor.parameters {
+        code.push(Instruction::load(parameter, slot));
+        slot += parameter.size() as u16;
+    }
+
+    let field = ConstantPoolIndex::Fieldref {
+        class: access.owner.clone(),
+        name: access.name.clone(),
+        descriptor: access.descriptor.clone(),
+    };
+    let method = ConstantPoolIndex::Methodref {
+        class: access.owner.clone(),
+        name: access.name.clone(),
+        descriptor: access.descriptor.clone(),
+    };
+    code.push(match access.kind {
+        AccessKind::GetField => Instruction::GetField(cp.insert(field)?),
+        AccessKind::PutField => Instruction::PutField(cp.insert(field)?),
+        AccessKind::GetStatic => Instruction::GetStatic(cp.insert(field)?),
+        AccessKind::PutStatic => Instruction::PutStatic(cp.insert(field)?),
+        AccessKind::Invoke => Instruction::InvokeSpecial(cp.insert(method)?),
+        AccessKind::InvokeStatic => Instruction::InvokeStatic(cp.insert(method)?),
+    });
+    code.push(Instruction::return_value(descriptor.return_type.as_ref()));
+
+    synthetic_method(ACC_STATIC | ACC_SYNTHETIC, name, &descriptor, code, cp)
+}
+
+fn synthetic_method(
+    access_flags: u16,
+    name: &str,
+    descriptor: &MethodDescriptor,
+    code: Vec<Instruction>,
+    cp: &ConstantPool,
+) -> JomResult<MethodInfo> {
+    let mut code = Code {
+        max_stack: 0,
+        max_locals: 0,
+        code,
+        exception_table: vec![],
+        attributes: vec![],
+    };
+    code.max_stack = code.compute_max_stack(cp)?;
+    code.max_locals = code.compute_max_locals(descriptor, true);
+
+    Ok(MethodInfo {
+        access_flags,
+        name: name.to_owned(),
+        descriptor: descriptor.to_string(),
+        attributes: vec![MethodAttribute::Code(code)],
+    })
+}
+
+/// The parts of the string an `invokedynamic` of `StringConcatFactory` builds, and the
+/// descriptor of its arguments, or `None` for other call sites.
+fn string_concat(
+    index: u16,
+    cp: &ConstantPool,
+    bootstrap_methods: &[(u16, Vec<u16>)],
+) -> JomResult<Option<(Vec<Part>, String)>> {
+    let ConstantPoolIndex::InvokeDynamic {
+        bootstrap_method_attr_index,
+        name,
+        descriptor,
+    } = cp.get(index)?
+    else {
+        return Ok(None);
+    };
+    let Some((handle, arguments)) = bootstrap_methods.get(bootstrap_method_attr_index as usize)
+    else {
+        return Ok(None);
+    };
+    let ConstantPoolIndex::MethodHandle { class, .. } = cp.get(*handle)? else {
+        return Ok(None);
+    };
+    if class != STRING_CONCAT_FACTORY {
+        return Ok(None);
+    }
+
+    let count = MethodDescriptor::parse(&descriptor)?.parameters.len();
+    let recipe = match (name.as_str(), arguments.split_first()) {
+        ("makeConcat", _) => "\u{1}".repeat(count),
+        ("makeConcatWithConstants", Some((&recipe, _))) => cp.get(recipe)?.into_string()?,
+        _ => return Ok(None),
+    };
+    let mut constants = arguments.iter().skip(1);
+
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut argument = 0;
+    for c in recipe.chars() {
+        let part = match c {
+            '\u{1}' => {
+                argument += 1;
+                Part::Argument(argument - 1)
+            }
+            '\u{2}' => {
+                let Some(&constant) = constants.next() else {
+                    return Ok(None);
+                };
+                // Only strings are passed as constants, as the rest is part of the recipe.
+                let ConstantPoolIndex::String(s) = cp.get(constant)? else {
+                    return Ok(None);
+                };
+                literal.push_str(&s);
+                continue;
+            }
+            c => {
+                literal.push(c);
+                continue;
+            }
+        };
+        if !literal.is_empty() {
+            parts.push(Part::Literal(std::mem::take(&mut literal)));
+        }
+        parts.push(part);
+    }
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+
+    Ok(Some((parts, descriptor)))
+}
+
+/// Replaces string concatenation with `invokedynamic` by calls of synthetic methods that
+/// build the string with a `StringBuilder`, returning the concatenations left in place.
+fn replace_string_concat(
+    class: &mut ClassFile,
+    target: ClassVersion,
+) -> JomResult<Vec<VersionIssue>> {
+    let interface = class.access_flags() & ACC_INTERFACE != 0;
+    // Interfaces can only have public static methods, from Java 8 on.
+    let helpers_allowed = !interface || target.major >= 52;
+    let access_flags = match interface {
+        true => ACC_PUBLIC | ACC_STATIC | ACC_SYNTHETIC,
+        false => ACC_PRIVATE | ACC_STATIC | ACC_SYNTHETIC,
+    };
+
+    let bootstrap_methods = bootstrap_methods(class.attributes())?;
+    let mut names = class
+        .methods
+        .iter()
+        .map(|x| x.name.clone())
+        .collect::<HashSet<_>>();
+    let mut helpers: HashMap<(Vec<Part>, String), String> = HashMap::new();
+    let mut new_methods = vec![];
+    let mut issues = vec![];
+    let this = class.this_class.clone();
+    let cp = &mut class.constant_pool;
+
+    for method in &mut class.methods {
+        let path = ErrorPath::Method {
+            name: method.name.clone(),
+            descriptor: method.descriptor.clone(),
+        };
+        let Some(code) = method.code_mut() else {
+            continue;
+        };
+
+        let mut i = 0;
+        while i < code.code.len() {
+            let Instruction::InvokeDynamic(index) = code.code[i] else {
+                i += 1;
+                continue;
+            };
+            let Some((parts, descriptor)) = string_concat(index, cp, &bootstrap_methods)? else {
+                i += 1;
+                continue;
+            };
+            if !helpers_allowed {
+                let pc = code.code[..i].iter().fold(0, |pc, x| pc + x.size(pc));
+                issues.push(VersionIssue {
+                    feature: Feature::StringConcat,
+                    path: vec![
+                        path.clone(),
+                        ErrorPath::Attribute("Code".to_owned()),
+                        ErrorPath::Pc(pc),
+                    ],
+                });
+                i += 1;
+                continue;
+            }
+
+            let key = (parts, descriptor.clone());
+            let name = match helpers.get(&key) {
+                Some(x) => x.clone(),
+                None => {
+                    let name = (0..)
+                        .map(|x| format!("concat${x}"))
+                        .find(|x| !names.contains(x))
+                        .unwrap();
+                    let descriptor = MethodDescriptor::parse(&descriptor)?;
+                    let code = string_builder(&key.0, &descriptor, cp)?;
+                    new_methods.push(synthetic_method(
+                        access_flags,
+                        &name,
+                        &descriptor,
+                        code,
+                        cp,
+                    )?);
+                    names.insert(name.clone());
+                    helpers.insert(key, name.clone());
+                    name
+                }
+            };
+
+            let helper = match interface {
+                true => ConstantPoolIndex::InterfaceMethodref {
+                    class: this.clone(),
+                    name,
+                    descriptor,
+                },
+                false => ConstantPoolIndex::Methodref {
+                    class: this.clone(),
+                    name,
+                    descriptor,
+                },
+            };
+            code.code[i] = Instruction::InvokeStatic(cp.insert(helper)?);
+            code.code
+                .splice(i + 1..i + 1, [Instruction::Nop, Instruction::Nop]);
+            i += 3;
+        }
+    }
+    class.methods.extend(new_methods);
+
+    Ok(issues)
+}
+
+/// Code that appends the parts to a `StringBuilder`, taking the arguments from the
+/// parameters of a static method, and returns the string.
+fn string_builder(
+    parts: &[Part],
+    descriptor: &MethodDescriptor,
+    cp: &mut ConstantPool,
+) -> JomResult<Vec<Instruction>> {
+    let method = |name: &str, descriptor: String| ConstantPoolIndex::Methodref {
+        class: STRING_BUILDER.to_owned(),
+        name: name.to_owned(),
+        descriptor,
+    };
+
+    let mut code = vec![
+        Instruction::New(cp.insert_class(STRING_BUILDER.to_owned())?),
+        Instruction::Dup,
+        Instruction::InvokeSpecial(cp.insert(method("<init>", "()V".to_owned()))?),
+    ];
+    for part in parts {
+        let ty = match part {
+            Part::Literal(s) => {
+                code.push(Instruction::ldc(
+                    cp.insert(ConstantPoolIndex::String(s.clone()))?,
+                ));
+                "Ljava/lang/String;".to_owned()
+            }
+            Part::Argument(i) => {
+                let slot = descriptor.parameters[..*i]
+                    .iter()
+                    .map(FieldType::size)
+                    .sum::<usize>();
+                let parameter = &descriptor.parameters[*i];
+                code.push(Instruction::load(parameter, slot as u16));
+                // `StringBuilder` has no overloads for `byte`, `short` and most classes.
+                match parameter {
+                    FieldType::Byte | FieldType::Short => "I".to_owned(),
+                    FieldType::Object(x) if x == "java/lang/String" => parameter.to_string(),
+                    FieldType::Object(_) | FieldType::Array(_) => "Ljava/lang/Object;".to_owned(),
+                    x => x.to_string(),
+                }
+            }
+        };
+        let append = method("append", format!("({ty})L{STRING_BUILDER};"));
+        code.push(Instruction::InvokeVirtual(cp.insert(append)?));
+    }
+    let to_string = method("toString", "()Ljava/lang/String;".to_owned());
+    code.push(Instruction::InvokeVirtual(cp.insert(to_string)?));
+    code.push(Instruction::AReturn);
+
+    Ok(code)
+}
+
+/// Replaces the constants the target does not allow with empty strings, so that the indices
+/// of the rest stay valid. Constants still used by code or bootstrap methods are kept.
+fn remove_unused_constants(class: &mut ClassFile, target: ClassVersion) -> JomResult<()> {
+    let mut used = HashSet::new();
+    for code in class.methods.iter().filter_map(MethodInfo::code) {
+        used.extend(
+            code.code
+                .iter()
+                .filter_map(Instruction::constant_pool_index),
+        );
+    }
+    for (handle, arguments) in bootstrap_methods(class.attributes())? {
+        used.insert(handle);
+        used.extend(arguments);
+    }
+
+    for (i, entry) in class.constant_pool.0.iter_mut().enumerate() {
+        if !target.allows(&Feature::Constant(entry.name())) && !used.contains(&(i as u16)) {
+            *entry = ConstantPoolIndex::Utf8(String::new());
+        }
+    }
+
+    Ok(())
+}
@@ -1,21 +1,235 @@
+use std::{fmt, io, path::PathBuf};
+
 use binrw::Error as BinError;
 use thiserror::Error;
 
-pub(crate) type JomResult<T> = Result<T, JomError>;
+use crate::{
+    utf8::InvalidModifiedUtf8,
+    version::{ClassVersion, Feature},
+};
+
+pub type JomResult<T> = Result<T, JomError>;
 
 #[derive(Error, Debug)]
 pub enum JomError {
     #[error("{0}")]
-    BinError(#[from] BinError),
-    #[error("constant pool error: expected {0}, found {0}")]
+    BinError(BinError),
+    #[error("bad magic {0:#010x}, expected 0xcafebabe")]
+    BadMagic(u32),
+    #[error("unsupported class file version {0}.{1}")]
+    UnsupportedVersion(u16, u16),
+    #[error("{0} is not allowed in {1}")]
+    NotAllowed(Feature, ClassVersion),
+    #[error("invalid opcode {0:#04x}")]
+    InvalidOpcode(u8),
+    #[error("unexpected end of data")]
+    Truncated,
+    #[error("invalid modified UTF-8")]
+    InvalidUtf8,
+    #[error("constant pool error: expected {0}, found {1}")]
     ConstantPoolIndexError(&'static str, &'static str),
     #[error("value {0} not in constant pool")]
     ValueNotInConstantPool(String),
     #[error("constant pool index {0} is out of bounds")]
     OutOfBounds(u16),
+    #[error("invalid descriptor {0}")]
+    InvalidDescriptor(String),
+    #[error("analysis error at instruction {0}: {1}")]
+    AnalysisError(usize, String),
+    #[error("constant pool is full")]
+    ConstantPoolFull,
+    #[error("attribute {0} cannot be written")]
+    UnwritableAttribute(&'static str),
+    #[error("assembly error on line {0}: {1}")]
+    AssemblyError(usize, String),
+    #[error("invalid code: {0}")]
+    InvalidCode(String),
+    #[error("invalid class: {0}")]
+    InvalidClass(String),
+    #[error("invalid archive: {0}")]
+    InvalidArchive(String),
+    #[error("invalid manifest: {0}")]
+    InvalidManifest(String),
+    #[error("invalid mapping: {0}")]
+    InvalidMapping(String),
+    #[error("{}: {1}", .0.display())]
+    IoError(PathBuf, #[source] io::Error),
+    /// Another error, with where in the class it happened.
+    #[error("{}{source}", Location(.path, .offset))]
+    Located {
+        /// From the outside in, like the class, method, attribute and instruction.
+        path: Vec<ErrorPath>,
+        /// The offset in the class file.
+        offset: Option<u64>,
+        #[source]
+        source: Box<JomError>,
+    },
+}
+
+/// A part of the path to where an error happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErrorPath {
+    Class(String),
+    Field { name: String, descriptor: String },
+    Method { name: String, descriptor: String },
+    Attribute(String),
+    /// The offset of an instruction in the code.
+    Pc(u32),
+    /// The index of a constant pool entry.
+    Constant(u16),
+}
+
+impl fmt::Display for ErrorPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Class(name) => write!(f, "class {name}"),
+            Self::Field { name, descriptor } => write!(f, "field {name} {descriptor}"),
+            Self::Method { name, descriptor } => write!(f, "method {name}{descriptor}"),
+            Self::Attribute(name) => write!(f, "attribute {name}"),
+            Self::Pc(pc) => write!(f, "pc {pc}"),
+            Self::Constant(index) => write!(f, "constant #{index}"),
+        }
+    }
+}
+
+/// Displays the prefix of a located error, like `class A > method b()V at offset 12: `.
+pub(crate) struct Location<'a>(pub(crate) &'a [ErrorPath], pub(crate) &'a Option<u64>);
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, part) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " > ")?;
+            }
+            write!(f, "{part}")?;
+        }
+        match *self.1 {
+            Some(offset) if self.0.is_empty() => write!(f, "at offset {offset}: "),
+            Some(offset) => write!(f, " at offset {offset}: "),
+            None => write!(f, ": "),
+        }
+    }
+}
+
+/// Whether every variant of an enum failed before reading anything past its tag.
+pub(crate) fn unmatched(error: &BinError) -> bool {
+    match error.root_cause() {
+        BinError::BadMagic { .. } | BinError::AssertFail { .. } => true,
+        BinError::NoVariantMatch { .. } => true,
+        BinError::EnumErrors { variant_errors, .. } => {
+            variant_errors.iter().all(|(_, x)| unmatched(x))
+        }
+        _ => false,
+    }
+}
+
+/// The error of the only enum variant whose tag matched, if there is one.
+fn decisive(error: &BinError) -> &BinError {
+    match error.root_cause() {
+        BinError::EnumErrors { variant_errors, .. } => {
+            let mut matched = variant_errors.iter().filter(|(_, x)| !unmatched(x));
+            match (matched.next(), matched.next()) {
+                (Some((_, x)), None) => decisive(x),
+                _ => error,
+            }
+        }
+        x => x,
+    }
+}
+
+impl From<BinError> for JomError {
+    fn from(error: BinError) -> Self {
+        let cause = decisive(&error);
+        if error.is_eof() || cause.is_eof() {
+            return Self::Truncated;
+        }
+        if let BinError::Custom { pos, err } = cause {
+            if err.is::<InvalidModifiedUtf8>() {
+                return Self::InvalidUtf8.at(*pos);
+            }
+        }
+
+        Self::BinError(error)
+    }
 }
 
 impl JomError {
+    /// The error without the path and offset of where it happened.
+    pub fn root_cause(&self) -> &JomError {
+        match self {
+            Self::Located { source, .. } => source,
+            x => x,
+        }
+    }
+
+    /// Where the error happened, from the outside in.
+    pub fn path(&self) -> &[ErrorPath] {
+        match self {
+            Self::Located { path, .. } => path,
+            _ => &[],
+        }
+    }
+
+    /// The offset in the class file where the error happened, if it is known.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Self::Located { offset, .. } => *offset,
+            _ => None,
+        }
+    }
+
+    /// Adds an outer part to the path of the error.
+    pub(crate) fn within(self, part: ErrorPath) -> Self {
+        match self {
+            Self::Located {
+                mut path,
+                offset,
+                source,
+            } => {
+                path.insert(0, part);
+                Self::Located {
+                    path,
+                    offset,
+                    source,
+                }
+            }
+            x => Self::Located {
+                path: vec![part],
+                offset: None,
+                source: Box::new(x),
+            },
+        }
+    }
+
+    /// Sets the offset of the error, unless a more precise one is already known.
+    pub(crate) fn at(self, offset: u64) -> Self {
+        match self {
+            Self::Located {
+                path,
+                offset: None,
+                source,
+            } => Self::Located {
+                path,
+                offset: Some(offset),
+                source,
+            },
+            x @ Self::Located { .. } => x,
+            x => Self::Located {
+                path: vec![],
+                offset: Some(offset),
+                source: Box::new(x),
+            },
+        }
+    }
+
+    /// Gives the offset of a truncation, which is where the data ended.
+    pub(crate) fn truncated_at(self, length: usize) -> Self {
+        match self {
+            Self::Truncated => self.at(length as u64),
+            x => x,
+        }
+    }
+
     pub(crate) fn new_cp_index(expected: &'static str, found: &'static str) -> Self {
         Self::ConstantPoolIndexError(expected, found)
     }
@@ -27,4 +241,44 @@ impl JomError {
     pub(crate) fn out_of_bounds(index: u16) -> Self {
         Self::OutOfBounds(index)
     }
+
+    pub(crate) fn invalid_descriptor(descriptor: &str) -> Self {
+        Self::InvalidDescriptor(descriptor.to_owned())
+    }
+
+    pub(crate) fn analysis(index: usize, message: impl Into<String>) -> Self {
+        Self::AnalysisError(index, message.into())
+    }
+
+    pub(crate) fn unwritable(attribute: &'static str) -> Self {
+        Self::UnwritableAttribute(attribute)
+    }
+
+    pub(crate) fn assembly(line: usize, message: impl Into<String>) -> Self {
+        Self::AssemblyError(line, message.into())
+    }
+
+    pub(crate) fn invalid_code(message: impl Into<String>) -> Self {
+        Self::InvalidCode(message.into())
+    }
+
+    pub(crate) fn invalid_class(message: impl Into<String>) -> Self {
+        Self::InvalidClass(message.into())
+    }
+
+    pub(crate) fn invalid_archive(message: impl Into<String>) -> Self {
+        Self::InvalidArchive(message.into())
+    }
+
+    pub(crate) fn invalid_manifest(message: impl Into<String>) -> Self {
+        Self::InvalidManifest(message.into())
+    }
+
+    pub(crate) fn invalid_mapping(message: impl Into<String>) -> Self {
+        Self::InvalidMapping(message.into())
+    }
+
+    pub(crate) fn io(path: impl Into<PathBuf>, error: io::Error) -> Self {
+        Self::IoError(path.into(), error)
+    }
 }
@@ -2,7 +2,8 @@ use binrw::binrw;
 
 use crate::{
     constant_pool::ConstantPool,
-    error::JomResult, attribute::{RawAttribute, FieldAttribute},
+    error::{ErrorPath, JomResult}, attribute::{RawAttribute, FieldAttribute},
+    ReadState,
 };
 
 #[binrw]
@@ -18,18 +19,39 @@ pub(crate) struct RawFieldInfo {
 }
 
 impl RawFieldInfo {
-    pub fn into_field_info(self, cp: &ConstantPool) -> JomResult<FieldInfo> {
+    pub fn into_field_info(self, cp: &ConstantPool, state: &mut ReadState) -> JomResult<FieldInfo> {
         let name = cp.get_utf8(self.name)?;
         let descriptor = cp.get_utf8(self.descriptor)?;
 
+        let attributes = self
+            .attributes
+            .into_iter()
+            .map(|x| x.into_field_attr(cp, state))
+            .collect::<JomResult<Vec<_>>>()
+            .map_err(|e| {
+                e.within(ErrorPath::Field {
+                    name: name.clone(),
+                    descriptor: descriptor.clone(),
+                })
+            })?;
+
         Ok(FieldInfo {
             access_flags: self.access_flags,
             name,
             descriptor,
-            attributes: self
+            attributes,
+        })
+    }
+
+    pub fn from_field_info(field: &FieldInfo, cp: &mut ConstantPool) -> JomResult<Self> {
+        Ok(RawFieldInfo {
+            access_flags: field.access_flags,
+            name: cp.insert_utf8(field.name.clone())?,
+            descriptor: cp.insert_utf8(field.descriptor.clone())?,
+            attributes: field
                 .attributes
-                .into_iter()
-                .map(|x| x.into_field_attr(cp))
+                .iter()
+                .map(|x| RawAttribute::from_field_attr(x, cp))
                 .collect::<JomResult<Vec<_>>>()?,
         })
     }
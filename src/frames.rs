@@ -0,0 +1,241 @@
+//! Computing the `StackMapTable` of code that has none, which class files from version 50
+//! on need for code with branches or exception handlers.
+//!
+//! The types are those of an [`Analyzer`] with a [`VerifierInterpreter`], which merges two
+//! classes to their common superclass in a [`ClassHierarchy`]. Classes missing from the
+//! hierarchy are treated as direct subclasses of `java/lang/Object`, so it should hold the
+//! classes whose values the code merges, and their supertypes.
+
+use crate::{
+    access::ACC_STATIC,
+    analysis::{
+        frame::Value,
+        interpreter::Interpreter,
+        verifier::{VerifierInterpreter, VerifierValue},
+        Analyzer,
+    },
+    attribute::CodeAttribute,
+    constant_pool::ConstantPool,
+    descriptor::MethodDescriptor,
+    error::{ErrorPath, JomError, JomResult},
+    hierarchy::ClassHierarchy,
+    method::{
+        code::{
+            stack_map::{StackMapFrame, VerificationType},
+            Code,
+        },
+        MethodInfo,
+    },
+    ClassFile,
+};
+
+impl ClassFile {
+    /// Adds a `StackMapTable` to the code of every method that has branches or exception
+    /// handlers and no `StackMapTable`. Code skipped when the class was read is decoded
+    /// first.
+    ///
+    /// Fails if an instruction that needs a frame is unreachable, as its types are unknown.
+    pub fn compute_frames(&mut self, hierarchy: &ClassHierarchy) -> JomResult<()> {
+        let analyzer = Analyzer::new(VerifierInterpreter::new(hierarchy));
+
+        for i in 0..self.methods.len() {
+            let method = self.decode_method(i)?;
+            let path = ErrorPath::Method {
+                name: method.name.clone(),
+                descriptor: method.descriptor.clone(),
+            };
+            let within = |e: JomError| {
+                e.within(ErrorPath::Attribute("Code".to_owned()))
+                    .within(path.clone())
+            };
+
+            let Some(code) = self.methods[i].code() else {
+                continue;
+            };
+            let has_frames = code
+                .attributes
+                .iter()
+                .any(|x| matches!(x, CodeAttribute::StackMapTable) || x.name() == "StackMapTable");
+            let targets = frame_targets(code).map_err(within)?;
+            if has_frames || !targets.contains(&true) {
+                continue;
+            }
+
+            let frames = analyzer.analyze(self, &self.methods[i]).map_err(within)?;
+            let mut needed = vec![];
+            for (n, pc) in pcs(code).into_iter().enumerate() {
+                if !targets[n] {
+                    continue;
+                }
+                let frame = frames[n].as_ref().ok_or_else(|| {
+                    within(JomError::invalid_code(format!(
+                        "unreachable code at offset {pc} needs a stack map frame"
+                    )))
+                })?;
+                needed.push((pc, frame.locals().to_vec(), frame.stack().to_vec()));
+            }
+            let initial =
+                initial_locals(self, &self.methods[i], analyzer.interpreter()).map_err(within)?;
+
+            let pcs = pcs(self.methods[i].code().expect("the method has code"));
+            let info = stack_map_table(&initial, &needed, &pcs, &mut self.constant_pool)
+                .map_err(within)?;
+            let code = self.methods[i].code_mut().expect("the method has code");
+            code.attributes
+                .push(CodeAttribute::Unknown("StackMapTable".to_owned(), info));
+        }
+
+        Ok(())
+    }
+}
+
+/// The offset of every instruction.
+fn pcs(code: &Code) -> Vec<u32> {
+    let mut pcs = Vec::with_capacity(code.code.len());
+    let mut pc = 0;
+    for instruction in &code.code {
+        pcs.push(pc);
+        pc += instruction.size(pc);
+    }
+
+    pcs
+}
+
+/// Whether each instruction needs a frame, being the target of a jump or an exception
+/// handler, or following an instruction that does not fall through.
+fn frame_targets(code: &Code) -> JomResult<Vec<bool>> {
+    let pcs = pcs(code);
+    let index_of = |pc: i64| {
+        u32::try_from(pc)
+            .ok()
+            .and_then(|x| pcs.binary_search(&x).ok())
+            .ok_or_else(|| JomError::invalid_code(format!("invalid jump target {pc}")))
+    };
+
+    let mut targets = vec![false; code.code.len()];
+    let mut branches = !code.exception_table.is_empty();
+    for (i, instruction) in code.code.iter().enumerate() {
+        for offset in instruction.branch_offsets() {
+            targets[index_of(pcs[i] as i64 + offset as i64)?] = true;
+            branches = true;
+        }
+        if !instruction.falls_through() && i + 1 < targets.len() {
+            targets[i + 1] = true;
+        }
+    }
+    for exception in &code.exception_table {
+        targets[index_of(exception.handler_pc as i64)?] = true;
+    }
+
+    // Code that neither branches nor handles exceptions needs no frames.
+    if !branches {
+        targets.fill(false);
+    }
+
+    Ok(targets)
+}
+
+/// The locals at the start of the method, which the first frame is relative to.
+fn initial_locals(
+    class: &ClassFile,
+    method: &MethodInfo,
+    interpreter: &VerifierInterpreter,
+) -> JomResult<Vec<VerifierValue>> {
+    let mut locals = vec![];
+    if method.access_flags & ACC_STATIC == 0 {
+        locals.push(interpreter.new_this_value(class.this_class(), method.name == "<init>"));
+    }
+    for parameter in MethodDescriptor::parse(&method.descriptor)?.parameters {
+        locals.push(interpreter.new_value(Some(&parameter)));
+        if parameter.size() == 2 {
+            locals.push(interpreter.new_value(None));
+        }
+    }
+
+    Ok(locals)
+}
+
+fn stack_map_table(
+    initial: &[VerifierValue],
+    frames: &[(u32, Vec<VerifierValue>, Vec<VerifierValue>)],
+    pcs: &[u32],
+    constant_pool: &mut ConstantPool,
+) -> JomResult<Vec<u8>> {
+    let mut info = (frames.len() as u16).to_be_bytes().to_vec();
+    let mut previous_locals = types(initial, true, pcs, constant_pool)?;
+    let mut previous_pc = None;
+
+    for (pc, locals, stack) in frames {
+        let locals = types(locals, true, pcs, constant_pool)?;
+        let mut stack = types(stack, false, pcs, constant_pool)?;
+
+        let frame = if locals == previous_locals && stack.len() <= 1 {
+            match stack.pop() {
+                Some(x) => StackMapFrame::SameLocals1StackItem(x),
+                None => StackMapFrame::Same,
+            }
+        } else if stack.is_empty()
+            && locals.len() > previous_locals.len()
+            && locals.len() - previous_locals.len() <= 3
+            && locals.starts_with(&previous_locals)
+        {
+            StackMapFrame::Append(locals[previous_locals.len()..].to_vec())
+        } else if stack.is_empty()
+            && locals.len() < previous_locals.len()
+            && previous_locals.len() - locals.len() <= 3
+            && previous_locals.starts_with(&locals)
+        {
+            StackMapFrame::Chop((previous_locals.len() - locals.len()) as u8)
+        } else {
+            StackMapFrame::Full(locals.clone(), stack)
+        };
+
+        let delta = match previous_pc {
+            None => *pc,
+            Some(x) => pc - x - 1,
+        };
+        frame.encode(delta as u16, &mut info, &|&x| Ok(x))?;
+        previous_locals = locals;
+        previous_pc = Some(*pc);
+    }
+
+    Ok(info)
+}
+
+/// The verification types of the values, with the slot after a `long` or `double` left out.
+/// Trailing `Top` locals are trimmed.
+fn types(
+    values: &[VerifierValue],
+    locals: bool,
+    pcs: &[u32],
+    constant_pool: &mut ConstantPool,
+) -> JomResult<Vec<VerificationType<u16>>> {
+    let mut types = vec![];
+    let mut values = values.iter();
+    while let Some(value) = values.next() {
+        types.push(match value {
+            VerifierValue::Top => VerificationType::Top,
+            VerifierValue::Int => VerificationType::Integer,
+            VerifierValue::Float => VerificationType::Float,
+            VerifierValue::Long => VerificationType::Long,
+            VerifierValue::Double => VerificationType::Double,
+            VerifierValue::Null => VerificationType::Null,
+            VerifierValue::UninitializedThis(_) => VerificationType::UninitializedThis,
+            VerifierValue::Uninitialized(i, _) => VerificationType::Uninitialized(pcs[*i] as u16),
+            VerifierValue::Reference(class) => {
+                VerificationType::Object(constant_pool.insert_class(class.clone())?)
+            }
+        });
+        if locals && value.size() == 2 {
+            values.next();
+        }
+    }
+
+    if locals {
+        while types.last() == Some(&VerificationType::Top) {
+            types.pop();
+        }
+    }
+
+    Ok(types)
+}
@@ -0,0 +1,365 @@
+//! Subtyping and method lookup across a set of classes.
+//!
+//! Classes that aren't in the hierarchy are treated as having only `java/lang/Object` as a
+//! supertype, so queries on incomplete hierarchies give conservative answers instead of
+//! failing. Use [`ClassHierarchy::add_from`] to pull in the supertypes from a class path.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    access::{ACC_ABSTRACT, ACC_INTERFACE, ACC_PRIVATE, ACC_STATIC},
+    class_path::ClassPath,
+    error::JomResult,
+    ClassFile,
+};
+
+const OBJECT: &str = "java/lang/Object";
+
+/// The supertypes of every array besides `java/lang/Object`.
+const ARRAY_INTERFACES: &[&str] = &["java/lang/Cloneable", "java/io/Serializable"];
+
+struct Method {
+    name: String,
+    descriptor: String,
+    access_flags: u16,
+}
+
+struct Class {
+    access_flags: u16,
+    super_class: Option<String>,
+    interfaces: Vec<String>,
+    methods: Vec<Method>,
+}
+
+impl Class {
+    fn method(&self, name: &str, descriptor: &str) -> Option<&Method> {
+        self.methods
+            .iter()
+            .find(|x| x.name == name && x.descriptor == descriptor)
+    }
+}
+
+#[derive(Default)]
+pub struct ClassHierarchy {
+    classes: HashMap<String, Class>,
+    /// Direct subclasses and implementors of each class.
+    subtypes: HashMap<String, Vec<String>>,
+}
+
+impl ClassHierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_classes<'a>(classes: impl IntoIterator<Item = &'a ClassFile>) -> Self {
+        let mut hierarchy = Self::new();
+        for class in classes {
+            hierarchy.add(class);
+        }
+
+        hierarchy
+    }
+
+    /// Adds a class, replacing any class with the same name.
+    pub fn add(&mut self, class: &ClassFile) {
+        let name = class.this_class().to_owned();
+        self.remove(&name);
+
+        let super_class = match class.super_class() {
+            "" => None,
+            x => Some(x.to_owned()),
+        };
+        for supertype in super_class.iter().chain(class.interfaces()) {
+            self.subtypes
+                .entry(supertype.clone())
+                .or_default()
+                .push(name.clone());
+        }
+
+        let methods = class
+            .methods()
+            .iter()
+            .map(|x| Method {
+                name: x.name.clone(),
+                descriptor: x.descriptor.clone(),
+                access_flags: x.access_flags,
+            })
+            .collect();
+
+        self.classes.insert(
+            name,
+            Class {
+                access_flags: class.access_flags(),
+                super_class,
+                interfaces: class.interfaces().to_vec(),
+                methods,
+            },
+        );
+    }
+
+    fn remove(&mut self, name: &str) {
+        let Some(class) = self.classes.remove(name) else {
+            return;
+        };
+
+        for supertype in class.super_class.iter().chain(&class.interfaces) {
+            if let Some(subtypes) = self.subtypes.get_mut(supertype) {
+                subtypes.retain(|x| x != name);
+            }
+        }
+    }
+
+    /// Adds a class and, transitively, its supertypes that are on the class path.
+    pub fn add_from(&mut self, class_path: &ClassPath, name: &str) -> JomResult<()> {
+        let mut queue = VecDeque::from([name.to_owned()]);
+
+        while let Some(name) = queue.pop_front() {
+            if self.classes.contains_key(&name) {
+                continue;
+            }
+            let Some(class) = class_path.resolve(&name)? else {
+                continue;
+            };
+
+            self.add(&class);
+            let class = &self.classes[&name];
+            queue.extend(class.super_class.iter().chain(&class.interfaces).cloned());
+        }
+
+        Ok(())
+    }
+
+    /// The name as stored in the hierarchy, so that it can be returned with its lifetime.
+    fn own_name(&self, name: &str) -> Option<&str> {
+        self.classes.get_key_value(name).map(|(x, _)| x.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.classes.contains_key(name)
+    }
+
+    pub fn is_interface(&self, name: &str) -> bool {
+        self.classes
+            .get(name)
+            .is_some_and(|x| x.access_flags & ACC_INTERFACE != 0)
+    }
+
+    /// Whether the class is abstract or an interface, so it can't be instantiated.
+    pub fn is_abstract(&self, name: &str) -> bool {
+        self.classes
+            .get(name)
+            .is_some_and(|x| x.access_flags & (ACC_ABSTRACT | ACC_INTERFACE) != 0)
+    }
+
+    /// The access flags of a method declared by the class.
+    pub fn method_access_flags(&self, class: &str, name: &str, descriptor: &str) -> Option<u16> {
+        Some(self.classes.get(class)?.method(name, descriptor)?.access_flags)
+    }
+
+    /// The direct superclass, which is `None` for `java/lang/Object`.
+    pub fn super_class(&self, name: &str) -> Option<&str> {
+        match self.classes.get(name) {
+            Some(class) => class.super_class.as_deref(),
+            None if name == OBJECT => None,
+            None => Some(OBJECT),
+        }
+    }
+
+    /// The direct superinterfaces.
+    pub fn interfaces(&self, name: &str) -> &[String] {
+        self.classes.get(name).map_or(&[], |x| &x.interfaces)
+    }
+
+    /// The superclasses from the direct superclass up to `java/lang/Object`.
+    pub fn superclasses(&self, name: &str) -> Vec<&str> {
+        let mut superclasses = vec![];
+        let mut current = name;
+
+        while let Some(super_class) = self.super_class(current) {
+            // Guard against cyclic hierarchies in malformed input.
+            if superclasses.contains(&super_class) {
+                break;
+            }
+            superclasses.push(super_class);
+            current = super_class;
+        }
+
+        superclasses
+    }
+
+    /// Every superclass and superinterface, nearest first.
+    pub fn supertypes(&self, name: &str) -> Vec<&str> {
+        let mut supertypes = vec![];
+        let mut seen = HashSet::from([name]);
+        let mut queue = VecDeque::from([name]);
+
+        while let Some(current) = queue.pop_front() {
+            let direct = self
+                .super_class(current)
+                .into_iter()
+                .chain(self.interfaces(current).iter().map(String::as_str));
+
+            for supertype in direct {
+                if seen.insert(supertype) {
+                    supertypes.push(supertype);
+                    queue.push_back(supertype);
+                }
+            }
+        }
+
+        supertypes
+    }
+
+    /// Every class in the hierarchy that extends or implements the class, nearest first.
+    pub fn subtypes(&self, name: &str) -> Vec<&str> {
+        let mut subtypes = vec![];
+        let mut seen = HashSet::from([name]);
+        let mut queue = VecDeque::from([name]);
+
+        while let Some(current) = queue.pop_front() {
+            for subtype in self.subtypes.get(current).into_iter().flatten() {
+                if seen.insert(subtype) {
+                    subtypes.push(subtype.as_str());
+                    queue.push_back(subtype);
+                }
+            }
+        }
+
+        subtypes
+    }
+
+    /// Whether `name` is `supertype` or a subtype of it. Array types, like `[I` or
+    /// `[Ljava/lang/String;`, follow the subtyping rules of the JVM.
+    pub fn is_subtype_of(&self, name: &str, supertype: &str) -> bool {
+        if name == supertype || supertype == OBJECT {
+            return true;
+        }
+
+        match (name.strip_prefix('['), supertype.strip_prefix('[')) {
+            (Some(element), Some(super_element)) => {
+                match (reference_type(element), reference_type(super_element)) {
+                    (Some(element), Some(super_element)) => {
+                        self.is_subtype_of(element, super_element)
+                    }
+                    _ => false,
+                }
+            }
+            (Some(_), None) => ARRAY_INTERFACES.contains(&supertype),
+            (None, Some(_)) => false,
+            (None, None) => self.supertypes(name).contains(&supertype),
+        }
+    }
+
+    /// The most specific class that both types are assignable to, as used to merge
+    /// stack map frames. Interfaces have `java/lang/Object` in common with other types.
+    pub fn common_superclass(&self, a: &str, b: &str) -> String {
+        if self.is_subtype_of(a, b) {
+            return b.to_owned();
+        }
+        if self.is_subtype_of(b, a) {
+            return a.to_owned();
+        }
+
+        if let (Some(a), Some(b)) = (a.strip_prefix('['), b.strip_prefix('[')) {
+            if let (Some(a), Some(b)) = (reference_type(a), reference_type(b)) {
+                let common = self.common_superclass(a, b);
+                return match common.starts_with('[') {
+                    true => format!("[{common}"),
+                    false => format!("[L{common};"),
+                };
+            }
+        }
+
+        if a.starts_with('[') || b.starts_with('[') || self.is_interface(a) || self.is_interface(b)
+        {
+            return OBJECT.to_owned();
+        }
+
+        self.superclasses(a)
+            .into_iter()
+            .find(|x| self.is_subtype_of(b, x))
+            .unwrap_or(OBJECT)
+            .to_owned()
+    }
+
+    /// Resolves a method like `invokevirtual` does, searching the class, its
+    /// superclasses and then its superinterfaces. Returns the declaring class.
+    pub fn resolve_method(&self, class: &str, name: &str, descriptor: &str) -> Option<&str> {
+        let declares = |x: &&str| {
+            self.classes
+                .get(*x)
+                .is_some_and(|x| x.method(name, descriptor).is_some())
+        };
+
+        self.own_name(class)
+            .into_iter()
+            .chain(self.superclasses(class))
+            .find(declares)
+            .or_else(|| {
+                // Prefer a default method over an abstract declaration.
+                let interfaces = self
+                    .supertypes(class)
+                    .into_iter()
+                    .filter(|x| self.is_interface(x))
+                    .filter(declares)
+                    .collect::<Vec<_>>();
+
+                interfaces
+                    .iter()
+                    .find(|x| {
+                        let method = self.classes[**x].method(name, descriptor).unwrap();
+                        method.access_flags & ACC_ABSTRACT == 0
+                    })
+                    .or(interfaces.first())
+                    .copied()
+            })
+    }
+
+    /// The supertypes declaring a method that the class's method overrides.
+    pub fn overridden_methods(&self, class: &str, name: &str, descriptor: &str) -> Vec<&str> {
+        if !is_overridable(name) {
+            return vec![];
+        }
+
+        self.supertypes(class)
+            .into_iter()
+            .filter(|x| {
+                self.classes
+                    .get(*x)
+                    .and_then(|x| x.method(name, descriptor))
+                    .is_some_and(|x| x.access_flags & (ACC_PRIVATE | ACC_STATIC) == 0)
+            })
+            .collect()
+    }
+
+    /// The class and its subtypes that declare a concrete implementation of a method.
+    pub fn implementations(&self, class: &str, name: &str, descriptor: &str) -> Vec<&str> {
+        if !is_overridable(name) {
+            return vec![];
+        }
+
+        self.own_name(class)
+            .into_iter()
+            .chain(self.subtypes(class))
+            .filter(|x| {
+                self.classes
+                    .get(*x)
+                    .and_then(|x| x.method(name, descriptor))
+                    .is_some_and(|x| x.access_flags & (ACC_ABSTRACT | ACC_STATIC) == 0)
+            })
+            .collect()
+    }
+}
+
+fn is_overridable(name: &str) -> bool {
+    name != "<init>" && name != "<clinit>"
+}
+
+/// The internal name of a reference array element type, or the array type itself for
+/// nested arrays.
+fn reference_type(element: &str) -> Option<&str> {
+    match element.strip_prefix('L') {
+        Some(x) => x.strip_suffix(';'),
+        None => element.starts_with('[').then_some(element),
+    }
+}
@@ -0,0 +1,266 @@
+//! The `META-INF/MANIFEST.MF` format: sections of `Name: value` headers separated by blank
+//! lines, with lines longer than 72 bytes continued on lines starting with a space.
+
+use crate::error::{JomError, JomResult};
+
+const MAX_LINE_LENGTH: usize = 72;
+
+/// The headers of a manifest section, in order. Names are case-insensitive.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Attributes(Vec<(String, String)>);
+
+impl Attributes {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(x, _)| x.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets a header, keeping its position if it already exists.
+    pub fn insert(&mut self, name: String, value: String) {
+        match self
+            .0
+            .iter_mut()
+            .find(|(x, _)| x.eq_ignore_ascii_case(&name))
+        {
+            Some((_, x)) => *x = value,
+            None => self.0.push((name, value)),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let i = self
+            .0
+            .iter()
+            .position(|(x, _)| x.eq_ignore_ascii_case(name))?;
+
+        Some(self.0.remove(i).1)
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&str, &str) -> bool) {
+        self.0.retain(|(name, value)| f(name, value));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    main: Attributes,
+    /// Per-entry sections, keyed by their `Name` header, which isn't kept in the attributes.
+    sections: Vec<(String, Attributes)>,
+}
+
+impl Manifest {
+    /// Creates a manifest with only a `Manifest-Version` of 1.0.
+    pub fn new() -> Self {
+        let mut manifest = Self::default();
+        manifest
+            .main
+            .insert("Manifest-Version".to_owned(), "1.0".to_owned());
+
+        manifest
+    }
+
+    pub fn parse(bytes: &[u8]) -> JomResult<Self> {
+        let text =
+            std::str::from_utf8(bytes).map_err(|_| JomError::invalid_manifest("invalid UTF-8"))?;
+
+        let mut sections: Vec<Attributes> = vec![Attributes::default()];
+        let mut blank = false;
+
+        for (i, line) in lines(text).enumerate() {
+            let line_number = i + 1;
+
+            if line.is_empty() {
+                blank = true;
+                continue;
+            }
+
+            let attributes = match blank {
+                true => {
+                    blank = false;
+                    sections.push(Attributes::default());
+                    sections.last_mut().unwrap()
+                }
+                false => sections.last_mut().unwrap(),
+            };
+
+            if let Some(continuation) = line.strip_prefix(' ') {
+                let (_, value) = attributes.0.last_mut().ok_or_else(|| {
+                    JomError::invalid_manifest(format!(
+                        "line {line_number}: continuation without a header"
+                    ))
+                })?;
+                value.push_str(continuation);
+                continue;
+            }
+
+            let (name, value) = line.split_once(": ").ok_or_else(|| {
+                JomError::invalid_manifest(format!("line {line_number}: expected `Name: value`"))
+            })?;
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
+            {
+                return Err(JomError::invalid_manifest(format!(
+                    "line {line_number}: invalid header name {name:?}"
+                )));
+            }
+
+            attributes.0.push((name.to_owned(), value.to_owned()));
+        }
+
+        let mut sections = sections.into_iter();
+        let main = sections.next().unwrap_or_default();
+        let sections = sections
+            .filter(|x| !x.is_empty())
+            .map(|mut x| {
+                let name = x
+                    .remove("Name")
+                    .ok_or_else(|| JomError::invalid_manifest("section without a Name"))?;
+
+                Ok((name, x))
+            })
+            .collect::<JomResult<_>>()?;
+
+        Ok(Self { main, sections })
+    }
+
+    /// Writes the manifest with CRLF line endings, wrapping lines at 72 bytes without
+    /// splitting UTF-8 characters.
+    pub fn write(&self) -> Vec<u8> {
+        let mut out = String::new();
+
+        for (name, value) in self.main.iter() {
+            write_header(&mut out, name, value);
+        }
+        out.push_str("\r\n");
+
+        for (name, attributes) in &self.sections {
+            write_header(&mut out, "Name", name);
+            for (name, value) in attributes.iter() {
+                write_header(&mut out, name, value);
+            }
+            out.push_str("\r\n");
+        }
+
+        out.into_bytes()
+    }
+
+    pub fn main_attributes(&self) -> &Attributes {
+        &self.main
+    }
+
+    pub fn main_attributes_mut(&mut self) -> &mut Attributes {
+        &mut self.main
+    }
+
+    /// The per-entry sections in order, with the entry names they apply to.
+    pub fn sections(&self) -> impl Iterator<Item = (&str, &Attributes)> {
+        self.sections.iter().map(|(name, x)| (name.as_str(), x))
+    }
+
+    pub fn sections_mut(&mut self) -> &mut Vec<(String, Attributes)> {
+        &mut self.sections
+    }
+
+    pub fn section(&self, name: &str) -> Option<&Attributes> {
+        self.sections
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, x)| x)
+    }
+
+    /// The section for an entry, which is added if missing.
+    pub fn section_mut(&mut self, name: &str) -> &mut Attributes {
+        let i = match self.sections.iter().position(|(x, _)| x == name) {
+            Some(i) => i,
+            None => {
+                self.sections.push((name.to_owned(), Attributes::default()));
+                self.sections.len() - 1
+            }
+        };
+
+        &mut self.sections[i].1
+    }
+
+    pub fn remove_section(&mut self, name: &str) -> Option<Attributes> {
+        let i = self.sections.iter().position(|(x, _)| x == name)?;
+
+        Some(self.sections.remove(i).1)
+    }
+
+    pub fn main_class(&self) -> Option<&str> {
+        self.main.get("Main-Class")
+    }
+
+    pub fn is_multi_release(&self) -> bool {
+        self.main
+            .get("Multi-Release")
+            .is_some_and(|x| x.eq_ignore_ascii_case("true"))
+    }
+
+    /// Removes the `*-Digest` headers added by `jarsigner`, and the sections left empty.
+    pub fn strip_digests(&mut self) {
+        for (_, attributes) in &mut self.sections {
+            attributes.retain(|name, _| !is_digest(name));
+        }
+        self.sections.retain(|(_, x)| !x.is_empty());
+    }
+}
+
+fn is_digest(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with("-digest")
+}
+
+/// Splits on CRLF, LF or CR.
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    let text = text
+        .strip_suffix("\r\n")
+        .or_else(|| text.strip_suffix(['\n', '\r']))
+        .unwrap_or(text);
+
+    text.split('\n')
+        .flat_map(|x| x.strip_suffix('\r').unwrap_or(x).split('\r'))
+        .filter(move |_| !text.is_empty())
+}
+
+fn write_header(out: &mut String, name: &str, value: &str) {
+    let line = format!("{name}: {value}");
+    let mut rest = line.as_str();
+    let mut limit = MAX_LINE_LENGTH;
+
+    loop {
+        let mut end = rest.len().min(limit);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        out.push_str(&rest[..end]);
+        out.push_str("\r\n");
+        rest = &rest[end..];
+
+        if rest.is_empty() {
+            break;
+        }
+
+        // Continuation lines start with a space, which counts towards the limit.
+        out.push(' ');
+        limit = MAX_LINE_LENGTH - 1;
+    }
+}
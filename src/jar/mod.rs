@@ -0,0 +1,692 @@
+//! Reading and writing JAR files, which are ZIP archives of classes and resources.
+//!
+//! Entries keep their compressed bytes until they are read or replaced, so unchanged
+//! entries are written back exactly as they were read. ZIP64 and encrypted archives are
+//! not supported.
+
+pub mod manifest;
+
+use std::io::Cursor;
+
+use binrw::{binrw, BinRead, BinWrite};
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec};
+
+use crate::{
+    error::{JomError, JomResult},
+    ClassFile,
+};
+
+use self::manifest::Manifest;
+
+const FLAG_ENCRYPTED: u16 = 1;
+const FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+const FLAG_UTF8: u16 = 1 << 11;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+/// The version needed to extract deflated entries, 2.0.
+const VERSION_DEFLATE: u16 = 20;
+
+const DEFLATE_LEVEL: u8 = 6;
+
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+
+const MANIFEST: &str = "META-INF/MANIFEST.MF";
+const VERSIONS: &str = "META-INF/versions/";
+
+/// The extensions of the signature files and signature block files written by `jarsigner`.
+const SIGNATURE_EXTENSIONS: &[&str] = &["SF", "RSA", "DSA", "EC"];
+
+#[binrw]
+#[brw(little, magic = b"PK\x03\x04")]
+struct LocalFileHeader {
+    version_needed: u16,
+    flags: u16,
+    method: u16,
+    time: u16,
+    date: u16,
+    crc32: u32,
+    compressed_size: u32,
+    uncompressed_size: u32,
+    #[br(temp)]
+    #[bw(calc = name.len() as u16)]
+    name_length: u16,
+    #[br(temp)]
+    #[bw(calc = extra.len() as u16)]
+    extra_length: u16,
+    #[br(count = name_length)]
+    name: Vec<u8>,
+    #[br(count = extra_length)]
+    extra: Vec<u8>,
+}
+
+#[binrw]
+#[brw(little, magic = b"PK\x01\x02")]
+struct CentralDirectoryHeader {
+    version_made_by: u16,
+    version_needed: u16,
+    flags: u16,
+    method: u16,
+    time: u16,
+    date: u16,
+    crc32: u32,
+    compressed_size: u32,
+    uncompressed_size: u32,
+    #[br(temp)]
+    #[bw(calc = name.len() as u16)]
+    name_length: u16,
+    #[br(temp)]
+    #[bw(calc = extra.len() as u16)]
+    extra_length: u16,
+    #[br(temp)]
+    #[bw(calc = comment.len() as u16)]
+    comment_length: u16,
+    disk_start: u16,
+    internal_attributes: u16,
+    external_attributes: u32,
+    local_header_offset: u32,
+    #[br(count = name_length)]
+    name: Vec<u8>,
+    #[br(count = extra_length)]
+    extra: Vec<u8>,
+    #[br(count = comment_length)]
+    comment: Vec<u8>,
+}
+
+#[binrw]
+#[brw(little, magic = b"PK\x05\x06")]
+struct EndOfCentralDirectory {
+    disk: u16,
+    central_directory_disk: u16,
+    disk_entries: u16,
+    total_entries: u16,
+    central_directory_size: u32,
+    central_directory_offset: u32,
+    #[br(temp)]
+    #[bw(calc = comment.len() as u16)]
+    comment_length: u16,
+    #[br(count = comment_length)]
+    comment: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Stored,
+    Deflated,
+    /// A method that can't be decompressed, but is preserved when writing.
+    Other(u16),
+}
+
+impl Compression {
+    fn from_method(method: u16) -> Self {
+        match method {
+            METHOD_STORED => Self::Stored,
+            METHOD_DEFLATED => Self::Deflated,
+            x => Self::Other(x),
+        }
+    }
+
+    fn method(&self) -> u16 {
+        match self {
+            Self::Stored => METHOD_STORED,
+            Self::Deflated => METHOD_DEFLATED,
+            Self::Other(x) => *x,
+        }
+    }
+}
+
+/// An MS-DOS timestamp as stored in ZIP headers, with a resolution of two seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DosDateTime {
+    pub date: u16,
+    pub time: u16,
+}
+
+impl DosDateTime {
+    /// 1980-01-01 00:00:00, the earliest representable time, used for new entries so
+    /// that written archives are reproducible.
+    pub const EPOCH: Self = Self {
+        date: 1 << 5 | 1,
+        time: 0,
+    };
+
+    pub fn year(&self) -> u16 {
+        1980 + (self.date >> 9)
+    }
+
+    pub fn month(&self) -> u8 {
+        (self.date >> 5 & 0xf) as u8
+    }
+
+    pub fn day(&self) -> u8 {
+        (self.date & 0x1f) as u8
+    }
+
+    pub fn hour(&self) -> u8 {
+        (self.time >> 11) as u8
+    }
+
+    pub fn minute(&self) -> u8 {
+        (self.time >> 5 & 0x3f) as u8
+    }
+
+    pub fn second(&self) -> u8 {
+        (self.time & 0x1f) as u8 * 2
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct JarEntry {
+    name: String,
+    /// The name as stored, if it is in CP437 and not ASCII, to write it back unchanged.
+    cp437_name: Option<Vec<u8>>,
+    compression: Compression,
+    modified: DosDateTime,
+    crc32: u32,
+    size: u32,
+    /// The data as stored in the archive.
+    raw: Vec<u8>,
+    flags: u16,
+    version_made_by: u16,
+    version_needed: u16,
+    internal_attributes: u16,
+    external_attributes: u32,
+    local_extra: Vec<u8>,
+    extra: Vec<u8>,
+    comment: Vec<u8>,
+}
+
+impl JarEntry {
+    /// Creates a deflated entry.
+    pub fn new(name: String, data: &[u8]) -> Self {
+        let mut entry = Self {
+            name,
+            cp437_name: None,
+            compression: Compression::Deflated,
+            modified: DosDateTime::EPOCH,
+            crc32: 0,
+            size: 0,
+            raw: vec![],
+            flags: 0,
+            version_made_by: VERSION_DEFLATE,
+            version_needed: VERSION_DEFLATE,
+            internal_attributes: 0,
+            external_attributes: 0,
+            local_extra: vec![],
+            extra: vec![],
+            comment: vec![],
+        };
+        entry.set_data(data);
+
+        entry
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+        self.cp437_name = None;
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    pub fn modified(&self) -> DosDateTime {
+        self.modified
+    }
+
+    pub fn set_modified(&mut self, modified: DosDateTime) {
+        self.modified = modified;
+    }
+
+    /// The uncompressed size.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    pub fn is_class(&self) -> bool {
+        self.name.ends_with(".class") && !self.is_directory()
+    }
+
+    /// Whether this is a signature file or signature block file in `META-INF/`, which
+    /// is invalidated by changes to any signed entry.
+    pub fn is_signature(&self) -> bool {
+        let Some((file, extension)) = self
+            .name
+            .strip_prefix("META-INF/")
+            .and_then(|x| x.rsplit_once('.'))
+        else {
+            return false;
+        };
+
+        !file.contains('/')
+            && (SIGNATURE_EXTENSIONS
+                .iter()
+                .any(|x| x.eq_ignore_ascii_case(extension))
+                || extension.to_ascii_uppercase().starts_with("SIG-"))
+    }
+
+    /// The Java release of a multi-release entry under `META-INF/versions/N/`.
+    pub fn release(&self) -> Option<u16> {
+        let (release, _) = self.name.strip_prefix(VERSIONS)?.split_once('/')?;
+
+        release.parse().ok()
+    }
+
+    /// The internal name of the class in a class entry, without any multi-release prefix.
+    pub fn class_name(&self) -> Option<&str> {
+        if !self.is_class() {
+            return None;
+        }
+
+        let name = match self.release() {
+            Some(_) => self.name[VERSIONS.len()..].split_once('/')?.1,
+            None => &self.name,
+        };
+
+        name.strip_suffix(".class")
+    }
+
+    /// Decompresses the entry.
+    pub fn data(&self) -> JomResult<Vec<u8>> {
+        let data = match self.compression {
+            Compression::Stored => self.raw.clone(),
+            Compression::Deflated => decompress_to_vec(&self.raw)
+                .map_err(|e| JomError::invalid_archive(format!("{}: {e}", self.name)))?,
+            Compression::Other(x) => {
+                return Err(JomError::invalid_archive(format!(
+                    "{}: unsupported compression method {x}",
+                    self.name
+                )))
+            }
+        };
+
+        if data.len() != self.size as usize || crc32(&data) != self.crc32 {
+            return Err(JomError::invalid_archive(format!(
+                "{}: checksum mismatch",
+                self.name
+            )));
+        }
+
+        Ok(data)
+    }
+
+    /// Replaces the data, keeping the compression method if it is supported.
+    pub fn set_data(&mut self, data: &[u8]) {
+        if let Compression::Other(_) = self.compression {
+            self.compression = Compression::Deflated;
+            self.version_needed = self.version_needed.max(VERSION_DEFLATE);
+        }
+
+        self.raw = match self.compression {
+            Compression::Deflated => compress_to_vec(data, DEFLATE_LEVEL),
+            _ => data.to_vec(),
+        };
+        self.crc32 = crc32(data);
+        self.size = data.len() as u32;
+    }
+
+    /// Parses the entry as a class file.
+    pub fn class_file(&self) -> JomResult<ClassFile> {
+        ClassFile::read(&self.data()?)
+    }
+
+    /// Replaces the entry's data with the written class file.
+    pub fn set_class_file(&mut self, class: &ClassFile) -> JomResult<()> {
+        self.set_data(&class.write()?);
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Jar {
+    entries: Vec<JarEntry>,
+    comment: Vec<u8>,
+}
+
+impl Jar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn read(bytes: &[u8]) -> JomResult<Self> {
+        let end = find_end_of_central_directory(bytes)?;
+        let mut cursor = Cursor::new(bytes);
+
+        cursor.set_position(end as u64);
+        let eocd = EndOfCentralDirectory::read(&mut cursor)?;
+        if eocd.total_entries == u16::MAX || eocd.central_directory_offset == u32::MAX {
+            return Err(JomError::invalid_archive(
+                "ZIP64 archives are not supported",
+            ));
+        }
+
+        cursor.set_position(eocd.central_directory_offset as u64);
+        let headers = (0..eocd.total_entries)
+            .map(|_| CentralDirectoryHeader::read(&mut cursor))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let entries = headers
+            .into_iter()
+            .map(|header| {
+                // Names are in CP437 unless they are flagged as UTF-8.
+                let (name, cp437_name) = match header.flags & FLAG_UTF8 {
+                    0 if header.name.is_ascii() => (decode_cp437(&header.name), None),
+                    0 => (decode_cp437(&header.name), Some(header.name.clone())),
+                    _ => match String::from_utf8(header.name.clone()) {
+                        Ok(name) => (name, None),
+                        Err(_) => {
+                            return Err(JomError::invalid_archive(format!(
+                                "{}: name is not valid UTF-8",
+                                decode_cp437(&header.name)
+                            )))
+                        }
+                    },
+                };
+                if header.flags & FLAG_ENCRYPTED != 0 {
+                    return Err(JomError::invalid_archive(format!(
+                        "{name}: entry is encrypted"
+                    )));
+                }
+
+                cursor.set_position(header.local_header_offset as u64);
+                let local = LocalFileHeader::read(&mut cursor)?;
+
+                // The local header sizes are zero when followed by a data descriptor, so
+                // the central directory is used instead.
+                let start = cursor.position() as usize;
+                let raw = bytes
+                    .get(start..start + header.compressed_size as usize)
+                    .ok_or_else(|| JomError::invalid_archive(format!("{name}: truncated data")))?
+                    .to_vec();
+
+                Ok(JarEntry {
+                    name,
+                    cp437_name,
+                    compression: Compression::from_method(header.method),
+                    modified: DosDateTime {
+                        date: header.date,
+                        time: header.time,
+                    },
+                    crc32: header.crc32,
+                    size: header.uncompressed_size,
+                    raw,
+                    flags: header.flags,
+                    version_made_by: header.version_made_by,
+                    version_needed: header.version_needed,
+                    internal_attributes: header.internal_attributes,
+                    external_attributes: header.external_attributes,
+                    local_extra: local.extra,
+                    extra: header.extra,
+                    comment: header.comment,
+                })
+            })
+            .collect::<JomResult<Vec<_>>>()?;
+
+        Ok(Self {
+            entries,
+            comment: eocd.comment,
+        })
+    }
+
+    /// The entries in archive order.
+    pub fn entries(&self) -> &[JarEntry] {
+        &self.entries
+    }
+
+    pub fn entries_mut(&mut self) -> &mut Vec<JarEntry> {
+        &mut self.entries
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&JarEntry> {
+        self.entries.iter().find(|x| x.name == name)
+    }
+
+    pub fn entry_mut(&mut self, name: &str) -> Option<&mut JarEntry> {
+        self.entries.iter_mut().find(|x| x.name == name)
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &JarEntry> {
+        self.entries.iter().filter(|x| x.is_class())
+    }
+
+    /// The entries that are neither classes nor directories.
+    pub fn resources(&self) -> impl Iterator<Item = &JarEntry> {
+        self.entries
+            .iter()
+            .filter(|x| !x.is_class() && !x.is_directory())
+    }
+
+    pub fn manifest_entry(&self) -> Option<&JarEntry> {
+        self.entries
+            .iter()
+            .find(|x| x.name.eq_ignore_ascii_case(MANIFEST))
+    }
+
+    pub fn manifest(&self) -> JomResult<Option<Manifest>> {
+        self.manifest_entry()
+            .map(|x| Manifest::parse(&x.data()?))
+            .transpose()
+    }
+
+    /// Replaces the manifest, or adds it after the `META-INF/` directory like `jar` does.
+    pub fn set_manifest(&mut self, manifest: &Manifest) {
+        let data = manifest.write();
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|x| x.name.eq_ignore_ascii_case(MANIFEST))
+        {
+            Some(entry) => entry.set_data(&data),
+            None => {
+                let i = match self.entries.first() {
+                    Some(x) if x.name == "META-INF/" => 1,
+                    _ => 0,
+                };
+                self.entries
+                    .insert(i, JarEntry::new(MANIFEST.to_owned(), &data));
+            }
+        }
+    }
+
+    pub fn signature_files(&self) -> impl Iterator<Item = &JarEntry> {
+        self.entries.iter().filter(|x| x.is_signature())
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.signature_files().next().is_some()
+    }
+
+    /// Removes the signature files and the digests in the manifest, for when signed
+    /// entries have been modified.
+    pub fn strip_signatures(&mut self) -> JomResult<()> {
+        self.entries.retain(|x| !x.is_signature());
+
+        if let Some(manifest) = self.manifest()? {
+            let mut stripped = manifest.clone();
+            stripped.strip_digests();
+            if stripped != manifest {
+                self.set_manifest(&stripped);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds the entry of a class by internal name. With a `release`, the entry for the
+    /// highest multi-release version not above it is preferred over the base entry.
+    pub fn find_class(&self, name: &str, release: Option<u16>) -> Option<&JarEntry> {
+        self.entries
+            .iter()
+            .filter(|x| x.class_name() == Some(name))
+            .filter(|x| match (x.release(), release) {
+                (Some(version), Some(release)) => version <= release,
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .max_by_key(|x| x.release())
+    }
+
+    /// Adds an entry, replacing any entry with the same name in place.
+    pub fn insert(&mut self, entry: JarEntry) {
+        match self.entries.iter_mut().find(|x| x.name == entry.name) {
+            Some(x) => *x = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<JarEntry> {
+        let i = self.entries.iter().position(|x| x.name == name)?;
+
+        Some(self.entries.remove(i))
+    }
+
+    pub fn write(&self) -> JomResult<Vec<u8>> {
+        let too_large = || JomError::invalid_archive("archive is too large for ZIP without ZIP64");
+        let mut cursor = Cursor::new(vec![]);
+        let mut headers = vec![];
+
+        for entry in &self.entries {
+            let offset = u32::try_from(cursor.position()).map_err(|_| too_large())?;
+            let compressed_size = u32::try_from(entry.raw.len()).map_err(|_| too_large())?;
+            // Sizes are always known here, so no data descriptor is written.
+            let mut flags = entry.flags & !FLAG_DATA_DESCRIPTOR;
+            let name = match &entry.cp437_name {
+                Some(name) => name.clone(),
+                None => {
+                    if !entry.name.is_ascii() {
+                        flags |= FLAG_UTF8;
+                    }
+                    entry.name.as_bytes().to_vec()
+                }
+            };
+
+            LocalFileHeader {
+                version_needed: entry.version_needed,
+                flags,
+                method: entry.compression.method(),
+                time: entry.modified.time,
+                date: entry.modified.date,
+                crc32: entry.crc32,
+                compressed_size,
+                uncompressed_size: entry.size,
+                name: name.clone(),
+                extra: entry.local_extra.clone(),
+            }
+            .write(&mut cursor)?;
+            entry.raw.write(&mut cursor)?;
+
+            headers.push(CentralDirectoryHeader {
+                version_made_by: entry.version_made_by,
+                version_needed: entry.version_needed,
+                flags,
+                method: entry.compression.method(),
+                time: entry.modified.time,
+                date: entry.modified.date,
+                crc32: entry.crc32,
+                compressed_size,
+                uncompressed_size: entry.size,
+                disk_start: 0,
+                internal_attributes: entry.internal_attributes,
+                external_attributes: entry.external_attributes,
+                local_header_offset: offset,
+                name,
+                extra: entry.extra.clone(),
+                comment: entry.comment.clone(),
+            });
+        }
+
+        let central_directory_offset = u32::try_from(cursor.position()).map_err(|_| too_large())?;
+        for header in &headers {
+            header.write(&mut cursor)?;
+        }
+        let central_directory_size = cursor.position() as u32 - central_directory_offset;
+
+        let entries = u16::try_from(headers.len()).map_err(|_| too_large())?;
+        EndOfCentralDirectory {
+            disk: 0,
+            central_directory_disk: 0,
+            disk_entries: entries,
+            total_entries: entries,
+            central_directory_size,
+            central_directory_offset,
+            comment: self.comment.clone(),
+        }
+        .write(&mut cursor)?;
+
+        Ok(cursor.into_inner())
+    }
+}
+
+/// Finds the end of central directory record, which is followed by a comment of up to
+/// 64 KiB.
+fn find_end_of_central_directory(bytes: &[u8]) -> JomResult<usize> {
+    let last = bytes
+        .len()
+        .checked_sub(END_OF_CENTRAL_DIRECTORY_SIZE)
+        .ok_or_else(|| JomError::invalid_archive("file is too small"))?;
+    let first = last.saturating_sub(u16::MAX as usize);
+
+    (first..=last)
+        .rev()
+        .find(|&i| bytes[i..].starts_with(b"PK\x05\x06"))
+        .ok_or_else(|| JomError::invalid_archive("missing end of central directory"))
+}
+
+/// The characters of bytes 0x80 to 0xFF in CP437, the original IBM PC code page that ZIP
+/// uses for names not flagged as UTF-8. The lower half is ASCII.
+const CP437: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', 'É', 'æ', 'Æ',
+    'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', 'á', 'í', 'ó', 'ú', 'ñ', 'Ñ',
+    'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕',
+    '╣', '║', '╗', '╝', '╜', '╛', '┐', '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦',
+    '╠', '═', '╬', '╧', '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐',
+    '▀', 'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', '≡', '±',
+    '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+fn decode_cp437(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&x| match x {
+            0..=0x7f => x as char,
+            _ => CP437[x as usize - 0x80],
+        })
+        .collect()
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ crc >> 8
+    })
+}
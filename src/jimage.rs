@@ -0,0 +1,292 @@
+//! Reading the jimage format of a JDK's `lib/modules`, which holds the platform classes
+//! since Java 9.
+//!
+//! Resources are named `/<module>/<path>`, for example `/java.base/java/lang/Object.class`.
+//! Compressed images, created with `jlink --compress`, are not supported.
+
+use crate::{
+    error::{JomError, JomResult},
+    ClassFile,
+};
+
+const MAGIC: u32 = 0xcafedada;
+const MAJOR_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 7 * 4;
+
+const HASH_MULTIPLIER: u32 = 0x01000193;
+
+const ATTRIBUTE_END: u8 = 0;
+const ATTRIBUTE_MODULE: u8 = 1;
+const ATTRIBUTE_PARENT: u8 = 2;
+const ATTRIBUTE_BASE: u8 = 3;
+const ATTRIBUTE_EXTENSION: u8 = 4;
+const ATTRIBUTE_OFFSET: u8 = 5;
+const ATTRIBUTE_COMPRESSED: u8 = 6;
+const ATTRIBUTE_UNCOMPRESSED: u8 = 7;
+const ATTRIBUTE_COUNT: usize = 8;
+
+/// The directories of the image, whose entries aren't resources of a module.
+const DIRECTORIES: &[&str] = &["modules", "packages"];
+
+/// The decoded attributes of a resource, with names as offsets into the strings table.
+struct Location([u64; ATTRIBUTE_COUNT]);
+
+pub struct JImage {
+    bytes: Vec<u8>,
+    /// The image is written in the byte order of the platform that created it.
+    little_endian: bool,
+    table_length: usize,
+    redirect: usize,
+    offsets: usize,
+    locations: usize,
+    strings: usize,
+    /// The start of the resource data, after the index.
+    resources: usize,
+}
+
+impl JImage {
+    /// Reads the index of an image. Resources are only decoded when requested.
+    pub fn read(bytes: Vec<u8>) -> JomResult<Self> {
+        let header = bytes
+            .get(..HEADER_SIZE)
+            .ok_or_else(|| JomError::invalid_archive("jimage header is truncated"))?;
+
+        let little_endian = match header[..4].try_into().unwrap() {
+            x if u32::from_le_bytes(x) == MAGIC => true,
+            x if u32::from_be_bytes(x) == MAGIC => false,
+            _ => return Err(JomError::invalid_archive("not a jimage")),
+        };
+        let field = |i: usize| {
+            let x = header[i * 4..i * 4 + 4].try_into().unwrap();
+            match little_endian {
+                true => u32::from_le_bytes(x),
+                false => u32::from_be_bytes(x),
+            }
+        };
+
+        let major = field(1) >> 16;
+        if major != MAJOR_VERSION {
+            return Err(JomError::invalid_archive(format!(
+                "unsupported jimage version {major}"
+            )));
+        }
+
+        let table_length = field(4) as usize;
+        let redirect = HEADER_SIZE;
+        let offsets = redirect + table_length * 4;
+        let locations = offsets + table_length * 4;
+        let strings = locations + field(5) as usize;
+        let resources = strings + field(6) as usize;
+        if resources > bytes.len() {
+            return Err(JomError::invalid_archive("jimage index is truncated"));
+        }
+
+        Ok(Self {
+            bytes,
+            little_endian,
+            table_length,
+            redirect,
+            offsets,
+            locations,
+            strings,
+            resources,
+        })
+    }
+
+    /// Reads a resource by its full name, like `/java.base/java/lang/Object.class`.
+    pub fn resource(&self, name: &str) -> JomResult<Option<Vec<u8>>> {
+        self.find(name)?
+            .map(|location| self.resource_data(&location))
+            .transpose()
+    }
+
+    /// Finds a class by internal name, using the image's package index to find its module.
+    pub fn find_class(&self, name: &str) -> JomResult<Option<ClassFile>> {
+        let package = name.rsplit_once('/').map_or("", |(package, _)| package);
+
+        for module in self.package_modules(&package.replace('/', "."))? {
+            if let Some(data) = self.resource(&format!("/{module}/{name}.class"))? {
+                return ClassFile::read(&data).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The full names of every resource in the image, in table order.
+    pub fn resource_names(&self) -> JomResult<Vec<String>> {
+        let mut names = vec![];
+
+        for i in 0..self.table_length {
+            let location = self.location(self.u32_at(self.offsets + i * 4)? as usize)?;
+            let module = self.string(location.0[ATTRIBUTE_MODULE as usize])?;
+            if !DIRECTORIES.contains(&module) {
+                names.push(self.name(&location)?);
+            }
+        }
+
+        Ok(names)
+    }
+
+    /// The internal names of every class in the image, except `module-info`.
+    pub fn class_names(&self) -> JomResult<Vec<String>> {
+        Ok(self
+            .resource_names()?
+            .into_iter()
+            .filter_map(|x| {
+                let (_, path) = x[1..].split_once('/')?;
+                let class = path.strip_suffix(".class")?;
+
+                (class != "module-info").then(|| class.to_owned())
+            })
+            .collect())
+    }
+
+    /// The modules containing a package, in dotted form like `java.lang`.
+    fn package_modules(&self, package: &str) -> JomResult<Vec<&str>> {
+        let Some(location) = self.find(&format!("/packages/{package}"))? else {
+            return Ok(vec![]);
+        };
+        let data = self.resource_data(&location)?;
+
+        // Pairs of a flag for whether the package is empty in the module, and the
+        // offset of the module's name.
+        data.chunks_exact(8)
+            .filter(|x| self.u32_from(&x[..4]) == 0)
+            .map(|x| self.string(self.u32_from(&x[4..]) as u64))
+            .collect()
+    }
+
+    fn find(&self, name: &str) -> JomResult<Option<Location>> {
+        if self.table_length == 0 {
+            return Ok(None);
+        }
+
+        let slot = hash(name, HASH_MULTIPLIER) as usize % self.table_length;
+        let index = match self.u32_at(self.redirect + slot * 4)? as i32 {
+            0 => return Ok(None),
+            x if x < 0 => (-1 - x) as usize,
+            x => hash(name, x as u32) as usize % self.table_length,
+        };
+
+        let location = self.location(self.u32_at(self.offsets + index * 4)? as usize)?;
+
+        // Names that aren't in the image can still hash to an occupied slot.
+        Ok((self.name(&location)? == name).then_some(location))
+    }
+
+    fn location(&self, offset: usize) -> JomResult<Location> {
+        let mut attributes = [0; ATTRIBUTE_COUNT];
+        let mut i = self.locations + offset;
+
+        loop {
+            let byte = *self.byte(i)?;
+            let kind = byte >> 3;
+            if kind == ATTRIBUTE_END {
+                break;
+            }
+            if kind as usize >= ATTRIBUTE_COUNT {
+                return Err(JomError::invalid_archive(format!(
+                    "invalid jimage attribute {kind}"
+                )));
+            }
+
+            let length = (byte & 7) as usize + 1;
+            let mut value = 0;
+            for j in 0..length {
+                value = value << 8 | *self.byte(i + 1 + j)? as u64;
+            }
+            attributes[kind as usize] = value;
+            i += 1 + length;
+        }
+
+        Ok(Location(attributes))
+    }
+
+    fn name(&self, location: &Location) -> JomResult<String> {
+        let part = |kind: u8| self.string(location.0[kind as usize]);
+        let mut name = String::new();
+
+        let module = part(ATTRIBUTE_MODULE)?;
+        if !module.is_empty() {
+            name.push('/');
+            name.push_str(module);
+            name.push('/');
+        }
+        let parent = part(ATTRIBUTE_PARENT)?;
+        if !parent.is_empty() {
+            name.push_str(parent);
+            name.push('/');
+        }
+        name.push_str(part(ATTRIBUTE_BASE)?);
+        let extension = part(ATTRIBUTE_EXTENSION)?;
+        if !extension.is_empty() {
+            name.push('.');
+            name.push_str(extension);
+        }
+
+        Ok(name)
+    }
+
+    fn resource_data(&self, location: &Location) -> JomResult<Vec<u8>> {
+        if location.0[ATTRIBUTE_COMPRESSED as usize] != 0 {
+            return Err(JomError::invalid_archive(
+                "compressed jimage resources are not supported",
+            ));
+        }
+
+        let start = self.resources + location.0[ATTRIBUTE_OFFSET as usize] as usize;
+        let end = start + location.0[ATTRIBUTE_UNCOMPRESSED as usize] as usize;
+
+        self.bytes
+            .get(start..end)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| JomError::invalid_archive("jimage resource is truncated"))
+    }
+
+    /// A NUL-terminated string from the strings table.
+    fn string(&self, offset: u64) -> JomResult<&str> {
+        let start = self.strings + offset as usize;
+        let bytes = self
+            .bytes
+            .get(start..self.resources)
+            .ok_or_else(|| JomError::invalid_archive("jimage string is out of bounds"))?;
+        let end = bytes
+            .iter()
+            .position(|&x| x == 0)
+            .ok_or_else(|| JomError::invalid_archive("jimage string is not terminated"))?;
+
+        std::str::from_utf8(&bytes[..end])
+            .map_err(|_| JomError::invalid_archive("jimage string is not UTF-8"))
+    }
+
+    fn byte(&self, i: usize) -> JomResult<&u8> {
+        self.bytes
+            .get(i)
+            .ok_or_else(|| JomError::invalid_archive("jimage index is truncated"))
+    }
+
+    fn u32_at(&self, i: usize) -> JomResult<u32> {
+        let bytes = self
+            .bytes
+            .get(i..i + 4)
+            .ok_or_else(|| JomError::invalid_archive("jimage index is truncated"))?;
+
+        Ok(self.u32_from(bytes))
+    }
+
+    fn u32_from(&self, bytes: &[u8]) -> u32 {
+        let bytes = bytes.try_into().unwrap();
+        match self.little_endian {
+            true => u32::from_le_bytes(bytes),
+            false => u32::from_be_bytes(bytes),
+        }
+    }
+}
+
+/// The FNV-1 style hash used for the image's perfect hash table.
+fn hash(name: &str, seed: u32) -> u32 {
+    name.bytes().fold(seed, |hash, byte| {
+        hash.wrapping_mul(HASH_MULTIPLIER) ^ byte as u32
+    }) & 0x7fffffff
+}
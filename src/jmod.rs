@@ -0,0 +1,54 @@
+//! Reading JMOD files from a JDK's `jmods` directory, which are ZIP archives after a
+//! four byte header, with classes under `classes/`.
+
+use crate::{
+    error::{JomError, JomResult},
+    jar::{Jar, JarEntry},
+};
+
+const MAGIC: &[u8] = b"JM\x01\x00";
+
+const CLASSES: &str = "classes/";
+
+pub struct Jmod {
+    archive: Jar,
+}
+
+impl Jmod {
+    pub fn read(bytes: &[u8]) -> JomResult<Self> {
+        let archive = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| JomError::invalid_archive("not a jmod"))?;
+
+        Ok(Self {
+            archive: Jar::read(archive)?,
+        })
+    }
+
+    /// The underlying archive, including the `conf/`, `lib/` and `legal/` sections.
+    pub fn archive(&self) -> &Jar {
+        &self.archive
+    }
+
+    /// The class entries, except `module-info`.
+    pub fn classes(&self) -> impl Iterator<Item = &JarEntry> {
+        self.archive
+            .classes()
+            .filter(|x| x.name().starts_with(CLASSES) && x.name() != "classes/module-info.class")
+    }
+
+    /// The internal names of the classes.
+    pub fn class_names(&self) -> impl Iterator<Item = &str> {
+        self.classes()
+            .filter_map(|x| x.name().strip_prefix(CLASSES)?.strip_suffix(".class"))
+    }
+
+    /// Finds a class entry by internal name.
+    pub fn find_class(&self, name: &str) -> Option<&JarEntry> {
+        self.archive.entry(&format!("{CLASSES}{name}.class"))
+    }
+
+    pub fn module_info(&self) -> Option<&JarEntry> {
+        self.archive.entry("classes/module-info.class")
+    }
+}
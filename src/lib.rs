@@ -1,18 +1,45 @@
+pub mod access;
+pub mod analysis;
+pub mod assembler;
 pub mod attribute;
+pub mod builder;
+pub mod call_graph;
+pub mod class_path;
+pub mod class_ref;
+pub mod compact;
 pub mod constant_pool;
+pub mod dependencies;
+pub mod descriptor;
+pub mod downgrade;
 pub mod error;
 pub mod field;
+pub mod frames;
+pub mod hierarchy;
+pub mod jar;
+pub mod jimage;
+pub mod jmod;
+pub mod mapping;
 pub mod method;
+pub mod printer;
+pub mod remap;
+pub mod strip;
+pub mod version;
+pub mod visitor;
+mod bytes;
+mod signature;
 mod utf8;
+mod walker;
 
-use std::io::Cursor;
+use std::{fmt, io::Cursor};
 
 use attribute::{RawAttribute, ClassAttribute};
-use binrw::{binrw, BinRead};
+use binrw::{binrw, BinRead, BinWrite};
 use constant_pool::{constant_pool_parser, ConstantPool, ConstantPoolIndex, RawConstantPoolIndex, process_cp};
-use error::JomResult;
+use bytes::Reader;
+use error::{ErrorPath, JomError, JomResult};
 use field::{FieldInfo, RawFieldInfo};
 use method::{MethodInfo, RawMethodInfo};
+use version::ClassVersion;
 
 #[binrw]
 #[brw(big, magic = 0xCAFEBABEu32)]
@@ -24,7 +51,6 @@ struct RawClassFile {
     constant_pool_count: u16,
     #[br(args(constant_pool_count))]
     #[br(parse_with = constant_pool_parser)]
-    #[bw(map = |_| vec![0u8])]
     constant_pool: Vec<RawConstantPoolIndex>,
     access_flags: u16,
     this_class: u16,
@@ -64,8 +90,128 @@ pub struct ClassFile {
     attributes: Vec<ClassAttribute>,
 }
 
+/// What [`ClassFile::read_with`] leaves undecoded. Skipped attributes are kept as `Unknown`
+/// with their raw bytes, and are written back unchanged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Keeps `Code` attributes undecoded, which is most of the work of reading a class.
+    pub skip_code: bool,
+    /// Keeps the attributes of `Code`, like `LineNumberTable`, undecoded.
+    pub skip_code_attributes: bool,
+    /// Keeps attributes that fail to decode as `Unknown`, and constant pool entries that the
+    /// version does not allow, instead of failing the read, which is useful for obfuscated
+    /// classes. See [`ClassFile::read_with_diagnostics`].
+    pub lenient: bool,
+}
+
+/// An attribute that failed to decode in a lenient read, and was kept as `Unknown`, or a
+/// constant pool entry that the version of the class does not allow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The offset of the attribute or constant pool entry in the class file.
+    pub offset: u64,
+    /// The name of the attribute, empty for a constant pool entry.
+    pub attribute: String,
+    /// The index of the constant pool entry.
+    pub constant: Option<u16>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.constant {
+            Some(index) => write!(
+                f,
+                "constant #{index} at offset {}: {}",
+                self.offset, self.message
+            ),
+            None => write!(
+                f,
+                "{} attribute at offset {}: {}",
+                self.attribute, self.offset, self.message
+            ),
+        }
+    }
+}
+
+/// The options of a read and the diagnostics it has recorded.
+#[derive(Default)]
+pub(crate) struct ReadState {
+    pub(crate) options: ReadOptions,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+}
+
+/// The newest class file version that can be read, from Java 25.
+pub(crate) const MAX_MAJOR: u16 = 69;
+
+/// Checks the magic and version at the start of a class file, returning the minor and major
+/// versions.
+pub(crate) fn check_header(data: &[u8]) -> JomResult<(u16, u16)> {
+    let mut reader = Reader::new(data, 0);
+    let mut header = || -> JomResult<_> {
+        let magic = reader.u32()?;
+        if magic != 0xCAFEBABE {
+            return Err(JomError::BadMagic(magic).at(0));
+        }
+        let minor = reader.u16()?;
+        let major = reader.u16()?;
+        if !(45..=MAX_MAJOR).contains(&major) {
+            return Err(JomError::UnsupportedVersion(major, minor).at(4));
+        }
+
+        Ok((minor, major))
+    };
+
+    header().map_err(|e| e.truncated_at(data.len()))
+}
+
+/// The offset of a constant pool entry in a class file whose constant pool has been read.
+fn constant_offset(data: &[u8], index: u16) -> JomResult<u64> {
+    // The entries start after the magic, the version and their count.
+    let mut reader = Reader::new(data, 10);
+    let mut i = 1;
+    while i < index {
+        let size = match reader.u8()? {
+            1 => reader.u16()? as usize,
+            3 | 4 | 9..=12 | 17 | 18 => 4,
+            5 | 6 => {
+                // Longs and doubles take two entries.
+                i += 1;
+                8
+            }
+            15 => 3,
+            _ => 2,
+        };
+        reader.bytes(size)?;
+        i += 1;
+    }
+
+    Ok(reader.position() as u64)
+}
+
 impl ClassFile {
     pub fn read(slice: &[u8]) -> JomResult<Self> {
+        Self::read_with(slice, ReadOptions::default())
+    }
+
+    /// Reads a class, skipping the parts `options` asks to. Skipped methods can be decoded
+    /// later with [`ClassFile::decode_method`].
+    pub fn read_with(slice: &[u8], options: ReadOptions) -> JomResult<Self> {
+        Ok(Self::read_with_diagnostics(slice, options)?.0)
+    }
+
+    /// Reads a class like [`ClassFile::read_with`], also returning the attributes that a
+    /// lenient read could not decode and the constant pool entries it let through.
+    pub fn read_with_diagnostics(
+        slice: &[u8],
+        options: ReadOptions,
+    ) -> JomResult<(Self, Vec<Diagnostic>)> {
+        check_header(slice)?;
+
+        let mut state = ReadState {
+            options,
+            diagnostics: vec![],
+        };
         let RawClassFile {
             minor,
             major,
@@ -77,27 +223,52 @@ impl ClassFile {
             fields,
             methods,
             attributes,
-        } = RawClassFile::read(&mut Cursor::new(slice))?;
+        } = RawClassFile::read(&mut Cursor::new(slice))
+            .map_err(|e| JomError::from(e).truncated_at(slice.len()))?;
 
         let constant_pool = process_cp(constant_pool)?;
 
         let this_class = constant_pool.get_class(this_class)?;
-        let super_class = constant_pool.get_class(super_class)?;
+        let within = |e: JomError| e.within(ErrorPath::Class(this_class.clone()));
+        let super_class = match super_class {
+            0 => String::new(),
+            x => constant_pool.get_class(x).map_err(within)?,
+        };
+        let version = ClassVersion::new(major, minor);
+        if state.options.lenient {
+            for (i, feature) in version::disallowed_constants(&constant_pool, version) {
+                state.diagnostics.push(Diagnostic {
+                    offset: constant_offset(slice, i)?,
+                    attribute: String::new(),
+                    constant: Some(i),
+                    message: JomError::NotAllowed(feature, version).to_string(),
+                });
+            }
+        } else {
+            version::check_constants(&constant_pool, version).map_err(within)?;
+        }
         let interfaces = interfaces
             .into_iter()
             .map(|x| constant_pool.get_class(x))
-            .collect::<JomResult<Vec<_>>>()?;
+            .collect::<JomResult<Vec<_>>>()
+            .map_err(within)?;
         let fields = fields
             .into_iter()
-            .map(|x| x.into_field_info(&constant_pool))
-            .collect::<JomResult<Vec<_>>>()?;
+            .map(|x| x.into_field_info(&constant_pool, &mut state))
+            .collect::<JomResult<Vec<_>>>()
+            .map_err(within)?;
         let methods = methods
             .into_iter()
-            .map(|x| x.into_method_info(&constant_pool))
-            .collect::<JomResult<Vec<_>>>()?;
-        let attributes = attributes.into_iter().map(|x| x.into_class_attr(&constant_pool)).collect::<JomResult<Vec<_>>>()?;
+            .map(|x| x.into_method_info(&constant_pool, &mut state))
+            .collect::<JomResult<Vec<_>>>()
+            .map_err(within)?;
+        let attributes = attributes
+            .into_iter()
+            .map(|x| x.into_class_attr(&constant_pool))
+            .collect::<JomResult<Vec<_>>>()
+            .map_err(within)?;
 
-        Ok(Self {
+        let class = Self {
             minor,
             major,
             constant_pool,
@@ -108,7 +279,9 @@ impl ClassFile {
             fields,
             methods,
             attributes,
-        })
+        };
+
+        Ok((class, state.diagnostics))
     }
 }
 
@@ -144,8 +317,8 @@ impl ClassFile {
         self.major
     }
 
-    pub fn constant_pool(&self) -> &[ConstantPoolIndex] {
-        &self.constant_pool.0
+    pub fn constant_pool(&self) -> &ConstantPool {
+        &self.constant_pool
     }
 
     pub fn access_flags(&self) -> u16 {
@@ -176,3 +349,109 @@ impl ClassFile {
         &self.attributes
     }
 }
+
+impl ClassFile {
+    pub fn set_version(&mut self, major: u16, minor: u16) {
+        self.major = major;
+        self.minor = minor;
+    }
+
+    pub fn set_access_flags(&mut self, access_flags: u16) {
+        self.access_flags = access_flags;
+    }
+
+    pub fn set_this_class(&mut self, this_class: String) {
+        self.this_class = this_class;
+    }
+
+    pub fn set_super_class(&mut self, super_class: String) {
+        self.super_class = super_class;
+    }
+
+    pub fn constant_pool_mut(&mut self) -> &mut ConstantPool {
+        &mut self.constant_pool
+    }
+
+    pub fn interfaces_mut(&mut self) -> &mut Vec<String> {
+        &mut self.interfaces
+    }
+
+    pub fn fields_mut(&mut self) -> &mut Vec<FieldInfo> {
+        &mut self.fields
+    }
+
+    pub fn methods_mut(&mut self) -> &mut Vec<MethodInfo> {
+        &mut self.methods
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Vec<ClassAttribute> {
+        &mut self.attributes
+    }
+
+    /// Decodes what [`ReadOptions`] skipped in the method at `index`.
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn decode_method(&mut self, index: usize) -> JomResult<&mut MethodInfo> {
+        let method = &mut self.methods[index];
+        method.decode(&self.constant_pool)?;
+
+        Ok(method)
+    }
+}
+
+impl ClassFile {
+    /// Serializes the class file.
+    ///
+    /// Existing constant pool indices are kept, so instruction operands and unknown
+    /// attributes stay valid. Entries the class file refers to that are missing from
+    /// the pool are appended.
+    pub fn write(&self) -> JomResult<Vec<u8>> {
+        let mut constant_pool = ConstantPool(self.constant_pool.0.clone());
+
+        let this_class = constant_pool.insert_class(self.this_class.clone())?;
+        // Only `java/lang/Object` and modules have no super class.
+        let super_class = match self.super_class.as_str() {
+            "" => 0,
+            x => constant_pool.insert_class(x.to_owned())?,
+        };
+        let interfaces = self
+            .interfaces
+            .iter()
+            .map(|x| constant_pool.insert_class(x.clone()))
+            .collect::<JomResult<Vec<_>>>()?;
+        let fields = self
+            .fields
+            .iter()
+            .map(|x| RawFieldInfo::from_field_info(x, &mut constant_pool))
+            .collect::<JomResult<Vec<_>>>()?;
+        let methods = self
+            .methods
+            .iter()
+            .map(|x| RawMethodInfo::from_method_info(x, &mut constant_pool))
+            .collect::<JomResult<Vec<_>>>()?;
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|x| RawAttribute::from_class_attr(x, &mut constant_pool))
+            .collect::<JomResult<Vec<_>>>()?;
+
+        let constant_pool = constant_pool.encode()?;
+
+        let mut cursor = Cursor::new(vec![]);
+        RawClassFile {
+            minor: self.minor,
+            major: self.major,
+            constant_pool,
+            access_flags: self.access_flags,
+            this_class,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+        }
+        .write(&mut cursor)?;
+
+        Ok(cursor.into_inner())
+    }
+}
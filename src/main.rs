@@ -0,0 +1,252 @@
+use std::{env, fs, path::Path, process::ExitCode};
+
+use jom::{
+    analysis::{basic::BasicInterpreter, Analyzer},
+    error::{JomError, JomResult},
+    jar::Jar,
+    remap::Remapper,
+    strip::Stripper,
+    ClassFile,
+};
+
+const USAGE: &str = "\
+Usage: jom <command> <file> [arguments]
+
+The file is a class or a JAR, whose classes are each processed.
+
+Commands:
+    dump <file>                                  Disassemble the class like `javap -v`
+    cp <file>                                    List the constant pool
+    verify <file>                                Check that every method can be analyzed
+    strip <file> [-o <output>]                   Remove debug attributes
+    rename <file> <old>=<new>... [-o <output>]   Rename classes and every reference to them
+    version <file> [<major>[.<minor>]] [-o <output>]
+                                                 Print or set the class file version
+
+Commands that modify classes overwrite the file unless an output is given. Modified JARs
+lose their signatures.";
+
+type CliResult<T> = Result<T, String>;
+
+struct Arguments {
+    command: String,
+    input: String,
+    rest: Vec<String>,
+    output: Option<String>,
+}
+
+impl Arguments {
+    fn parse(mut args: impl Iterator<Item = String>) -> CliResult<Self> {
+        let mut positional = vec![];
+        let mut output = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "--output" => {
+                    output = Some(args.next().ok_or("missing output after -o")?);
+                }
+                "-h" | "--help" => return Err(USAGE.to_owned()),
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let (Some(command), Some(input)) = (positional.next(), positional.next()) else {
+            return Err(USAGE.to_owned());
+        };
+
+        Ok(Self {
+            command,
+            input,
+            rest: positional.collect(),
+            output,
+        })
+    }
+
+    fn output(&self) -> &str {
+        self.output.as_deref().unwrap_or(&self.input)
+    }
+}
+
+fn main() -> ExitCode {
+    match Arguments::parse(env::args().skip(1)).and_then(|x| run(&x)) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// What to do with each class.
+enum Command {
+    Dump,
+    ConstantPool,
+    Verify,
+    Strip(Stripper),
+    Rename(Box<Remapper>),
+    PrintVersion,
+    SetVersion(u16, u16),
+}
+
+impl Command {
+    fn parse(args: &Arguments) -> CliResult<Self> {
+        Ok(match args.command.as_str() {
+            "dump" => Self::Dump,
+            "cp" => Self::ConstantPool,
+            "verify" => Self::Verify,
+            "strip" => Self::Strip(Stripper::new()),
+            "rename" => {
+                if args.rest.is_empty() {
+                    return Err("rename needs at least one <old>=<new> mapping".to_owned());
+                }
+
+                let mut remapper = Remapper::new();
+                for mapping in &args.rest {
+                    let (old, new) = mapping.split_once('=').ok_or_else(|| {
+                        format!("invalid mapping {mapping}, expected <old>=<new>")
+                    })?;
+                    remapper.rename_class(old, new);
+                }
+
+                Self::Rename(Box::new(remapper))
+            }
+            "version" => match args.rest.as_slice() {
+                [] => Self::PrintVersion,
+                [version] => {
+                    let (major, minor) = version.split_once('.').unwrap_or((version, "0"));
+                    let major = major
+                        .parse()
+                        .map_err(|_| format!("invalid major version {major}"))?;
+                    let minor = minor
+                        .parse()
+                        .map_err(|_| format!("invalid minor version {minor}"))?;
+
+                    Self::SetVersion(major, minor)
+                }
+                _ => return Err(USAGE.to_owned()),
+            },
+            x => return Err(format!("unknown command {x}\n\n{USAGE}")),
+        })
+    }
+
+    /// Whether the command changes the classes, which are then written.
+    fn modifies(&self) -> bool {
+        matches!(
+            self,
+            Self::Strip(_) | Self::Rename(_) | Self::SetVersion(..)
+        )
+    }
+
+    /// Runs the command on a class, returning whether it succeeded.
+    fn run(&self, class: &mut ClassFile) -> JomResult<bool> {
+        match self {
+            Self::Dump => print!("{class}"),
+            Self::ConstantPool => print!("{}", class.constant_pool()),
+            Self::Verify => return Ok(verify(class)),
+            Self::Strip(stripper) => {
+                if !stripper.strip(class)? {
+                    eprintln!(
+                        "{}: kept the constant pool, an attribute has an unknown layout",
+                        class.this_class()
+                    );
+                }
+            }
+            Self::Rename(remapper) => remapper.remap(class)?,
+            Self::PrintVersion => println!("{}.{}", class.major(), class.minor()),
+            Self::SetVersion(major, minor) => class.set_version(*major, *minor),
+        }
+
+        Ok(true)
+    }
+}
+
+/// Runs the command on the class or on every class in the JAR, returning whether it
+/// succeeded.
+fn run(args: &Arguments) -> CliResult<bool> {
+    let command = Command::parse(args)?;
+    let bytes = fs::read(&args.input).map_err(|e| format!("{}: {e}", args.input))?;
+    let error = |e: JomError| format!("{}: {e}", args.input);
+
+    if !is_jar(&args.input, &bytes) {
+        let mut class = ClassFile::read(&bytes).map_err(error)?;
+        let ok = command.run(&mut class).map_err(error)?;
+        if command.modifies() {
+            write(args.output(), class.write().map_err(error)?)?;
+        }
+
+        return Ok(ok);
+    }
+
+    let mut jar = Jar::read(&bytes).map_err(error)?;
+    let mut ok = true;
+    for entry in jar.entries_mut().iter_mut().filter(|x| x.is_class()) {
+        let name = entry.name().to_owned();
+        let error = |e: JomError| format!("{}!{name}: {e}", args.input);
+        let mut class = entry.class_file().map_err(error)?;
+
+        // The output of every class is headed by its entry, except where it names the class.
+        if let Command::Dump | Command::ConstantPool = command {
+            println!("{name}:");
+        } else if let Command::PrintVersion = command {
+            print!("{name}: ");
+        }
+        ok &= command.run(&mut class).map_err(error)?;
+
+        if command.modifies() {
+            entry.set_class_file(&class).map_err(error)?;
+            // A renamed class moves to the entry of its new name, in the same release.
+            if let Some(old) = entry.class_name().filter(|&x| x != class.this_class()) {
+                let release = &name[..name.len() - old.len() - ".class".len()];
+                entry.set_name(format!("{release}{}.class", class.this_class()));
+            }
+        }
+    }
+
+    if command.modifies() {
+        // The signatures no longer match the changed classes.
+        jar.strip_signatures().map_err(error)?;
+        write(args.output(), jar.write().map_err(error)?)?;
+    }
+
+    Ok(ok)
+}
+
+/// Whether the input is a JAR, by its extension or by starting like a ZIP archive.
+fn is_jar(path: &str, bytes: &[u8]) -> bool {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|x| x.to_str())
+        .unwrap_or("");
+
+    matches!(extension.to_ascii_lowercase().as_str(), "jar" | "zip")
+        || bytes.starts_with(b"PK\x03\x04")
+}
+
+fn write(path: &str, bytes: Vec<u8>) -> CliResult<()> {
+    fs::write(path, bytes).map_err(|e| format!("{path}: {e}"))
+}
+
+fn verify(class: &ClassFile) -> bool {
+    let analyzer = Analyzer::new(BasicInterpreter);
+    let mut ok = true;
+
+    for method in class.methods() {
+        if let Err(e) = analyzer.analyze(class, method) {
+            println!(
+                "{}.{}{}: {e}",
+                class.this_class(),
+                method.name,
+                method.descriptor
+            );
+            ok = false;
+        }
+    }
+
+    if ok {
+        println!("{}: ok", class.this_class());
+    }
+
+    ok
+}
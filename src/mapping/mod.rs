@@ -0,0 +1,252 @@
+//! Name mappings, as shipped with obfuscated builds, in ProGuard, Tiny and SRG formats.
+//!
+//! Mappings go from source names to target names, like ProGuard's original to obfuscated
+//! names. Descriptors are always given with source names.
+
+mod proguard;
+mod srg;
+mod stack_trace;
+mod tiny;
+
+use crate::{
+    descriptor::{FieldType, MethodDescriptor},
+    error::{JomError, JomResult},
+    remap::Remapper,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Mappings {
+    pub classes: Vec<ClassMapping>,
+}
+
+/// A class, by internal name, and its members.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClassMapping {
+    pub source: String,
+    pub target: String,
+    pub fields: Vec<FieldMapping>,
+    pub methods: Vec<MethodMapping>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldMapping {
+    pub source: String,
+    pub target: String,
+    /// Missing from formats like SRG, which only map field names.
+    pub descriptor: Option<String>,
+}
+
+/// A method, which may appear more than once for ProGuard mappings, once for each range of
+/// lines and for each method inlined into it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MethodMapping {
+    pub source: String,
+    pub target: String,
+    pub descriptor: String,
+    /// The class the method was inlined from, if it isn't the mapped class.
+    pub inlined_from: Option<String>,
+    /// The first and last line of the method, or of the inlined call, in source and target.
+    pub lines: Option<LineMapping>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineMapping {
+    pub source: (u32, u32),
+    pub target: (u32, u32),
+}
+
+impl LineMapping {
+    /// The source line for a target line in the range.
+    fn source_line(&self, line: u32) -> Option<u32> {
+        if !(self.target.0..=self.target.1).contains(&line) {
+            return None;
+        }
+
+        // Ranges of different lengths, or a reversed source range, map to a single line.
+        match self.source.1.checked_sub(self.source.0) == Some(self.target.1 - self.target.0) {
+            true => Some(self.source.0 + line - self.target.0),
+            false => Some(self.source.0),
+        }
+    }
+
+    fn reverse(&self) -> Self {
+        Self {
+            source: self.target,
+            target: self.source,
+        }
+    }
+}
+
+impl ClassMapping {
+    pub fn new(source: impl Into<String>, target: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            target: target.into(),
+            fields: vec![],
+            methods: vec![],
+        }
+    }
+
+    pub fn field(&self, source: &str) -> Option<&FieldMapping> {
+        self.fields.iter().find(|x| x.source == source)
+    }
+
+    pub fn method(&self, source: &str, descriptor: &str) -> Option<&MethodMapping> {
+        self.methods
+            .iter()
+            .find(|x| x.source == source && x.descriptor == descriptor)
+    }
+
+    /// The methods once each, without the line ranges and inlined methods of ProGuard
+    /// mappings.
+    fn unique_methods(&self) -> impl Iterator<Item = &MethodMapping> {
+        self.methods.iter().enumerate().filter_map(|(i, method)| {
+            let first = self.methods.iter().position(|x| {
+                x.inlined_from.is_none()
+                    && x.source == method.source
+                    && x.descriptor == method.descriptor
+            });
+            (first == Some(i)).then_some(method)
+        })
+    }
+}
+
+impl Mappings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn class(&self, source: &str) -> Option<&ClassMapping> {
+        self.classes.iter().find(|x| x.source == source)
+    }
+
+    /// Returns the mapping for a class, adding one that keeps its name if there is none.
+    pub fn class_mut(&mut self, source: &str) -> &mut ClassMapping {
+        match self.classes.iter().position(|x| x.source == source) {
+            Some(i) => &mut self.classes[i],
+            None => {
+                self.classes.push(ClassMapping::new(source, source));
+                self.classes.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// The mappings from target to source names.
+    pub fn reverse(&self) -> JomResult<Self> {
+        let remapper = self.remapper();
+        let map_descriptor = |x: &str| remapper.map_signature(x);
+
+        let classes = self
+            .classes
+            .iter()
+            .map(|class| {
+                let fields = class
+                    .fields
+                    .iter()
+                    .map(|x| {
+                        Ok(FieldMapping {
+                            source: x.target.clone(),
+                            target: x.source.clone(),
+                            descriptor: x.descriptor.as_deref().map(map_descriptor).transpose()?,
+                        })
+                    })
+                    .collect::<JomResult<Vec<_>>>()?;
+                let methods = class
+                    .methods
+                    .iter()
+                    .map(|x| {
+                        Ok(MethodMapping {
+                            source: x.target.clone(),
+                            target: x.source.clone(),
+                            descriptor: map_descriptor(&x.descriptor)?,
+                            inlined_from: x.inlined_from.as_deref().map(|x| remapper.map_class(x)),
+                            lines: x.lines.as_ref().map(LineMapping::reverse),
+                        })
+                    })
+                    .collect::<JomResult<Vec<_>>>()?;
+
+                Ok(ClassMapping {
+                    source: class.target.clone(),
+                    target: class.source.clone(),
+                    fields,
+                    methods,
+                })
+            })
+            .collect::<JomResult<Vec<_>>>()?;
+
+        Ok(Self { classes })
+    }
+
+    /// A remapper renaming classes and members from source to target names.
+    pub fn remapper(&self) -> Remapper {
+        let mut remapper = Remapper::new();
+
+        for class in &self.classes {
+            if class.source != class.target {
+                remapper.rename_class(&class.source, &class.target);
+            }
+            for field in &class.fields {
+                if field.source != field.target {
+                    remapper.rename_field(
+                        &class.source,
+                        &field.source,
+                        field.descriptor.as_deref(),
+                        &field.target,
+                    );
+                }
+            }
+            for method in class.methods.iter().filter(|x| x.inlined_from.is_none()) {
+                if method.source != method.target {
+                    remapper.rename_method(
+                        &class.source,
+                        &method.source,
+                        &method.descriptor,
+                        &method.target,
+                    );
+                }
+            }
+        }
+
+        remapper
+    }
+}
+
+/// A field descriptor from a type as written in Java source, e.g. `java.lang.String[]`.
+fn java_field_descriptor(name: &str) -> JomResult<String> {
+    Ok(FieldType::from_java_name(name)?.to_string())
+}
+
+/// A method descriptor from Java source types, e.g. `void` and `int,java.lang.String`.
+fn java_method_descriptor(return_type: &str, parameters: &str) -> JomResult<String> {
+    let mut descriptor = String::from("(");
+    for parameter in parameters.split(',').filter(|x| !x.is_empty()) {
+        descriptor.push_str(&java_field_descriptor(parameter.trim())?);
+    }
+    descriptor.push(')');
+    match return_type {
+        "void" => descriptor.push('V'),
+        x => descriptor.push_str(&java_field_descriptor(x)?),
+    }
+
+    Ok(descriptor)
+}
+
+/// The return type and parameters of a method descriptor, as written in Java source.
+fn java_method_types(descriptor: &str) -> JomResult<(String, String)> {
+    let descriptor = MethodDescriptor::parse(descriptor)?;
+    let parameters = descriptor
+        .parameters
+        .iter()
+        .map(FieldType::java_name)
+        .collect::<Vec<_>>();
+    let return_type = descriptor
+        .return_type
+        .as_ref()
+        .map_or("void".to_owned(), FieldType::java_name);
+
+    Ok((return_type, parameters.join(",")))
+}
+
+fn invalid_line(line: usize, message: impl std::fmt::Display) -> JomError {
+    JomError::invalid_mapping(format!("line {}: {message}", line + 1))
+}
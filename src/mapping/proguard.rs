@@ -0,0 +1,172 @@
+//! ProGuard and R8 `mapping.txt` files:
+//!
+//! ```text
+//! com.example.Main -> a.a:
+//!     java.lang.String name -> a
+//!     1:3:void run(int):10:12 -> b
+//!     4:4:void com.example.Util.log():5:5 -> b
+//! ```
+
+use std::fmt::Write;
+
+use super::{
+    invalid_line, java_field_descriptor, java_method_descriptor, java_method_types, ClassMapping,
+    FieldMapping, LineMapping, Mappings, MethodMapping,
+};
+use crate::{
+    descriptor::FieldType,
+    error::{JomError, JomResult},
+};
+
+impl Mappings {
+    pub fn parse_proguard(text: &str) -> JomResult<Self> {
+        let mut mappings = Self::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if !line.starts_with(char::is_whitespace) {
+                let (source, target) = trimmed
+                    .strip_suffix(':')
+                    .and_then(|x| x.split_once(" -> "))
+                    .ok_or_else(|| invalid_line(i, "expected a class mapping"))?;
+                mappings.classes.push(ClassMapping::new(
+                    source.trim().replace('.', "/"),
+                    target.trim().replace('.', "/"),
+                ));
+                continue;
+            }
+
+            let class = mappings
+                .classes
+                .last_mut()
+                .ok_or_else(|| invalid_line(i, "member outside of a class"))?;
+            let (member, target) = trimmed
+                .split_once(" -> ")
+                .ok_or_else(|| invalid_line(i, "expected a member mapping"))?;
+            let target = target.trim().to_owned();
+
+            // The obfuscated lines before the type, as `1:3:`.
+            let mut rest = member;
+            let mut target_lines = vec![];
+            while rest.starts_with(|c: char| c.is_ascii_digit()) {
+                let (number, tail) = rest
+                    .split_once(':')
+                    .ok_or_else(|| invalid_line(i, "expected a line number"))?;
+                target_lines.push(parse_line_number(i, number)?);
+                rest = tail;
+            }
+
+            let (ty, name) = rest
+                .split_once(' ')
+                .ok_or_else(|| invalid_line(i, "expected a type and a name"))?;
+            let Some((name, tail)) = name.split_once('(') else {
+                class.fields.push(FieldMapping {
+                    source: name.trim().to_owned(),
+                    target,
+                    descriptor: Some(java_field_descriptor(ty).map_err(|e| invalid_line(i, e))?),
+                });
+                continue;
+            };
+
+            let (parameters, tail) = tail
+                .split_once(')')
+                .ok_or_else(|| invalid_line(i, "expected a closing parenthesis"))?;
+            let descriptor =
+                java_method_descriptor(ty, parameters).map_err(|e| invalid_line(i, e))?;
+
+            // The original lines after the parameters, as `:10:12`.
+            let source_lines = tail
+                .split(':')
+                .skip(1)
+                .map(|x| parse_line_number(i, x))
+                .collect::<JomResult<Vec<_>>>()?;
+
+            let lines = match target_lines[..] {
+                [] => None,
+                [start] | [start, _] => {
+                    let target = (start, *target_lines.last().unwrap());
+                    let source = match source_lines[..] {
+                        [] => target,
+                        [start] => (start, start),
+                        [start, end] => (start, end),
+                        _ => return Err(invalid_line(i, "too many line numbers")),
+                    };
+                    Some(LineMapping { source, target })
+                }
+                _ => return Err(invalid_line(i, "too many line numbers")),
+            };
+
+            // Methods inlined from other classes are qualified with their class.
+            let (inlined_from, name) = match name.rsplit_once('.') {
+                Some((class, name)) => (Some(class.replace('.', "/")), name),
+                None => (None, name),
+            };
+
+            class.methods.push(MethodMapping {
+                source: name.to_owned(),
+                target,
+                descriptor,
+                inlined_from,
+                lines,
+            });
+        }
+
+        Ok(mappings)
+    }
+
+    /// Writes the mappings, which fails for fields without a descriptor.
+    pub fn write_proguard(&self) -> JomResult<String> {
+        let mut text = String::new();
+
+        for class in &self.classes {
+            writeln!(
+                text,
+                "{} -> {}:",
+                class.source.replace('/', "."),
+                class.target.replace('/', ".")
+            )
+            .unwrap();
+
+            for field in &class.fields {
+                let descriptor = field.descriptor.as_deref().ok_or_else(|| {
+                    JomError::invalid_mapping(format!(
+                        "missing descriptor for field {}.{}",
+                        class.source, field.source
+                    ))
+                })?;
+                let ty = FieldType::parse(descriptor)?.java_name();
+                writeln!(text, "    {ty} {} -> {}", field.source, field.target).unwrap();
+            }
+
+            for method in &class.methods {
+                let (return_type, parameters) = java_method_types(&method.descriptor)?;
+                text.push_str("    ");
+                if let Some(lines) = &method.lines {
+                    write!(text, "{}:{}:", lines.target.0, lines.target.1).unwrap();
+                }
+                write!(text, "{return_type} ").unwrap();
+                if let Some(inlined_from) = &method.inlined_from {
+                    write!(text, "{}.", inlined_from.replace('/', ".")).unwrap();
+                }
+                write!(text, "{}({parameters})", method.source).unwrap();
+                if let Some(lines) = method.lines.filter(|x| x.source != x.target) {
+                    write!(text, ":{}:{}", lines.source.0, lines.source.1).unwrap();
+                }
+                writeln!(text, " -> {}", method.target).unwrap();
+            }
+        }
+
+        Ok(text)
+    }
+}
+
+fn parse_line_number(line: usize, number: &str) -> JomResult<u32> {
+    number
+        .trim()
+        .parse()
+        .map_err(|_| invalid_line(line, format!("invalid line number {number}")))
+}
@@ -0,0 +1,207 @@
+//! SRG and TSRG files, as used by Forge's tooling.
+//!
+//! ```text
+//! CL: a com/example/Main
+//! FD: a/a com/example/Main/count
+//! MD: a/b (La;)V com/example/Main/run (Lcom/example/Main;)V
+//! ```
+//!
+//! TSRG has the same entries, grouped by class and indented by tabs:
+//!
+//! ```text
+//! a com/example/Main
+//!     a count
+//!     b (La;)V run
+//! ```
+
+use std::fmt::Write;
+
+use super::{invalid_line, ClassMapping, FieldMapping, Mappings, MethodMapping};
+use crate::error::JomResult;
+
+impl Mappings {
+    pub fn parse_srg(text: &str) -> JomResult<Self> {
+        let mut mappings = Self::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let columns = line.split_whitespace().collect::<Vec<_>>();
+            match columns[..] {
+                [] | ["PK:", ..] => {}
+                ["CL:", source, target] => {
+                    mappings.class_mut(source).target = target.to_owned();
+                }
+                ["FD:", source, target] => mappings.add_srg_field(i, source, target, None)?,
+                // XSRG adds field descriptors.
+                ["FD:", source, descriptor, target, _] => {
+                    mappings.add_srg_field(i, source, target, Some(descriptor))?
+                }
+                ["MD:", source, descriptor, target, _] => {
+                    let (class, source) = split_member(i, source)?;
+                    let (_, target) = split_member(i, target)?;
+                    mappings.class_mut(class).methods.push(MethodMapping {
+                        source: source.to_owned(),
+                        target: target.to_owned(),
+                        descriptor: descriptor.to_owned(),
+                        inlined_from: None,
+                        lines: None,
+                    });
+                }
+                _ => return Err(invalid_line(i, "expected a PK, CL, FD or MD entry")),
+            }
+        }
+
+        Ok(mappings)
+    }
+
+    fn add_srg_field(
+        &mut self,
+        line: usize,
+        source: &str,
+        target: &str,
+        descriptor: Option<&str>,
+    ) -> JomResult<()> {
+        let (class, source) = split_member(line, source)?;
+        let (_, target) = split_member(line, target)?;
+        self.class_mut(class).fields.push(FieldMapping {
+            source: source.to_owned(),
+            target: target.to_owned(),
+            descriptor: descriptor.map(str::to_owned),
+        });
+
+        Ok(())
+    }
+
+    /// Parses a TSRG or TSRG v2 file, mapping between the first two namespaces of the latter.
+    pub fn parse_tsrg(text: &str) -> JomResult<Self> {
+        let mut mappings = Self::new();
+        let mut lines = text.lines().enumerate().peekable();
+
+        // TSRG v2 names its namespaces in a header, and adds names for the other ones.
+        let namespaces = match lines.peek() {
+            Some((_, header)) if header.starts_with("tsrg2 ") => {
+                let count = header.split_whitespace().count() - 1;
+                lines.next();
+                count
+            }
+            _ => 2,
+        };
+        if namespaces < 2 {
+            return Err(invalid_line(0, "expected at least two namespaces"));
+        }
+
+        for (i, line) in lines {
+            let depth = line.len() - line.trim_start_matches('\t').len();
+            let columns = line.split_whitespace().collect::<Vec<_>>();
+            if columns.is_empty() {
+                continue;
+            }
+
+            match depth {
+                0 if columns.len() == namespaces => {
+                    mappings
+                        .classes
+                        .push(ClassMapping::new(columns[0], columns[1]));
+                }
+                1 => {
+                    let class = mappings
+                        .classes
+                        .last_mut()
+                        .ok_or_else(|| invalid_line(i, "member outside of a class"))?;
+                    match columns.len().checked_sub(namespaces) {
+                        Some(0) => class.fields.push(FieldMapping {
+                            source: columns[0].to_owned(),
+                            target: columns[1].to_owned(),
+                            descriptor: None,
+                        }),
+                        Some(1) if columns[1].starts_with('(') => {
+                            class.methods.push(MethodMapping {
+                                source: columns[0].to_owned(),
+                                target: columns[2].to_owned(),
+                                descriptor: columns[1].to_owned(),
+                                inlined_from: None,
+                                lines: None,
+                            })
+                        }
+                        Some(1) => class.fields.push(FieldMapping {
+                            source: columns[0].to_owned(),
+                            target: columns[2].to_owned(),
+                            descriptor: Some(columns[1].to_owned()),
+                        }),
+                        _ => return Err(invalid_line(i, "wrong number of member names")),
+                    }
+                }
+                // Parameters and the `static` marker of TSRG v2 methods.
+                2.. => {}
+                _ => return Err(invalid_line(i, "wrong number of class names")),
+            }
+        }
+
+        Ok(mappings)
+    }
+
+    /// Writes the mappings as SRG, with the target descriptors of methods mapped to target
+    /// class names.
+    pub fn write_srg(&self) -> JomResult<String> {
+        let remapper = self.remapper();
+        let mut text = String::new();
+
+        for class in &self.classes {
+            writeln!(text, "CL: {} {}", class.source, class.target).unwrap();
+        }
+        for class in &self.classes {
+            for field in &class.fields {
+                writeln!(
+                    text,
+                    "FD: {}/{} {}/{}",
+                    class.source, field.source, class.target, field.target
+                )
+                .unwrap();
+            }
+        }
+        for class in &self.classes {
+            for method in class.unique_methods() {
+                writeln!(
+                    text,
+                    "MD: {}/{} {} {}/{} {}",
+                    class.source,
+                    method.source,
+                    method.descriptor,
+                    class.target,
+                    method.target,
+                    remapper.map_signature(&method.descriptor)?
+                )
+                .unwrap();
+            }
+        }
+
+        Ok(text)
+    }
+
+    /// Writes the mappings as TSRG, without field descriptors.
+    pub fn write_tsrg(&self) -> String {
+        let mut text = String::new();
+
+        for class in &self.classes {
+            writeln!(text, "{} {}", class.source, class.target).unwrap();
+            for field in &class.fields {
+                writeln!(text, "\t{} {}", field.source, field.target).unwrap();
+            }
+            for method in class.unique_methods() {
+                writeln!(
+                    text,
+                    "\t{} {} {}",
+                    method.source, method.descriptor, method.target
+                )
+                .unwrap();
+            }
+        }
+
+        text
+    }
+}
+
+/// Splits `pkg/Class/member` into the class and the member.
+fn split_member(line: usize, name: &str) -> JomResult<(&str, &str)> {
+    name.rsplit_once('/')
+        .ok_or_else(|| invalid_line(line, format!("expected a class and member in {name}")))
+}
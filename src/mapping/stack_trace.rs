@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use super::{ClassMapping, Mappings};
+
+impl Mappings {
+    /// Rewrites the target names in a stack trace, as printed by `Throwable.printStackTrace`,
+    /// to source names.
+    ///
+    /// Frames with a line number are matched against the line ranges of ProGuard mappings,
+    /// which expands inlined methods into a frame each. Frames that still match more than
+    /// one method list all of their names, separated by `|`.
+    pub fn deobfuscate_stack_trace(&self, trace: &str) -> String {
+        let classes = self
+            .classes
+            .iter()
+            .map(|x| (x.target.as_str(), x))
+            .collect::<HashMap<_, _>>();
+        let class = |name: &str| classes.get(name.replace('.', "/").as_str()).copied();
+
+        let mut deobfuscated = trace
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                let indent = &line[..line.len() - trimmed.len()];
+                match trimmed.strip_prefix("at ") {
+                    Some(frame) => deobfuscate_frame(indent, frame, class),
+                    None => deobfuscate_exception(line, indent, trimmed, class),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if trace.ends_with('\n') {
+            deobfuscated.push('\n');
+        }
+
+        deobfuscated
+    }
+}
+
+/// A frame, as `[module/]class.method(File.java:line)`.
+fn deobfuscate_frame<'a>(
+    indent: &str,
+    frame: &str,
+    class: impl Fn(&str) -> Option<&'a ClassMapping>,
+) -> String {
+    let unchanged = || format!("{indent}at {frame}");
+    let Some((qualified, location)) = frame.split_once('(') else {
+        return unchanged();
+    };
+    let location = location.strip_suffix(')').unwrap_or(location);
+    // Modules and class loaders, as in `java.base/` or `app//`.
+    let (prefix, qualified) = match qualified.rfind('/') {
+        Some(i) => qualified.split_at(i + 1),
+        None => ("", qualified),
+    };
+    let Some((class_name, method_name)) = qualified.rsplit_once('.') else {
+        return unchanged();
+    };
+    let Some(class) = class(class_name) else {
+        return unchanged();
+    };
+
+    let (file, line) = match location.rsplit_once(':') {
+        Some((file, line)) => match line.parse::<u32>() {
+            Ok(line) => (file, Some(line)),
+            Err(_) => (location, None),
+        },
+        None => (location, None),
+    };
+    let format_frame = |class_name: &str, method_name: &str, line: Option<u32>| {
+        // Obfuscated classes lose their source file name.
+        let file = match file {
+            "SourceFile" => {
+                let outer = class_name.split('$').next().unwrap_or(class_name);
+                format!("{}.java", outer.rsplit('/').next().unwrap_or(outer))
+            }
+            file => file.to_owned(),
+        };
+        let location = match line {
+            Some(line) => format!("{file}:{line}"),
+            None => file,
+        };
+        format!(
+            "{indent}at {prefix}{}.{method_name}({location})",
+            class_name.replace('/', ".")
+        )
+    };
+
+    let methods = class
+        .methods
+        .iter()
+        .filter(|x| x.target == method_name)
+        .collect::<Vec<_>>();
+
+    // The methods whose line ranges contain the line, innermost inlined method first.
+    if let Some(line) = line {
+        let frames = methods
+            .iter()
+            .filter_map(|method| {
+                let source_line = method.lines?.source_line(line)?;
+                let class_name = method.inlined_from.as_deref().unwrap_or(&class.source);
+                Some(format_frame(class_name, &method.source, Some(source_line)))
+            })
+            .collect::<Vec<_>>();
+        if !frames.is_empty() {
+            return frames.join("\n");
+        }
+    }
+
+    let mut names = Vec::<&str>::new();
+    for method in methods.iter().filter(|x| x.inlined_from.is_none()) {
+        if !names.contains(&method.source.as_str()) {
+            names.push(&method.source);
+        }
+    }
+    let method_name = match names.is_empty() {
+        true => method_name.to_owned(),
+        false => names.join("|"),
+    };
+
+    format_frame(&class.source, &method_name, line)
+}
+
+/// A line starting with an exception class, as in `Caused by: a.b: message`.
+fn deobfuscate_exception<'a>(
+    line: &str,
+    indent: &str,
+    trimmed: &str,
+    class: impl Fn(&str) -> Option<&'a ClassMapping>,
+) -> String {
+    let mut prefix = "";
+    for candidate in ["Caused by: ", "Suppressed: "] {
+        if trimmed.starts_with(candidate) {
+            prefix = candidate;
+        }
+    }
+    if let Some(thread) = trimmed.strip_prefix("Exception in thread \"") {
+        if let Some(i) = thread.find("\" ") {
+            prefix = &trimmed[..trimmed.len() - thread.len() + i + 2];
+        }
+    }
+
+    let rest = &trimmed[prefix.len()..];
+    let (name, message) = rest.split_at(rest.find(':').unwrap_or(rest.len()));
+    match class(name).filter(|_| !name.contains(char::is_whitespace)) {
+        Some(class) => format!(
+            "{indent}{prefix}{}{message}",
+            class.source.replace('/', ".")
+        ),
+        None => line.to_owned(),
+    }
+}
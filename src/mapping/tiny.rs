@@ -0,0 +1,279 @@
+//! Tiny v1 and v2 files, as used by Fabric, which map between any number of namespaces.
+//! Descriptors are given with the class names of the first namespace:
+//!
+//! ```text
+//! tiny  2  0  official  named
+//! c  a  com/example/Main
+//!     f  I  a  count
+//!     m  (La;)V  b  run
+//! ```
+//!
+//! Columns are separated by tabs, as is the indentation of members.
+
+use std::{collections::HashMap, fmt::Write};
+
+use super::{invalid_line, ClassMapping, FieldMapping, Mappings, MethodMapping};
+use crate::{
+    error::{JomError, JomResult},
+    remap::Remapper,
+};
+
+/// A member as read, with names in every namespace and the descriptor in the first.
+struct TinyMember {
+    class: String,
+    descriptor: Option<String>,
+    names: Vec<String>,
+    is_method: bool,
+}
+
+impl Mappings {
+    /// Parses a Tiny v1 or v2 file, mapping from one namespace in its header to another.
+    pub fn parse_tiny(
+        text: &str,
+        source_namespace: &str,
+        target_namespace: &str,
+    ) -> JomResult<Self> {
+        let mut lines = text.lines().enumerate();
+        let header = lines
+            .next()
+            .map(|(_, x)| x.split('\t').collect::<Vec<_>>())
+            .unwrap_or_default();
+        let (version, namespaces) = match header[..] {
+            ["v1", ref namespaces @ ..] => (1, namespaces),
+            ["tiny", "2", _, ref namespaces @ ..] => (2, namespaces),
+            _ => return Err(invalid_line(0, "expected a Tiny header")),
+        };
+
+        let position = |namespace: &str| {
+            namespaces
+                .iter()
+                .position(|x| *x == namespace)
+                .ok_or_else(|| JomError::invalid_mapping(format!("unknown namespace {namespace}")))
+        };
+        let source = position(source_namespace)?;
+        let target = position(target_namespace)?;
+
+        let mut classes = Vec::<Vec<String>>::new();
+        let mut members = Vec::<TinyMember>::new();
+
+        if version == 1 {
+            for (i, line) in lines {
+                let columns = line.split('\t').collect::<Vec<_>>();
+                match columns[..] {
+                    [] | [""] => {}
+                    ["CLASS", ref names @ ..] if names.len() == namespaces.len() => {
+                        classes.push(names.iter().map(|x| x.to_string()).collect());
+                    }
+                    [kind @ ("FIELD" | "METHOD"), class, descriptor, ref names @ ..]
+                        if names.len() == namespaces.len() =>
+                    {
+                        members.push(TinyMember {
+                            class: class.to_owned(),
+                            descriptor: Some(descriptor.to_owned()),
+                            names: names.iter().map(|x| x.to_string()).collect(),
+                            is_method: kind == "METHOD",
+                        });
+                    }
+                    _ => return Err(invalid_line(i, "expected a CLASS, FIELD or METHOD entry")),
+                }
+            }
+        } else {
+            let mut escaped = false;
+            let mut in_header = true;
+
+            for (i, line) in lines {
+                let depth = line.len() - line.trim_start_matches('\t').len();
+                let mut columns = line[depth..].split('\t').map(|x| match escaped {
+                    true => unescape(x),
+                    false => x.to_owned(),
+                });
+                let kind = columns.next().unwrap_or_default();
+
+                match (depth, kind.as_str()) {
+                    (1, "escaped-names") if in_header => escaped = true,
+                    (1, _) if in_header => {}
+                    (0, "c") => {
+                        in_header = false;
+                        let names = columns.collect::<Vec<_>>();
+                        if names.len() != namespaces.len() {
+                            return Err(invalid_line(i, "wrong number of class names"));
+                        }
+                        classes.push(names);
+                    }
+                    (1, kind @ ("f" | "m")) => {
+                        let class = classes
+                            .last()
+                            .ok_or_else(|| invalid_line(i, "member outside of a class"))?;
+                        let descriptor = columns.next();
+                        let names = columns.collect::<Vec<_>>();
+                        if names.len() != namespaces.len() {
+                            return Err(invalid_line(i, "wrong number of member names"));
+                        }
+                        members.push(TinyMember {
+                            class: class[0].clone(),
+                            descriptor,
+                            names,
+                            is_method: kind == "m",
+                        });
+                    }
+                    // Comments, parameters and local variables.
+                    (1.., _) | (_, "") => {}
+                    _ => return Err(invalid_line(i, format!("unexpected entry {kind}"))),
+                }
+            }
+        }
+
+        // Missing names fall back to the source name, which falls back to the first name.
+        let name = |names: &[String], namespace: usize| match (
+            names[namespace].as_str(),
+            names[source].as_str(),
+        ) {
+            ("", "") => names[0].clone(),
+            ("", x) | (x, _) => x.to_owned(),
+        };
+
+        let mut mappings = Self::new();
+        let mut index = HashMap::new();
+        let mut first_to_source = Remapper::new();
+        for names in &classes {
+            let source_name = name(names, source);
+            if source_name != names[0] {
+                first_to_source.rename_class(&names[0], &source_name);
+            }
+            index.insert(names[0].clone(), mappings.classes.len());
+            mappings
+                .classes
+                .push(ClassMapping::new(source_name, name(names, target)));
+        }
+
+        for member in members {
+            let i = *index.entry(member.class.clone()).or_insert_with(|| {
+                let class = first_to_source.map_class(&member.class);
+                mappings.classes.push(ClassMapping::new(&class, &class));
+                mappings.classes.len() - 1
+            });
+            let descriptor = member
+                .descriptor
+                .map(|x| first_to_source.map_signature(&x))
+                .transpose()?;
+            let (source_name, target_name) =
+                (name(&member.names, source), name(&member.names, target));
+
+            let class = &mut mappings.classes[i];
+            match (member.is_method, descriptor) {
+                (true, Some(descriptor)) => class.methods.push(MethodMapping {
+                    source: source_name,
+                    target: target_name,
+                    descriptor,
+                    inlined_from: None,
+                    lines: None,
+                }),
+                (_, descriptor) => class.fields.push(FieldMapping {
+                    source: source_name,
+                    target: target_name,
+                    descriptor,
+                }),
+            }
+        }
+
+        Ok(mappings)
+    }
+
+    /// Writes the mappings as Tiny v1, which fails for fields without a descriptor.
+    pub fn write_tiny_v1(
+        &self,
+        source_namespace: &str,
+        target_namespace: &str,
+    ) -> JomResult<String> {
+        let mut text = format!("v1\t{source_namespace}\t{target_namespace}\n");
+
+        for class in &self.classes {
+            writeln!(text, "CLASS\t{}\t{}", class.source, class.target).unwrap();
+        }
+        for class in &self.classes {
+            for field in &class.fields {
+                writeln!(
+                    text,
+                    "FIELD\t{}\t{}\t{}\t{}",
+                    class.source,
+                    field_descriptor(class, field)?,
+                    field.source,
+                    field.target
+                )
+                .unwrap();
+            }
+            for method in class.unique_methods() {
+                writeln!(
+                    text,
+                    "METHOD\t{}\t{}\t{}\t{}",
+                    class.source, method.descriptor, method.source, method.target
+                )
+                .unwrap();
+            }
+        }
+
+        Ok(text)
+    }
+
+    /// Writes the mappings as Tiny v2, which fails for fields without a descriptor.
+    pub fn write_tiny_v2(
+        &self,
+        source_namespace: &str,
+        target_namespace: &str,
+    ) -> JomResult<String> {
+        let mut text = format!("tiny\t2\t0\t{source_namespace}\t{target_namespace}\n");
+
+        for class in &self.classes {
+            writeln!(text, "c\t{}\t{}", class.source, class.target).unwrap();
+            for field in &class.fields {
+                writeln!(
+                    text,
+                    "\tf\t{}\t{}\t{}",
+                    field_descriptor(class, field)?,
+                    field.source,
+                    field.target
+                )
+                .unwrap();
+            }
+            for method in class.unique_methods() {
+                writeln!(
+                    text,
+                    "\tm\t{}\t{}\t{}",
+                    method.descriptor, method.source, method.target
+                )
+                .unwrap();
+            }
+        }
+
+        Ok(text)
+    }
+}
+
+fn field_descriptor<'a>(class: &ClassMapping, field: &'a FieldMapping) -> JomResult<&'a str> {
+    field.descriptor.as_deref().ok_or_else(|| {
+        JomError::invalid_mapping(format!(
+            "missing descriptor for field {}.{}",
+            class.source, field.source
+        ))
+    })
+}
+
+fn unescape(name: &str) -> String {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('t') => unescaped.push('\t'),
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some('0') => unescaped.push('\0'),
+                Some(c) => unescaped.push(c),
+                None => unescaped.push('\\'),
+            },
+            c => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
@@ -0,0 +1,494 @@
+//! Emitting code with labels and constants instead of offsets and constant pool indices.
+
+use crate::{
+    attribute::{CodeAttribute, LineNumberTableIndex, LocalVariableTableIndex},
+    constant_pool::{ConstantPool, ConstantPoolIndex},
+    descriptor::{FieldType, MethodDescriptor},
+    error::{JomError, JomResult},
+};
+
+use super::{
+    instruction::{AType, Instruction, Wide},
+    layout::{self, Catch, Item, Layout},
+    Code,
+};
+
+/// A position in the code, which jumps, exception handlers and local variables refer to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Label(usize);
+
+struct Local {
+    index: u16,
+    name: String,
+    descriptor: String,
+    start: Label,
+    end: Label,
+}
+
+/// Builds the code of a method. Instructions with operands have methods of their own that
+/// pick the shortest form, and the others are added with [`CodeBuilder::emit`].
+///
+/// Labels are resolved, and constants added to the constant pool, by
+/// [`CodeBuilder::finish`]. The `StackMapTable` that code with branches needs from version
+/// 50 on is computed for the whole class by [`ClassFile::compute_frames`], which
+/// [`ClassBuilder::finish`](crate::builder::ClassBuilder::finish) calls.
+///
+/// [`ClassFile::compute_frames`]: crate::ClassFile::compute_frames
+#[derive(Default)]
+pub struct CodeBuilder {
+    code: Vec<Item<Label>>,
+    /// The constants of the instructions added with a placeholder index, by their items.
+    constants: Vec<(usize, ConstantPoolIndex)>,
+    /// The index of the item following each label, once it is placed.
+    labels: Vec<Option<usize>>,
+    line_numbers: Vec<(usize, u16)>,
+    catches: Vec<Catch<Label>>,
+    locals: Vec<Local>,
+}
+
+impl CodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A label to place later with [`Self::place`].
+    pub fn new_label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+
+    /// Places `label` before the next instruction.
+    ///
+    /// Panics if the label is already placed or comes from another builder.
+    pub fn place(&mut self, label: Label) {
+        let position = &mut self.labels[label.0];
+        assert!(position.is_none(), "label is already placed");
+        *position = Some(self.code.len());
+    }
+
+    /// A label placed before the next instruction.
+    pub fn label(&mut self) -> Label {
+        let label = self.new_label();
+        self.place(label);
+        label
+    }
+
+    /// Adds an instruction as is, without changing its offsets or constant pool index.
+    pub fn emit(&mut self, instruction: Instruction) {
+        self.code.push(Item::Instruction(instruction));
+    }
+
+    /// Adds an instruction that refers to `constant`, whose index is set when finishing.
+    /// An `invokeinterface` count of 0 is computed from the descriptor.
+    pub fn emit_constant(&mut self, instruction: Instruction, constant: ConstantPoolIndex) {
+        self.constants.push((self.code.len(), constant));
+        self.code.push(Item::Instruction(instruction));
+    }
+
+    /// Sets the source line of the next instruction.
+    pub fn line_number(&mut self, line: u16) {
+        self.line_numbers.push((self.code.len(), line));
+    }
+
+    /// Pushes an `int` with `iconst`, `bipush` or `sipush` if it fits, or `ldc`.
+    pub fn push_int(&mut self, value: i32) {
+        use Instruction::*;
+
+        let instruction = match value {
+            -1 => IConstM1,
+            0 => IConst0,
+            1 => IConst1,
+            2 => IConst2,
+            3 => IConst3,
+            4 => IConst4,
+            5 => IConst5,
+            _ => match (i8::try_from(value), i16::try_from(value)) {
+                (Ok(x), _) => BiPush(x as u8),
+                (_, Ok(x)) => Sipush(x as u16),
+                _ => return self.ldc(ConstantPoolIndex::Integer(value)),
+            },
+        };
+        self.emit(instruction);
+    }
+
+    /// Pushes a constant with `ldc`, `ldc_w` if its index does not fit in a byte, or
+    /// `ldc2_w` for a `Long` or `Double`.
+    pub fn ldc(&mut self, constant: ConstantPoolIndex) {
+        let instruction = match constant {
+            ConstantPoolIndex::Long(_) | ConstantPoolIndex::Double(_) => Instruction::Ldc2W(0),
+            _ => Instruction::Ldc(0),
+        };
+        self.emit_constant(instruction, constant);
+    }
+
+    /// Loads the local variable at `index`, which holds a value of type `ty`.
+    pub fn load(&mut self, ty: &FieldType, index: u16) {
+        self.emit(Instruction::load(ty, index));
+    }
+
+    /// Stores a value of type `ty` in the local variable at `index`.
+    pub fn store(&mut self, ty: &FieldType, index: u16) {
+        self.emit(Instruction::store(ty, index));
+    }
+
+    pub fn iload(&mut self, index: u16) {
+        self.load(&FieldType::Int, index);
+    }
+
+    pub fn lload(&mut self, index: u16) {
+        self.load(&FieldType::Long, index);
+    }
+
+    pub fn fload(&mut self, index: u16) {
+        self.load(&FieldType::Float, index);
+    }
+
+    pub fn dload(&mut self, index: u16) {
+        self.load(&FieldType::Double, index);
+    }
+
+    pub fn aload(&mut self, index: u16) {
+        self.emit(Instruction::aload(index));
+    }
+
+    pub fn istore(&mut self, index: u16) {
+        self.store(&FieldType::Int, index);
+    }
+
+    pub fn lstore(&mut self, index: u16) {
+        self.store(&FieldType::Long, index);
+    }
+
+    pub fn fstore(&mut self, index: u16) {
+        self.store(&FieldType::Float, index);
+    }
+
+    pub fn dstore(&mut self, index: u16) {
+        self.store(&FieldType::Double, index);
+    }
+
+    pub fn astore(&mut self, index: u16) {
+        self.emit(Instruction::astore(index));
+    }
+
+    /// Adds `increment` to the `int` in the local variable at `index`.
+    pub fn iinc(&mut self, index: u16, increment: i16) {
+        let instruction = match (u8::try_from(index), i8::try_from(increment)) {
+            (Ok(index), Ok(increment)) => Instruction::IInc(index, increment as u8),
+            _ => Instruction::Wide(Wide::IInc(index, increment)),
+        };
+        self.emit(instruction);
+    }
+
+    fn field(&mut self, instruction: Instruction, class: &str, name: &str, descriptor: &str) {
+        let field = ConstantPoolIndex::Fieldref {
+            class: class.to_owned(),
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+        };
+        self.emit_constant(instruction, field);
+    }
+
+    pub fn get_static(&mut self, class: &str, name: &str, descriptor: &str) {
+        self.field(Instruction::GetStatic(0), class, name, descriptor);
+    }
+
+    pub fn put_static(&mut self, class: &str, name: &str, descriptor: &str) {
+        self.field(Instruction::PutStatic(0), class, name, descriptor);
+    }
+
+    pub fn get_field(&mut self, class: &str, name: &str, descriptor: &str) {
+        self.field(Instruction::GetField(0), class, name, descriptor);
+    }
+
+    pub fn put_field(&mut self, class: &str, name: &str, descriptor: &str) {
+        self.field(Instruction::PutField(0), class, name, descriptor);
+    }
+
+    fn method(&mut self, instruction: Instruction, class: &str, name: &str, descriptor: &str) {
+        let method = ConstantPoolIndex::Methodref {
+            class: class.to_owned(),
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+        };
+        self.emit_constant(instruction, method);
+    }
+
+    pub fn invoke_virtual(&mut self, class: &str, name: &str, descriptor: &str) {
+        self.method(Instruction::InvokeVirtual(0), class, name, descriptor);
+    }
+
+    pub fn invoke_special(&mut self, class: &str, name: &str, descriptor: &str) {
+        self.method(Instruction::InvokeSpecial(0), class, name, descriptor);
+    }
+
+    pub fn invoke_static(&mut self, class: &str, name: &str, descriptor: &str) {
+        self.method(Instruction::InvokeStatic(0), class, name, descriptor);
+    }
+
+    pub fn invoke_interface(&mut self, class: &str, name: &str, descriptor: &str) {
+        let method = ConstantPoolIndex::InterfaceMethodref {
+            class: class.to_owned(),
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+        };
+        self.emit_constant(Instruction::InvokeInterface(0, 0), method);
+    }
+
+    /// Calls the call site made by the bootstrap method at `bootstrap_method_attr_index` in
+    /// the class's `BootstrapMethods`.
+    pub fn invoke_dynamic(
+        &mut self,
+        bootstrap_method_attr_index: u16,
+        name: &str,
+        descriptor: &str,
+    ) {
+        let call_site = ConstantPoolIndex::InvokeDynamic {
+            bootstrap_method_attr_index,
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+        };
+        self.emit_constant(Instruction::InvokeDynamic(0), call_site);
+    }
+
+    fn class(&mut self, instruction: Instruction, class: &str) {
+        self.emit_constant(instruction, ConstantPoolIndex::Class(class.to_owned()));
+    }
+
+    /// Creates an uninitialized object, with `new`.
+    pub fn new_object(&mut self, class: &str) {
+        self.class(Instruction::New(0), class);
+    }
+
+    /// Creates an array of primitives, with `newarray`.
+    pub fn new_primitive_array(&mut self, ty: AType) {
+        self.emit(Instruction::NewArray(ty));
+    }
+
+    /// Creates an array of references to `class`, with `anewarray`.
+    pub fn new_array(&mut self, class: &str) {
+        self.class(Instruction::ANewArray(0), class);
+    }
+
+    /// Creates an array of the array type `class`, with the lengths of its first
+    /// `dimensions` taken from the stack.
+    pub fn new_multi_array(&mut self, class: &str, dimensions: u8) {
+        self.class(Instruction::MultiANewArray(0, dimensions), class);
+    }
+
+    pub fn check_cast(&mut self, class: &str) {
+        self.class(Instruction::CheckCast(0), class);
+    }
+
+    pub fn instance_of(&mut self, class: &str) {
+        self.class(Instruction::InstanceOf(0), class);
+    }
+
+    /// Adds a jump to `target`, where `jump` is an instruction like [`Instruction::IfEq`] or
+    /// [`Instruction::GoTo`].
+    pub fn jump(&mut self, jump: fn(u16) -> Instruction, target: Label) {
+        self.code.push(Item::Jump(jump, target));
+    }
+
+    pub fn goto(&mut self, target: Label) {
+        self.jump(Instruction::GoTo, target);
+    }
+
+    /// Jumps to the target for the `int` on the stack, from `low` upwards, or to `default`.
+    pub fn table_switch(&mut self, low: i32, targets: Vec<Label>, default: Label) {
+        self.code.push(Item::TableSwitch {
+            low,
+            targets,
+            default,
+        });
+    }
+
+    /// Jumps to the target paired with the `int` on the stack, or to `default`.
+    pub fn lookup_switch(&mut self, mut pairs: Vec<(i32, Label)>, default: Label) {
+        pairs.sort_by_key(|(key, _)| *key);
+        self.code.push(Item::LookupSwitch { pairs, default });
+    }
+
+    /// Jumps to `handler` when an exception of `catch_type`, or any for `None`, is thrown
+    /// from `start` up to `end`. Handlers are tried in the order they are added.
+    pub fn try_catch(
+        &mut self,
+        start: Label,
+        end: Label,
+        handler: Label,
+        catch_type: Option<&str>,
+    ) {
+        self.catches.push(Catch {
+            start,
+            end,
+            handler,
+            catch_type: catch_type.map(str::to_owned),
+        });
+    }
+
+    /// Names the local variable at `index` from `start` up to `end`, for debuggers.
+    pub fn local_variable(
+        &mut self,
+        index: u16,
+        name: &str,
+        descriptor: &str,
+        start: Label,
+        end: Label,
+    ) {
+        self.locals.push(Local {
+            index,
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+            start,
+            end,
+        });
+    }
+
+    /// Resolves the labels and adds the constants to `constant_pool`. The maximums are those
+    /// of a method with `descriptor`, which has no `this` if `is_static`.
+    pub fn finish(
+        self,
+        constant_pool: &mut ConstantPool,
+        descriptor: &MethodDescriptor,
+        is_static: bool,
+    ) -> JomResult<Code> {
+        let Self {
+            code: mut items,
+            constants,
+            labels,
+            line_numbers,
+            catches,
+            locals,
+        } = self;
+
+        // The indices of the constants decide between `ldc` and `ldc_w`.
+        for (i, constant) in constants {
+            if let Item::Instruction(instruction) = &mut items[i] {
+                *instruction = resolve(instruction.clone(), constant, constant_pool)?;
+            }
+        }
+        for item in &items {
+            if let Item::Jump(f, _) = item {
+                if !f(0).is_branch() {
+                    return Err(JomError::invalid_code(format!(
+                        "{} is not a jump",
+                        f(0).mnemonic()
+                    )));
+                }
+            }
+        }
+
+        let position =
+            |label: Label| {
+                labels.get(label.0).copied().flatten().ok_or_else(|| {
+                    JomError::invalid_code(format!("label {} is not placed", label.0))
+                })
+            };
+        let Layout { code, pcs } = layout::lay_out(
+            &items,
+            |_, &x| position(x),
+            true,
+            |_, _| JomError::invalid_code("jump is out of range"),
+        )?;
+        if pcs[items.len()] > u16::MAX as u32 {
+            return Err(JomError::invalid_code("code is too large"));
+        }
+        let label = |label: Label| Ok::<_, JomError>(pcs[position(label)?]);
+
+        let exception_table = catches
+            .iter()
+            .map(|x| {
+                let exception = x.exception(|&x| label(x))?;
+                if exception.start_pc >= exception.end_pc {
+                    return Err(JomError::invalid_code(format!(
+                        "exception handler range at offset {} is empty",
+                        exception.start_pc
+                    )));
+                }
+
+                Ok(exception)
+            })
+            .collect::<JomResult<Vec<_>>>()?;
+
+        let mut attributes = vec![];
+        if !line_numbers.is_empty() {
+            let table = line_numbers
+                .into_iter()
+                .map(|(i, line_number)| {
+                    if i == items.len() {
+                        return Err(JomError::invalid_code(format!(
+                            "line {line_number} has no instructions"
+                        )));
+                    }
+
+                    Ok(LineNumberTableIndex {
+                        start_pc: pcs[i] as u16,
+                        line_number,
+                    })
+                })
+                .collect::<JomResult<Vec<_>>>()?;
+            attributes.push(CodeAttribute::LineNumberTable(table));
+        }
+
+        let mut max_locals = 0;
+        if !locals.is_empty() {
+            let table = locals
+                .into_iter()
+                .map(|x| {
+                    let size = FieldType::parse(&x.descriptor)?.size() as u16;
+                    max_locals = max_locals.max(x.index + size);
+                    let start_pc = label(x.start)?;
+                    let end_pc = label(x.end)?;
+
+                    Ok(LocalVariableTableIndex {
+                        start_pc: start_pc as u16,
+                        length: end_pc.saturating_sub(start_pc) as u16,
+                        name: x.name,
+                        descriptor: x.descriptor,
+                        index: x.index,
+                    })
+                })
+                .collect::<JomResult<Vec<_>>>()?;
+            attributes.push(CodeAttribute::LocalVariableTable(table));
+        }
+
+        let mut code = Code {
+            max_stack: 0,
+            max_locals: 0,
+            code,
+            exception_table,
+            attributes,
+        };
+        code.max_stack = code.compute_max_stack(constant_pool)?;
+        code.max_locals = code
+            .compute_max_locals(descriptor, is_static)
+            .max(max_locals);
+
+        Ok(code)
+    }
+}
+
+/// Adds `constant` to the pool and points the instruction to it.
+fn resolve(
+    instruction: Instruction,
+    constant: ConstantPoolIndex,
+    constant_pool: &mut ConstantPool,
+) -> JomResult<Instruction> {
+    let count = match (&instruction, &constant) {
+        (
+            Instruction::InvokeInterface(_, 0),
+            ConstantPoolIndex::InterfaceMethodref { descriptor, .. },
+        ) => MethodDescriptor::parse(descriptor)?.parameters_size() as u8 + 1,
+        _ => 0,
+    };
+    let index = constant_pool.insert(constant)?;
+
+    Ok(match instruction {
+        Instruction::Ldc(_) => Instruction::ldc(index),
+        Instruction::InvokeInterface(_, 0) => Instruction::InvokeInterface(index, count),
+        mut x => {
+            x.set_constant_pool_index(index)?;
+            x
+        }
+    })
+}
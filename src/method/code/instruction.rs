@@ -1,8 +1,15 @@
 use binrw::binrw;
 
+use crate::{
+    constant_pool::{ConstantPool, ConstantPoolIndex},
+    descriptor::{FieldType, MethodDescriptor},
+    error::{JomError, JomResult},
+};
+
 #[binrw]
 #[brw(big)]
 #[repr(u8)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Instruction {
     #[brw(magic = 0x00u8)]
     Nop,
@@ -346,7 +353,7 @@ pub enum Instruction {
     Ret(u8),
     #[brw(magic = 0xaau8)]
     TableSwitch {
-        #[br(align_before = 4)]
+        #[brw(align_before = 4)]
         default: i32,
         low: i32,
         high: i32,
@@ -356,7 +363,7 @@ pub enum Instruction {
     },
     #[brw(magic = 0xabu8)]
     LookupSwitch {
-        #[br(align_before = 4)]
+        #[brw(align_before = 4)]
         default: i32,
         #[br(assert(npairs >= 0))]
         npairs: i32,
@@ -425,8 +432,1049 @@ pub enum Instruction {
     JsrW(u32),
 }
 
+/// The number of operand stack slots an instruction consumes and produces,
+/// with `long` and `double` values counting as two slots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StackEffect {
+    pub pops: u16,
+    pub pushes: u16,
+}
+
+impl StackEffect {
+    const fn new(pops: u16, pushes: u16) -> Self {
+        Self { pops, pushes }
+    }
+
+    /// The change in stack height.
+    pub fn delta(&self) -> i32 {
+        self.pushes as i32 - self.pops as i32
+    }
+}
+
+impl Instruction {
+    /// Loads the local variable at `index`, which holds a value of type `ty`, with the
+    /// shortest instruction.
+    pub fn load(ty: &FieldType, index: u16) -> Self {
+        use FieldType::*;
+
+        match ty {
+            Boolean | Byte | Char | Short | Int => Self::local(
+                index,
+                [Self::ILoad0, Self::ILoad1, Self::ILoad2, Self::ILoad3],
+                Self::ILoad,
+                Wide::ILoad,
+            ),
+            Long => Self::local(
+                index,
+                [Self::LLoad0, Self::LLoad1, Self::LLoad2, Self::LLoad3],
+                Self::LLoad,
+                Wide::LLoad,
+            ),
+            Float => Self::local(
+                index,
+                [Self::FLoad0, Self::FLoad1, Self::FLoad2, Self::FLoad3],
+                Self::FLoad,
+                Wide::FLoad,
+            ),
+            Double => Self::local(
+                index,
+                [Self::DLoad0, Self::DLoad1, Self::DLoad2, Self::DLoad3],
+                Self::DLoad,
+                Wide::DLoad,
+            ),
+            Object(_) | Array(_) => Self::aload(index),
+        }
+    }
+
+    /// Stores a value of type `ty` in the local variable at `index`, with the shortest
+    /// instruction.
+    pub fn store(ty: &FieldType, index: u16) -> Self {
+        use FieldType::*;
+
+        match ty {
+            Boolean | Byte | Char | Short | Int => Self::local(
+                index,
+                [Self::IStore0, Self::IStore1, Self::IStore2, Self::IStore3],
+                Self::IStore,
+                Wide::IStore,
+            ),
+            Long => Self::local(
+                index,
+                [Self::LStore0, Self::LStore1, Self::LStore2, Self::LStore3],
+                Self::LStore,
+                Wide::LStore,
+            ),
+            Float => Self::local(
+                index,
+                [Self::FStore0, Self::FStore1, Self::FStore2, Self::FStore3],
+                Self::FStore,
+                Wide::FStore,
+            ),
+            Double => Self::local(
+                index,
+                [Self::DStore0, Self::DStore1, Self::DStore2, Self::DStore3],
+                Self::DStore,
+                Wide::DStore,
+            ),
+            Object(_) | Array(_) => Self::astore(index),
+        }
+    }
+
+    /// Loads the reference in the local variable at `index`, with the shortest instruction.
+    pub fn aload(index: u16) -> Self {
+        Self::local(
+            index,
+            [Self::ALoad0, Self::ALoad1, Self::ALoad2, Self::ALoad3],
+            Self::ALoad,
+            Wide::ALoad,
+        )
+    }
+
+    /// Stores a reference in the local variable at `index`, with the shortest instruction.
+    pub fn astore(index: u16) -> Self {
+        Self::local(
+            index,
+            [Self::AStore0, Self::AStore1, Self::AStore2, Self::AStore3],
+            Self::AStore,
+            Wide::AStore,
+        )
+    }
+
+    /// Accesses the local variable at `index` with one of the `short` instructions for the
+    /// first four, a one byte index, or `wide`.
+    pub(crate) fn local(
+        index: u16,
+        short: [Self; 4],
+        narrow: fn(u8) -> Self,
+        wide: fn(u16) -> Wide,
+    ) -> Self {
+        match u8::try_from(index) {
+            Ok(x @ 0..=3) => short[x as usize].clone(),
+            Ok(x) => narrow(x),
+            Err(_) => Self::Wide(wide(index)),
+        }
+    }
+
+    /// Returns a value of type `ty` from a method, or nothing for `None`.
+    pub fn return_value(ty: Option<&FieldType>) -> Self {
+        use FieldType::*;
+
+        match ty {
+            None => Self::Return,
+            Some(Boolean | Byte | Char | Short | Int) => Self::IReturn,
+            Some(Long) => Self::LReturn,
+            Some(Float) => Self::FReturn,
+            Some(Double) => Self::DReturn,
+            Some(Object(_) | Array(_)) => Self::AReturn,
+        }
+    }
+
+    /// Pushes the constant at `index`, which must not be a `Long` or `Double`.
+    pub fn ldc(index: u16) -> Self {
+        match u8::try_from(index) {
+            Ok(x) => Self::Ldc(x),
+            Err(_) => Self::LdcW(index),
+        }
+    }
+}
+
+impl Instruction {
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Self::Nop => 0x00,
+            Self::AConstNull => 0x01,
+            Self::IConstM1 => 0x02,
+            Self::IConst0 => 0x03,
+            Self::IConst1 => 0x04,
+            Self::IConst2 => 0x05,
+            Self::IConst3 => 0x06,
+            Self::IConst4 => 0x07,
+            Self::IConst5 => 0x08,
+            Self::LConst0 => 0x09,
+            Self::LConst1 => 0x0a,
+            Self::FConst0 => 0x0b,
+            Self::FConst1 => 0x0c,
+            Self::FConst2 => 0x0d,
+            Self::DConst0 => 0x0e,
+            Self::DConst1 => 0x0f,
+            Self::BiPush(..) => 0x10,
+            Self::Sipush(..) => 0x11,
+            Self::Ldc(..) => 0x12,
+            Self::LdcW(..) => 0x13,
+            Self::Ldc2W(..) => 0x14,
+            Self::ILoad(..) => 0x15,
+            Self::LLoad(..) => 0x16,
+            Self::FLoad(..) => 0x17,
+            Self::DLoad(..) => 0x18,
+            Self::ALoad(..) => 0x19,
+            Self::ILoad0 => 0x1a,
+            Self::ILoad1 => 0x1b,
+            Self::ILoad2 => 0x1c,
+            Self::ILoad3 => 0x1d,
+            Self::LLoad0 => 0x1e,
+            Self::LLoad1 => 0x1f,
+            Self::LLoad2 => 0x20,
+            Self::LLoad3 => 0x21,
+            Self::FLoad0 => 0x22,
+            Self::FLoad1 => 0x23,
+            Self::FLoad2 => 0x24,
+            Self::FLoad3 => 0x25,
+            Self::DLoad0 => 0x26,
+            Self::DLoad1 => 0x27,
+            Self::DLoad2 => 0x28,
+            Self::DLoad3 => 0x29,
+            Self::ALoad0 => 0x2a,
+            Self::ALoad1 => 0x2b,
+            Self::ALoad2 => 0x2c,
+            Self::ALoad3 => 0x2d,
+            Self::IALoad => 0x2e,
+            Self::LALoad => 0x2f,
+            Self::FALoad => 0x30,
+            Self::DALoad => 0x31,
+            Self::AALoad => 0x32,
+            Self::BALoad => 0x33,
+            Self::CALoad => 0x34,
+            Self::SALoad => 0x35,
+            Self::IStore(..) => 0x36,
+            Self::LStore(..) => 0x37,
+            Self::FStore(..) => 0x38,
+            Self::DStore(..) => 0x39,
+            Self::AStore(..) => 0x3a,
+            Self::IStore0 => 0x3b,
+            Self::IStore1 => 0x3c,
+            Self::IStore2 => 0x3d,
+            Self::IStore3 => 0x3e,
+            Self::LStore0 => 0x3f,
+            Self::LStore1 => 0x40,
+            Self::LStore2 => 0x41,
+            Self::LStore3 => 0x42,
+            Self::FStore0 => 0x43,
+            Self::FStore1 => 0x44,
+            Self::FStore2 => 0x45,
+            Self::FStore3 => 0x46,
+            Self::DStore0 => 0x47,
+            Self::DStore1 => 0x48,
+            Self::DStore2 => 0x49,
+            Self::DStore3 => 0x4a,
+            Self::AStore0 => 0x4b,
+            Self::AStore1 => 0x4c,
+            Self::AStore2 => 0x4d,
+            Self::AStore3 => 0x4e,
+            Self::IAStore => 0x4f,
+            Self::LAStore => 0x50,
+            Self::FAStore => 0x51,
+            Self::DAStore => 0x52,
+            Self::AAStore => 0x53,
+            Self::BAStore => 0x54,
+            Self::CAStore => 0x55,
+            Self::SAStore => 0x56,
+            Self::Pop => 0x57,
+            Self::Pop2 => 0x58,
+            Self::Dup => 0x59,
+            Self::DupX1 => 0x5a,
+            Self::DupX2 => 0x5b,
+            Self::Dup2 => 0x5c,
+            Self::Dup2X1 => 0x5d,
+            Self::Dup2X2 => 0x5e,
+            Self::Swap => 0x5f,
+            Self::IAdd => 0x60,
+            Self::LAdd => 0x61,
+            Self::FAdd => 0x62,
+            Self::DAdd => 0x63,
+            Self::ISub => 0x64,
+            Self::LSub => 0x65,
+            Self::FSub => 0x66,
+            Self::DSub => 0x67,
+            Self::IMul => 0x68,
+            Self::LMul => 0x69,
+            Self::FMul => 0x6a,
+            Self::DMul => 0x6b,
+            Self::IDiv => 0x6c,
+            Self::LDiv => 0x6d,
+            Self::FDiv => 0x6e,
+            Self::DDiv => 0x6f,
+            Self::IRem => 0x70,
+            Self::LRem => 0x71,
+            Self::FRem => 0x72,
+            Self::DRem => 0x73,
+            Self::INeg => 0x74,
+            Self::LNeg => 0x75,
+            Self::FNeg => 0x76,
+            Self::DNeg => 0x77,
+            Self::IShl => 0x78,
+            Self::LShl => 0x79,
+            Self::IShr => 0x7a,
+            Self::LShr => 0x7b,
+            Self::IUShr => 0x7c,
+            Self::LUShr => 0x7d,
+            Self::IAnd => 0x7e,
+            Self::LAnd => 0x7f,
+            Self::IOr => 0x80,
+            Self::LOr => 0x81,
+            Self::IXor => 0x82,
+            Self::LXor => 0x83,
+            Self::IInc(..) => 0x84,
+            Self::I2L => 0x85,
+            Self::I2F => 0x86,
+            Self::I2D => 0x87,
+            Self::L2I => 0x88,
+            Self::L2F => 0x89,
+            Self::L2D => 0x8a,
+            Self::F2I => 0x8b,
+            Self::F2L => 0x8c,
+            Self::F2D => 0x8d,
+            Self::D2I => 0x8e,
+            Self::D2L => 0x8f,
+            Self::D2F => 0x90,
+            Self::I2B => 0x91,
+            Self::I2C => 0x92,
+            Self::I2S => 0x93,
+            Self::LCmp => 0x94,
+            Self::FCmpL => 0x95,
+            Self::FCmpG => 0x96,
+            Self::DCmpL => 0x97,
+            Self::DCmpG => 0x98,
+            Self::IfEq(..) => 0x99,
+            Self::IfNe(..) => 0x9a,
+            Self::IfLt(..) => 0x9b,
+            Self::IfGe(..) => 0x9c,
+            Self::IfGt(..) => 0x9d,
+            Self::IfLe(..) => 0x9e,
+            Self::IfICmpEq(..) => 0x9f,
+            Self::IfICmpNe(..) => 0xa0,
+            Self::IfICmpLt(..) => 0xa1,
+            Self::IfICmpGe(..) => 0xa2,
+            Self::IfICmpGt(..) => 0xa3,
+            Self::IfICmpLe(..) => 0xa4,
+            Self::IfACmpEq(..) => 0xa5,
+            Self::IfACmpNe(..) => 0xa6,
+            Self::GoTo(..) => 0xa7,
+            Self::Jsr(..) => 0xa8,
+            Self::Ret(..) => 0xa9,
+            Self::TableSwitch { .. } => 0xaa,
+            Self::LookupSwitch { .. } => 0xab,
+            Self::IReturn => 0xac,
+            Self::LReturn => 0xad,
+            Self::FReturn => 0xae,
+            Self::DReturn => 0xaf,
+            Self::AReturn => 0xb0,
+            Self::Return => 0xb1,
+            Self::GetStatic(..) => 0xb2,
+            Self::PutStatic(..) => 0xb3,
+            Self::GetField(..) => 0xb4,
+            Self::PutField(..) => 0xb5,
+            Self::InvokeVirtual(..) => 0xb6,
+            Self::InvokeSpecial(..) => 0xb7,
+            Self::InvokeStatic(..) => 0xb8,
+            Self::InvokeInterface(..) => 0xb9,
+            Self::InvokeDynamic(..) => 0xba,
+            Self::New(..) => 0xbb,
+            Self::NewArray(..) => 0xbc,
+            Self::ANewArray(..) => 0xbd,
+            Self::ArrayLength => 0xbe,
+            Self::AThrow => 0xbf,
+            Self::CheckCast(..) => 0xc0,
+            Self::InstanceOf(..) => 0xc1,
+            Self::MonitorEnter => 0xc2,
+            Self::MonitorExit => 0xc3,
+            Self::Wide(..) => 0xc4,
+            Self::MultiANewArray(..) => 0xc5,
+            Self::IfNull(..) => 0xc6,
+            Self::IfNonNull(..) => 0xc7,
+            Self::GotoW(..) => 0xc8,
+            Self::JsrW(..) => 0xc9,
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Nop => "nop",
+            Self::AConstNull => "aconst_null",
+            Self::IConstM1 => "iconst_m1",
+            Self::IConst0 => "iconst_0",
+            Self::IConst1 => "iconst_1",
+            Self::IConst2 => "iconst_2",
+            Self::IConst3 => "iconst_3",
+            Self::IConst4 => "iconst_4",
+            Self::IConst5 => "iconst_5",
+            Self::LConst0 => "lconst_0",
+            Self::LConst1 => "lconst_1",
+            Self::FConst0 => "fconst_0",
+            Self::FConst1 => "fconst_1",
+            Self::FConst2 => "fconst_2",
+            Self::DConst0 => "dconst_0",
+            Self::DConst1 => "dconst_1",
+            Self::BiPush(..) => "bipush",
+            Self::Sipush(..) => "sipush",
+            Self::Ldc(..) => "ldc",
+            Self::LdcW(..) => "ldc_w",
+            Self::Ldc2W(..) => "ldc2_w",
+            Self::ILoad(..) => "iload",
+            Self::LLoad(..) => "lload",
+            Self::FLoad(..) => "fload",
+            Self::DLoad(..) => "dload",
+            Self::ALoad(..) => "aload",
+            Self::ILoad0 => "iload_0",
+            Self::ILoad1 => "iload_1",
+            Self::ILoad2 => "iload_2",
+            Self::ILoad3 => "iload_3",
+            Self::LLoad0 => "lload_0",
+            Self::LLoad1 => "lload_1",
+            Self::LLoad2 => "lload_2",
+            Self::LLoad3 => "lload_3",
+            Self::FLoad0 => "fload_0",
+            Self::FLoad1 => "fload_1",
+            Self::FLoad2 => "fload_2",
+            Self::FLoad3 => "fload_3",
+            Self::DLoad0 => "dload_0",
+            Self::DLoad1 => "dload_1",
+            Self::DLoad2 => "dload_2",
+            Self::DLoad3 => "dload_3",
+            Self::ALoad0 => "aload_0",
+            Self::ALoad1 => "aload_1",
+            Self::ALoad2 => "aload_2",
+            Self::ALoad3 => "aload_3",
+            Self::IALoad => "iaload",
+            Self::LALoad => "laload",
+            Self::FALoad => "faload",
+            Self::DALoad => "daload",
+            Self::AALoad => "aaload",
+            Self::BALoad => "baload",
+            Self::CALoad => "caload",
+            Self::SALoad => "saload",
+            Self::IStore(..) => "istore",
+            Self::LStore(..) => "lstore",
+            Self::FStore(..) => "fstore",
+            Self::DStore(..) => "dstore",
+            Self::AStore(..) => "astore",
+            Self::IStore0 => "istore_0",
+            Self::IStore1 => "istore_1",
+            Self::IStore2 => "istore_2",
+            Self::IStore3 => "istore_3",
+            Self::LStore0 => "lstore_0",
+            Self::LStore1 => "lstore_1",
+            Self::LStore2 => "lstore_2",
+            Self::LStore3 => "lstore_3",
+            Self::FStore0 => "fstore_0",
+            Self::FStore1 => "fstore_1",
+            Self::FStore2 => "fstore_2",
+            Self::FStore3 => "fstore_3",
+            Self::DStore0 => "dstore_0",
+            Self::DStore1 => "dstore_1",
+            Self::DStore2 => "dstore_2",
+            Self::DStore3 => "dstore_3",
+            Self::AStore0 => "astore_0",
+            Self::AStore1 => "astore_1",
+            Self::AStore2 => "astore_2",
+            Self::AStore3 => "astore_3",
+            Self::IAStore => "iastore",
+            Self::LAStore => "lastore",
+            Self::FAStore => "fastore",
+            Self::DAStore => "dastore",
+            Self::AAStore => "aastore",
+            Self::BAStore => "bastore",
+            Self::CAStore => "castore",
+            Self::SAStore => "sastore",
+            Self::Pop => "pop",
+            Self::Pop2 => "pop2",
+            Self::Dup => "dup",
+            Self::DupX1 => "dup_x1",
+            Self::DupX2 => "dup_x2",
+            Self::Dup2 => "dup2",
+            Self::Dup2X1 => "dup2_x1",
+            Self::Dup2X2 => "dup2_x2",
+            Self::Swap => "swap",
+            Self::IAdd => "iadd",
+            Self::LAdd => "ladd",
+            Self::FAdd => "fadd",
+            Self::DAdd => "dadd",
+            Self::ISub => "isub",
+            Self::LSub => "lsub",
+            Self::FSub => "fsub",
+            Self::DSub => "dsub",
+            Self::IMul => "imul",
+            Self::LMul => "lmul",
+            Self::FMul => "fmul",
+            Self::DMul => "dmul",
+            Self::IDiv => "idiv",
+            Self::LDiv => "ldiv",
+            Self::FDiv => "fdiv",
+            Self::DDiv => "ddiv",
+            Self::IRem => "irem",
+            Self::LRem => "lrem",
+            Self::FRem => "frem",
+            Self::DRem => "drem",
+            Self::INeg => "ineg",
+            Self::LNeg => "lneg",
+            Self::FNeg => "fneg",
+            Self::DNeg => "dneg",
+            Self::IShl => "ishl",
+            Self::LShl => "lshl",
+            Self::IShr => "ishr",
+            Self::LShr => "lshr",
+            Self::IUShr => "iushr",
+            Self::LUShr => "lushr",
+            Self::IAnd => "iand",
+            Self::LAnd => "land",
+            Self::IOr => "ior",
+            Self::LOr => "lor",
+            Self::IXor => "ixor",
+            Self::LXor => "lxor",
+            Self::IInc(..) => "iinc",
+            Self::I2L => "i2l",
+            Self::I2F => "i2f",
+            Self::I2D => "i2d",
+            Self::L2I => "l2i",
+            Self::L2F => "l2f",
+            Self::L2D => "l2d",
+            Self::F2I => "f2i",
+            Self::F2L => "f2l",
+            Self::F2D => "f2d",
+            Self::D2I => "d2i",
+            Self::D2L => "d2l",
+            Self::D2F => "d2f",
+            Self::I2B => "i2b",
+            Self::I2C => "i2c",
+            Self::I2S => "i2s",
+            Self::LCmp => "lcmp",
+            Self::FCmpL => "fcmpl",
+            Self::FCmpG => "fcmpg",
+            Self::DCmpL => "dcmpl",
+            Self::DCmpG => "dcmpg",
+            Self::IfEq(..) => "ifeq",
+            Self::IfNe(..) => "ifne",
+            Self::IfLt(..) => "iflt",
+            Self::IfGe(..) => "ifge",
+            Self::IfGt(..) => "ifgt",
+            Self::IfLe(..) => "ifle",
+            Self::IfICmpEq(..) => "if_icmpeq",
+            Self::IfICmpNe(..) => "if_icmpne",
+            Self::IfICmpLt(..) => "if_icmplt",
+            Self::IfICmpGe(..) => "if_icmpge",
+            Self::IfICmpGt(..) => "if_icmpgt",
+            Self::IfICmpLe(..) => "if_icmple",
+            Self::IfACmpEq(..) => "if_acmpeq",
+            Self::IfACmpNe(..) => "if_acmpne",
+            Self::GoTo(..) => "goto",
+            Self::Jsr(..) => "jsr",
+            Self::Ret(..) => "ret",
+            Self::TableSwitch { .. } => "tableswitch",
+            Self::LookupSwitch { .. } => "lookupswitch",
+            Self::IReturn => "ireturn",
+            Self::LReturn => "lreturn",
+            Self::FReturn => "freturn",
+            Self::DReturn => "dreturn",
+            Self::AReturn => "areturn",
+            Self::Return => "return",
+            Self::GetStatic(..) => "getstatic",
+            Self::PutStatic(..) => "putstatic",
+            Self::GetField(..) => "getfield",
+            Self::PutField(..) => "putfield",
+            Self::InvokeVirtual(..) => "invokevirtual",
+            Self::InvokeSpecial(..) => "invokespecial",
+            Self::InvokeStatic(..) => "invokestatic",
+            Self::InvokeInterface(..) => "invokeinterface",
+            Self::InvokeDynamic(..) => "invokedynamic",
+            Self::New(..) => "new",
+            Self::NewArray(..) => "newarray",
+            Self::ANewArray(..) => "anewarray",
+            Self::ArrayLength => "arraylength",
+            Self::AThrow => "athrow",
+            Self::CheckCast(..) => "checkcast",
+            Self::InstanceOf(..) => "instanceof",
+            Self::MonitorEnter => "monitorenter",
+            Self::MonitorExit => "monitorexit",
+            Self::Wide(..) => "wide",
+            Self::MultiANewArray(..) => "multianewarray",
+            Self::IfNull(..) => "ifnull",
+            Self::IfNonNull(..) => "ifnonnull",
+            Self::GotoW(..) => "goto_w",
+            Self::JsrW(..) => "jsr_w",
+        }
+    }
+
+    /// The number of bytes this instruction occupies when encoded at `pc`.
+    pub fn size(&self, pc: u32) -> u32 {
+        // `tableswitch` and `lookupswitch` pad their operands to a multiple of 4
+        let padding = 3 - pc % 4;
+
+        match self {
+            Self::BiPush(_)
+            | Self::Ldc(_)
+            | Self::ILoad(_)
+            | Self::LLoad(_)
+            | Self::FLoad(_)
+            | Self::DLoad(_)
+            | Self::ALoad(_)
+            | Self::IStore(_)
+            | Self::LStore(_)
+            | Self::FStore(_)
+            | Self::DStore(_)
+            | Self::AStore(_)
+            | Self::Ret(_)
+            | Self::NewArray(_) => 2,
+            Self::Sipush(_)
+            | Self::LdcW(_)
+            | Self::Ldc2W(_)
+            | Self::IInc(_, _)
+            | Self::IfEq(_)
+            | Self::IfNe(_)
+            | Self::IfLt(_)
+            | Self::IfGe(_)
+            | Self::IfGt(_)
+            | Self::IfLe(_)
+            | Self::IfICmpEq(_)
+            | Self::IfICmpNe(_)
+            | Self::IfICmpLt(_)
+            | Self::IfICmpGe(_)
+            | Self::IfICmpGt(_)
+            | Self::IfICmpLe(_)
+            | Self::IfACmpEq(_)
+            | Self::IfACmpNe(_)
+            | Self::GoTo(_)
+            | Self::Jsr(_)
+            | Self::GetStatic(_)
+            | Self::PutStatic(_)
+            | Self::GetField(_)
+            | Self::PutField(_)
+            | Self::InvokeVirtual(_)
+            | Self::InvokeSpecial(_)
+            | Self::InvokeStatic(_)
+            | Self::New(_)
+            | Self::ANewArray(_)
+            | Self::CheckCast(_)
+            | Self::InstanceOf(_)
+            | Self::IfNull(_)
+            | Self::IfNonNull(_) => 3,
+            Self::MultiANewArray(_, _) => 4,
+            Self::InvokeInterface(_, _)
+            | Self::InvokeDynamic(_)
+            | Self::GotoW(_)
+            | Self::JsrW(_) => 5,
+            Self::Wide(Wide::IInc(_, _)) => 6,
+            Self::Wide(_) => 4,
+            Self::TableSwitch { offsets, .. } => 1 + padding + 12 + 4 * offsets.len() as u32,
+            Self::LookupSwitch { pairs, .. } => 1 + padding + 8 + 8 * pairs.len() as u32,
+            _ => 1,
+        }
+    }
+}
+
+impl Instruction {
+    /// Whether this is a conditional or unconditional jump, a switch or a subroutine call.
+    pub fn is_branch(&self) -> bool {
+        !self.branch_offsets().is_empty()
+    }
+
+    pub fn is_conditional_branch(&self) -> bool {
+        matches!(
+            self,
+            Self::IfEq(_)
+                | Self::IfNe(_)
+                | Self::IfLt(_)
+                | Self::IfGe(_)
+                | Self::IfGt(_)
+                | Self::IfLe(_)
+                | Self::IfICmpEq(_)
+                | Self::IfICmpNe(_)
+                | Self::IfICmpLt(_)
+                | Self::IfICmpGe(_)
+                | Self::IfICmpGt(_)
+                | Self::IfICmpLe(_)
+                | Self::IfACmpEq(_)
+                | Self::IfACmpNe(_)
+                | Self::IfNull(_)
+                | Self::IfNonNull(_)
+        )
+    }
+
+    pub fn is_switch(&self) -> bool {
+        matches!(self, Self::TableSwitch { .. } | Self::LookupSwitch { .. })
+    }
+
+    pub fn is_return(&self) -> bool {
+        matches!(
+            self,
+            Self::IReturn
+                | Self::LReturn
+                | Self::FReturn
+                | Self::DReturn
+                | Self::AReturn
+                | Self::Return
+        )
+    }
+
+    pub fn is_invoke(&self) -> bool {
+        matches!(
+            self,
+            Self::InvokeVirtual(_)
+                | Self::InvokeSpecial(_)
+                | Self::InvokeStatic(_)
+                | Self::InvokeInterface(_, _)
+                | Self::InvokeDynamic(_)
+        )
+    }
+
+    /// Whether execution can continue with the next instruction.
+    pub fn falls_through(&self) -> bool {
+        !matches!(
+            self,
+            Self::GoTo(_)
+                | Self::GotoW(_)
+                | Self::Ret(_)
+                | Self::Wide(Wide::Ret(_))
+                | Self::TableSwitch { .. }
+                | Self::LookupSwitch { .. }
+                | Self::AThrow
+        ) && !self.is_return()
+    }
+
+    /// Whether the instruction may complete abruptly with an exception, ignoring
+    /// linkage errors and virtual machine errors which any instruction may throw.
+    pub fn can_throw(&self) -> bool {
+        matches!(
+            self,
+            Self::IALoad
+                | Self::LALoad
+                | Self::FALoad
+                | Self::DALoad
+                | Self::AALoad
+                | Self::BALoad
+                | Self::CALoad
+                | Self::SALoad
+                | Self::IAStore
+                | Self::LAStore
+                | Self::FAStore
+                | Self::DAStore
+                | Self::AAStore
+                | Self::BAStore
+                | Self::CAStore
+                | Self::SAStore
+                | Self::IDiv
+                | Self::LDiv
+                | Self::IRem
+                | Self::LRem
+                | Self::GetStatic(_)
+                | Self::PutStatic(_)
+                | Self::GetField(_)
+                | Self::PutField(_)
+                | Self::New(_)
+                | Self::NewArray(_)
+                | Self::ANewArray(_)
+                | Self::MultiANewArray(_, _)
+                | Self::ArrayLength
+                | Self::AThrow
+                | Self::CheckCast(_)
+                | Self::MonitorEnter
+                | Self::MonitorExit
+        ) || self.is_invoke()
+    }
+
+    /// The jump offsets relative to the address of this instruction, including the
+    /// default offset of switches.
+    pub fn branch_offsets(&self) -> Vec<i32> {
+        match self {
+            Self::IfEq(x)
+            | Self::IfNe(x)
+            | Self::IfLt(x)
+            | Self::IfGe(x)
+            | Self::IfGt(x)
+            | Self::IfLe(x)
+            | Self::IfICmpEq(x)
+            | Self::IfICmpNe(x)
+            | Self::IfICmpLt(x)
+            | Self::IfICmpGe(x)
+            | Self::IfICmpGt(x)
+            | Self::IfICmpLe(x)
+            | Self::IfACmpEq(x)
+            | Self::IfACmpNe(x)
+            | Self::IfNull(x)
+            | Self::IfNonNull(x)
+            | Self::GoTo(x)
+            | Self::Jsr(x) => vec![*x as i16 as i32],
+            Self::GotoW(x) | Self::JsrW(x) => vec![*x as i32],
+            Self::TableSwitch {
+                default, offsets, ..
+            } => std::iter::once(*default).chain(offsets.iter().copied()).collect(),
+            Self::LookupSwitch { default, pairs, .. } => std::iter::once(*default)
+                .chain(pairs.iter().map(|(_, x)| *x))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Replaces the offsets returned by [`Self::branch_offsets`], in the same order. Returns
+    /// `false`, leaving the instruction unchanged, if an offset does not fit its operand.
+    pub(crate) fn set_branch_offsets(&mut self, new: &[i32]) -> bool {
+        match self {
+            Self::IfEq(x)
+            | Self::IfNe(x)
+            | Self::IfLt(x)
+            | Self::IfGe(x)
+            | Self::IfGt(x)
+            | Self::IfLe(x)
+            | Self::IfICmpEq(x)
+            | Self::IfICmpNe(x)
+            | Self::IfICmpLt(x)
+            | Self::IfICmpGe(x)
+            | Self::IfICmpGt(x)
+            | Self::IfICmpLe(x)
+            | Self::IfACmpEq(x)
+            | Self::IfACmpNe(x)
+            | Self::IfNull(x)
+            | Self::IfNonNull(x)
+            | Self::GoTo(x)
+            | Self::Jsr(x) => match i16::try_from(new[0]) {
+                Ok(offset) => *x = offset as u16,
+                Err(_) => return false,
+            },
+            Self::GotoW(x) | Self::JsrW(x) => *x = new[0] as u32,
+            Self::TableSwitch {
+                default, offsets, ..
+            } => {
+                *default = new[0];
+                offsets.copy_from_slice(&new[1..]);
+            }
+            Self::LookupSwitch { default, pairs, .. } => {
+                *default = new[0];
+                for ((_, x), offset) in pairs.iter_mut().zip(&new[1..]) {
+                    *x = *offset;
+                }
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    /// The local variable index read or written by the instruction.
+    pub fn local_index(&self) -> Option<u16> {
+        Some(match self {
+            Self::ILoad(n)
+            | Self::LLoad(n)
+            | Self::FLoad(n)
+            | Self::DLoad(n)
+            | Self::ALoad(n)
+            | Self::IStore(n)
+            | Self::LStore(n)
+            | Self::FStore(n)
+            | Self::DStore(n)
+            | Self::AStore(n)
+            | Self::IInc(n, _)
+            | Self::Ret(n) => *n as u16,
+            Self::ILoad0
+            | Self::LLoad0
+            | Self::FLoad0
+            | Self::DLoad0
+            | Self::ALoad0
+            | Self::IStore0
+            | Self::LStore0
+            | Self::FStore0
+            | Self::DStore0
+            | Self::AStore0 => 0,
+            Self::ILoad1
+            | Self::LLoad1
+            | Self::FLoad1
+            | Self::DLoad1
+            | Self::ALoad1
+            | Self::IStore1
+            | Self::LStore1
+            | Self::FStore1
+            | Self::DStore1
+            | Self::AStore1 => 1,
+            Self::ILoad2
+            | Self::LLoad2
+            | Self::FLoad2
+            | Self::DLoad2
+            | Self::ALoad2
+            | Self::IStore2
+            | Self::LStore2
+            | Self::FStore2
+            | Self::DStore2
+            | Self::AStore2 => 2,
+            Self::ILoad3
+            | Self::LLoad3
+            | Self::FLoad3
+            | Self::DLoad3
+            | Self::ALoad3
+            | Self::IStore3
+            | Self::LStore3
+            | Self::FStore3
+            | Self::DStore3
+            | Self::AStore3 => 3,
+            Self::Wide(
+                Wide::ILoad(n)
+                | Wide::LLoad(n)
+                | Wide::FLoad(n)
+                | Wide::DLoad(n)
+                | Wide::ALoad(n)
+                | Wide::IStore(n)
+                | Wide::LStore(n)
+                | Wide::FStore(n)
+                | Wide::DStore(n)
+                | Wide::AStore(n)
+                | Wide::Ret(n)
+                | Wide::IInc(n, _),
+            ) => *n,
+            _ => return None,
+        })
+    }
+
+    /// The constant pool index referenced by the instruction.
+    pub fn constant_pool_index(&self) -> Option<u16> {
+        match self {
+            Self::Ldc(x) => Some(*x as u16),
+            Self::LdcW(x)
+            | Self::Ldc2W(x)
+            | Self::GetStatic(x)
+            | Self::PutStatic(x)
+            | Self::GetField(x)
+            | Self::PutField(x)
+            | Self::InvokeVirtual(x)
+            | Self::InvokeSpecial(x)
+            | Self::InvokeStatic(x)
+            | Self::InvokeInterface(x, _)
+            | Self::InvokeDynamic(x)
+            | Self::New(x)
+            | Self::ANewArray(x)
+            | Self::CheckCast(x)
+            | Self::InstanceOf(x)
+            | Self::MultiANewArray(x, _) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Points the instruction to another constant pool entry, if it has one. The operand of
+    /// `ldc` is a single byte, so the new index has to fit in it.
+    pub(crate) fn set_constant_pool_index(&mut self, index: u16) -> JomResult<()> {
+        match self {
+            Self::Ldc(x) => *x = u8::try_from(index).map_err(|_| JomError::OutOfBounds(index))?,
+            Self::LdcW(x)
+            | Self::Ldc2W(x)
+            | Self::GetStatic(x)
+            | Self::PutStatic(x)
+            | Self::GetField(x)
+            | Self::PutField(x)
+            | Self::InvokeVirtual(x)
+            | Self::InvokeSpecial(x)
+            | Self::InvokeStatic(x)
+            | Self::InvokeInterface(x, _)
+            | Self::InvokeDynamic(x)
+            | Self::New(x)
+            | Self::ANewArray(x)
+            | Self::CheckCast(x)
+            | Self::InstanceOf(x)
+            | Self::MultiANewArray(x, _) => *x = index,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// The operand stack slots consumed and produced by the instruction. Field and
+    /// method instructions resolve their descriptors in `constant_pool`.
+    pub fn stack_effect(&self, constant_pool: &ConstantPool) -> JomResult<StackEffect> {
+        use Instruction::*;
+
+        Ok(match self {
+            Nop | GoTo(_) | GotoW(_) | Return | Ret(_) | IInc(_, _) => StackEffect::new(0, 0),
+            Wide(x) => match x {
+                self::Wide::ILoad(_) | self::Wide::FLoad(_) | self::Wide::ALoad(_) => {
+                    StackEffect::new(0, 1)
+                }
+                self::Wide::LLoad(_) | self::Wide::DLoad(_) => StackEffect::new(0, 2),
+                self::Wide::IStore(_) | self::Wide::FStore(_) | self::Wide::AStore(_) => {
+                    StackEffect::new(1, 0)
+                }
+                self::Wide::LStore(_) | self::Wide::DStore(_) => StackEffect::new(2, 0),
+                self::Wide::Ret(_) | self::Wide::IInc(_, _) => StackEffect::new(0, 0),
+            },
+            AConstNull | IConstM1 | IConst0 | IConst1 | IConst2 | IConst3 | IConst4 | IConst5
+            | FConst0 | FConst1 | FConst2 | BiPush(_) | Sipush(_) | Ldc(_) | LdcW(_) | ILoad(_)
+            | FLoad(_) | ALoad(_) | ILoad0 | ILoad1 | ILoad2 | ILoad3 | FLoad0 | FLoad1
+            | FLoad2 | FLoad3 | ALoad0 | ALoad1 | ALoad2 | ALoad3 | New(_) | Jsr(_) | JsrW(_) => {
+                StackEffect::new(0, 1)
+            }
+            LConst0 | LConst1 | DConst0 | DConst1 | Ldc2W(_) | LLoad(_) | DLoad(_) | LLoad0
+            | LLoad1 | LLoad2 | LLoad3 | DLoad0 | DLoad1 | DLoad2 | DLoad3 => StackEffect::new(0, 2),
+            IStore(_) | FStore(_) | AStore(_) | IStore0 | IStore1 | IStore2 | IStore3 | FStore0
+            | FStore1 | FStore2 | FStore3 | AStore0 | AStore1 | AStore2 | AStore3 | Pop
+            | IfEq(_) | IfNe(_) | IfLt(_) | IfGe(_) | IfGt(_) | IfLe(_) | IfNull(_)
+            | IfNonNull(_) | TableSwitch { .. } | LookupSwitch { .. } | IReturn | FReturn
+            | AReturn | AThrow | MonitorEnter | MonitorExit => StackEffect::new(1, 0),
+            LStore(_) | DStore(_) | LStore0 | LStore1 | LStore2 | LStore3 | DStore0 | DStore1
+            | DStore2 | DStore3 | Pop2 | IfICmpEq(_) | IfICmpNe(_) | IfICmpLt(_) | IfICmpGe(_)
+            | IfICmpGt(_) | IfICmpLe(_) | IfACmpEq(_) | IfACmpNe(_) | LReturn | DReturn => {
+                StackEffect::new(2, 0)
+            }
+            IALoad | FALoad | AALoad | BALoad | CALoad | SALoad | IAdd | FAdd | ISub | FSub
+            | IMul | FMul | IDiv | FDiv | IRem | FRem | IShl | IShr | IUShr | IAnd | IOr | IXor
+            | FCmpL | FCmpG => StackEffect::new(2, 1),
+            LALoad | DALoad => StackEffect::new(2, 2),
+            IAStore | FAStore | AAStore | BAStore | CAStore | SAStore => StackEffect::new(3, 0),
+            LAStore | DAStore => StackEffect::new(4, 0),
+            Dup => StackEffect::new(1, 2),
+            DupX1 => StackEffect::new(2, 3),
+            DupX2 => StackEffect::new(3, 4),
+            Dup2 => StackEffect::new(2, 4),
+            Dup2X1 => StackEffect::new(3, 5),
+            Dup2X2 => StackEffect::new(4, 6),
+            Swap => StackEffect::new(2, 2),
+            LAdd | DAdd | LSub | DSub | LMul | DMul | LDiv | DDiv | LRem | DRem | LAnd | LOr
+            | LXor => StackEffect::new(4, 2),
+            LShl | LShr | LUShr => StackEffect::new(3, 2),
+            LCmp | DCmpL | DCmpG => StackEffect::new(4, 1),
+            INeg | FNeg | I2F | F2I | I2B | I2C | I2S | ArrayLength | NewArray(_) | ANewArray(_)
+            | CheckCast(_) | InstanceOf(_) => StackEffect::new(1, 1),
+            LNeg | DNeg | L2D | D2L => StackEffect::new(2, 2),
+            I2L | I2D | F2L | F2D => StackEffect::new(1, 2),
+            L2I | L2F | D2I | D2F => StackEffect::new(2, 1),
+            MultiANewArray(_, dimensions) => StackEffect::new(*dimensions as u16, 1),
+            GetStatic(index) => StackEffect::new(0, field_size(constant_pool, *index)?),
+            PutStatic(index) => StackEffect::new(field_size(constant_pool, *index)?, 0),
+            GetField(index) => StackEffect::new(1, field_size(constant_pool, *index)?),
+            PutField(index) => StackEffect::new(1 + field_size(constant_pool, *index)?, 0),
+            InvokeVirtual(index) | InvokeSpecial(index) | InvokeInterface(index, _) => {
+                let (parameters, result) = method_sizes(constant_pool, *index)?;
+                StackEffect::new(1 + parameters, result)
+            }
+            InvokeStatic(index) | InvokeDynamic(index) => {
+                let (parameters, result) = method_sizes(constant_pool, *index)?;
+                StackEffect::new(parameters, result)
+            }
+        })
+    }
+}
+
+fn field_size(constant_pool: &ConstantPool, index: u16) -> JomResult<u16> {
+    let descriptor = constant_pool.get(index)?.into_fieldref()?.descriptor;
+
+    Ok(FieldType::parse(&descriptor)?.size() as u16)
+}
+
+fn method_sizes(constant_pool: &ConstantPool, index: u16) -> JomResult<(u16, u16)> {
+    let descriptor = match constant_pool.get(index)? {
+        ConstantPoolIndex::Methodref { descriptor, .. }
+        | ConstantPoolIndex::InterfaceMethodref { descriptor, .. }
+        | ConstantPoolIndex::InvokeDynamic { descriptor, .. } => descriptor,
+        x => {
+            return Err(JomError::new_cp_index(
+                "Methodref, InterfaceMethodref or InvokeDynamic",
+                x.name(),
+            ))
+        }
+    };
+    let descriptor = MethodDescriptor::parse(&descriptor)?;
+
+    Ok((
+        descriptor.parameters_size() as u16,
+        descriptor.return_type.map_or(0, |x| x.size() as u16),
+    ))
+}
+
 #[binrw]
 #[brw(repr = u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AType {
     Boolean = 4,
     Char,
@@ -439,6 +1487,7 @@ pub enum AType {
 }
 
 #[binrw]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Wide {
     #[brw(magic = 0x15u8)]
     ILoad(u16),
@@ -0,0 +1,223 @@
+//! Laying out code whose jumps refer to labels, for the assembler and [`CodeBuilder`].
+//!
+//! [`CodeBuilder`]: super::builder::CodeBuilder
+
+use crate::error::{JomError, JomResult};
+
+use super::{instruction::Instruction, Exception};
+
+/// An instruction whose jump targets are still labels of type `L`.
+pub(crate) enum Item<L> {
+    Instruction(Instruction),
+    Jump(fn(u16) -> Instruction, L),
+    JumpW(fn(u32) -> Instruction, L),
+    TableSwitch {
+        low: i32,
+        targets: Vec<L>,
+        default: L,
+    },
+    LookupSwitch {
+        pairs: Vec<(i32, L)>,
+        default: L,
+    },
+}
+
+impl<L> Item<L> {
+    /// The instruction with every jump offset set to zero, which has the final size unless
+    /// the jump is widened.
+    fn placeholder(&self) -> Instruction {
+        match self {
+            Item::Instruction(x) => x.clone(),
+            Item::Jump(f, _) => f(0),
+            Item::JumpW(f, _) => f(0),
+            Item::TableSwitch { low, targets, .. } => Instruction::TableSwitch {
+                default: 0,
+                low: *low,
+                high: low + targets.len() as i32 - 1,
+                offsets: vec![0; targets.len()],
+            },
+            Item::LookupSwitch { pairs, .. } => Instruction::LookupSwitch {
+                default: 0,
+                npairs: pairs.len() as i32,
+                pairs: pairs.iter().map(|(key, _)| (*key, 0)).collect(),
+            },
+        }
+    }
+}
+
+/// An exception handler, from `start` up to `end`.
+pub(crate) struct Catch<L> {
+    pub(crate) start: L,
+    pub(crate) end: L,
+    pub(crate) handler: L,
+    pub(crate) catch_type: Option<String>,
+}
+
+impl<L> Catch<L> {
+    /// The entry of the exception table, given the offsets of the labels.
+    pub(crate) fn exception(
+        &self,
+        mut pc: impl FnMut(&L) -> JomResult<u32>,
+    ) -> JomResult<Exception> {
+        Ok(Exception {
+            start_pc: pc(&self.start)? as u16,
+            end_pc: pc(&self.end)? as u16,
+            handler_pc: pc(&self.handler)? as u16,
+            catch_type: self.catch_type.clone(),
+        })
+    }
+}
+
+/// Code with its jump offsets resolved.
+pub(crate) struct Layout {
+    pub(crate) code: Vec<Instruction>,
+    /// The offset of each item, and of the end of the code.
+    pub(crate) pcs: Vec<u32>,
+}
+
+/// Lays out `items`, where `position` gives the index of the item that a label is placed
+/// before. A jump whose offset does not fit in 16 bits fails with `out_of_range`, unless
+/// `widen` is set: then `goto` and `jsr` become `goto_w` and `jsr_w`, and a conditional
+/// jump becomes the opposite one over a `goto_w`.
+pub(crate) fn lay_out<L>(
+    items: &[Item<L>],
+    position: impl Fn(usize, &L) -> JomResult<usize>,
+    widen: bool,
+    out_of_range: impl Fn(usize, &L) -> JomError,
+) -> JomResult<Layout> {
+    let offsets = |wide: &[bool]| {
+        let mut pcs = Vec::with_capacity(items.len() + 1);
+        let mut pc = 0;
+        for (item, &wide) in items.iter().zip(wide) {
+            pcs.push(pc);
+            pc += match item {
+                Item::Jump(f, _) if wide => widened(f(0)).map_or(0, |x| x.size()),
+                x => x.placeholder().size(pc),
+            };
+        }
+        pcs.push(pc);
+        pcs
+    };
+
+    // Widening a jump moves the code after it, which can put more jumps out of range.
+    let mut wide = vec![false; items.len()];
+    let pcs = loop {
+        let pcs = offsets(&wide);
+        let mut changed = false;
+        for (i, item) in items.iter().enumerate() {
+            let Item::Jump(f, target) = item else {
+                continue;
+            };
+            let offset = pcs[position(i, target)?] as i64 - pcs[i] as i64;
+            if wide[i] || i16::try_from(offset).is_ok() {
+                continue;
+            }
+            if !widen || widened(f(0)).is_none() {
+                return Err(out_of_range(i, target));
+            }
+            wide[i] = true;
+            changed = true;
+        }
+        if !changed {
+            break pcs;
+        }
+    };
+
+    let mut code = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        let offset =
+            |target: &L| Ok::<_, JomError>(pcs[position(i, target)?] as i32 - pcs[i] as i32);
+
+        match item {
+            Item::Instruction(x) => code.push(x.clone()),
+            Item::Jump(f, target) if wide[i] => {
+                let widened = widened(f(0)).expect("only jumps are widened");
+                let mut pc = pcs[i] as i32;
+                if let Some(opposite) = widened.opposite {
+                    // It skips over the `goto_w` that follows.
+                    code.push(opposite(8));
+                    pc += 3;
+                }
+                code.push((widened.jump)(
+                    (pcs[position(i, target)?] as i32 - pc) as u32,
+                ));
+            }
+            Item::Jump(f, target) => code.push(f(offset(target)? as u16)),
+            Item::JumpW(f, target) => code.push(f(offset(target)? as u32)),
+            Item::TableSwitch {
+                low,
+                targets,
+                default,
+            } => code.push(Instruction::TableSwitch {
+                default: offset(default)?,
+                low: *low,
+                high: low + targets.len() as i32 - 1,
+                offsets: targets.iter().map(offset).collect::<JomResult<Vec<_>>>()?,
+            }),
+            Item::LookupSwitch { pairs, default } => code.push(Instruction::LookupSwitch {
+                default: offset(default)?,
+                npairs: pairs.len() as i32,
+                pairs: pairs
+                    .iter()
+                    .map(|(key, x)| Ok((*key, offset(x)?)))
+                    .collect::<JomResult<Vec<_>>>()?,
+            }),
+        }
+    }
+
+    Ok(Layout { code, pcs })
+}
+
+/// The wide form of a jump: a `goto_w` or `jsr_w`, after the opposite conditional jump for
+/// a conditional one.
+struct Widened {
+    opposite: Option<fn(u16) -> Instruction>,
+    jump: fn(u32) -> Instruction,
+}
+
+impl Widened {
+    fn size(&self) -> u32 {
+        5 + 3 * self.opposite.is_some() as u32
+    }
+}
+
+fn widened(jump: Instruction) -> Option<Widened> {
+    use Instruction::*;
+
+    let opposite: fn(u16) -> Instruction = match jump {
+        GoTo(_) => {
+            return Some(Widened {
+                opposite: None,
+                jump: GotoW,
+            })
+        }
+        Jsr(_) => {
+            return Some(Widened {
+                opposite: None,
+                jump: JsrW,
+            })
+        }
+        IfEq(_) => IfNe,
+        IfNe(_) => IfEq,
+        IfLt(_) => IfGe,
+        IfGe(_) => IfLt,
+        IfGt(_) => IfLe,
+        IfLe(_) => IfGt,
+        IfICmpEq(_) => IfICmpNe,
+        IfICmpNe(_) => IfICmpEq,
+        IfICmpLt(_) => IfICmpGe,
+        IfICmpGe(_) => IfICmpLt,
+        IfICmpGt(_) => IfICmpLe,
+        IfICmpLe(_) => IfICmpGt,
+        IfACmpEq(_) => IfACmpNe,
+        IfACmpNe(_) => IfACmpEq,
+        IfNull(_) => IfNonNull,
+        IfNonNull(_) => IfNull,
+        _ => return None,
+    };
+
+    Some(Widened {
+        opposite: Some(opposite),
+        jump: GotoW,
+    })
+}
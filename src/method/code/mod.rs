@@ -1,16 +1,22 @@
+pub mod builder;
 pub mod instruction;
+pub(crate) mod layout;
+pub(crate) mod stack_map;
 
 use std::io::Cursor;
 
-use binrw::{binrw, BinRead};
+use binrw::{binrw, BinRead, BinWrite};
 
 use crate::{
+    analysis::cfg::ControlFlowGraph,
     attribute::{CodeAttribute, RawAttribute},
     constant_pool::ConstantPool,
-    error::JomResult,
+    descriptor::MethodDescriptor,
+    error::{ErrorPath, JomError, JomResult},
+    ReadState,
 };
 
-use self::instruction::Instruction;
+use self::instruction::{Instruction, Wide};
 
 #[binrw]
 pub(super) struct RawCode {
@@ -50,7 +56,11 @@ impl RawException {
             catch_type,
         } = self;
 
-        let catch_type = constant_pool.get_class(catch_type)?;
+        // A `catch_type` of zero catches every exception and is used to implement `finally`.
+        let catch_type = match catch_type {
+            0 => None,
+            x => Some(constant_pool.get_class(x)?),
+        };
 
         Ok(Exception {
             start_pc,
@@ -59,6 +69,34 @@ impl RawException {
             catch_type,
         })
     }
+
+    fn from_exception(exception: &Exception, constant_pool: &mut ConstantPool) -> JomResult<Self> {
+        let catch_type = match &exception.catch_type {
+            None => 0,
+            Some(x) => constant_pool.insert_class(x.clone())?,
+        };
+
+        Ok(RawException {
+            start_pc: exception.start_pc,
+            end_pc: exception.end_pc,
+            handler_pc: exception.handler_pc,
+            catch_type,
+        })
+    }
+}
+
+/// Describes a failure to decode the instruction at the start of `code`, which is either an
+/// invalid opcode or operands that run past the end of the code.
+pub(crate) fn instruction_error(error: binrw::Error, code: &[u8]) -> JomError {
+    if !crate::error::unmatched(&error) {
+        return error.into();
+    }
+    match code {
+        // The opcode after `wide` is the one that is invalid.
+        [0xc4, opcode, ..] => JomError::InvalidOpcode(*opcode),
+        [opcode, ..] => JomError::InvalidOpcode(*opcode),
+        [] => JomError::Truncated,
+    }
 }
 
 pub struct Code {
@@ -70,7 +108,13 @@ pub struct Code {
 }
 
 impl Code {
-    pub(crate) fn read(info: &[u8], constant_pool: &ConstantPool) -> JomResult<Self> {
+    /// Decodes the attribute's info, which starts at `offset` in the class file.
+    pub(crate) fn read(
+        info: &[u8],
+        constant_pool: &ConstantPool,
+        state: &mut ReadState,
+        offset: u64,
+    ) -> JomResult<Self> {
         let RawCode {
             max_stack,
             max_locals,
@@ -83,9 +127,14 @@ impl Code {
         let mut cursor = Cursor::new(code);
         let mut code = vec![];
 
-
-        while !cursor.position() < len as u64 {
-            code.push(Instruction::read(&mut cursor)?);
+        while cursor.position() < len as u64 {
+            let pc = cursor.position();
+            let instruction = Instruction::read(&mut cursor).map_err(|e| {
+                instruction_error(e, &cursor.get_ref()[pc as usize..])
+                    .within(ErrorPath::Pc(pc as u32))
+                    .at(offset + 8 + pc)
+            })?;
+            code.push(instruction);
         }
 
         let exception_table = exception_table
@@ -94,7 +143,7 @@ impl Code {
             .collect::<JomResult<Vec<_>>>()?;
         let attributes = attributes
             .into_iter()
-            .map(|x| x.into_code_attr(constant_pool))
+            .map(|x| x.into_code_attr(constant_pool, state, offset))
             .collect::<JomResult<Vec<_>>>()?;
 
         Ok(Self {
@@ -105,11 +154,113 @@ impl Code {
             attributes,
         })
     }
+
+    /// Encodes the attribute's info, adding the referenced entries to the constant pool.
+    pub(crate) fn write(&self, constant_pool: &mut ConstantPool) -> JomResult<Vec<u8>> {
+        // Switch padding depends on the offset, so the instructions get a buffer of their own.
+        let mut cursor = Cursor::new(vec![]);
+        for instruction in &self.code {
+            instruction.write(&mut cursor)?;
+        }
+
+        let exception_table = self
+            .exception_table
+            .iter()
+            .map(|x| RawException::from_exception(x, constant_pool))
+            .collect::<JomResult<Vec<_>>>()?;
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|x| RawAttribute::from_code_attr(x, constant_pool))
+            .collect::<JomResult<Vec<_>>>()?;
+
+        let mut info = Cursor::new(vec![]);
+        RawCode {
+            max_stack: self.max_stack,
+            max_locals: self.max_locals,
+            code: cursor.into_inner(),
+            exception_table,
+            attributes,
+        }
+        .write_be(&mut info)?;
+
+        Ok(info.into_inner())
+    }
+
+    /// Computes the maximum operand stack depth reached by the instructions.
+    pub fn compute_max_stack(&self, constant_pool: &ConstantPool) -> JomResult<u16> {
+        let cfg = ControlFlowGraph::new(self)?;
+        let mut heights: Vec<Option<u32>> = vec![None; self.code.len()];
+        let mut worklist = vec![];
+        let mut max = 0;
+
+        if !self.code.is_empty() {
+            heights[0] = Some(0);
+            worklist.push(0);
+        }
+
+        while let Some(i) = worklist.pop() {
+            let height = heights[i].unwrap();
+            let effect = self.code[i].stack_effect(constant_pool)?;
+
+            let after = height
+                .checked_sub(effect.pops as u32)
+                .ok_or_else(|| JomError::analysis(i, "operand stack underflow"))?
+                + effect.pushes as u32;
+            max = max.max(height).max(after);
+
+            let normal = cfg.successors(i).iter().map(|&s| (s, after));
+            // Handlers start with only the exception on the stack.
+            let exceptional = cfg.handlers(i).iter().map(|&n| (cfg.handler_start(n), 1));
+
+            for (successor, height) in normal.chain(exceptional) {
+                match heights[successor] {
+                    None => {
+                        heights[successor] = Some(height);
+                        worklist.push(successor);
+                    }
+                    Some(x) if x != height => {
+                        return Err(JomError::analysis(
+                            successor,
+                            format!("inconsistent stack height {x} and {height}"),
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        u16::try_from(max).map_err(|_| JomError::analysis(0, "operand stack is too deep"))
+    }
+
+    /// Computes the number of local variable slots used by the parameters and the instructions.
+    pub fn compute_max_locals(&self, descriptor: &MethodDescriptor, is_static: bool) -> u16 {
+        let parameters = descriptor.parameters_size() as u16 + !is_static as u16;
+
+        self.code
+            .iter()
+            .filter_map(|x| x.local_index().map(|i| i + local_size(x)))
+            .fold(parameters, u16::max)
+    }
+}
+
+fn local_size(instruction: &Instruction) -> u16 {
+    use Instruction::*;
+
+    match instruction {
+        LLoad(_) | LLoad0 | LLoad1 | LLoad2 | LLoad3 | DLoad(_) | DLoad0 | DLoad1 | DLoad2
+        | DLoad3 | LStore(_) | LStore0 | LStore1 | LStore2 | LStore3 | DStore(_) | DStore0
+        | DStore1 | DStore2 | DStore3 => 2,
+        Wide(self::Wide::LLoad(_) | self::Wide::DLoad(_))
+        | Wide(self::Wide::LStore(_) | self::Wide::DStore(_)) => 2,
+        _ => 1,
+    }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Exception {
     pub start_pc: u16,
     pub end_pc: u16,
     pub handler_pc: u16,
-    pub catch_type: String,
+    pub catch_type: Option<String>,
 }
@@ -0,0 +1,97 @@
+//! Encoding `StackMapTable` frames, for the assembler and [`ClassFile::compute_frames`].
+//!
+//! [`ClassFile::compute_frames`]: crate::ClassFile::compute_frames
+
+use crate::error::JomResult;
+
+/// A verification type whose `new` instruction is still a label of type `L`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum VerificationType<L> {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    Object(u16),
+    Uninitialized(L),
+}
+
+pub(crate) enum StackMapFrame<L> {
+    Same,
+    SameLocals1StackItem(VerificationType<L>),
+    Chop(u8),
+    Append(Vec<VerificationType<L>>),
+    Full(Vec<VerificationType<L>>, Vec<VerificationType<L>>),
+}
+
+impl<L> StackMapFrame<L> {
+    /// Appends the frame with its offset delta to `info`, using `offset` for the offsets of
+    /// the `new` instructions of uninitialized types.
+    pub(crate) fn encode(
+        &self,
+        delta: u16,
+        info: &mut Vec<u8>,
+        offset: &impl Fn(&L) -> JomResult<u16>,
+    ) -> JomResult<()> {
+        let types = |types: &[VerificationType<L>], info: &mut Vec<u8>| {
+            for ty in types {
+                match ty {
+                    VerificationType::Top => info.push(0),
+                    VerificationType::Integer => info.push(1),
+                    VerificationType::Float => info.push(2),
+                    VerificationType::Double => info.push(3),
+                    VerificationType::Long => info.push(4),
+                    VerificationType::Null => info.push(5),
+                    VerificationType::UninitializedThis => info.push(6),
+                    VerificationType::Object(index) => {
+                        info.push(7);
+                        info.extend(index.to_be_bytes());
+                    }
+                    VerificationType::Uninitialized(x) => {
+                        info.push(8);
+                        info.extend(offset(x)?.to_be_bytes());
+                    }
+                }
+            }
+            JomResult::Ok(())
+        };
+
+        match self {
+            StackMapFrame::Same if delta < 64 => info.push(delta as u8),
+            StackMapFrame::Same => {
+                info.push(251);
+                info.extend(delta.to_be_bytes());
+            }
+            StackMapFrame::SameLocals1StackItem(ty) => {
+                if delta < 64 {
+                    info.push(64 + delta as u8);
+                } else {
+                    info.push(247);
+                    info.extend(delta.to_be_bytes());
+                }
+                types(std::slice::from_ref(ty), info)?;
+            }
+            StackMapFrame::Chop(n) => {
+                info.push(251 - n);
+                info.extend(delta.to_be_bytes());
+            }
+            StackMapFrame::Append(locals) => {
+                info.push(251 + locals.len() as u8);
+                info.extend(delta.to_be_bytes());
+                types(locals, info)?;
+            }
+            StackMapFrame::Full(locals, stack) => {
+                info.push(255);
+                info.extend(delta.to_be_bytes());
+                info.extend((locals.len() as u16).to_be_bytes());
+                types(locals, info)?;
+                info.extend((stack.len() as u16).to_be_bytes());
+                types(stack, info)?;
+            }
+        }
+
+        Ok(())
+    }
+}
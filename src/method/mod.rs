@@ -5,9 +5,12 @@ use binrw::binrw;
 use crate::{
     attribute::{MethodAttribute, RawAttribute},
     constant_pool::ConstantPool,
-    error::JomResult,
+    error::{ErrorPath, JomResult},
+    ReadState,
 };
 
+use self::code::Code;
+
 #[binrw]
 pub(crate) struct RawMethodInfo {
     access_flags: u16,
@@ -21,18 +24,43 @@ pub(crate) struct RawMethodInfo {
 }
 
 impl RawMethodInfo {
-    pub fn into_method_info(self, cp: &ConstantPool) -> JomResult<MethodInfo> {
+    pub fn into_method_info(
+        self,
+        cp: &ConstantPool,
+        state: &mut ReadState,
+    ) -> JomResult<MethodInfo> {
         let name = cp.get_utf8(self.name)?;
         let descriptor = cp.get_utf8(self.descriptor)?;
 
+        let attributes = self
+            .attributes
+            .into_iter()
+            .map(|x| x.into_method_attr(cp, state))
+            .collect::<JomResult<Vec<_>>>()
+            .map_err(|e| {
+                e.within(ErrorPath::Method {
+                    name: name.clone(),
+                    descriptor: descriptor.clone(),
+                })
+            })?;
+
         Ok(MethodInfo {
             access_flags: self.access_flags,
             name,
             descriptor,
-            attributes: self
+            attributes,
+        })
+    }
+
+    pub fn from_method_info(method: &MethodInfo, cp: &mut ConstantPool) -> JomResult<Self> {
+        Ok(RawMethodInfo {
+            access_flags: method.access_flags,
+            name: cp.insert_utf8(method.name.clone())?,
+            descriptor: cp.insert_utf8(method.descriptor.clone())?,
+            attributes: method
                 .attributes
-                .into_iter()
-                .map(|x| x.into_method_attr(cp))
+                .iter()
+                .map(|x| RawAttribute::from_method_attr(x, cp))
                 .collect::<JomResult<Vec<_>>>()?,
         })
     }
@@ -44,3 +72,38 @@ pub struct MethodInfo {
     pub descriptor: String,
     pub attributes: Vec<MethodAttribute>,
 }
+
+impl MethodInfo {
+    /// Returns `None` for methods without code, and for methods whose code was skipped when
+    /// the class was read, until [`MethodInfo::decode`] is called.
+    pub fn code(&self) -> Option<&Code> {
+        self.attributes.iter().find_map(|x| match x {
+            MethodAttribute::Code(code) => Some(code),
+            _ => None,
+        })
+    }
+
+    pub fn code_mut(&mut self) -> Option<&mut Code> {
+        self.attributes.iter_mut().find_map(|x| match x {
+            MethodAttribute::Code(code) => Some(code),
+            _ => None,
+        })
+    }
+
+    /// Decodes the `Code` attribute and the attributes of the code, if
+    /// [`ReadOptions`](crate::ReadOptions) skipped them. Offsets in errors are relative to the
+    /// info of the `Code` attribute.
+    pub fn decode(&mut self, constant_pool: &ConstantPool) -> JomResult<()> {
+        for attribute in &mut self.attributes {
+            attribute.decode(constant_pool)?;
+
+            if let MethodAttribute::Code(code) = attribute {
+                for attribute in &mut code.attributes {
+                    attribute.decode(constant_pool)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,648 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::{
+    access::{flag_names, modifiers, FlagTarget, ACC_INTERFACE, ACC_STATIC},
+    attribute::{ClassAttribute, CodeAttribute, ConstantValue, FieldAttribute, MethodAttribute},
+    constant_pool::{ConstantPool, ConstantPoolIndex, MethodHandleReferenceKind},
+    descriptor::{FieldType, MethodDescriptor},
+    field::FieldInfo,
+    method::{
+        code::{
+            instruction::{AType, Instruction, Wide},
+            Code,
+        },
+        MethodInfo,
+    },
+    ClassFile,
+};
+
+/// Renders an instruction like `javap -c`, with symbolic operands resolved in the
+/// constant pool and jump targets as absolute addresses.
+pub struct InstructionDisplay<'a> {
+    instruction: &'a Instruction,
+    pc: u32,
+    constant_pool: &'a ConstantPool,
+}
+
+impl Instruction {
+    pub fn display<'a>(
+        &'a self,
+        pc: u32,
+        constant_pool: &'a ConstantPool,
+    ) -> InstructionDisplay<'a> {
+        InstructionDisplay {
+            instruction: self,
+            pc,
+            constant_pool,
+        }
+    }
+}
+
+impl Display for InstructionDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let instruction = self.instruction;
+        let mnemonic = instruction.mnemonic();
+        let target = |offset: i32| self.pc as i64 + offset as i64;
+
+        match instruction {
+            Instruction::InvokeInterface(index, count) => write!(
+                f,
+                "{mnemonic:<13} {:<18} // {}",
+                format!("#{index},  {count}"),
+                self.comment(*index)
+            ),
+            Instruction::MultiANewArray(index, dimensions) => write!(
+                f,
+                "{mnemonic:<13} {:<18} // {}",
+                format!("#{index},  {dimensions}"),
+                self.comment(*index)
+            ),
+            Instruction::TableSwitch {
+                default,
+                low,
+                high,
+                offsets,
+            } => {
+                writeln!(f, "{mnemonic:<13} {{ // {low} to {high}")?;
+                for (key, offset) in (*low..=*high).zip(offsets) {
+                    writeln!(f, "{key:>24}: {}", target(*offset))?;
+                }
+                writeln!(f, "{:>24}: {}", "default", target(*default))?;
+                write!(f, "{:>13}", "}")
+            }
+            Instruction::LookupSwitch { default, pairs, .. } => {
+                writeln!(f, "{mnemonic:<13} {{ // {}", pairs.len())?;
+                for (key, offset) in pairs {
+                    writeln!(f, "{key:>24}: {}", target(*offset))?;
+                }
+                writeln!(f, "{:>24}: {}", "default", target(*default))?;
+                write!(f, "{:>13}", "}")
+            }
+            Instruction::BiPush(x) => write!(f, "{mnemonic:<13} {}", *x as i8),
+            Instruction::Sipush(x) => write!(f, "{mnemonic:<13} {}", *x as i16),
+            Instruction::IInc(index, x) => write!(f, "{mnemonic:<13} {index}, {}", *x as i8),
+            Instruction::NewArray(ty) => write!(f, "{mnemonic:<13} {}", atype_name(*ty)),
+            Instruction::Wide(wide) => match wide {
+                Wide::IInc(index, x) => write!(f, "{mnemonic} iinc {index}, {x}"),
+                Wide::ILoad(index) => write!(f, "{mnemonic} iload {index}"),
+                Wide::LLoad(index) => write!(f, "{mnemonic} lload {index}"),
+                Wide::FLoad(index) => write!(f, "{mnemonic} fload {index}"),
+                Wide::DLoad(index) => write!(f, "{mnemonic} dload {index}"),
+                Wide::ALoad(index) => write!(f, "{mnemonic} aload {index}"),
+                Wide::IStore(index) => write!(f, "{mnemonic} istore {index}"),
+                Wide::LStore(index) => write!(f, "{mnemonic} lstore {index}"),
+                Wide::FStore(index) => write!(f, "{mnemonic} fstore {index}"),
+                Wide::DStore(index) => write!(f, "{mnemonic} dstore {index}"),
+                Wide::AStore(index) => write!(f, "{mnemonic} astore {index}"),
+                Wide::Ret(index) => write!(f, "{mnemonic} ret {index}"),
+            },
+            _ => {
+                if let Some(index) = instruction.constant_pool_index() {
+                    write!(
+                        f,
+                        "{mnemonic:<13} {:<18} // {}",
+                        format!("#{index}"),
+                        self.comment(index)
+                    )
+                } else if let Some(&offset) = instruction.branch_offsets().first() {
+                    write!(f, "{mnemonic:<13} {}", target(offset))
+                } else if let Some(index) = instruction
+                    .local_index()
+                    .filter(|_| has_local_operand(instruction))
+                {
+                    write!(f, "{mnemonic:<13} {index}")
+                } else {
+                    write!(f, "{mnemonic}")
+                }
+            }
+        }
+    }
+}
+
+impl InstructionDisplay<'_> {
+    fn comment(&self, index: u16) -> String {
+        match self.constant_pool.get(index) {
+            Ok(entry) => describe_reference(&entry),
+            Err(_) => "<invalid>".to_owned(),
+        }
+    }
+}
+
+/// Whether the local variable index is an explicit operand rather than part of the opcode.
+fn has_local_operand(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::ILoad(_)
+            | Instruction::LLoad(_)
+            | Instruction::FLoad(_)
+            | Instruction::DLoad(_)
+            | Instruction::ALoad(_)
+            | Instruction::IStore(_)
+            | Instruction::LStore(_)
+            | Instruction::FStore(_)
+            | Instruction::DStore(_)
+            | Instruction::AStore(_)
+            | Instruction::Ret(_)
+    )
+}
+
+fn atype_name(ty: AType) -> &'static str {
+    match ty {
+        AType::Boolean => "boolean",
+        AType::Char => "char",
+        AType::Float => "float",
+        AType::Double => "double",
+        AType::Byte => "byte",
+        AType::Short => "short",
+        AType::Int => "int",
+        AType::Long => "long",
+    }
+}
+
+fn reference_kind_name(kind: &MethodHandleReferenceKind) -> &'static str {
+    match kind {
+        MethodHandleReferenceKind::GetField => "REF_getField",
+        MethodHandleReferenceKind::GetStatic => "REF_getStatic",
+        MethodHandleReferenceKind::PutField => "REF_putField",
+        MethodHandleReferenceKind::PutStatic => "REF_putStatic",
+        MethodHandleReferenceKind::InvokeVirtual => "REF_invokeVirtual",
+        MethodHandleReferenceKind::InvokeStatic => "REF_invokeStatic",
+        MethodHandleReferenceKind::InvokeSpecial => "REF_invokeSpecial",
+        MethodHandleReferenceKind::NewInvokeSpecial => "REF_newInvokeSpecial",
+        MethodHandleReferenceKind::InvokeInterface => "REF_invokeInterface",
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Escapes a string with unpaired surrogates, which are written as `\uXXXX`.
+fn escape_units(units: &[u16]) -> String {
+    char::decode_utf16(units.iter().copied())
+        .map(|x| match x {
+            Ok(c) => escape(c.encode_utf8(&mut [0; 4])),
+            Err(e) => format!("\\u{:04x}", e.unpaired_surrogate()),
+        })
+        .collect()
+}
+
+/// Quotes special method names like `"<init>"`, as javap does.
+fn member_name(name: &str) -> String {
+    if name.starts_with('<') {
+        format!("\"{name}\"")
+    } else {
+        name.to_owned()
+    }
+}
+
+/// The resolved value of a constant pool entry.
+fn describe(entry: &ConstantPoolIndex) -> String {
+    match entry {
+        ConstantPoolIndex::Utf8(s) | ConstantPoolIndex::String(s) => escape(s),
+        ConstantPoolIndex::Utf8Units(x) | ConstantPoolIndex::StringUnits(x) => escape_units(x),
+        ConstantPoolIndex::Integer(x) => x.to_string(),
+        ConstantPoolIndex::Float(x) => format!("{x:?}f"),
+        ConstantPoolIndex::Long(x) => format!("{x}l"),
+        ConstantPoolIndex::Double(x) => format!("{x:?}d"),
+        ConstantPoolIndex::Class(x)
+        | ConstantPoolIndex::MethodType(x)
+        | ConstantPoolIndex::Module(x)
+        | ConstantPoolIndex::Package(x) => x.clone(),
+        ConstantPoolIndex::Fieldref {
+            class,
+            name,
+            descriptor,
+        }
+        | ConstantPoolIndex::Methodref {
+            class,
+            name,
+            descriptor,
+        }
+        | ConstantPoolIndex::InterfaceMethodref {
+            class,
+            name,
+            descriptor,
+        } => format!("{class}.{}:{descriptor}", member_name(name)),
+        ConstantPoolIndex::NameAndType(name, descriptor) => {
+            format!("{}:{descriptor}", member_name(name))
+        }
+        ConstantPoolIndex::MethodHandle {
+            kind,
+            class,
+            name,
+            descriptor,
+        } => format!(
+            "{} {class}.{}:{descriptor}",
+            reference_kind_name(kind),
+            member_name(name)
+        ),
+        ConstantPoolIndex::Dynamic {
+            bootstrap_method_attr_index,
+            name,
+            descriptor,
+        }
+        | ConstantPoolIndex::InvokeDynamic {
+            bootstrap_method_attr_index,
+            name,
+            descriptor,
+        } => format!("#{bootstrap_method_attr_index}:{name}:{descriptor}"),
+        ConstantPoolIndex::Unusable => String::new(),
+    }
+}
+
+/// The comment javap prints after an instruction operand referencing the constant pool.
+fn describe_reference(entry: &ConstantPoolIndex) -> String {
+    let kind = match entry {
+        ConstantPoolIndex::Integer(_) => "int",
+        ConstantPoolIndex::Float(_) => "float",
+        ConstantPoolIndex::Long(_) => "long",
+        ConstantPoolIndex::Double(_) => "double",
+        ConstantPoolIndex::Class(_) => "class",
+        ConstantPoolIndex::String(_) | ConstantPoolIndex::StringUnits(_) => "String",
+        ConstantPoolIndex::Fieldref { .. } => "Field",
+        ConstantPoolIndex::Methodref { .. } => "Method",
+        ConstantPoolIndex::InterfaceMethodref { .. } => "InterfaceMethod",
+        x => x.name(),
+    };
+
+    format!("{kind} {}", describe(entry))
+}
+
+fn write_flags(f: &mut Formatter<'_>, indent: &str, flags: u16, target: FlagTarget) -> fmt::Result {
+    writeln!(
+        f,
+        "{indent}flags: (0x{flags:04x}) {}",
+        flag_names(flags, target).join(", ")
+    )
+}
+
+fn write_unknown(f: &mut Formatter<'_>, indent: &str, name: &str, info: &[u8]) -> fmt::Result {
+    writeln!(f, "{indent}{name}: length = 0x{:x}", info.len())?;
+    for chunk in info.chunks(16) {
+        let bytes = chunk
+            .iter()
+            .map(|x| format!("{x:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(f, "{indent}  {bytes}")?;
+    }
+
+    Ok(())
+}
+
+fn java_type(descriptor: &str) -> String {
+    FieldType::parse(descriptor)
+        .map(|x| x.java_name())
+        .unwrap_or_else(|_| descriptor.to_owned())
+}
+
+fn write_field(f: &mut Formatter<'_>, field: &FieldInfo) -> fmt::Result {
+    let modifiers = modifiers(field.access_flags, FlagTarget::Field);
+    let ty = java_type(&field.descriptor);
+    if modifiers.is_empty() {
+        writeln!(f, "  {ty} {};", field.name)?;
+    } else {
+        writeln!(f, "  {modifiers} {ty} {};", field.name)?;
+    }
+    writeln!(f, "    descriptor: {}", field.descriptor)?;
+    write_flags(f, "    ", field.access_flags, FlagTarget::Field)?;
+
+    for attribute in &field.attributes {
+        match attribute {
+            FieldAttribute::ConstantValue(value) => {
+                let value = match value {
+                    ConstantValue::Integer(x) => format!("int {x}"),
+                    ConstantValue::Float(x) => format!("float {x:?}f"),
+                    ConstantValue::Long(x) => format!("long {x}l"),
+                    ConstantValue::Double(x) => format!("double {x:?}d"),
+                    ConstantValue::String(x) => format!("String {}", escape(x)),
+                    ConstantValue::StringUnits(x) => format!("String {}", escape_units(x)),
+                };
+                writeln!(f, "    ConstantValue: {value}")?;
+            }
+            FieldAttribute::Synthetic => writeln!(f, "    Synthetic: true")?,
+            FieldAttribute::Deprecated => writeln!(f, "    Deprecated: true")?,
+            FieldAttribute::Signature(x) => writeln!(f, "    Signature: {x}")?,
+            FieldAttribute::RuntimeVisibleAnnotations => {
+                writeln!(f, "    RuntimeVisibleAnnotations")?
+            }
+            FieldAttribute::RuntimeInvisibleAnnotations => {
+                writeln!(f, "    RuntimeInvisibleAnnotations")?
+            }
+            FieldAttribute::RuntimeVisibleTypeAnnotations => {
+                writeln!(f, "    RuntimeVisibleTypeAnnotations")?
+            }
+            FieldAttribute::RuntimeInvisibleTypeAnnotations => {
+                writeln!(f, "    RuntimeInvisibleTypeAnnotations")?
+            }
+            FieldAttribute::Unknown(name, info) => write_unknown(f, "    ", name, info)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn method_declaration(class: &ClassFile, method: &MethodInfo) -> String {
+    let modifiers = modifiers(method.access_flags, FlagTarget::Method);
+    let prefix = if modifiers.is_empty() {
+        String::new()
+    } else {
+        format!("{modifiers} ")
+    };
+
+    if method.name == "<clinit>" {
+        return "static {};".to_owned();
+    }
+
+    let Ok(descriptor) = MethodDescriptor::parse(&method.descriptor) else {
+        return format!("{prefix}{}{};", method.name, method.descriptor);
+    };
+    let parameters = descriptor
+        .parameters
+        .iter()
+        .map(FieldType::java_name)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if method.name == "<init>" {
+        format!(
+            "{prefix}{}({parameters});",
+            class.this_class.replace('/', ".")
+        )
+    } else {
+        let return_type = descriptor
+            .return_type
+            .as_ref()
+            .map_or("void".to_owned(), FieldType::java_name);
+        format!("{prefix}{return_type} {}({parameters});", method.name)
+    }
+}
+
+fn write_code(
+    f: &mut Formatter<'_>,
+    class: &ClassFile,
+    method: &MethodInfo,
+    code: &Code,
+) -> fmt::Result {
+    let args_size = MethodDescriptor::parse(&method.descriptor)
+        .map(|x| x.parameters_size())
+        .unwrap_or(0)
+        + (method.access_flags & ACC_STATIC == 0) as usize;
+
+    writeln!(f, "    Code:")?;
+    writeln!(
+        f,
+        "      stack={}, locals={}, args_size={args_size}",
+        code.max_stack, code.max_locals
+    )?;
+
+    let mut pc = 0;
+    for instruction in &code.code {
+        writeln!(
+            f,
+            "{pc:>10}: {}",
+            instruction.display(pc, &class.constant_pool)
+        )?;
+        pc += instruction.size(pc);
+    }
+
+    if !code.exception_table.is_empty() {
+        writeln!(f, "      Exception table:")?;
+        writeln!(f, "         from    to  target type")?;
+        for exception in &code.exception_table {
+            let catch_type = match &exception.catch_type {
+                Some(class) => format!("Class {class}"),
+                None => "any".to_owned(),
+            };
+            writeln!(
+                f,
+                "{:>14}{:>6}{:>8}   {catch_type}",
+                exception.start_pc, exception.end_pc, exception.handler_pc
+            )?;
+        }
+    }
+
+    for attribute in &code.attributes {
+        match attribute {
+            CodeAttribute::LineNumberTable(table) => {
+                writeln!(f, "      LineNumberTable:")?;
+                for line in table {
+                    writeln!(f, "        line {}: {}", line.line_number, line.start_pc)?;
+                }
+            }
+            CodeAttribute::LocalVariableTable(table) => {
+                writeln!(f, "      LocalVariableTable:")?;
+                writeln!(f, "        Start  Length  Slot  Name   Signature")?;
+                for local in table {
+                    writeln!(
+                        f,
+                        "{:>13}{:>8}{:>6}{:>6}   {}",
+                        local.start_pc, local.length, local.index, local.name, local.descriptor
+                    )?;
+                }
+            }
+            CodeAttribute::LocalVariableTypeTable(table) => {
+                writeln!(f, "      LocalVariableTypeTable:")?;
+                writeln!(f, "        Start  Length  Slot  Name   Signature")?;
+                for local in table {
+                    writeln!(
+                        f,
+                        "{:>13}{:>8}{:>6}{:>6}   {}",
+                        local.start_pc, local.length, local.index, local.name, local.descriptor
+                    )?;
+                }
+            }
+            CodeAttribute::StackMapTable => writeln!(f, "      StackMapTable")?,
+            CodeAttribute::RuntimeVisibleTypeAnnotations => {
+                writeln!(f, "      RuntimeVisibleTypeAnnotations")?
+            }
+            CodeAttribute::RuntimeInvisibleTypeAnnotations => {
+                writeln!(f, "      RuntimeInvisibleTypeAnnotations")?
+            }
+            CodeAttribute::Unknown(name, info) => write_unknown(f, "      ", name, info)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn write_method(f: &mut Formatter<'_>, class: &ClassFile, method: &MethodInfo) -> fmt::Result {
+    writeln!(f, "  {}", method_declaration(class, method))?;
+    writeln!(f, "    descriptor: {}", method.descriptor)?;
+    write_flags(f, "    ", method.access_flags, FlagTarget::Method)?;
+
+    for attribute in &method.attributes {
+        match attribute {
+            MethodAttribute::Code(code) => write_code(f, class, method, code)?,
+            MethodAttribute::Synthetic => writeln!(f, "    Synthetic: true")?,
+            MethodAttribute::Deprecated => writeln!(f, "    Deprecated: true")?,
+            MethodAttribute::Signature(x) => writeln!(f, "    Signature: {x}")?,
+            MethodAttribute::Unknown(name, info) => write_unknown(f, "    ", name, info)?,
+            MethodAttribute::Exceptions => writeln!(f, "    Exceptions")?,
+            MethodAttribute::RuntimeVisibleParameterAnnotations => {
+                writeln!(f, "    RuntimeVisibleParameterAnnotations")?
+            }
+            MethodAttribute::RuntimeInvisibleParameterAnnotations => {
+                writeln!(f, "    RuntimeInvisibleParameterAnnotations")?
+            }
+            MethodAttribute::AnnotationDefault => writeln!(f, "    AnnotationDefault")?,
+            MethodAttribute::MethodParameters => writeln!(f, "    MethodParameters")?,
+            MethodAttribute::RuntimeVisibleAnnotations => {
+                writeln!(f, "    RuntimeVisibleAnnotations")?
+            }
+            MethodAttribute::RuntimeInvisibleAnnotations => {
+                writeln!(f, "    RuntimeInvisibleAnnotations")?
+            }
+            MethodAttribute::RuntimeVisibleTypeAnnotations => {
+                writeln!(f, "    RuntimeVisibleTypeAnnotations")?
+            }
+            MethodAttribute::RuntimeInvisibleTypeAnnotations => {
+                writeln!(f, "    RuntimeInvisibleTypeAnnotations")?
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_class_attribute(f: &mut Formatter<'_>, attribute: &ClassAttribute) -> fmt::Result {
+    let name = match attribute {
+        ClassAttribute::SourceFile(x) => return writeln!(f, "SourceFile: \"{x}\""),
+        ClassAttribute::Unknown(name, info) => return write_unknown(f, "", name, info),
+        ClassAttribute::InnerClasses => "InnerClasses",
+        ClassAttribute::EnclosingMethod => "EnclosingMethod",
+        ClassAttribute::SourceDebugExtension => "SourceDebugExtension",
+        ClassAttribute::BootstrapMethods => "BootstrapMethods",
+        ClassAttribute::Module => "Module",
+        ClassAttribute::ModulePackages => "ModulePackages",
+        ClassAttribute::ModuleMainClass => "ModuleMainClass",
+        ClassAttribute::NestHost => "NestHost",
+        ClassAttribute::NestMembers => "NestMembers",
+        ClassAttribute::Record => "Record",
+        ClassAttribute::PermittedSubclasses => "PermittedSubclasses",
+        ClassAttribute::Synthetic => "Synthetic",
+        ClassAttribute::Deprecated => "Deprecated",
+        ClassAttribute::Signature => "Signature",
+        ClassAttribute::RuntimeVisibleAnnotations => "RuntimeVisibleAnnotations",
+        ClassAttribute::RuntimeInvisibleAnnotations => "RuntimeInvisibleAnnotations",
+        ClassAttribute::RuntimeVisibleTypeAnnotations => "RuntimeVisibleTypeAnnotations",
+        ClassAttribute::RuntimeInvisibleTypeAnnotations => "RuntimeInvisibleTypeAnnotations",
+    };
+
+    writeln!(f, "{name}")
+}
+
+/// Lists the usable entries with their indices, like the `Constant pool:` section of `javap -v`.
+impl Display for ConstantPool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Constant pool:")?;
+        for (i, entry) in self.iter().enumerate() {
+            if let ConstantPoolIndex::Unusable = entry {
+                continue;
+            }
+            writeln!(
+                f,
+                "{:>6} = {:<18} {}",
+                format!("#{i}"),
+                entry.name(),
+                describe(entry)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders the class like `javap -c -v -p`.
+impl Display for ClassFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let is_interface = self.access_flags & ACC_INTERFACE != 0;
+        let modifiers = modifiers(self.access_flags, FlagTarget::Class);
+        let mut declaration = String::new();
+        if !modifiers.is_empty() {
+            declaration.push_str(&modifiers);
+            declaration.push(' ');
+        }
+        declaration.push_str(if is_interface { "interface " } else { "class " });
+        declaration.push_str(&self.this_class.replace('/', "."));
+
+        let interfaces = self
+            .interfaces
+            .iter()
+            .map(|x| x.replace('/', "."))
+            .collect::<Vec<_>>()
+            .join(",");
+        if is_interface {
+            if !interfaces.is_empty() {
+                write!(declaration, " extends {interfaces}")?;
+            }
+        } else {
+            if self.super_class != "java/lang/Object" {
+                write!(
+                    declaration,
+                    " extends {}",
+                    self.super_class.replace('/', ".")
+                )?;
+            }
+            if !interfaces.is_empty() {
+                write!(declaration, " implements {interfaces}")?;
+            }
+        }
+
+        writeln!(f, "{declaration}")?;
+        writeln!(f, "  minor version: {}", self.minor)?;
+        writeln!(f, "  major version: {}", self.major)?;
+        write_flags(f, "  ", self.access_flags, FlagTarget::Class)?;
+        writeln!(f, "  this_class: {}", self.this_class)?;
+        writeln!(f, "  super_class: {}", self.super_class)?;
+        writeln!(
+            f,
+            "  interfaces: {}, fields: {}, methods: {}, attributes: {}",
+            self.interfaces.len(),
+            self.fields.len(),
+            self.methods.len(),
+            self.attributes.len()
+        )?;
+
+        write!(f, "{}", self.constant_pool)?;
+
+        writeln!(f, "{{")?;
+        let mut first = true;
+        for field in &self.fields {
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            write_field(f, field)?;
+        }
+        for method in &self.methods {
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            write_method(f, self, method)?;
+        }
+        writeln!(f, "}}")?;
+
+        for attribute in &self.attributes {
+            write_class_attribute(f, attribute)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,538 @@
+//! Renaming classes and members consistently across class files, like relocating a
+//! dependency's packages when shading it.
+//!
+//! Constant pool entries are renamed in place, so instructions and attributes referring
+//! to them stay valid. Attributes kept as raw bytes, like annotations or `InnerClasses`,
+//! get new `Utf8` entries for the names they hold. The old `Utf8` entries are left in the
+//! constant pool.
+
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{
+    attribute::{
+        bootstrap_methods, ClassAttribute, CodeAttribute, FieldAttribute, MethodAttribute,
+    },
+    constant_pool::{ConstantPool, ConstantPoolIndex, MethodHandleReferenceKind},
+    error::JomResult,
+    hierarchy::ClassHierarchy,
+    signature,
+    walker::{self, Reference, Visitor},
+    ClassFile,
+};
+
+const LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory";
+const OBJECT_METHODS: &str = "java/lang/runtime/ObjectMethods";
+
+#[derive(Default)]
+pub struct Remapper {
+    classes: HashMap<String, String>,
+    /// Package prefixes with a trailing `/`, longest first.
+    packages: Vec<(String, String)>,
+    fields: HashMap<(String, String, String), String>,
+    /// Fields renamed regardless of their descriptor.
+    field_names: HashMap<(String, String), String>,
+    methods: HashMap<(String, String, String), String>,
+    hierarchy: Option<ClassHierarchy>,
+    remap_strings: bool,
+}
+
+impl Remapper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renames a class, given by internal name like `com/example/Foo`.
+    pub fn rename_class(&mut self, old: impl Into<String>, new: impl Into<String>) {
+        self.classes.insert(old.into(), new.into());
+    }
+
+    /// Moves the classes in a package and its subpackages, like `com/google` to
+    /// `shaded/com/google`. Renamed classes take precedence over moved packages.
+    pub fn rename_package(&mut self, old: &str, new: &str) {
+        let old = format!("{}/", old.trim_end_matches('/'));
+        let new = match new.trim_end_matches('/') {
+            "" => String::new(),
+            x => format!("{x}/"),
+        };
+
+        self.packages.retain(|(x, _)| x != &old);
+        self.packages.push((old, new));
+        self.packages.sort_by_key(|(old, _)| Reverse(old.len()));
+    }
+
+    /// Renames a field of a class, or every field with the name if no descriptor is given.
+    pub fn rename_field(&mut self, class: &str, name: &str, descriptor: Option<&str>, new: &str) {
+        match descriptor {
+            Some(descriptor) => self.fields.insert(
+                (class.to_owned(), name.to_owned(), descriptor.to_owned()),
+                new.to_owned(),
+            ),
+            None => self
+                .field_names
+                .insert((class.to_owned(), name.to_owned()), new.to_owned()),
+        };
+    }
+
+    pub fn rename_method(&mut self, class: &str, name: &str, descriptor: &str, new: &str) {
+        self.methods.insert(
+            (class.to_owned(), name.to_owned(), descriptor.to_owned()),
+            new.to_owned(),
+        );
+    }
+
+    /// Makes renamed members apply to the subtypes of their class in the hierarchy, so
+    /// that overriding methods and references through subclasses are renamed too.
+    pub fn set_hierarchy(&mut self, hierarchy: ClassHierarchy) {
+        self.hierarchy = Some(hierarchy);
+    }
+
+    /// Whether string constants holding a class name, in internal or binary form, or a
+    /// path in a moved package are renamed too. Off by default.
+    pub fn set_remap_strings(&mut self, remap_strings: bool) {
+        self.remap_strings = remap_strings;
+    }
+
+    /// The new internal name of a class. Array types are mapped with [`Self::map_signature`].
+    pub fn map_class(&self, name: &str) -> String {
+        self.map_internal_name(name)
+            .unwrap_or_else(|| name.to_owned())
+    }
+
+    fn map_internal_name(&self, name: &str) -> Option<String> {
+        if let Some(new) = self.classes.get(name) {
+            return Some(new.clone());
+        }
+
+        self.packages.iter().find_map(|(old, new)| {
+            name.strip_prefix(old.as_str())
+                .map(|rest| format!("{new}{rest}"))
+        })
+    }
+
+    fn map_package(&self, package: &str) -> String {
+        match self.map_internal_name(&format!("{package}/")) {
+            Some(x) => x.trim_end_matches('/').to_owned(),
+            None => package.to_owned(),
+        }
+    }
+
+    /// Maps the classes in a descriptor or generic signature.
+    pub fn map_signature(&self, signature: &str) -> JomResult<String> {
+        signature::map_classes(signature, |x| self.map_class(x))
+    }
+
+    fn map_class_or_array(&self, name: &str) -> JomResult<String> {
+        match name.starts_with('[') {
+            true => self.map_signature(name),
+            false => Ok(self.map_class(name)),
+        }
+    }
+
+    /// The new name of a string constant. See [`Self::set_remap_strings`].
+    pub fn map_string(&self, s: &str) -> String {
+        if !s.contains('/') && s.contains('.') {
+            let internal = s.replace('.', "/");
+            return match self.map_internal_name(&internal) {
+                Some(x) => x.replace('/', "."),
+                None => s.to_owned(),
+            };
+        }
+
+        self.map_class(s)
+    }
+
+    /// The new name of a field, given the class it's referenced through and the old
+    /// descriptor.
+    pub fn map_field(&self, class: &str, name: &str, descriptor: &str) -> String {
+        self.lookup(class, |class| {
+            self.fields
+                .get(&(class.to_owned(), name.to_owned(), descriptor.to_owned()))
+                .or_else(|| self.field_names.get(&(class.to_owned(), name.to_owned())))
+        })
+        .unwrap_or_else(|| name.to_owned())
+    }
+
+    /// The new name of a method, given the class it's referenced through and the old
+    /// descriptor. Constructors and static initializers keep their names.
+    pub fn map_method(&self, class: &str, name: &str, descriptor: &str) -> String {
+        if name.starts_with('<') {
+            return name.to_owned();
+        }
+
+        self.lookup(class, |class| {
+            self.methods
+                .get(&(class.to_owned(), name.to_owned(), descriptor.to_owned()))
+        })
+        .unwrap_or_else(|| name.to_owned())
+    }
+
+    /// Searches the class and then its supertypes for a renamed member.
+    fn lookup<'a>(
+        &'a self,
+        class: &str,
+        get: impl Fn(&str) -> Option<&'a String>,
+    ) -> Option<String> {
+        if let Some(new) = get(class) {
+            return Some(new.clone());
+        }
+
+        let hierarchy = self.hierarchy.as_ref()?;
+        hierarchy
+            .supertypes(class)
+            .into_iter()
+            .find_map(get)
+            .cloned()
+    }
+
+    /// Renames the class, its members and every reference in it.
+    pub fn remap(&self, class: &mut ClassFile) -> JomResult<()> {
+        let owner = class.this_class.clone();
+        let cp = &mut class.constant_pool;
+
+        // The old names are needed for the members, so the constant pool is renamed
+        // after the attributes, which may append entries that are already mapped.
+        let lambda_names = self.lambda_names(cp, &class.attributes)?;
+        let len = cp.len();
+
+        for field in &mut class.fields {
+            for attribute in &mut field.attributes {
+                match attribute {
+                    FieldAttribute::Signature(s) => *s = self.map_signature(s)?,
+                    FieldAttribute::Unknown(name, info) => {
+                        self.remap_attribute(name, info, cp, &owner)?
+                    }
+                    _ => {}
+                }
+            }
+            field.name = self.map_field(&owner, &field.name, &field.descriptor);
+            field.descriptor = self.map_signature(&field.descriptor)?;
+        }
+
+        for method in &mut class.methods {
+            for attribute in &mut method.attributes {
+                match attribute {
+                    MethodAttribute::Signature(s) => *s = self.map_signature(s)?,
+                    MethodAttribute::Code(code) => {
+                        for exception in &mut code.exception_table {
+                            if let Some(catch_type) = &mut exception.catch_type {
+                                *catch_type = self.map_class(catch_type);
+                            }
+                        }
+                        for attribute in &mut code.attributes {
+                            match attribute {
+                                CodeAttribute::LocalVariableTable(table) => {
+                                    for variable in table {
+                                        variable.descriptor =
+                                            self.map_signature(&variable.descriptor)?;
+                                    }
+                                }
+                                CodeAttribute::LocalVariableTypeTable(table) => {
+                                    for variable in table {
+                                        variable.descriptor =
+                                            self.map_signature(&variable.descriptor)?;
+                                    }
+                                }
+                                CodeAttribute::Unknown(name, info) => {
+                                    self.remap_attribute(name, info, cp, &owner)?
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    MethodAttribute::Unknown(name, info) => {
+                        self.remap_attribute(name, info, cp, &owner)?
+                    }
+                    _ => {}
+                }
+            }
+            method.name = self.map_method(&owner, &method.name, &method.descriptor);
+            method.descriptor = self.map_signature(&method.descriptor)?;
+        }
+
+        for attribute in &mut class.attributes {
+            if let ClassAttribute::Unknown(name, info) = attribute {
+                self.remap_attribute(name, info, cp, &owner)?;
+            }
+        }
+
+        for (i, entry) in cp.iter_mut().enumerate().take(len) {
+            self.remap_cp_index(entry, lambda_names.get(&i))?;
+        }
+
+        class.this_class = self.map_class(&owner);
+        if !class.super_class.is_empty() {
+            class.super_class = self.map_class(&class.super_class);
+        }
+        for interface in &mut class.interfaces {
+            *interface = self.map_class(interface);
+        }
+
+        Ok(())
+    }
+
+    fn remap_cp_index(
+        &self,
+        entry: &mut ConstantPoolIndex,
+        lambda_name: Option<&String>,
+    ) -> JomResult<()> {
+        match entry {
+            ConstantPoolIndex::Class(class) => *class = self.map_class_or_array(class)?,
+            ConstantPoolIndex::String(s) if self.remap_strings => *s = self.map_string(s),
+            ConstantPoolIndex::Fieldref {
+                class,
+                name,
+                descriptor,
+            } => {
+                *name = self.map_field(class, name, descriptor);
+                *descriptor = self.map_signature(descriptor)?;
+                *class = self.map_class_or_array(class)?;
+            }
+            ConstantPoolIndex::Methodref {
+                class,
+                name,
+                descriptor,
+            }
+            | ConstantPoolIndex::InterfaceMethodref {
+                class,
+                name,
+                descriptor,
+            } => {
+                *name = self.map_method(class, name, descriptor);
+                *descriptor = self.map_signature(descriptor)?;
+                *class = self.map_class_or_array(class)?;
+            }
+            ConstantPoolIndex::MethodHandle {
+                kind,
+                class,
+                name,
+                descriptor,
+            } => {
+                *name = match kind {
+                    MethodHandleReferenceKind::GetField
+                    | MethodHandleReferenceKind::GetStatic
+                    | MethodHandleReferenceKind::PutField
+                    | MethodHandleReferenceKind::PutStatic => {
+                        self.map_field(class, name, descriptor)
+                    }
+                    _ => self.map_method(class, name, descriptor),
+                };
+                *descriptor = self.map_signature(descriptor)?;
+                *class = self.map_class_or_array(class)?;
+            }
+            ConstantPoolIndex::NameAndType(_, descriptor)
+            | ConstantPoolIndex::MethodType(descriptor)
+            | ConstantPoolIndex::Dynamic { descriptor, .. } => {
+                *descriptor = self.map_signature(descriptor)?;
+            }
+            ConstantPoolIndex::InvokeDynamic {
+                name, descriptor, ..
+            } => {
+                if let Some(lambda_name) = lambda_name {
+                    *name = lambda_name.clone();
+                }
+                *descriptor = self.map_signature(descriptor)?;
+            }
+            ConstantPoolIndex::Package(package) => *package = self.map_package(package),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// The new names of `invokedynamic` entries bootstrapped by `LambdaMetafactory`,
+    /// whose name is the implemented method of the functional interface they return.
+    fn lambda_names(
+        &self,
+        cp: &ConstantPool,
+        attributes: &[ClassAttribute],
+    ) -> JomResult<HashMap<usize, String>> {
+        let bootstrap_methods = bootstrap_methods(attributes)?;
+
+        let mut names = HashMap::new();
+        for (i, entry) in cp.iter().enumerate() {
+            let ConstantPoolIndex::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name,
+                descriptor,
+            } = entry
+            else {
+                continue;
+            };
+            let Some((handle, arguments)) =
+                bootstrap_methods.get(*bootstrap_method_attr_index as usize)
+            else {
+                continue;
+            };
+            let ConstantPoolIndex::MethodHandle { class, .. } = cp.get(*handle)? else {
+                continue;
+            };
+            let (Some(interface), Some(&argument)) = (
+                descriptor
+                    .rsplit_once(")L")
+                    .and_then(|(_, x)| x.strip_suffix(';')),
+                arguments.first(),
+            ) else {
+                continue;
+            };
+            let ConstantPoolIndex::MethodType(method_type) = cp.get(argument)? else {
+                continue;
+            };
+
+            if class == LAMBDA_METAFACTORY {
+                names.insert(i, self.map_method(interface, name, &method_type));
+            }
+        }
+
+        Ok(names)
+    }
+
+    fn remap_attribute(
+        &self,
+        name: &str,
+        info: &mut [u8],
+        cp: &mut ConstantPool,
+        owner: &str,
+    ) -> JomResult<()> {
+        let mut remapper = AttributeRemapper {
+            remapper: self,
+            cp,
+            owner,
+        };
+        // The attributes of unknown layout refer to classes and members through constant
+        // pool entries, which are renamed in place.
+        walker::walk(name, info, &mut remapper)?;
+
+        Ok(())
+    }
+}
+
+/// Points the `Utf8` and `NameAndType` indices of raw attributes to mapped entries.
+struct AttributeRemapper<'a> {
+    remapper: &'a Remapper,
+    cp: &'a mut ConstantPool,
+    /// The old name of the class the attribute belongs to.
+    owner: &'a str,
+}
+
+impl AttributeRemapper<'_> {
+    /// Returns the index of the `Utf8` entry that `map` turns the one at `index` into.
+    fn utf8(&mut self, index: u16, map: impl FnOnce(&str) -> JomResult<String>) -> JomResult<u16> {
+        let old = self.cp.get_utf8(index)?;
+        let new = map(&old)?;
+        if new == old {
+            return Ok(index);
+        }
+
+        self.cp.insert_utf8(new)
+    }
+
+    fn enclosing_method(&mut self, index: u16, class: &str) -> JomResult<u16> {
+        let (name, descriptor) = self.cp.get(index)?.into_name_and_type()?;
+        let mapped = ConstantPoolIndex::NameAndType(
+            self.remapper.map_method(class, &name, &descriptor),
+            self.remapper.map_signature(&descriptor)?,
+        );
+        if mapped == ConstantPoolIndex::NameAndType(name, descriptor) {
+            return Ok(index);
+        }
+
+        self.cp.insert(mapped)
+    }
+
+    /// Renames the simple name of an inner class to match its new name.
+    fn inner_name(&mut self, index: u16, inner: &str, outer: Option<&str>) -> JomResult<u16> {
+        let remapper = self.remapper;
+        self.utf8(index, |name| {
+            let mapped = remapper.map_class(inner);
+            if mapped == inner {
+                return Ok(name.to_owned());
+            }
+
+            let outer = outer.map(|x| format!("{}$", remapper.map_class(x)));
+            Ok(outer
+                .and_then(|x| mapped.strip_prefix(&x))
+                .or_else(|| mapped.rsplit_once('$').map(|(_, x)| x))
+                .unwrap_or(name)
+                .to_owned())
+        })
+    }
+
+    /// Renames the record components given to `ObjectMethods` as a string, like
+    /// `"first;second"`, to match their getters.
+    fn object_methods_names(&mut self, index: u16, method: u16, getters: &[u16]) -> JomResult<u16> {
+        let ConstantPoolIndex::MethodHandle { class, .. } = self.cp.get(method)? else {
+            return Ok(index);
+        };
+        if class != OBJECT_METHODS {
+            return Ok(index);
+        }
+
+        let mut mapped = vec![];
+        for &getter in getters {
+            let ConstantPoolIndex::MethodHandle {
+                class,
+                name,
+                descriptor,
+                ..
+            } = self.cp.get(getter)?
+            else {
+                return Ok(index);
+            };
+            mapped.push(self.remapper.map_field(&class, &name, &descriptor));
+        }
+
+        let mapped = ConstantPoolIndex::String(mapped.join(";"));
+        if self.cp.get(index)? == mapped {
+            return Ok(index);
+        }
+
+        self.cp.insert(mapped)
+    }
+}
+
+impl Visitor for AttributeRemapper<'_> {
+    fn constant_pool(&self) -> &ConstantPool {
+        self.cp
+    }
+
+    fn index(&mut self, index: u16, reference: Reference) -> JomResult<u16> {
+        let remapper = self.remapper;
+        match reference {
+            Reference::Signature => self.utf8(index, |x| remapper.map_signature(x)),
+            Reference::EnclosingMethod { class } => self.enclosing_method(index, class),
+            Reference::InnerName {
+                inner: Some(inner),
+                outer,
+            } => self.inner_name(index, inner, outer),
+            // Record components and enum constants are mapped as fields, and annotation
+            // elements as methods of the annotation type.
+            Reference::RecordComponent { descriptor } => {
+                let owner = self.owner;
+                self.utf8(index, |x| Ok(remapper.map_field(owner, x, descriptor)))
+            }
+            Reference::EnumConstant { descriptor } => self.utf8(index, |x| {
+                Ok(remapper.map_field(class_of(descriptor), x, descriptor))
+            }),
+            Reference::ElementName {
+                annotation,
+                value: Some(value),
+            } => self.utf8(index, |x| {
+                Ok(remapper.map_method(class_of(annotation), x, &format!("(){value}")))
+            }),
+            Reference::BootstrapArgument {
+                method,
+                arguments: [_, _, getters @ ..],
+                position: 1,
+            } => self.object_methods_names(index, method, getters),
+            _ => Ok(index),
+        }
+    }
+}
+
+/// The class name of an object type descriptor.
+fn class_of(descriptor: &str) -> &str {
+    descriptor
+        .strip_prefix('L')
+        .and_then(|x| x.strip_suffix(';'))
+        .unwrap_or(descriptor)
+}
@@ -0,0 +1,157 @@
+use crate::error::{JomError, JomResult};
+
+/// Rewrites the class names in a descriptor or generic signature.
+///
+/// In `Lpkg/Outer<TT;>.Inner;`, the inner class is passed to `map` as `pkg/Outer$Inner`
+/// and written back as the part of its new name after the new outer class name.
+pub(crate) fn map_classes(signature: &str, map: impl FnMut(&str) -> String) -> JomResult<String> {
+    let mut mapper = SignatureMapper {
+        signature,
+        rest: signature,
+        mapped: String::with_capacity(signature.len()),
+        map,
+    };
+
+    if mapper.rest.starts_with('<') {
+        mapper.type_parameters()?;
+    }
+    while let Some(c) = mapper.next() {
+        match c {
+            '(' | ')' | '^' | 'V' => {}
+            _ => mapper.type_signature(c)?,
+        }
+    }
+
+    Ok(mapper.mapped)
+}
+
+struct SignatureMapper<'a, F> {
+    signature: &'a str,
+    rest: &'a str,
+    mapped: String,
+    map: F,
+}
+
+impl<'a, F: FnMut(&str) -> String> SignatureMapper<'a, F> {
+    /// Consumes and copies the next character.
+    fn next(&mut self) -> Option<char> {
+        let c = self.rest.chars().next()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        self.mapped.push(c);
+
+        Some(c)
+    }
+
+    fn invalid(&self) -> JomError {
+        JomError::invalid_descriptor(self.signature)
+    }
+
+    /// Consumes up to, and including, the first of the delimiters, without copying the
+    /// identifier.
+    fn identifier(&mut self, delimiters: &[char]) -> JomResult<(&'a str, char)> {
+        let end = self.rest.find(delimiters).ok_or_else(|| self.invalid())?;
+        let identifier = &self.rest[..end];
+        let delimiter = self.rest[end..].chars().next().unwrap();
+        self.rest = &self.rest[end + 1..];
+
+        Ok((identifier, delimiter))
+    }
+
+    /// `<T:Ljava/lang/Object;U::Ljava/lang/Comparable<TU;>;>`, where class bounds may be
+    /// empty.
+    fn type_parameters(&mut self) -> JomResult<()> {
+        self.next();
+        loop {
+            if self.rest.starts_with('>') {
+                self.next();
+                return Ok(());
+            }
+
+            let (name, _) = self.identifier(&[':'])?;
+            self.mapped.push_str(name);
+            self.mapped.push(':');
+            if !self.rest.starts_with(':') {
+                self.bound()?;
+            }
+            while self.rest.starts_with(':') {
+                self.next();
+                self.bound()?;
+            }
+        }
+    }
+
+    fn bound(&mut self) -> JomResult<()> {
+        match self.next() {
+            Some(c) => self.type_signature(c),
+            None => Err(self.invalid()),
+        }
+    }
+
+    fn type_signature(&mut self, c: char) -> JomResult<()> {
+        match c {
+            'B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z' => Ok(()),
+            '[' => self.bound(),
+            'T' => {
+                let (name, _) = self.identifier(&[';'])?;
+                self.mapped.push_str(name);
+                self.mapped.push(';');
+                Ok(())
+            }
+            'L' => self.class_type(),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    fn class_type(&mut self) -> JomResult<()> {
+        let mut class = String::new();
+        let mut mapped_class = String::new();
+
+        loop {
+            let (identifier, mut delimiter) = self.identifier(&['<', '.', ';'])?;
+            let mapped = match class.is_empty() {
+                true => {
+                    class.push_str(identifier);
+                    let mapped = (self.map)(&class);
+                    self.mapped.push_str(&mapped);
+                    mapped
+                }
+                false => {
+                    class.push('$');
+                    class.push_str(identifier);
+                    let mapped = (self.map)(&class);
+                    let inner = mapped
+                        .strip_prefix(&format!("{mapped_class}$"))
+                        .or_else(|| mapped.rsplit_once('$').map(|(_, x)| x))
+                        .unwrap_or(identifier);
+                    self.mapped.push_str(inner);
+                    mapped
+                }
+            };
+            mapped_class = mapped;
+
+            self.mapped.push(delimiter);
+            if delimiter == '<' {
+                self.type_arguments()?;
+                delimiter = self.next().ok_or_else(|| self.invalid())?;
+            }
+
+            match delimiter {
+                ';' => return Ok(()),
+                '.' => {}
+                _ => return Err(self.invalid()),
+            }
+        }
+    }
+
+    /// The type arguments after a `<`, up to and including the `>`.
+    fn type_arguments(&mut self) -> JomResult<()> {
+        loop {
+            match self.next().ok_or_else(|| self.invalid())? {
+                '>' => return Ok(()),
+                '*' => {}
+                '+' | '-' => self.bound()?,
+                c => self.type_signature(c)?,
+            }
+        }
+    }
+}
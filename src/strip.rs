@@ -0,0 +1,65 @@
+//! Removing debug information and other attributes the JVM does not need, to make classes
+//! smaller.
+
+use crate::{error::JomResult, ClassFile};
+
+/// Attributes that only debuggers, compilers and stack traces use.
+const DEBUG_ATTRIBUTES: &[&str] = &[
+    "LineNumberTable",
+    "LocalVariableTable",
+    "LocalVariableTypeTable",
+    "SourceFile",
+    "SourceDebugExtension",
+    "Deprecated",
+];
+
+/// Annotations that are kept in class files but not loaded by the JVM, like those with
+/// `@Retention(CLASS)`.
+const INVISIBLE_ANNOTATIONS: &[&str] = &[
+    "RuntimeInvisibleAnnotations",
+    "RuntimeInvisibleParameterAnnotations",
+    "RuntimeInvisibleTypeAnnotations",
+];
+
+#[derive(Default)]
+pub struct Stripper {
+    invisible_annotations: bool,
+}
+
+impl Stripper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also removes the annotations the JVM does not load, which tools like annotation
+    /// processors and null checkers may still read.
+    pub fn set_strip_invisible_annotations(&mut self, strip: bool) {
+        self.invisible_annotations = strip;
+    }
+
+    fn strips(&self, name: &str) -> bool {
+        DEBUG_ATTRIBUTES.contains(&name)
+            || self.invisible_annotations && INVISIBLE_ANNOTATIONS.contains(&name)
+    }
+
+    /// Removes the attributes, then the constant pool entries nothing else uses. Returns
+    /// `false` if the pool was left unchanged, like [`ClassFile::compact_constant_pool`].
+    pub fn strip(&self, class: &mut ClassFile) -> JomResult<bool> {
+        for i in 0..class.methods.len() {
+            class.decode_method(i)?;
+        }
+
+        class.attributes.retain(|x| !self.strips(x.name()));
+        for field in &mut class.fields {
+            field.attributes.retain(|x| !self.strips(x.name()));
+        }
+        for method in &mut class.methods {
+            method.attributes.retain(|x| !self.strips(x.name()));
+            if let Some(code) = method.code_mut() {
+                code.attributes.retain(|x| !self.strips(x.name()));
+            }
+        }
+
+        class.compact_constant_pool()
+    }
+}
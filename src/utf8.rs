@@ -1,6 +1,76 @@
+use std::borrow::Cow;
+
 use binrw::{BinRead, BinWrite, Endian, VecArgs};
 
-pub struct ModifiedUtf8(String);
+/// The error of a string that is not valid modified UTF-8.
+#[derive(Debug)]
+pub(crate) struct InvalidModifiedUtf8;
+
+impl std::fmt::Display for InvalidModifiedUtf8 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid modified UTF-8")
+    }
+}
+
+/// A string in the class file's modified UTF-8, which encodes NUL as two bytes and
+/// supplementary characters as surrogate pairs of three bytes each.
+///
+/// The string is kept as UTF-16 code units, so that unpaired surrogates, which Java strings
+/// allow, are written back unchanged.
+pub(crate) struct ModifiedUtf8(pub(crate) Vec<u16>);
+
+impl ModifiedUtf8 {
+    fn decode(data: &[u8]) -> Option<Vec<u16>> {
+        let mut units = Vec::with_capacity(data.len());
+        let mut bytes = data.iter().map(|&x| x as u16);
+
+        while let Some(a) = bytes.next() {
+            let mut continuation = || bytes.next().filter(|x| x & 0xc0 == 0x80);
+
+            units.push(match a {
+                0x01..=0x7f => a,
+                0xc0..=0xdf => (a & 0x1f) << 6 | continuation()? & 0x3f,
+                0xe0..=0xef => {
+                    (a & 0x0f) << 12 | (continuation()? & 0x3f) << 6 | continuation()? & 0x3f
+                }
+                _ => return None,
+            });
+        }
+
+        Some(units)
+    }
+
+    /// Decodes the bytes without copying them when they are also valid UTF-8, which they
+    /// are unless they contain NUL or supplementary characters. Strings with unpaired
+    /// surrogates are returned as their UTF-16 code units.
+    pub(crate) fn decode_borrowed(data: &[u8]) -> Option<Result<Cow<'_, str>, Vec<u16>>> {
+        match std::str::from_utf8(data) {
+            Ok(s) if !data.iter().any(|&x| x == 0 || x >= 0xf0) => Some(Ok(Cow::Borrowed(s))),
+            _ => Self::decode(data).map(|x| String::from_utf16(&x).map(Cow::Owned).or(Err(x))),
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.0.len());
+
+        for &unit in &self.0 {
+            match unit {
+                0x01..=0x7f => bytes.push(unit as u8),
+                0x00 | 0x80..=0x7ff => {
+                    bytes.push(0xc0 | (unit >> 6) as u8);
+                    bytes.push(0x80 | (unit & 0x3f) as u8);
+                }
+                _ => {
+                    bytes.push(0xe0 | (unit >> 12) as u8);
+                    bytes.push(0x80 | (unit >> 6 & 0x3f) as u8);
+                    bytes.push(0x80 | (unit & 0x3f) as u8);
+                }
+            }
+        }
+
+        bytes
+    }
+}
 
 impl BinRead for ModifiedUtf8 {
     type Args<'a> = ();
@@ -10,13 +80,14 @@ impl BinRead for ModifiedUtf8 {
         endian: Endian,
         _: Self::Args<'_>,
     ) -> binrw::BinResult<Self> {
+        let pos = reader.stream_position()?;
         let count = u16::read_options(reader, endian, ())? as usize;
         let data = Vec::<u8>::read_options(reader, endian, VecArgs { count, inner: () })?;
-        // TODO: Check validity
-        Ok(Self(String::from_utf8(data).map_err(|_| {
+
+        Ok(Self(Self::decode(&data).ok_or_else(|| {
             binrw::Error::Custom {
-                pos: 0,
-                err: Box::new("Failed to read utf8 string."),
+                pos,
+                err: Box::new(InvalidModifiedUtf8),
             }
         })?))
     }
@@ -31,25 +102,16 @@ impl BinWrite for ModifiedUtf8 {
         endian: Endian,
         _: Self::Args<'_>,
     ) -> binrw::BinResult<()> {
-        let bytes = self.0.as_bytes();
-        (bytes.len() as u16).write_options(writer, endian, ())?;
+        let bytes = self.encode();
+        let pos = writer.stream_position()?;
+        let length = u16::try_from(bytes.len()).map_err(|_| binrw::Error::Custom {
+            pos,
+            err: Box::new("utf8 string is longer than 65535 bytes."),
+        })?;
+
+        length.write_options(writer, endian, ())?;
         bytes.write_options(writer, endian, ())?;
 
         Ok(())
     }
 }
-
-impl ToString for ModifiedUtf8 {
-    fn to_string(&self) -> String {
-        self.0.clone()
-    }
-}
-
-impl TryFrom<String> for ModifiedUtf8 {
-    type Error = binrw::Error;
-
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        // TODO: check validity
-        Ok(Self(value))
-    }
-}
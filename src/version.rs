@@ -0,0 +1,307 @@
+use std::fmt;
+
+use crate::{
+    access::ACC_INTERFACE,
+    attribute::CodeAttribute,
+    constant_pool::ConstantPool,
+    error::{ErrorPath, JomError, JomResult, Location},
+    method::{
+        code::instruction::{Instruction, Wide},
+        MethodInfo,
+    },
+    ClassFile, MAX_MAJOR,
+};
+
+/// The minor version of classes that use the preview features of their Java release.
+pub const PREVIEW_MINOR: u16 = 0xFFFF;
+
+/// The version of a class file, which decides the features it can use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClassVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl ClassVersion {
+    /// The newest version that can be read.
+    pub const LATEST: Self = Self::new(MAX_MAJOR, 0);
+
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self { major, minor }
+    }
+
+    /// The version of a Java release, where releases before Java 5 are numbered like 4 for
+    /// Java 1.4. Java 1.0 and 1.1 share version 45.3.
+    pub fn from_release(release: u16) -> Option<Self> {
+        match release {
+            0 => None,
+            1 => Some(Self::new(45, 3)),
+            x => Some(Self::new(x.checked_add(44)?, 0)),
+        }
+    }
+
+    /// The Java release of the version, numbered like [`ClassVersion::from_release`].
+    pub fn release(&self) -> u16 {
+        self.major.saturating_sub(44).max(1)
+    }
+
+    /// Whether the class uses the preview features of its release, which is only possible
+    /// from Java 12 on.
+    pub fn is_preview(&self) -> bool {
+        self.minor == PREVIEW_MINOR
+    }
+
+    /// The same release with its preview features enabled.
+    pub fn preview(self) -> Self {
+        Self::new(self.major, PREVIEW_MINOR)
+    }
+
+    /// Whether classes of this version can be read.
+    pub fn is_supported(&self) -> bool {
+        (45..=MAX_MAJOR).contains(&self.major) && (!self.is_preview() || self.major >= 56)
+    }
+
+    /// Whether a class of this version can use `feature`.
+    pub fn allows(&self, feature: &Feature) -> bool {
+        self.major >= feature.since() && feature.until().is_none_or(|x| self.major <= x)
+    }
+}
+
+impl fmt::Display for ClassVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.release() {
+            x if x < 5 => write!(f, "Java 1.{x}")?,
+            x => write!(f, "Java {x}")?,
+        }
+        if self.is_preview() {
+            write!(f, " preview")?;
+        }
+        write!(f, " ({}.{})", self.major, self.minor)
+    }
+}
+
+/// Something in a class that only some versions allow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// A kind of constant pool entry, like `Dynamic`.
+    Constant(&'static str),
+    Attribute(String),
+    /// An instruction, by its mnemonic.
+    Instruction(&'static str),
+    /// A default or static method in an interface.
+    InterfaceMethodCode,
+    /// Code with branches or exception handlers but no `StackMapTable`.
+    MissingStackMapTable,
+    /// String concatenation with `invokedynamic`, whose `StringConcatFactory` bootstrap
+    /// method was added in Java 9.
+    StringConcat,
+}
+
+impl Feature {
+    /// The first major version that allows the feature.
+    pub fn since(&self) -> u16 {
+        match self {
+            Self::Constant("MethodHandle" | "MethodType" | "InvokeDynamic") => 51,
+            Self::Constant("Module" | "Package") => 53,
+            Self::Constant("Dynamic") => 55,
+            Self::Attribute(name) => match name.as_str() {
+                "EnclosingMethod"
+                | "Signature"
+                | "SourceDebugExtension"
+                | "LocalVariableTypeTable"
+                | "RuntimeVisibleAnnotations"
+                | "RuntimeInvisibleAnnotations"
+                | "RuntimeVisibleParameterAnnotations"
+                | "RuntimeInvisibleParameterAnnotations"
+                | "AnnotationDefault" => 49,
+                "StackMapTable" => 50,
+                "BootstrapMethods" => 51,
+                "RuntimeVisibleTypeAnnotations"
+                | "RuntimeInvisibleTypeAnnotations"
+                | "MethodParameters" => 52,
+                "Module" | "ModulePackages" | "ModuleMainClass" => 53,
+                "NestHost" | "NestMembers" => 55,
+                "Record" => 60,
+                "PermittedSubclasses" => 61,
+                _ => 45,
+            },
+            Self::Instruction("invokedynamic") => 51,
+            Self::InterfaceMethodCode => 52,
+            Self::StringConcat => 53,
+            _ => 45,
+        }
+    }
+
+    /// The last major version that allows the feature, if there is one.
+    pub fn until(&self) -> Option<u16> {
+        match self {
+            Self::Instruction("jsr" | "jsr_w" | "ret") => Some(50),
+            Self::MissingStackMapTable => Some(50),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Constant(name) => write!(f, "{name} constant"),
+            Self::Attribute(name) => write!(f, "{name} attribute"),
+            Self::Instruction(mnemonic) => write!(f, "{mnemonic} instruction"),
+            Self::InterfaceMethodCode => write!(f, "interface method with code"),
+            Self::MissingStackMapTable => write!(f, "code without a StackMapTable"),
+            Self::StringConcat => write!(f, "invokedynamic string concatenation"),
+        }
+    }
+}
+
+/// A feature a class uses that a version does not allow, and where it is used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionIssue {
+    pub feature: Feature,
+    /// Empty for the class itself.
+    pub path: Vec<ErrorPath>,
+}
+
+impl fmt::Display for VersionIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}", Location(&self.path, &None))?;
+        }
+        match self.feature.until() {
+            Some(x) => write!(
+                f,
+                "{} is only allowed up to {}",
+                self.feature,
+                ClassVersion::new(x, 0)
+            ),
+            None => write!(
+                f,
+                "{} is only allowed from {}",
+                self.feature,
+                ClassVersion::new(self.feature.since(), 0)
+            ),
+        }
+    }
+}
+
+/// The constant pool entries that `version` does not allow, with their indices.
+pub(crate) fn disallowed_constants(
+    constant_pool: &ConstantPool,
+    version: ClassVersion,
+) -> impl Iterator<Item = (u16, Feature)> + '_ {
+    constant_pool
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| (i as u16, Feature::Constant(entry.name())))
+        .filter(move |(_, feature)| !version.allows(feature))
+}
+
+/// Fails on the first constant pool entry that `version` does not allow, as the JVM does.
+pub(crate) fn check_constants(
+    constant_pool: &ConstantPool,
+    version: ClassVersion,
+) -> JomResult<()> {
+    match disallowed_constants(constant_pool, version).next() {
+        Some((i, feature)) => {
+            Err(JomError::NotAllowed(feature, version).within(ErrorPath::Constant(i)))
+        }
+        None => Ok(()),
+    }
+}
+
+impl ClassFile {
+    pub fn version(&self) -> ClassVersion {
+        ClassVersion::new(self.major(), self.minor())
+    }
+
+    /// The features the class uses that its own version does not allow. The JVM ignores
+    /// such attributes, but rejects the rest.
+    pub fn version_issues(&self) -> Vec<VersionIssue> {
+        self.retarget_issues(self.version())
+    }
+
+    /// What must change for the class to be valid at `target`: the features it uses that
+    /// `target` does not allow, and those it lacks that `target` requires.
+    pub fn retarget_issues(&self, target: ClassVersion) -> Vec<VersionIssue> {
+        let mut issues = vec![];
+        let mut check = |feature: Feature, path: &[ErrorPath]| {
+            if !target.allows(&feature) {
+                issues.push(VersionIssue {
+                    feature,
+                    path: path.to_vec(),
+                });
+            }
+        };
+
+        for (i, entry) in self.constant_pool().iter().enumerate() {
+            check(
+                Feature::Constant(entry.name()),
+                &[ErrorPath::Constant(i as u16)],
+            );
+        }
+        for attribute in self.attributes() {
+            check(Feature::Attribute(attribute.name().to_owned()), &[]);
+        }
+        for field in self.fields() {
+            let path = [ErrorPath::Field {
+                name: field.name.clone(),
+                descriptor: field.descriptor.clone(),
+            }];
+            for attribute in &field.attributes {
+                check(Feature::Attribute(attribute.name().to_owned()), &path);
+            }
+        }
+        let interface = self.access_flags() & ACC_INTERFACE != 0;
+        for method in self.methods() {
+            method_issues(method, interface, &mut check);
+        }
+
+        issues
+    }
+}
+
+fn method_issues(
+    method: &MethodInfo,
+    interface: bool,
+    check: &mut impl FnMut(Feature, &[ErrorPath]),
+) {
+    let mut path = vec![ErrorPath::Method {
+        name: method.name.clone(),
+        descriptor: method.descriptor.clone(),
+    }];
+    for attribute in &method.attributes {
+        check(Feature::Attribute(attribute.name().to_owned()), &path);
+    }
+    let Some(code) = method.code() else {
+        return;
+    };
+    if interface && method.name != "<clinit>" {
+        check(Feature::InterfaceMethodCode, &path);
+    }
+
+    path.push(ErrorPath::Attribute("Code".to_owned()));
+    for attribute in &code.attributes {
+        check(Feature::Attribute(attribute.name().to_owned()), &path);
+    }
+    let has_frames = code
+        .attributes
+        .iter()
+        .any(|x| matches!(x, CodeAttribute::StackMapTable) || x.name() == "StackMapTable");
+    let branches = code.code.iter().any(Instruction::is_branch);
+    if !has_frames && (branches || !code.exception_table.is_empty()) {
+        check(Feature::MissingStackMapTable, &path);
+    }
+
+    let mut pc = 0;
+    for instruction in &code.code {
+        let mnemonic = match instruction {
+            Instruction::Wide(Wide::Ret(_)) => "ret",
+            x => x.mnemonic(),
+        };
+        path.push(ErrorPath::Pc(pc));
+        check(Feature::Instruction(mnemonic), &path);
+        path.pop();
+        pc += instruction.size(pc);
+    }
+}
@@ -0,0 +1,181 @@
+//! Streaming class file processing, without building a [`ClassFile`](crate::ClassFile).
+//!
+//! A [`ClassReader`] walks the bytes of a class and calls a [`ClassVisitor`] for each part
+//! of it, and a [`ClassWriter`] is a visitor that encodes what it is called with. Transforms
+//! sit in between, wrapping the next visitor and forwarding calls with changes.
+//!
+//! Visitors for nested parts are returned as `Option<Box<dyn ...>>`, where `None` skips
+//! that part without decoding it. Only annotations and `Code` are decoded; every other
+//! attribute is passed on as raw bytes. The constant pool indices in those and in
+//! instructions refer to the pool given to [`ClassVisitor::visit_constant_pool`].
+
+mod reader;
+mod writer;
+
+pub use self::{reader::ClassReader, writer::ClassWriter};
+
+use crate::{
+    class_ref::ClassFileRef,
+    error::JomResult,
+    method::code::{instruction::Instruction, Exception},
+};
+
+/// Everything before the members of a class.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassHeader {
+    pub major: u16,
+    pub minor: u16,
+    pub access_flags: u16,
+    pub name: String,
+    /// Empty for `java/lang/Object` and modules.
+    pub super_class: String,
+    pub interfaces: Vec<String>,
+}
+
+/// A constant element value of an annotation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnnotationValue {
+    Byte(i8),
+    Char(u16),
+    Double(f64),
+    Float(f32),
+    Int(i32),
+    Long(i64),
+    Short(i16),
+    Boolean(bool),
+    String(String),
+    /// A class literal, as a return descriptor like `Ljava/lang/String;` or `V`.
+    Class(String),
+}
+
+pub trait ClassVisitor {
+    /// The class whose constant pool the indices in instructions and raw attributes refer
+    /// to, before anything else is visited. Its entries are resolved when they are asked for.
+    fn visit_constant_pool(&mut self, _class: &ClassFileRef) -> JomResult<()> {
+        Ok(())
+    }
+
+    fn visit(&mut self, _header: &ClassHeader) -> JomResult<()> {
+        Ok(())
+    }
+
+    /// A `RuntimeVisibleAnnotations` or `RuntimeInvisibleAnnotations` entry.
+    fn visit_annotation(
+        &mut self,
+        _descriptor: &str,
+        _visible: bool,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        Ok(None)
+    }
+
+    /// Any other class attribute.
+    fn visit_attribute(&mut self, _name: &str, _info: &[u8]) -> JomResult<()> {
+        Ok(())
+    }
+
+    fn visit_field(
+        &mut self,
+        _access_flags: u16,
+        _name: &str,
+        _descriptor: &str,
+    ) -> JomResult<Option<Box<dyn FieldVisitor + '_>>> {
+        Ok(None)
+    }
+
+    fn visit_method(
+        &mut self,
+        _access_flags: u16,
+        _name: &str,
+        _descriptor: &str,
+    ) -> JomResult<Option<Box<dyn MethodVisitor + '_>>> {
+        Ok(None)
+    }
+
+    fn visit_end(&mut self) -> JomResult<()> {
+        Ok(())
+    }
+}
+
+pub trait FieldVisitor {
+    fn visit_annotation(
+        &mut self,
+        _descriptor: &str,
+        _visible: bool,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        Ok(None)
+    }
+
+    fn visit_attribute(&mut self, _name: &str, _info: &[u8]) -> JomResult<()> {
+        Ok(())
+    }
+
+    fn visit_end(&mut self) -> JomResult<()> {
+        Ok(())
+    }
+}
+
+/// Visits a method's annotations and attributes, then its code, if it has any, in the
+/// order `visit_code`, `visit_instruction`, `visit_exception` and `visit_code_attribute`.
+pub trait MethodVisitor {
+    fn visit_annotation(
+        &mut self,
+        _descriptor: &str,
+        _visible: bool,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        Ok(None)
+    }
+
+    fn visit_attribute(&mut self, _name: &str, _info: &[u8]) -> JomResult<()> {
+        Ok(())
+    }
+
+    fn visit_code(&mut self, _max_stack: u16, _max_locals: u16) -> JomResult<()> {
+        Ok(())
+    }
+
+    /// An instruction and its offset in the code.
+    fn visit_instruction(&mut self, _offset: u32, _instruction: &Instruction) -> JomResult<()> {
+        Ok(())
+    }
+
+    fn visit_exception(&mut self, _exception: &Exception) -> JomResult<()> {
+        Ok(())
+    }
+
+    /// An attribute of the code, like `LineNumberTable` or `StackMapTable`.
+    fn visit_code_attribute(&mut self, _name: &str, _info: &[u8]) -> JomResult<()> {
+        Ok(())
+    }
+
+    fn visit_end(&mut self) -> JomResult<()> {
+        Ok(())
+    }
+}
+
+/// Visits the element values of an annotation, or of an array element value. Elements of
+/// arrays have an empty name.
+pub trait AnnotationVisitor {
+    fn visit(&mut self, _name: &str, _value: &AnnotationValue) -> JomResult<()> {
+        Ok(())
+    }
+
+    fn visit_enum(&mut self, _name: &str, _descriptor: &str, _value: &str) -> JomResult<()> {
+        Ok(())
+    }
+
+    fn visit_annotation(
+        &mut self,
+        _name: &str,
+        _descriptor: &str,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        Ok(None)
+    }
+
+    fn visit_array(&mut self, _name: &str) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        Ok(None)
+    }
+
+    fn visit_end(&mut self) -> JomResult<()> {
+        Ok(())
+    }
+}
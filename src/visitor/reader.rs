@@ -0,0 +1,262 @@
+use crate::{
+    bytes::Reader,
+    class_ref::{AttributeRef, ClassFileRef, ConstantRef},
+    error::{JomError, JomResult},
+};
+
+use super::{
+    AnnotationValue, AnnotationVisitor, ClassHeader, ClassVisitor, FieldVisitor, MethodVisitor,
+};
+
+/// Drives visitors from the bytes of a class file. Constant pool entries are resolved when
+/// they are used, as in a [`ClassFileRef`], and visitors get names and descriptors borrowed
+/// from the bytes where possible.
+pub struct ClassReader<'a> {
+    class: ClassFileRef<'a>,
+}
+
+impl<'a> ClassReader<'a> {
+    pub fn new(data: &'a [u8]) -> JomResult<Self> {
+        Ok(Self {
+            class: ClassFileRef::read(data)?,
+        })
+    }
+
+    /// The class being read, whose constant pool the visited indices refer to.
+    pub fn class(&self) -> &ClassFileRef<'a> {
+        &self.class
+    }
+
+    /// Reads the header, which is much cheaper than visiting the whole class.
+    pub fn header(&self) -> JomResult<ClassHeader> {
+        Ok(ClassHeader {
+            major: self.class.major(),
+            minor: self.class.minor(),
+            access_flags: self.class.access_flags(),
+            name: self.class.this_class()?.into_owned(),
+            super_class: self.class.super_class()?.into_owned(),
+            interfaces: self
+                .class
+                .interfaces()
+                .map(|x| x.map(|x| x.into_owned()))
+                .collect::<JomResult<Vec<_>>>()?,
+        })
+    }
+
+    /// Visits the constant pool, the header, the class's annotations and attributes, its
+    /// fields and then its methods.
+    pub fn accept(&self, visitor: &mut dyn ClassVisitor) -> JomResult<()> {
+        visitor.visit_constant_pool(&self.class)?;
+        visitor.visit(&self.header()?)?;
+
+        for attribute in self.class.attributes() {
+            let AttributeRef { name, info } = attribute?;
+            match annotations_visibility(&name) {
+                Some(visible) => self.annotations(info, visible, visitor)?,
+                None => visitor.visit_attribute(&name, info)?,
+            }
+        }
+
+        for member in self.class.fields() {
+            let (name, descriptor) = (member.name()?, member.descriptor()?);
+            let Some(mut field) = visitor.visit_field(member.access_flags(), &name, &descriptor)?
+            else {
+                continue;
+            };
+
+            for attribute in member.attributes() {
+                let AttributeRef { name, info } = attribute?;
+                match annotations_visibility(&name) {
+                    Some(visible) => self.annotations(info, visible, field.as_mut())?,
+                    None => field.visit_attribute(&name, info)?,
+                }
+            }
+            field.visit_end()?;
+        }
+
+        for member in self.class.methods() {
+            let (name, descriptor) = (member.name()?, member.descriptor()?);
+            let Some(mut method) =
+                visitor.visit_method(member.access_flags(), &name, &descriptor)?
+            else {
+                continue;
+            };
+
+            for attribute in member.attributes() {
+                let AttributeRef { name, info } = attribute?;
+                match annotations_visibility(&name) {
+                    Some(visible) => self.annotations(info, visible, method.as_mut())?,
+                    None if name == "Code" => {}
+                    None => method.visit_attribute(&name, info)?,
+                }
+            }
+            if let Some(code) = member.code()? {
+                method.visit_code(code.max_stack, code.max_locals)?;
+                for instruction in code.instructions() {
+                    let (offset, instruction) = instruction?;
+                    method.visit_instruction(offset, &instruction)?;
+                }
+                for exception in code.exception_table() {
+                    method.visit_exception(&exception?)?;
+                }
+                for attribute in code.attributes() {
+                    let AttributeRef { name, info } = attribute?;
+                    method.visit_code_attribute(&name, info)?;
+                }
+            }
+            method.visit_end()?;
+        }
+
+        visitor.visit_end()
+    }
+
+    /// Reads the annotations of a `RuntimeVisibleAnnotations` or `RuntimeInvisibleAnnotations`
+    /// attribute.
+    fn annotations(
+        &self,
+        info: &[u8],
+        visible: bool,
+        target: &mut (impl Annotated + ?Sized),
+    ) -> JomResult<()> {
+        let mut reader = Reader::new(info, 0);
+
+        for _ in 0..reader.u16()? {
+            let descriptor = self.class.get_utf8(reader.u16()?)?;
+            let visitor = target.annotation(&descriptor, visible)?;
+            self.annotation(&mut reader, visitor)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the element values of an annotation, skipping them if there is no visitor.
+    fn annotation(
+        &self,
+        reader: &mut Reader,
+        mut visitor: Option<Box<dyn AnnotationVisitor + '_>>,
+    ) -> JomResult<()> {
+        for _ in 0..reader.u16()? {
+            let name = self.class.get_utf8(reader.u16()?)?;
+            self.element_value(reader, &name, visitor.as_deref_mut())?;
+        }
+
+        match visitor {
+            Some(mut visitor) => visitor.visit_end(),
+            None => Ok(()),
+        }
+    }
+
+    fn element_value(
+        &self,
+        reader: &mut Reader,
+        name: &str,
+        visitor: Option<&mut (dyn AnnotationVisitor + '_)>,
+    ) -> JomResult<()> {
+        let tag = reader.u8()?;
+        let cp = &self.class;
+
+        match tag {
+            b'e' => {
+                let descriptor = cp.get_utf8(reader.u16()?)?;
+                let value = cp.get_utf8(reader.u16()?)?;
+                if let Some(visitor) = visitor {
+                    visitor.visit_enum(name, &descriptor, &value)?;
+                }
+            }
+            b'@' => {
+                let descriptor = cp.get_utf8(reader.u16()?)?;
+                let nested = match visitor {
+                    Some(visitor) => visitor.visit_annotation(name, &descriptor)?,
+                    None => None,
+                };
+                self.annotation(reader, nested)?;
+            }
+            b'[' => {
+                let mut array = match visitor {
+                    Some(visitor) => visitor.visit_array(name)?,
+                    None => None,
+                };
+                for _ in 0..reader.u16()? {
+                    self.element_value(reader, "", array.as_deref_mut())?;
+                }
+                if let Some(mut array) = array {
+                    array.visit_end()?;
+                }
+            }
+            _ => {
+                let index = cp.get(reader.u16()?)?;
+                let value = match (tag, index) {
+                    (b'B', ConstantRef::Integer(x)) => AnnotationValue::Byte(x as i8),
+                    (b'C', ConstantRef::Integer(x)) => AnnotationValue::Char(x as u16),
+                    (b'D', ConstantRef::Double(x)) => AnnotationValue::Double(x),
+                    (b'F', ConstantRef::Float(x)) => AnnotationValue::Float(x),
+                    (b'I', ConstantRef::Integer(x)) => AnnotationValue::Int(x),
+                    (b'J', ConstantRef::Long(x)) => AnnotationValue::Long(x),
+                    (b'S', ConstantRef::Integer(x)) => AnnotationValue::Short(x as i16),
+                    (b'Z', ConstantRef::Integer(x)) => AnnotationValue::Boolean(x != 0),
+                    (b's', ConstantRef::Utf8(x)) => AnnotationValue::String(x.into_owned()),
+                    (b'c', ConstantRef::Utf8(x)) => AnnotationValue::Class(x.into_owned()),
+                    (_, index) => {
+                        return Err(JomError::new_cp_index(
+                            "element value",
+                            index.into_owned().name(),
+                        ))
+                    }
+                };
+                if let Some(visitor) = visitor {
+                    visitor.visit(name, &value)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether an attribute holds visible or invisible annotations, or `None` if it holds none.
+fn annotations_visibility(name: &str) -> Option<bool> {
+    match name {
+        "RuntimeVisibleAnnotations" => Some(true),
+        "RuntimeInvisibleAnnotations" => Some(false),
+        _ => None,
+    }
+}
+
+/// The visitors with annotations.
+trait Annotated {
+    fn annotation(
+        &mut self,
+        descriptor: &str,
+        visible: bool,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>>;
+}
+
+impl Annotated for dyn ClassVisitor + '_ {
+    fn annotation(
+        &mut self,
+        descriptor: &str,
+        visible: bool,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        self.visit_annotation(descriptor, visible)
+    }
+}
+
+impl Annotated for dyn FieldVisitor + '_ {
+    fn annotation(
+        &mut self,
+        descriptor: &str,
+        visible: bool,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        self.visit_annotation(descriptor, visible)
+    }
+}
+
+impl Annotated for dyn MethodVisitor + '_ {
+    fn annotation(
+        &mut self,
+        descriptor: &str,
+        visible: bool,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        self.visit_annotation(descriptor, visible)
+    }
+}
@@ -0,0 +1,583 @@
+use std::io::Cursor;
+
+use binrw::BinWrite;
+
+use crate::{
+    class_ref::ClassFileRef,
+    constant_pool::{ConstantPool, ConstantPoolIndex},
+    error::{JomError, JomResult},
+    method::code::{instruction::Instruction, Exception},
+    walker::{self, Reference, Visitor},
+};
+
+use super::{
+    AnnotationValue, AnnotationVisitor, ClassHeader, ClassReader, ClassVisitor, FieldVisitor,
+    MethodVisitor,
+};
+
+/// Encodes the class it visits. Fields and methods are added when their visitor's
+/// `visit_end` is called, as are nested annotations.
+///
+/// Annotations attributes are written after the other attributes, so classes that have
+/// them elsewhere don't round-trip byte for byte.
+pub struct ClassWriter {
+    constant_pool: ConstantPool,
+    /// Whether the indices in visited instructions and raw attributes refer to
+    /// `constant_pool`, which they don't before a pool is visited.
+    indexed: bool,
+    major: u16,
+    minor: u16,
+    access_flags: u16,
+    this_class: u16,
+    super_class: u16,
+    interfaces: Vec<u16>,
+    fields: Table,
+    methods: Table,
+    attributes: Table,
+    annotations: Annotations,
+}
+
+impl ClassWriter {
+    /// A writer with an empty constant pool, which starts from the pool given to
+    /// `visit_constant_pool`. Instructions and raw attributes with constant pool indices
+    /// are rejected until then.
+    pub fn new() -> Self {
+        Self::with_constant_pool(ConstantPool(vec![ConstantPoolIndex::Unusable]), false)
+    }
+
+    /// A writer starting from the reader's constant pool, so the raw attributes and
+    /// instructions it visits keep referring to the right entries.
+    pub fn from_reader(reader: &ClassReader) -> JomResult<Self> {
+        Ok(Self::with_constant_pool(
+            reader.class().constant_pool()?,
+            true,
+        ))
+    }
+
+    fn with_constant_pool(constant_pool: ConstantPool, indexed: bool) -> Self {
+        Self {
+            constant_pool,
+            indexed,
+            major: 0,
+            minor: 0,
+            access_flags: 0,
+            this_class: 0,
+            super_class: 0,
+            interfaces: vec![],
+            fields: Table::default(),
+            methods: Table::default(),
+            attributes: Table::default(),
+            annotations: Annotations::default(),
+        }
+    }
+
+    pub fn into_bytes(mut self) -> JomResult<Vec<u8>> {
+        self.annotations
+            .write(&mut self.constant_pool, &mut self.attributes)?;
+        let constant_pool = self.constant_pool.encode()?;
+
+        let mut cursor = Cursor::new(vec![]);
+        (
+            0xCAFEBABEu32,
+            self.minor,
+            self.major,
+            constant_pool.len() as u16,
+        )
+            .write_be(&mut cursor)?;
+        constant_pool.write_be(&mut cursor)?;
+        (
+            self.access_flags,
+            self.this_class,
+            self.super_class,
+            self.interfaces.len() as u16,
+        )
+            .write_be(&mut cursor)?;
+        self.interfaces.write_be(&mut cursor)?;
+
+        let mut data = cursor.into_inner();
+        for table in [&self.fields, &self.methods, &self.attributes] {
+            table.write(&mut data);
+        }
+
+        Ok(data)
+    }
+}
+
+impl Default for ClassWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClassVisitor for ClassWriter {
+    /// Starts from `constant_pool`, unless the writer already has one.
+    fn visit_constant_pool(&mut self, class: &ClassFileRef) -> JomResult<()> {
+        if self.indexed {
+            return Ok(());
+        }
+        if self.constant_pool.len() > 1 {
+            return Err(JomError::invalid_class(
+                "the constant pool must be visited before anything that uses it",
+            ));
+        }
+        self.constant_pool = class.constant_pool()?;
+        self.indexed = true;
+
+        Ok(())
+    }
+
+    fn visit(&mut self, header: &ClassHeader) -> JomResult<()> {
+        let cp = &mut self.constant_pool;
+
+        self.major = header.major;
+        self.minor = header.minor;
+        self.access_flags = header.access_flags;
+        self.this_class = cp.insert_class(header.name.clone())?;
+        // Only `java/lang/Object` and modules have no super class.
+        self.super_class = match header.super_class.as_str() {
+            "" => 0,
+            x => cp.insert_class(x.to_owned())?,
+        };
+        self.interfaces = header
+            .interfaces
+            .iter()
+            .map(|x| cp.insert_class(x.clone()))
+            .collect::<JomResult<Vec<_>>>()?;
+
+        Ok(())
+    }
+
+    fn visit_annotation(
+        &mut self,
+        descriptor: &str,
+        visible: bool,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        self.annotations
+            .add(&mut self.constant_pool, descriptor, visible)
+            .map(Some)
+    }
+
+    fn visit_attribute(&mut self, name: &str, info: &[u8]) -> JomResult<()> {
+        check_attribute(self.indexed, name, info)?;
+        self.attributes.add(&mut self.constant_pool, name, info)
+    }
+
+    fn visit_field(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+    ) -> JomResult<Option<Box<dyn FieldVisitor + '_>>> {
+        let member = Member::new(&mut self.constant_pool, access_flags, name, descriptor)?;
+
+        Ok(Some(Box::new(FieldWriter {
+            constant_pool: &mut self.constant_pool,
+            indexed: self.indexed,
+            fields: &mut self.fields,
+            member,
+        })))
+    }
+
+    fn visit_method(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+    ) -> JomResult<Option<Box<dyn MethodVisitor + '_>>> {
+        let member = Member::new(&mut self.constant_pool, access_flags, name, descriptor)?;
+
+        Ok(Some(Box::new(MethodWriter {
+            constant_pool: &mut self.constant_pool,
+            indexed: self.indexed,
+            methods: &mut self.methods,
+            member,
+            code: None,
+        })))
+    }
+}
+
+/// Fails if the info of a raw attribute has constant pool indices, unless they are
+/// `indexed`. Attributes with an unknown layout are passed on as they are.
+fn check_attribute(indexed: bool, name: &str, info: &[u8]) -> JomResult<()> {
+    if !indexed {
+        walker::walk(name, &mut info.to_vec(), &mut Unindexed)?;
+    }
+
+    Ok(())
+}
+
+fn unindexed(index: u16) -> JomError {
+    JomError::invalid_class(format!(
+        "constant pool index {index} was visited before the constant pool"
+    ))
+}
+
+/// Rejects every index in an attribute.
+struct Unindexed;
+
+static EMPTY: ConstantPool = ConstantPool(vec![]);
+
+impl Visitor for Unindexed {
+    fn constant_pool(&self) -> &ConstantPool {
+        &EMPTY
+    }
+
+    fn index(&mut self, index: u16, _reference: Reference) -> JomResult<u16> {
+        Err(unindexed(index))
+    }
+}
+
+/// An encoded field, method, attribute or annotation table, without its count.
+#[derive(Default)]
+struct Table {
+    count: u16,
+    data: Vec<u8>,
+}
+
+impl Table {
+    fn add(&mut self, cp: &mut ConstantPool, name: &str, info: &[u8]) -> JomResult<()> {
+        self.count += 1;
+        push_u16(&mut self.data, cp.insert_utf8(name.to_owned())?);
+        self.data.extend((info.len() as u32).to_be_bytes());
+        self.data.extend(info);
+
+        Ok(())
+    }
+
+    fn write(&self, data: &mut Vec<u8>) {
+        push_u16(data, self.count);
+        data.extend(&self.data);
+    }
+}
+
+#[derive(Default)]
+struct Annotations {
+    visible: Table,
+    invisible: Table,
+}
+
+impl Annotations {
+    fn add<'a>(
+        &'a mut self,
+        cp: &'a mut ConstantPool,
+        descriptor: &str,
+        visible: bool,
+    ) -> JomResult<Box<dyn AnnotationVisitor + 'a>> {
+        let table = match visible {
+            true => &mut self.visible,
+            false => &mut self.invisible,
+        };
+        table.count += 1;
+        push_u16(&mut table.data, cp.insert_utf8(descriptor.to_owned())?);
+
+        Ok(Box::new(AnnotationWriter::new(cp, &mut table.data, true)))
+    }
+
+    /// Adds the annotations attributes, if there are any annotations.
+    fn write(&self, cp: &mut ConstantPool, attributes: &mut Table) -> JomResult<()> {
+        for (name, table) in [
+            ("RuntimeVisibleAnnotations", &self.visible),
+            ("RuntimeInvisibleAnnotations", &self.invisible),
+        ] {
+            if table.count > 0 {
+                let mut info = vec![];
+                table.write(&mut info);
+                attributes.add(cp, name, &info)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes element values, patching their count in once it is known.
+struct AnnotationWriter<'a> {
+    constant_pool: &'a mut ConstantPool,
+    data: &'a mut Vec<u8>,
+    count_position: usize,
+    count: u16,
+    /// Whether the values are named, as in annotations but not in arrays.
+    named: bool,
+}
+
+impl<'a> AnnotationWriter<'a> {
+    fn new(constant_pool: &'a mut ConstantPool, data: &'a mut Vec<u8>, named: bool) -> Self {
+        let count_position = data.len();
+        push_u16(data, 0);
+
+        Self {
+            constant_pool,
+            data,
+            count_position,
+            count: 0,
+            named,
+        }
+    }
+
+    fn element(&mut self, name: &str, tag: u8) -> JomResult<()> {
+        self.count += 1;
+        if self.named {
+            let name = self.constant_pool.insert_utf8(name.to_owned())?;
+            push_u16(self.data, name);
+        }
+        self.data.push(tag);
+
+        Ok(())
+    }
+
+    fn utf8(&mut self, s: &str) -> JomResult<()> {
+        let index = self.constant_pool.insert_utf8(s.to_owned())?;
+        push_u16(self.data, index);
+
+        Ok(())
+    }
+}
+
+impl AnnotationVisitor for AnnotationWriter<'_> {
+    fn visit(&mut self, name: &str, value: &AnnotationValue) -> JomResult<()> {
+        let (tag, index) = match value {
+            AnnotationValue::Byte(x) => (b'B', ConstantPoolIndex::Integer(*x as i32)),
+            AnnotationValue::Char(x) => (b'C', ConstantPoolIndex::Integer(*x as i32)),
+            AnnotationValue::Double(x) => (b'D', ConstantPoolIndex::Double(*x)),
+            AnnotationValue::Float(x) => (b'F', ConstantPoolIndex::Float(*x)),
+            AnnotationValue::Int(x) => (b'I', ConstantPoolIndex::Integer(*x)),
+            AnnotationValue::Long(x) => (b'J', ConstantPoolIndex::Long(*x)),
+            AnnotationValue::Short(x) => (b'S', ConstantPoolIndex::Integer(*x as i32)),
+            AnnotationValue::Boolean(x) => (b'Z', ConstantPoolIndex::Integer(*x as i32)),
+            AnnotationValue::String(x) => (b's', ConstantPoolIndex::Utf8(x.clone())),
+            AnnotationValue::Class(x) => (b'c', ConstantPoolIndex::Utf8(x.clone())),
+        };
+
+        self.element(name, tag)?;
+        let index = self.constant_pool.insert(index)?;
+        push_u16(self.data, index);
+
+        Ok(())
+    }
+
+    fn visit_enum(&mut self, name: &str, descriptor: &str, value: &str) -> JomResult<()> {
+        self.element(name, b'e')?;
+        self.utf8(descriptor)?;
+        self.utf8(value)
+    }
+
+    fn visit_annotation(
+        &mut self,
+        name: &str,
+        descriptor: &str,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        self.element(name, b'@')?;
+        self.utf8(descriptor)?;
+
+        Ok(Some(Box::new(AnnotationWriter::new(
+            self.constant_pool,
+            self.data,
+            true,
+        ))))
+    }
+
+    fn visit_array(&mut self, name: &str) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        self.element(name, b'[')?;
+
+        Ok(Some(Box::new(AnnotationWriter::new(
+            self.constant_pool,
+            self.data,
+            false,
+        ))))
+    }
+
+    fn visit_end(&mut self) -> JomResult<()> {
+        let position = self.count_position;
+        self.data[position..position + 2].copy_from_slice(&self.count.to_be_bytes());
+
+        Ok(())
+    }
+}
+
+/// The parts of a field or method that are common to both.
+struct Member {
+    access_flags: u16,
+    name: u16,
+    descriptor: u16,
+    attributes: Table,
+    annotations: Annotations,
+}
+
+impl Member {
+    fn new(
+        cp: &mut ConstantPool,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+    ) -> JomResult<Self> {
+        Ok(Self {
+            access_flags,
+            name: cp.insert_utf8(name.to_owned())?,
+            descriptor: cp.insert_utf8(descriptor.to_owned())?,
+            attributes: Table::default(),
+            annotations: Annotations::default(),
+        })
+    }
+
+    /// Adds the member to its table, after the attributes in `first`.
+    fn write(&self, cp: &mut ConstantPool, members: &mut Table, first: Table) -> JomResult<()> {
+        let mut attributes = first;
+        attributes.count += self.attributes.count;
+        attributes.data.extend(&self.attributes.data);
+        self.annotations.write(cp, &mut attributes)?;
+
+        members.count += 1;
+        for x in [self.access_flags, self.name, self.descriptor] {
+            push_u16(&mut members.data, x);
+        }
+        attributes.write(&mut members.data);
+
+        Ok(())
+    }
+}
+
+struct FieldWriter<'a> {
+    constant_pool: &'a mut ConstantPool,
+    indexed: bool,
+    fields: &'a mut Table,
+    member: Member,
+}
+
+impl FieldVisitor for FieldWriter<'_> {
+    fn visit_annotation(
+        &mut self,
+        descriptor: &str,
+        visible: bool,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        self.member
+            .annotations
+            .add(self.constant_pool, descriptor, visible)
+            .map(Some)
+    }
+
+    fn visit_attribute(&mut self, name: &str, info: &[u8]) -> JomResult<()> {
+        check_attribute(self.indexed, name, info)?;
+        self.member.attributes.add(self.constant_pool, name, info)
+    }
+
+    fn visit_end(&mut self) -> JomResult<()> {
+        self.member
+            .write(self.constant_pool, self.fields, Table::default())
+    }
+}
+
+struct MethodWriter<'a> {
+    constant_pool: &'a mut ConstantPool,
+    indexed: bool,
+    methods: &'a mut Table,
+    member: Member,
+    code: Option<CodeWriter>,
+}
+
+#[derive(Default)]
+struct CodeWriter {
+    max_stack: u16,
+    max_locals: u16,
+    /// Switch padding depends on the offset, so the instructions get a buffer of their own.
+    code: Cursor<Vec<u8>>,
+    exception_table: Vec<u8>,
+    exception_count: u16,
+    attributes: Table,
+}
+
+impl MethodWriter<'_> {
+    fn code(&mut self) -> &mut CodeWriter {
+        self.code.get_or_insert_with(CodeWriter::default)
+    }
+}
+
+impl MethodVisitor for MethodWriter<'_> {
+    fn visit_annotation(
+        &mut self,
+        descriptor: &str,
+        visible: bool,
+    ) -> JomResult<Option<Box<dyn AnnotationVisitor + '_>>> {
+        self.member
+            .annotations
+            .add(self.constant_pool, descriptor, visible)
+            .map(Some)
+    }
+
+    fn visit_attribute(&mut self, name: &str, info: &[u8]) -> JomResult<()> {
+        check_attribute(self.indexed, name, info)?;
+        self.member.attributes.add(self.constant_pool, name, info)
+    }
+
+    fn visit_code(&mut self, max_stack: u16, max_locals: u16) -> JomResult<()> {
+        let code = self.code();
+        code.max_stack = max_stack;
+        code.max_locals = max_locals;
+
+        Ok(())
+    }
+
+    fn visit_instruction(&mut self, _offset: u32, instruction: &Instruction) -> JomResult<()> {
+        match instruction.constant_pool_index() {
+            Some(index) if !self.indexed => return Err(unindexed(index)),
+            _ => {}
+        }
+        instruction.write(&mut self.code().code)?;
+
+        Ok(())
+    }
+
+    fn visit_exception(&mut self, exception: &Exception) -> JomResult<()> {
+        // A `catch_type` of zero catches every exception and is used to implement `finally`.
+        let catch_type = match &exception.catch_type {
+            None => 0,
+            Some(x) => self.constant_pool.insert_class(x.clone())?,
+        };
+
+        let code = self.code();
+        code.exception_count += 1;
+        for x in [
+            exception.start_pc,
+            exception.end_pc,
+            exception.handler_pc,
+            catch_type,
+        ] {
+            push_u16(&mut code.exception_table, x);
+        }
+
+        Ok(())
+    }
+
+    fn visit_code_attribute(&mut self, name: &str, info: &[u8]) -> JomResult<()> {
+        check_attribute(self.indexed, name, info)?;
+        let cp = &mut *self.constant_pool;
+        self.code
+            .get_or_insert_with(CodeWriter::default)
+            .attributes
+            .add(cp, name, info)
+    }
+
+    fn visit_end(&mut self) -> JomResult<()> {
+        // `Code` comes first, as javac writes it.
+        let mut first = Table::default();
+        if let Some(code) = self.code.take() {
+            let instructions = code.code.into_inner();
+
+            let mut info = vec![];
+            push_u16(&mut info, code.max_stack);
+            push_u16(&mut info, code.max_locals);
+            info.extend((instructions.len() as u32).to_be_bytes());
+            info.extend(instructions);
+            push_u16(&mut info, code.exception_count);
+            info.extend(code.exception_table);
+            code.attributes.write(&mut info);
+
+            first.add(self.constant_pool, "Code", &info)?;
+        }
+
+        self.member.write(self.constant_pool, self.methods, first)
+    }
+}
+
+fn push_u16(data: &mut Vec<u8>, x: u16) {
+    data.extend(x.to_be_bytes());
+}
@@ -0,0 +1,523 @@
+//! Walking the constant pool indices and code offsets in attributes kept as raw bytes.
+//!
+//! The walker knows the layout of the standard attributes, and hands every index it finds
+//! to a [`Visitor`] together with what the index refers to, so that the visitor can read it
+//! or point it to another entry.
+
+use std::io::Cursor;
+
+use binrw::{BinRead, BinWrite};
+
+use crate::{
+    constant_pool::ConstantPool,
+    error::{JomError, JomResult},
+};
+
+/// What a constant pool index in a raw attribute refers to.
+pub(crate) enum Reference<'a> {
+    /// An entry of any kind, like a constant value, a bootstrap method or a module.
+    Constant,
+    Class,
+    /// A `Utf8` descriptor or generic signature.
+    Signature,
+    /// Any other `Utf8` entry, like an attribute name or a string annotation value.
+    Utf8,
+    /// The `NameAndType` of the method enclosing a local class, which is a method of
+    /// `class`.
+    EnclosingMethod {
+        class: &'a str,
+    },
+    /// The `Utf8` simple name of the class `inner`, declared in `outer`.
+    InnerName {
+        inner: Option<&'a str>,
+        outer: Option<&'a str>,
+    },
+    /// The `Utf8` name of a record component, which is also the name of a field with
+    /// `descriptor` in the class of the attribute.
+    RecordComponent {
+        descriptor: &'a str,
+    },
+    /// The `Utf8` name of an enum constant, a field of the enum type `descriptor`.
+    EnumConstant {
+        descriptor: &'a str,
+    },
+    /// The `Utf8` name of an annotation element, a method of the annotation type
+    /// `annotation`. `value` is the descriptor of its value, unless that is an empty array.
+    ElementName {
+        annotation: &'a str,
+        value: Option<&'a str>,
+    },
+    /// The argument at `position` of the bootstrap method `method`.
+    BootstrapArgument {
+        method: u16,
+        arguments: &'a [u16],
+        position: usize,
+    },
+}
+
+pub(crate) trait Visitor {
+    /// The pool the indices refer to, for the names of nested attributes and the
+    /// descriptors that the meaning of other indices depends on.
+    fn constant_pool(&self) -> &ConstantPool;
+
+    /// Visits an index other than 0, which stands for no entry, returning the index to
+    /// write in its place.
+    fn index(&mut self, index: u16, reference: Reference) -> JomResult<u16>;
+
+    /// Where the instruction at `pc` moves to, or `None` if it cannot be moved.
+    fn pc(&self, pc: u32) -> Option<u32> {
+        Some(pc)
+    }
+}
+
+/// Walks the info of the attribute called `name`. Returns `false` if the layout of the
+/// attribute, or of one nested in it, is unknown, or if a code offset could not be moved.
+pub(crate) fn walk(name: &str, info: &mut [u8], visitor: &mut impl Visitor) -> JomResult<bool> {
+    let mut walker = Walker {
+        cursor: Cursor::new(info),
+        visitor,
+        known: true,
+    };
+    walker.attribute(name)?;
+
+    Ok(walker.known)
+}
+
+struct Walker<'a, V> {
+    cursor: Cursor<&'a mut [u8]>,
+    visitor: &'a mut V,
+    known: bool,
+}
+
+impl<V: Visitor> Walker<'_, V> {
+    fn u8(&mut self) -> JomResult<u8> {
+        Ok(<u8 as BinRead>::read_be(&mut self.cursor)?)
+    }
+
+    fn u16(&mut self) -> JomResult<u16> {
+        Ok(<u16 as BinRead>::read_be(&mut self.cursor)?)
+    }
+
+    fn skip(&mut self, count: u64) {
+        self.cursor.set_position(self.cursor.position() + count);
+    }
+
+    /// Overwrites the `u16` that ends at the current position.
+    fn rewrite(&mut self, value: u16) -> JomResult<()> {
+        self.cursor.set_position(self.cursor.position() - 2);
+        Ok(value.write_be(&mut self.cursor)?)
+    }
+
+    /// Visits the next index, returning the old one.
+    fn index(&mut self, reference: Reference) -> JomResult<u16> {
+        let index = self.u16()?;
+        if index != 0 {
+            let mapped = self.visitor.index(index, reference)?;
+            if mapped != index {
+                self.rewrite(mapped)?;
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Visits a table of indices with a `u16` length.
+    fn indices(&mut self, reference: impl Fn() -> Reference<'static>) -> JomResult<()> {
+        for _ in 0..self.u16()? {
+            self.index(reference())?;
+        }
+
+        Ok(())
+    }
+
+    fn utf8(&self, index: u16) -> JomResult<String> {
+        self.visitor.constant_pool().get_utf8(index)
+    }
+
+    fn class(&self, index: u16) -> JomResult<Option<String>> {
+        match index {
+            0 => Ok(None),
+            x => self.visitor.constant_pool().get_class(x).map(Some),
+        }
+    }
+
+    /// Moves the next offset in the code.
+    fn pc(&mut self) -> JomResult<()> {
+        let pc = self.u16()?;
+        match self.visitor.pc(pc as u32) {
+            Some(x) if x != pc as u32 => self.rewrite(x as u16)?,
+            Some(_) => {}
+            None => self.known = false,
+        }
+
+        Ok(())
+    }
+
+    /// Moves the next range of the code, given by its start and length.
+    fn range(&mut self) -> JomResult<()> {
+        let start = self.u16()? as u32;
+        let length = self.u16()? as u32;
+        match (self.visitor.pc(start), self.visitor.pc(start + length)) {
+            (Some(x), Some(y)) => {
+                self.cursor.set_position(self.cursor.position() - 4);
+                (x as u16).write_be(&mut self.cursor)?;
+                ((y - x) as u16).write_be(&mut self.cursor)?;
+            }
+            _ => self.known = false,
+        }
+
+        Ok(())
+    }
+
+    fn attribute(&mut self, name: &str) -> JomResult<()> {
+        match name {
+            "Synthetic" | "Deprecated" | "SourceDebugExtension" => {}
+            "ConstantValue" => {
+                self.index(Reference::Constant)?;
+            }
+            "Signature" => {
+                self.index(Reference::Signature)?;
+            }
+            "SourceFile" => {
+                self.index(Reference::Utf8)?;
+            }
+            "NestHost" | "ModuleMainClass" => {
+                self.index(Reference::Class)?;
+            }
+            "Exceptions" | "NestMembers" | "PermittedSubclasses" => {
+                self.indices(|| Reference::Class)?
+            }
+            "ModulePackages" => self.indices(|| Reference::Constant)?,
+            "EnclosingMethod" => {
+                let index = self.index(Reference::Class)?;
+                let class = self.class(index)?.unwrap_or_default();
+                self.index(Reference::EnclosingMethod { class: &class })?;
+            }
+            "InnerClasses" => {
+                for _ in 0..self.u16()? {
+                    let inner = self.index(Reference::Class)?;
+                    let outer = self.index(Reference::Class)?;
+                    let (inner, outer) = (self.class(inner)?, self.class(outer)?);
+                    self.index(Reference::InnerName {
+                        inner: inner.as_deref(),
+                        outer: outer.as_deref(),
+                    })?;
+                    self.skip(2);
+                }
+            }
+            "LineNumberTable" => {
+                for _ in 0..self.u16()? {
+                    self.pc()?;
+                    self.skip(2);
+                }
+            }
+            "LocalVariableTable" | "LocalVariableTypeTable" => {
+                for _ in 0..self.u16()? {
+                    self.range()?;
+                    self.index(Reference::Utf8)?;
+                    self.index(Reference::Signature)?;
+                    self.skip(2);
+                }
+            }
+            "MethodParameters" => {
+                for _ in 0..self.u8()? {
+                    self.index(Reference::Utf8)?;
+                    self.skip(2);
+                }
+            }
+            "BootstrapMethods" => {
+                for _ in 0..self.u16()? {
+                    self.bootstrap_method()?;
+                }
+            }
+            "StackMapTable" => self.stack_map_table()?,
+            "Module" => self.module()?,
+            "Record" => {
+                for _ in 0..self.u16()? {
+                    // The name is visited after the descriptor it depends on.
+                    let position = self.cursor.position();
+                    self.skip(2);
+                    let descriptor = self.index(Reference::Signature)?;
+                    let descriptor = self.utf8(descriptor)?;
+                    let end = self.cursor.position();
+                    self.cursor.set_position(position);
+                    self.index(Reference::RecordComponent {
+                        descriptor: &descriptor,
+                    })?;
+                    self.cursor.set_position(end);
+
+                    self.attributes()?;
+                }
+            }
+            "RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations" => {
+                for _ in 0..self.u16()? {
+                    self.annotation()?;
+                }
+            }
+            "RuntimeVisibleParameterAnnotations" | "RuntimeInvisibleParameterAnnotations" => {
+                for _ in 0..self.u8()? {
+                    for _ in 0..self.u16()? {
+                        self.annotation()?;
+                    }
+                }
+            }
+            "RuntimeVisibleTypeAnnotations" | "RuntimeInvisibleTypeAnnotations" => {
+                for _ in 0..self.u16()? {
+                    self.type_annotation()?;
+                }
+            }
+            "AnnotationDefault" => {
+                self.element_value()?;
+            }
+            _ => self.known = false,
+        }
+
+        Ok(())
+    }
+
+    /// Walks a table of nested attributes, like those of record components.
+    fn attributes(&mut self) -> JomResult<()> {
+        for _ in 0..self.u16()? {
+            let name = self.index(Reference::Utf8)?;
+            let name = self.utf8(name)?;
+            let length = <u32 as BinRead>::read_be(&mut self.cursor)? as u64;
+            let start = self.cursor.position() as usize;
+            // An attribute that runs past the end is walked as far as it goes, and the
+            // read after it fails.
+            let data = &mut **self.cursor.get_mut();
+            let end = (start + length as usize).min(data.len());
+
+            let mut walker = Walker {
+                cursor: Cursor::new(&mut data[start.min(end)..end]),
+                visitor: &mut *self.visitor,
+                known: true,
+            };
+            walker.attribute(&name)?;
+            self.known &= walker.known;
+
+            self.skip(length);
+        }
+
+        Ok(())
+    }
+
+    fn bootstrap_method(&mut self) -> JomResult<()> {
+        let method = self.index(Reference::Constant)?;
+        let count = self.u16()?;
+        let start = self.cursor.position();
+        let arguments = (0..count)
+            .map(|_| self.u16())
+            .collect::<JomResult<Vec<_>>>()?;
+
+        self.cursor.set_position(start);
+        for position in 0..arguments.len() {
+            self.index(Reference::BootstrapArgument {
+                method,
+                arguments: &arguments,
+                position,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn stack_map_table(&mut self) -> JomResult<()> {
+        // The offsets of the previous frame, before and after moving the code. Each frame
+        // after the first is one past the previous one plus its delta.
+        let mut previous: Option<(u32, u32)> = None;
+        for _ in 0..self.u16()? {
+            let frame_type = self.u8()?;
+            let delta = match frame_type {
+                0..=63 => frame_type as u16,
+                64..=127 => frame_type as u16 - 64,
+                247..=255 => self.u16()?,
+                x => return Err(JomError::invalid_class(format!("invalid frame type {x}"))),
+            };
+
+            let (old, new) = previous.map_or((0, 0), |(x, y)| (x + 1, y + 1));
+            let pc = old + delta as u32;
+            match self.visitor.pc(pc).filter(|&x| x >= new) {
+                Some(x) => {
+                    let moved = (x - new) as u16;
+                    match frame_type {
+                        _ if moved == delta => {}
+                        247..=255 => self.rewrite(moved)?,
+                        // The other frames hold the delta in the low bits of their type.
+                        _ if moved < 64 => {
+                            self.cursor.set_position(self.cursor.position() - 1);
+                            (frame_type & !63 | moved as u8).write_be(&mut self.cursor)?;
+                        }
+                        _ => self.known = false,
+                    }
+                    previous = Some((pc, x));
+                }
+                None => self.known = false,
+            }
+
+            match frame_type {
+                64..=127 | 247 => self.verification_types(1)?,
+                252..=254 => self.verification_types(frame_type as u16 - 251)?,
+                255 => {
+                    let locals = self.u16()?;
+                    self.verification_types(locals)?;
+                    let stack = self.u16()?;
+                    self.verification_types(stack)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verification_types(&mut self, count: u16) -> JomResult<()> {
+        for _ in 0..count {
+            match self.u8()? {
+                0..=6 => {}
+                // `Object`, with the index of its class.
+                7 => {
+                    self.index(Reference::Class)?;
+                }
+                // `Uninitialized`, with the offset of its `new` instruction.
+                8 => self.pc()?,
+                x => {
+                    return Err(JomError::invalid_class(format!(
+                        "invalid verification type {x}"
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn module(&mut self) -> JomResult<()> {
+        // The name, flags and version.
+        self.index(Reference::Constant)?;
+        self.skip(2);
+        self.index(Reference::Utf8)?;
+
+        // `requires`
+        for _ in 0..self.u16()? {
+            self.index(Reference::Constant)?;
+            self.skip(2);
+            self.index(Reference::Utf8)?;
+        }
+        // `exports` and `opens`
+        for _ in 0..2 {
+            for _ in 0..self.u16()? {
+                self.index(Reference::Constant)?;
+                self.skip(2);
+                self.indices(|| Reference::Constant)?;
+            }
+        }
+        // `uses`
+        self.indices(|| Reference::Class)?;
+        // `provides`
+        for _ in 0..self.u16()? {
+            self.index(Reference::Class)?;
+            self.indices(|| Reference::Class)?;
+        }
+
+        Ok(())
+    }
+
+    /// Walks an annotation, returning the descriptor of its type.
+    fn annotation(&mut self) -> JomResult<String> {
+        let annotation = self.index(Reference::Signature)?;
+        let annotation = self.utf8(annotation)?;
+
+        for _ in 0..self.u16()? {
+            // The name is visited after the value, whose type it depends on.
+            let position = self.cursor.position();
+            self.skip(2);
+            let value = self.element_value()?;
+            let end = self.cursor.position();
+            self.cursor.set_position(position);
+            self.index(Reference::ElementName {
+                annotation: &annotation,
+                value: value.as_deref(),
+            })?;
+            self.cursor.set_position(end);
+        }
+
+        Ok(annotation)
+    }
+
+    /// Walks an element value, returning the descriptor of its type unless it is an empty
+    /// array.
+    fn element_value(&mut self) -> JomResult<Option<String>> {
+        let tag = self.u8()?;
+        let descriptor = match tag {
+            b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => {
+                self.index(Reference::Constant)?;
+                char::from(tag).to_string()
+            }
+            b's' => {
+                self.index(Reference::Utf8)?;
+                "Ljava/lang/String;".to_owned()
+            }
+            b'c' => {
+                self.index(Reference::Signature)?;
+                "Ljava/lang/Class;".to_owned()
+            }
+            b'e' => {
+                let descriptor = self.index(Reference::Signature)?;
+                let descriptor = self.utf8(descriptor)?;
+                self.index(Reference::EnumConstant {
+                    descriptor: &descriptor,
+                })?;
+                descriptor
+            }
+            b'@' => self.annotation()?,
+            b'[' => {
+                let mut component = None;
+                for _ in 0..self.u16()? {
+                    component = component.or(self.element_value()?);
+                }
+                return Ok(component.map(|x| format!("[{x}")));
+            }
+            x => {
+                return Err(JomError::invalid_class(format!(
+                    "invalid element value tag {:?}",
+                    char::from(x)
+                )))
+            }
+        };
+
+        Ok(Some(descriptor))
+    }
+
+    fn type_annotation(&mut self) -> JomResult<()> {
+        // The target_info, whose layout is given by the target_type.
+        match self.u8()? {
+            0x00 | 0x01 | 0x16 => self.skip(1),
+            0x10..=0x12 | 0x17 | 0x42 => self.skip(2),
+            0x13..=0x15 => {}
+            // Local variables, with the ranges of code they are live in.
+            0x40 | 0x41 => {
+                for _ in 0..self.u16()? {
+                    self.range()?;
+                    self.skip(2);
+                }
+            }
+            0x43..=0x46 => self.pc()?,
+            0x47..=0x4b => {
+                self.pc()?;
+                self.skip(1);
+            }
+            x => {
+                return Err(JomError::invalid_class(format!(
+                    "invalid type annotation target type {x:#04x}"
+                )))
+            }
+        }
+        // The type_path, of two bytes per step.
+        let length = self.u8()?;
+        self.skip(2 * length as u64);
+
+        self.annotation()?;
+
+        Ok(())
+    }
+}
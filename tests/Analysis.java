@@ -0,0 +1,42 @@
+public class Analysis {
+    static int constants() {
+        int a = 6;
+        int b = a * 7;
+        return b;
+    }
+
+    static int deadStore(int x) {
+        int unused = x + 1;
+        unused = 2;
+        return x;
+    }
+
+    static long branches(boolean flag, long value) {
+        long result;
+        if (flag) {
+            result = value * 2;
+        } else {
+            result = -value;
+        }
+        return result;
+    }
+
+    static String caught(String s) {
+        try {
+            return s.trim();
+        } catch (NullPointerException e) {
+            return "null";
+        } finally {
+            System.out.println(s);
+        }
+    }
+
+    static int switched(int x) {
+        switch (x) {
+            case 1: return 10;
+            case 2: return 20;
+            case 3: return 30;
+            default: return 0;
+        }
+    }
+}
@@ -0,0 +1,4 @@
+/// Concatenates strings in the static initializer of an interface.
+public interface Concat {
+    String GREETING = "Hello " + System.getProperty("user.name");
+}
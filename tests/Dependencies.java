@@ -0,0 +1,48 @@
+import java.io.IOException;
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+import java.nio.file.Path;
+import java.util.Iterator;
+import java.util.List;
+import java.util.Map;
+import java.util.Optional;
+import java.util.function.Supplier;
+
+class Box<V> {
+    class Item {}
+}
+
+public class Dependencies<T extends Comparable<? super T>> {
+    @Retention(RetentionPolicy.RUNTIME)
+    @Target({ElementType.METHOD, ElementType.TYPE_USE})
+    @interface Tag {
+        Class<?> value() default Void.class;
+
+        Thread.State state() default Thread.State.NEW;
+    }
+
+    record Pair(java.time.Instant first, List<java.net.URI> second) {}
+
+    private Map<String, List<java.util.regex.Pattern>> patterns;
+    private Box<String>.Item item;
+
+    @Tag(value = java.math.BigDecimal.class, state = Thread.State.BLOCKED)
+    public Optional<T> first(Iterator<T> iterator) throws IOException {
+        return iterator.hasNext() ? Optional.of(iterator.next()) : Optional.empty();
+    }
+
+    Supplier<?> handle(Runnable runnable) {
+        try {
+            runnable.run();
+        } catch (IllegalStateException e) {
+            return null;
+        }
+        return java.util.UUID::randomUUID;
+    }
+
+    List<@Tag Path> paths() {
+        return null;
+    }
+}
@@ -0,0 +1,36 @@
+public class Nest {
+    private static String prefix = "#";
+    private int count;
+
+    private Nest() {}
+
+    private String label(int n) {
+        return prefix + n + '/' + count;
+    }
+
+    private static long twice(long x) {
+        return x * 2;
+    }
+
+    public class Inner {
+        public String run() {
+            count += 1;
+            if (count > 1) {
+                prefix = "*";
+            }
+            return label(count) + " " + twice(count) + " " + 1.5f;
+        }
+    }
+
+    public static class Builder {
+        public Nest build() {
+            return new Nest();
+        }
+    }
+
+    public static void main(String[] args) {
+        Inner inner = new Builder().build().new Inner();
+        System.out.println(inner.run());
+        System.out.println(inner.run());
+    }
+}
@@ -0,0 +1,5 @@
+public class Strings {
+    static final String NUL = "a\0b";
+    static final String EMOJI = "😀";
+    static final String GREEK = "λ";
+}
@@ -0,0 +1,8 @@
+public class Surrogates {
+    static final String HIGH = "a\ud800";
+    static final String LOW = "\udc00b";
+
+    static String reversed() {
+        return "\udc00\ud800";
+    }
+}
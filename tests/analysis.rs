@@ -0,0 +1,102 @@
+use std::collections::BTreeSet;
+
+use jom::{
+    analysis::{
+        basic::{BasicInterpreter, BasicValue},
+        cfg::ControlFlowGraph,
+        constant::{ConstValue, Constant, ConstantInterpreter},
+        dataflow::LiveVariables,
+        interpreter::Interpreter,
+        source::SourceInterpreter,
+        Analyzer,
+    },
+    error::JomError,
+    method::MethodInfo,
+    ClassFile,
+};
+
+fn method<'a>(class: &'a ClassFile, name: &str) -> &'a MethodInfo {
+    class.methods().iter().find(|x| x.name == name).unwrap()
+}
+
+#[test]
+fn constant_propagation() {
+    let class = ClassFile::read(include_bytes!("Analysis.class")).unwrap();
+    let frames = Analyzer::new(ConstantInterpreter)
+        .analyze(&class, method(&class, "constants"))
+        .unwrap();
+
+    let before_return = frames[7].as_ref().unwrap();
+    assert_eq!(before_return.peek(0).unwrap().constant, Some(Constant::Int(42)));
+}
+
+#[test]
+fn signed_zero_merge() {
+    let value = |x: f64| ConstValue {
+        basic: BasicValue::Double,
+        constant: Some(Constant::Double(x)),
+    };
+
+    let merged = ConstantInterpreter.merge(&value(0.0), &value(-0.0));
+    assert_eq!(merged.constant, None);
+    let merged = ConstantInterpreter.merge(&value(f64::NAN), &value(f64::NAN));
+    assert_eq!(merged.constant, Some(Constant::Double(f64::NAN)));
+}
+
+#[test]
+fn merged_locals() {
+    let class = ClassFile::read(include_bytes!("Analysis.class")).unwrap();
+    let method = method(&class, "branches");
+
+    let frames = Analyzer::new(BasicInterpreter).analyze(&class, method).unwrap();
+    let join = frames[10].as_ref().unwrap();
+    assert_eq!(join.local(3), Some(&BasicValue::Long));
+
+    let frames = Analyzer::new(SourceInterpreter).analyze(&class, method).unwrap();
+    let join = frames[10].as_ref().unwrap();
+    assert_eq!(join.local(3).unwrap().sources, BTreeSet::from([5, 9]));
+}
+
+#[test]
+fn exception_handlers() {
+    let class = ClassFile::read(include_bytes!("Analysis.class")).unwrap();
+    let method = method(&class, "caught");
+    let code = method.code().unwrap();
+    let cfg = ControlFlowGraph::new(code).unwrap();
+
+    let frames = Analyzer::new(BasicInterpreter).analyze(&class, method).unwrap();
+    assert!(frames.iter().all(Option::is_some));
+
+    for exception in &code.exception_table {
+        let handler = cfg.index_of(exception.handler_pc as u32).unwrap();
+        assert_eq!(frames[handler].as_ref().unwrap().stack(), &[BasicValue::Reference]);
+    }
+}
+
+#[test]
+fn dead_stores() {
+    let class = ClassFile::read(include_bytes!("Analysis.class")).unwrap();
+    let code = method(&class, "deadStore").code().unwrap();
+    let cfg = ControlFlowGraph::new(code).unwrap();
+
+    assert_eq!(LiveVariables::dead_stores(code, &cfg), vec![3, 5]);
+}
+
+#[test]
+fn stack_slots() {
+    let mut class = ClassFile::read(include_bytes!("Analysis.class")).unwrap();
+    let analyzer = Analyzer::new(BasicInterpreter);
+    assert!(analyzer.analyze(&class, method(&class, "branches")).is_ok());
+
+    // Adding two longs takes four slots of the stack.
+    let branches = class
+        .methods_mut()
+        .iter_mut()
+        .find(|x| x.name == "branches")
+        .unwrap();
+    branches.code_mut().unwrap().max_stack = 3;
+    let error = analyzer
+        .analyze(&class, method(&class, "branches"))
+        .unwrap_err();
+    assert!(matches!(error.root_cause(), JomError::AnalysisError(..)));
+}
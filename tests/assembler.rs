@@ -0,0 +1,57 @@
+use jom::{
+    assembler::assemble,
+    attribute::{ConstantValue, FieldAttribute},
+    method::code::instruction::Instruction,
+    ClassFile,
+};
+
+#[test]
+fn assemble_and_write() {
+    let class = assemble(include_str!("Assembled.j")).unwrap();
+    let class = ClassFile::read(&class.write().unwrap()).unwrap();
+
+    assert_eq!(class.major(), 61);
+    assert_eq!(class.this_class(), "Assembled");
+    assert!(matches!(
+        class.fields()[0].attributes[..],
+        [FieldAttribute::ConstantValue(ConstantValue::Integer(42))]
+    ));
+
+    let main = class.methods()[1].code().unwrap();
+    assert_eq!(main.code.len(), 17);
+    assert_eq!(main.code[6], Instruction::BiPush(10));
+    assert_eq!(main.code[11], Instruction::GoTo(12));
+    assert_eq!(main.exception_table[0].start_pc, 11);
+    assert_eq!(main.exception_table[0].handler_pc, 25);
+    assert_eq!(
+        main.exception_table[0].catch_type.as_deref(),
+        Some("java/lang/ArithmeticException")
+    );
+
+    // The limits of `describe` are computed.
+    let describe = class.methods()[2].code().unwrap();
+    assert_eq!((describe.max_stack, describe.max_locals), (1, 1));
+    assert_eq!(
+        describe.code[1],
+        Instruction::TableSwitch {
+            default: 29,
+            low: 0,
+            high: 1,
+            offsets: vec![23, 26],
+        }
+    );
+}
+
+#[test]
+fn unknown_label() {
+    let source = "
+.class Broken
+.method static broken()V
+    goto nowhere
+.end method
+";
+
+    let error = assemble(source).err().unwrap();
+
+    assert_eq!(error.to_string(), "assembly error on line 4: unknown label nowhere");
+}
@@ -0,0 +1,577 @@
+use jom::{
+    access::{ACC_ABSTRACT, ACC_INTERFACE, ACC_PRIVATE, ACC_PUBLIC, ACC_STATIC, ACC_SUPER},
+    attribute::{CodeAttribute, MethodAttribute},
+    builder::ClassBuilder,
+    constant_pool::{ConstantPool, ConstantPoolIndex},
+    descriptor::MethodDescriptor,
+    error::{ErrorPath, JomError},
+    field::FieldInfo,
+    hierarchy::ClassHierarchy,
+    method::{
+        code::{
+            builder::CodeBuilder,
+            instruction::{self, Instruction},
+            Code,
+        },
+        MethodInfo,
+    },
+    version::ClassVersion,
+    ClassFile,
+};
+
+fn finish(code: CodeBuilder, constant_pool: &mut ConstantPool, descriptor: &str) -> Code {
+    let descriptor = MethodDescriptor::parse(descriptor).unwrap();
+    code.finish(constant_pool, &descriptor, true).unwrap()
+}
+
+/// `static int sum(int n)`, adding the numbers below `n` in a loop.
+fn sum() -> CodeBuilder {
+    let mut code = CodeBuilder::new();
+    let start = code.label();
+    code.line_number(3);
+    code.push_int(0);
+    code.istore(1);
+    code.push_int(0);
+    code.istore(2);
+    let condition = code.new_label();
+    code.goto(condition);
+    let body = code.label();
+    code.line_number(4);
+    code.iload(1);
+    code.iload(2);
+    code.emit(Instruction::IAdd);
+    code.istore(1);
+    code.iinc(2, 1);
+    code.place(condition);
+    code.iload(2);
+    code.iload(0);
+    code.jump(Instruction::IfICmpLt, body);
+    code.line_number(5);
+    code.iload(1);
+    code.emit(Instruction::IReturn);
+    let end = code.label();
+    code.local_variable(0, "n", "I", start, end);
+    code.local_variable(1, "total", "I", start, end);
+
+    code
+}
+
+/// `static int divide(int a, int b)`, returning -1 when dividing by zero.
+fn divide() -> CodeBuilder {
+    let mut code = CodeBuilder::new();
+    let start = code.label();
+    code.iload(0);
+    code.iload(1);
+    code.emit(Instruction::IDiv);
+    code.emit(Instruction::IReturn);
+    let end = code.label();
+    code.emit(Instruction::Pop);
+    code.push_int(-1);
+    code.emit(Instruction::IReturn);
+    code.try_catch(start, end, end, Some("java/lang/ArithmeticException"));
+
+    code
+}
+
+/// `static String name(int n)`, with both kinds of switch.
+fn name() -> CodeBuilder {
+    let mut code = CodeBuilder::new();
+    let (zero, one, many, other) = (
+        code.new_label(),
+        code.new_label(),
+        code.new_label(),
+        code.new_label(),
+    );
+    code.iload(0);
+    code.table_switch(0, vec![zero, one], other);
+    code.place(zero);
+    code.ldc(ConstantPoolIndex::String("zero".to_owned()));
+    code.emit(Instruction::AReturn);
+    code.place(one);
+    code.ldc(ConstantPoolIndex::String("one".to_owned()));
+    code.emit(Instruction::AReturn);
+    code.place(other);
+    code.iload(0);
+    code.lookup_switch(vec![(1000, many), (100, many)], many);
+    code.place(many);
+    code.ldc(ConstantPoolIndex::String("many".to_owned()));
+    code.emit(Instruction::AReturn);
+
+    code
+}
+
+/// A class with the methods and their stack map frames.
+fn class() -> ClassFile {
+    let mut class = ClassFile::new("Built".to_owned());
+    class.set_access_flags(ACC_PUBLIC);
+    for (name, descriptor, code) in [
+        ("sum", "(I)I", sum()),
+        ("divide", "(II)I", divide()),
+        ("name", "(I)Ljava/lang/String;", name()),
+    ] {
+        let code = finish(code, class.constant_pool_mut(), descriptor);
+        class.methods_mut().push(MethodInfo {
+            access_flags: ACC_PUBLIC | ACC_STATIC,
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+            attributes: vec![MethodAttribute::Code(code)],
+        });
+    }
+    class.compute_frames(&ClassHierarchy::new()).unwrap();
+
+    ClassFile::read(&class.write().unwrap()).unwrap()
+}
+
+#[test]
+fn build() {
+    use Instruction::*;
+
+    let class = class();
+    let sum = class.methods()[0].code().unwrap();
+    assert_eq!(
+        sum.code,
+        [
+            IConst0,
+            IStore1,
+            IConst0,
+            IStore2,
+            GoTo(10),
+            ILoad1,
+            ILoad2,
+            IAdd,
+            IStore1,
+            IInc(2, 1),
+            ILoad2,
+            ILoad0,
+            IfICmpLt(-9i16 as u16),
+            ILoad1,
+            IReturn,
+        ]
+    );
+    assert_eq!((sum.max_stack, sum.max_locals), (2, 3));
+    let [CodeAttribute::LineNumberTable(lines), CodeAttribute::LocalVariableTable(locals), _] =
+        &sum.attributes[..]
+    else {
+        panic!("unexpected attributes");
+    };
+    assert_eq!(
+        lines
+            .iter()
+            .map(|x| (x.start_pc, x.line_number))
+            .collect::<Vec<_>>(),
+        [(0, 3), (7, 4), (19, 5)]
+    );
+    assert_eq!((locals[1].name.as_str(), locals[1].length), ("total", 21));
+
+    let divide = class.methods()[1].code().unwrap();
+    let handler = &divide.exception_table[0];
+    assert_eq!(
+        (handler.start_pc, handler.end_pc, handler.handler_pc),
+        (0, 4, 4)
+    );
+    assert_eq!((divide.max_stack, divide.max_locals), (2, 2));
+
+    let name = class.methods()[2].code().unwrap();
+    assert_eq!(
+        name.code[1],
+        TableSwitch {
+            default: 29,
+            low: 0,
+            high: 1,
+            offsets: vec![23, 26],
+        }
+    );
+    assert!(matches!(
+        &name.code[7],
+        LookupSwitch { pairs, .. } if pairs[0].0 == 100
+    ));
+}
+
+fn stack_map_table(code: &Code) -> &[u8] {
+    match code.attributes.last() {
+        Some(CodeAttribute::Unknown(name, info)) if name == "StackMapTable" => info,
+        _ => panic!("no StackMapTable"),
+    }
+}
+
+#[test]
+fn frames() {
+    let class = class();
+
+    // An `append` of the two `int` locals at the loop body and a `same` frame at its condition.
+    let sum = class.methods()[0].code().unwrap();
+    assert_eq!(stack_map_table(sum), [0, 2, 253, 0, 7, 1, 1, 6]);
+
+    let divide = class.methods()[1].code().unwrap();
+    let exception = class
+        .constant_pool()
+        .find_class("java/lang/ArithmeticException".to_owned())
+        .unwrap()
+        .to_be_bytes();
+    assert_eq!(
+        stack_map_table(divide),
+        [0, 1, 68, 7, exception[0], exception[1]]
+    );
+
+    let name = class.methods()[2].code().unwrap();
+    assert_eq!(stack_map_table(name)[..2], [0, 4]);
+}
+
+#[test]
+fn uninitialized_frames() {
+    use Instruction::*;
+
+    // `new Example(n == 0 ? 0 : 1)`, with the new object on the stack across the branch.
+    let mut code = CodeBuilder::new();
+    let (zero, done) = (code.new_label(), code.new_label());
+    code.new_object("Example");
+    code.emit(Dup);
+    code.iload(0);
+    code.jump(IfEq, zero);
+    code.push_int(1);
+    code.goto(done);
+    code.place(zero);
+    code.push_int(0);
+    code.place(done);
+    code.invoke_special("Example", "<init>", "(I)V");
+    code.emit(AReturn);
+
+    let mut class = ClassFile::new("Example".to_owned());
+    let code = finish(code, class.constant_pool_mut(), "(I)LExample;");
+    class.methods_mut().push(MethodInfo {
+        access_flags: ACC_PUBLIC | ACC_STATIC,
+        name: "create".to_owned(),
+        descriptor: "(I)LExample;".to_owned(),
+        attributes: vec![MethodAttribute::Code(code)],
+    });
+    class.compute_frames(&ClassHierarchy::new()).unwrap();
+
+    let code = class.methods()[0].code().unwrap();
+    assert_eq!(
+        stack_map_table(code),
+        [
+            0, 2, 255, 0, 12, 0, 1, 1, 0, 2, 8, 0, 0, 8, 0, 0, 255, 0, 0, 0, 1, 1, 0, 3, 8, 0, 0,
+            8, 0, 0, 1
+        ]
+    );
+}
+
+#[test]
+fn unreachable_frame() {
+    let mut code = CodeBuilder::new();
+    let end = code.new_label();
+    code.goto(end);
+    code.emit(Instruction::Nop);
+    code.place(end);
+    code.emit(Instruction::Return);
+
+    let mut class = ClassFile::new("Example".to_owned());
+    let code = finish(code, class.constant_pool_mut(), "()V");
+    class.methods_mut().push(MethodInfo {
+        access_flags: ACC_PUBLIC | ACC_STATIC,
+        name: "skip".to_owned(),
+        descriptor: "()V".to_owned(),
+        attributes: vec![MethodAttribute::Code(code)],
+    });
+    let error = class.compute_frames(&ClassHierarchy::new()).unwrap_err();
+    assert!(matches!(error.root_cause(), JomError::InvalidCode(_)));
+    assert_eq!(
+        error.path()[0],
+        ErrorPath::Method {
+            name: "skip".to_owned(),
+            descriptor: "()V".to_owned()
+        }
+    );
+}
+
+#[test]
+fn shortest_forms() {
+    use Instruction::*;
+
+    let mut code = CodeBuilder::new();
+    for value in [5, -1, 100, -200, 40000] {
+        code.push_int(value);
+        code.emit(Pop);
+    }
+    code.aload(3);
+    code.astore(4);
+    code.lload(300);
+    code.emit(Pop2);
+    code.iinc(1, -1);
+    code.iinc(1, 1000);
+    code.emit(Return);
+
+    let mut class = ClassFile::new("Example".to_owned());
+    let code = finish(code, class.constant_pool_mut(), "()V");
+    assert_eq!(
+        code.code,
+        [
+            IConst5,
+            Pop,
+            IConstM1,
+            Pop,
+            BiPush(100),
+            Pop,
+            Sipush(-200i16 as u16),
+            Pop,
+            Ldc(class
+                .constant_pool()
+                .find(ConstantPoolIndex::Integer(40000))
+                .unwrap() as u8),
+            Pop,
+            ALoad3,
+            AStore(4),
+            Wide(instruction::Wide::LLoad(300)),
+            Pop2,
+            IInc(1, -1i8 as u8),
+            Wide(instruction::Wide::IInc(1, 1000)),
+            Return,
+        ]
+    );
+    assert_eq!(code.max_locals, 302);
+}
+
+#[test]
+fn constants() {
+    use Instruction::*;
+
+    let mut class = ClassFile::new("Example".to_owned());
+    for i in 0..300 {
+        class
+            .constant_pool_mut()
+            .insert(ConstantPoolIndex::Integer(100_000 + i))
+            .unwrap();
+    }
+    let mut code = CodeBuilder::new();
+    code.ldc(ConstantPoolIndex::Integer(100_000));
+    code.ldc(ConstantPoolIndex::Integer(100_299));
+    code.ldc(ConstantPoolIndex::Long(1));
+    code.aload(0);
+    code.invoke_interface(
+        "java/util/Map",
+        "put",
+        "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;",
+    );
+    code.emit(Return);
+    let code = finish(code, class.constant_pool_mut(), "()V");
+
+    let constant_pool = class.constant_pool();
+    let index = |x| constant_pool.find(x).unwrap();
+    assert!(matches!(code.code[0], Ldc(_)));
+    assert_eq!(
+        code.code[1],
+        LdcW(index(ConstantPoolIndex::Integer(100_299)))
+    );
+    assert_eq!(code.code[2], Ldc2W(index(ConstantPoolIndex::Long(1))));
+    assert!(matches!(code.code[4], InvokeInterface(_, 3)));
+}
+
+#[test]
+fn errors() {
+    let mut class = ClassFile::new("Example".to_owned());
+    let descriptor = MethodDescriptor::parse("()V").unwrap();
+
+    let mut code = CodeBuilder::new();
+    let label = code.new_label();
+    code.goto(label);
+    assert!(matches!(
+        code.finish(class.constant_pool_mut(), &descriptor, true),
+        Err(JomError::InvalidCode(_))
+    ));
+
+    let mut code = CodeBuilder::new();
+    for _ in 0..70000 {
+        code.emit(Instruction::Nop);
+    }
+    assert!(matches!(
+        code.finish(class.constant_pool_mut(), &descriptor, true),
+        Err(JomError::InvalidCode(_))
+    ));
+}
+
+#[test]
+fn wide_jumps() {
+    use Instruction::*;
+
+    let mut code = CodeBuilder::new();
+    let (start, end) = (code.label(), code.new_label());
+    code.iload(0);
+    code.jump(IfEq, end);
+    for _ in 0..40000 {
+        code.emit(Nop);
+    }
+    code.goto(start);
+    code.place(end);
+    code.emit(Return);
+
+    let mut class = ClassFile::new("Example".to_owned());
+    let code = finish(code, class.constant_pool_mut(), "(I)V");
+    // The `ifeq` becomes an `ifne` over a `goto_w`, which moves the `goto` out of range too.
+    assert_eq!(code.code[1..3], [IfNe(8), GotoW(40010)]);
+    assert_eq!(code.code[40003..], [GotoW(-40009i32 as u32), Return]);
+}
+
+fn greeter() -> ClassBuilder {
+    let mut class = ClassBuilder::new("Greeter");
+    class.add_interface("java/lang/Runnable");
+    class.add_field(FieldInfo {
+        access_flags: ACC_PRIVATE,
+        name: "count".to_owned(),
+        descriptor: "I".to_owned(),
+        attributes: vec![],
+    });
+    class.add_default_constructor(ACC_PUBLIC).unwrap();
+
+    let mut code = CodeBuilder::new();
+    code.aload(0);
+    code.emit(Instruction::Dup);
+    code.get_field("Greeter", "count", "I");
+    code.push_int(1);
+    code.emit(Instruction::IAdd);
+    code.put_field("Greeter", "count", "I");
+    code.get_static("java/lang/System", "out", "Ljava/io/PrintStream;");
+    code.ldc(ConstantPoolIndex::String("Hello World!".to_owned()));
+    code.invoke_virtual("java/io/PrintStream", "println", "(Ljava/lang/String;)V");
+    code.emit(Instruction::Return);
+    class
+        .add_code_method(ACC_PUBLIC, "run", "()V", code)
+        .unwrap();
+
+    class
+}
+
+#[test]
+fn build_class() {
+    use Instruction::*;
+
+    let class = greeter().finish().unwrap();
+    let class = ClassFile::read(&class.write().unwrap()).unwrap();
+    assert_eq!(
+        (class.major(), class.access_flags()),
+        (63, ACC_PUBLIC | ACC_SUPER)
+    );
+    assert_eq!(class.this_class(), "Greeter");
+    assert_eq!(class.super_class(), "java/lang/Object");
+    assert_eq!(class.interfaces(), ["java/lang/Runnable"]);
+    assert_eq!(class.fields()[0].name, "count");
+
+    let constructor = &class.methods()[0];
+    assert_eq!(
+        (constructor.name.as_str(), constructor.descriptor.as_str()),
+        ("<init>", "()V")
+    );
+    let code = constructor.code().unwrap();
+    assert_eq!(
+        code.code,
+        [
+            ALoad0,
+            InvokeSpecial(code.code[1].constant_pool_index().unwrap()),
+            Return
+        ]
+    );
+    assert_eq!((code.max_stack, code.max_locals), (1, 1));
+
+    let run = class.methods()[1].code().unwrap();
+    assert_eq!((run.max_stack, run.max_locals), (3, 1));
+}
+
+#[test]
+fn invalid_class() {
+    let mut class = greeter();
+    class.add_default_constructor(ACC_PUBLIC).unwrap();
+    assert!(matches!(class.finish(), Err(JomError::InvalidClass(_))));
+
+    let mut class = ClassBuilder::new("Named");
+    class.set_access_flags(ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT);
+    assert!(matches!(
+        class.add_default_constructor(ACC_PUBLIC),
+        Err(JomError::InvalidClass(_))
+    ));
+
+    let mut code = CodeBuilder::new();
+    let label = code.new_label();
+    code.goto(label);
+    let error = class
+        .add_code_method(ACC_PUBLIC | ACC_STATIC, "loop", "()V", code)
+        .unwrap_err();
+    assert!(matches!(error.root_cause(), JomError::InvalidCode(_)));
+    assert_eq!(error.path().len(), 1);
+}
+
+#[test]
+fn computed_frames() {
+    let looping = || {
+        let mut class = ClassBuilder::new("Loop");
+        let mut code = CodeBuilder::new();
+        let start = code.new_label();
+        code.place(start);
+        code.goto(start);
+        class
+            .add_code_method(ACC_PUBLIC | ACC_STATIC, "loop", "()V", code)
+            .unwrap();
+        class
+    };
+
+    let class = looping().finish().unwrap();
+    let code = class.methods()[0].code().unwrap();
+    assert!(matches!(
+        &code.attributes[..],
+        [CodeAttribute::Unknown(name, info)] if name == "StackMapTable" && info == &[0, 1, 0]
+    ));
+    assert!(class.version_issues().is_empty());
+
+    // Versions that allow code without frames get none.
+    let mut class = looping();
+    class.set_version(ClassVersion::new(49, 0));
+    let class = class.finish().unwrap();
+    assert!(class.methods()[0].code().unwrap().attributes.is_empty());
+}
+
+/// `static RuntimeException pick(int, IllegalStateException, IllegalArgumentException)`,
+/// merging the exceptions to their common superclass in the hierarchy.
+#[test]
+fn hierarchy_frames() {
+    let mut code = CodeBuilder::new();
+    let (second, done) = (code.new_label(), code.new_label());
+    code.iload(0);
+    code.jump(Instruction::IfEq, second);
+    code.aload(1);
+    code.goto(done);
+    code.place(second);
+    code.aload(2);
+    code.place(done);
+    code.emit(Instruction::AReturn);
+
+    let mut class = ClassBuilder::new("Picker");
+    class
+        .add_code_method(
+            ACC_PUBLIC | ACC_STATIC,
+            "pick",
+            "(ILjava/lang/IllegalStateException;Ljava/lang/IllegalArgumentException;)\
+             Ljava/lang/RuntimeException;",
+            code,
+        )
+        .unwrap();
+    let mut exceptions = vec![];
+    for name in [
+        "java/lang/IllegalStateException",
+        "java/lang/IllegalArgumentException",
+    ] {
+        let mut exception = ClassFile::new(name.to_owned());
+        exception.set_super_class("java/lang/RuntimeException".to_owned());
+        exceptions.push(exception);
+    }
+    class.set_hierarchy(ClassHierarchy::from_classes(&exceptions));
+    let class = class.finish().unwrap();
+
+    let runtime_exception = class
+        .constant_pool()
+        .find_class("java/lang/RuntimeException".to_owned())
+        .unwrap()
+        .to_be_bytes();
+    let code = class.methods()[0].code().unwrap();
+    assert_eq!(
+        stack_map_table(code),
+        [0, 2, 8, 64, 7, runtime_exception[0], runtime_exception[1]]
+    );
+}
@@ -0,0 +1,93 @@
+use jom::{
+    call_graph::{CallGraph, CallKind, MethodRef, Precision},
+    jar::Jar,
+    ClassFile,
+};
+
+fn classes() -> Vec<ClassFile> {
+    let jar = Jar::read(include_bytes!("calls.jar")).unwrap();
+    jar.classes().map(|x| x.class_file().unwrap()).collect()
+}
+
+fn method(class: &str, name: &str, descriptor: &str) -> MethodRef {
+    MethodRef::new(format!("calls/{class}"), name, descriptor)
+}
+
+#[test]
+fn reachable() {
+    let classes = classes();
+    let entry_points = CallGraph::main_methods(&classes);
+    assert_eq!(
+        entry_points,
+        [method("Main", "main", "([Ljava/lang/String;)V")]
+    );
+
+    let greet = "(Ljava/lang/String;)Ljava/lang/String;";
+    let cha = CallGraph::build(&classes, &entry_points, Precision::Cha).unwrap();
+    let rta = CallGraph::build(&classes, &entry_points, Precision::Rta).unwrap();
+
+    for graph in [&cha, &rta] {
+        assert!(graph.is_reachable(&method("English", "greet", greet)));
+        assert!(graph.is_reachable(&method("Names", "<clinit>", "()V")));
+        assert!(graph.is_reachable(&method("Main", "lambda$main$0", "()V")));
+        // Only the lambda of the reachable `invokedynamic` is called.
+        assert!(!graph.is_reachable(&method("Main", "lambda$later$1", "()V")));
+        assert!(graph.is_reachable(&MethodRef::new(
+            "java/io/PrintStream",
+            "println",
+            "(Ljava/lang/String;)V"
+        )));
+        assert!(!graph.is_reachable(&method("Main", "unused", "()V")));
+    }
+    assert!(cha.is_reachable(&method("French", "greet", greet)));
+    assert!(!rta.is_reachable(&method("French", "greet", greet)));
+
+    let mut unreachable = rta.unreachable(&classes);
+    unreachable.sort();
+    assert_eq!(
+        unreachable,
+        [
+            method("French", "<init>", "()V"),
+            method("French", "greet", greet),
+            method("Greeter", "greet", greet),
+            method("Main", "<init>", "()V"),
+            method("Main", "lambda$later$1", "()V"),
+            method("Main", "later", "()V"),
+            method("Main", "unused", "()V"),
+            method("Names", "<init>", "()V"),
+        ]
+    );
+
+    let greet_all = method("Main", "greetAll", "(Lcalls/Greeter;)V");
+    let callees = rta
+        .callees(&greet_all)
+        .filter(|x| x.kind == CallKind::Interface && x.callee.class.starts_with("calls/"))
+        .map(|x| &x.callee)
+        .collect::<Vec<_>>();
+    assert_eq!(callees, [&method("English", "greet", greet)]);
+    assert_eq!(
+        rta.callers(&method("Names", "<clinit>", "()V"))
+            .map(|x| (&x.caller, x.kind))
+            .collect::<Vec<_>>(),
+        [(&greet_all, CallKind::Initializer)]
+    );
+}
+
+#[test]
+fn export() {
+    let classes = classes();
+    let entry_points = [method("Main", "greetAll", "(Lcalls/Greeter;)V")];
+    let graph = CallGraph::build(&classes, &entry_points, Precision::Rta).unwrap();
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph calls {\n"));
+    assert!(dot.contains(
+        "    \"calls/Main.greetAll(Lcalls/Greeter;)V\" -> \"calls/Main.log(Ljava/lang/String;)V\" [label=\"static\"];\n"
+    ));
+
+    let json = graph.to_json();
+    assert!(json.starts_with("{\"methods\": [\"calls/Main.greetAll(Lcalls/Greeter;)V\", "));
+    assert!(json.contains(
+        "{\"caller\": \"calls/Main.greetAll(Lcalls/Greeter;)V\", \"callee\": \"calls/Names.<clinit>()V\", \"kind\": \"initializer\"}"
+    ));
+}
@@ -0,0 +1,50 @@
+package calls;
+
+import java.util.List;
+
+interface Greeter {
+    String greet(String name);
+}
+
+class English implements Greeter {
+    public String greet(String name) {
+        return "Hello, " + name;
+    }
+}
+
+class French implements Greeter {
+    public String greet(String name) {
+        return "Bonjour, " + name;
+    }
+}
+
+class Names {
+    static final List<String> ALL = List.of("Ada", "Alan");
+}
+
+public class Main {
+    public static void main(String[] args) {
+        greetAll(new English());
+        Runnable done = () -> log("done");
+        done.run();
+    }
+
+    static void greetAll(Greeter greeter) {
+        for (String name : Names.ALL) {
+            log(greeter.greet(name));
+        }
+    }
+
+    static void log(String message) {
+        System.out.println(message);
+    }
+
+    static void unused() {
+        log("unused");
+    }
+
+    static void later() {
+        Runnable done = () -> log("later");
+        done.run();
+    }
+}
@@ -0,0 +1,68 @@
+use std::{sync::Arc, thread};
+
+use jom::{
+    class_path::{ClassPath, ClassPathEntry},
+    jar::Jar,
+};
+
+const TESTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests");
+
+#[test]
+fn resolve() {
+    let class_path = ClassPath::from_paths([
+        format!("{TESTS}/hello.jmod"),
+        format!("{TESTS}/hello.jar"),
+        TESTS.to_owned(),
+    ])
+    .unwrap();
+
+    let greeter = class_path
+        .resolve("hello/greeting/Greeter")
+        .unwrap()
+        .unwrap();
+    assert_eq!(greeter.this_class(), "hello/greeting/Greeter");
+    assert_eq!(
+        class_path
+            .resolve("Analysis")
+            .unwrap()
+            .unwrap()
+            .this_class(),
+        "Analysis"
+    );
+    assert!(class_path.resolve("Missing").unwrap().is_none());
+    assert!(!class_path.contains("hello/greeting/Missing").unwrap());
+
+    let hello_world = class_path.resolve("HelloWorld").unwrap().unwrap();
+    assert!(Arc::ptr_eq(
+        &hello_world,
+        &class_path.resolve("HelloWorld").unwrap().unwrap()
+    ));
+
+    let names = class_path.class_names().unwrap();
+    for name in ["HelloWorld", "Strings", "hello/greeting/Greeter"] {
+        assert!(names.contains(&name.to_owned()), "{name}");
+    }
+    assert!(names.windows(2).all(|x| x[0] < x[1]));
+}
+
+#[test]
+fn shared() {
+    let mut class_path = ClassPath::new();
+    class_path.push(ClassPathEntry::Jar(
+        Jar::read(include_bytes!("hello.jar")).unwrap(),
+    ));
+    class_path.set_release(Some(17));
+
+    let classes = thread::scope(|s| {
+        let threads = (0..4)
+            .map(|_| s.spawn(|| class_path.resolve("HelloWorld").unwrap().unwrap()))
+            .collect::<Vec<_>>();
+
+        threads
+            .into_iter()
+            .map(|x| x.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    assert!(classes.windows(2).all(|x| Arc::ptr_eq(&x[0], &x[1])));
+}
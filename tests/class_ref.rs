@@ -0,0 +1,146 @@
+use std::borrow::Cow;
+
+use jom::{
+    class_ref::{ClassFileRef, ConstantRef},
+    jar::Jar,
+    ClassFile,
+};
+
+fn classes() -> Vec<Vec<u8>> {
+    let mut classes = Jar::read(include_bytes!("calls.jar"))
+        .unwrap()
+        .classes()
+        .map(|x| x.data().unwrap())
+        .collect::<Vec<_>>();
+    for bytes in [
+        &include_bytes!("HelloWorld.class")[..],
+        include_bytes!("Analysis.class"),
+        include_bytes!("Dependencies.class"),
+        include_bytes!("Dependencies$Pair.class"),
+        include_bytes!("Strings.class"),
+        include_bytes!("Surrogates.class"),
+    ] {
+        classes.push(bytes.to_vec());
+    }
+
+    classes
+}
+
+#[test]
+fn matches_class_file() {
+    for bytes in classes() {
+        let view = ClassFileRef::read(&bytes).unwrap();
+        let class = ClassFile::read(&bytes).unwrap();
+
+        assert_eq!(view.major(), class.major());
+        assert_eq!(view.access_flags(), class.access_flags());
+        assert_eq!(view.this_class().unwrap(), class.this_class());
+        assert_eq!(view.super_class().unwrap(), class.super_class());
+        let interfaces = view.interfaces().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(interfaces, class.interfaces());
+
+        assert_eq!(view.constant_pool_len(), class.constant_pool().len());
+        for (i, entry) in class.constant_pool().iter().enumerate() {
+            assert_eq!(&view.get(i as u16).unwrap().into_owned(), entry);
+        }
+
+        assert_eq!(view.fields().len(), class.fields().len());
+        for (field, expected) in view.fields().zip(class.fields()) {
+            assert_eq!(field.access_flags(), expected.access_flags);
+            assert_eq!(field.name().unwrap(), expected.name);
+            assert_eq!(field.descriptor().unwrap(), expected.descriptor);
+            assert!(field.code().unwrap().is_none());
+        }
+
+        assert_eq!(view.methods().len(), class.methods().len());
+        for (method, expected) in view.methods().zip(class.methods()) {
+            assert_eq!(method.name().unwrap(), expected.name);
+            assert_eq!(method.descriptor().unwrap(), expected.descriptor);
+
+            let (Some(code), Some(expected)) = (method.code().unwrap(), expected.code()) else {
+                assert!(expected.code().is_none());
+                continue;
+            };
+            assert_eq!(code.max_stack, expected.max_stack);
+            assert_eq!(code.max_locals, expected.max_locals);
+            let instructions = code
+                .instructions()
+                .map(|x| x.map(|(_, instruction)| instruction))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(instructions, expected.code);
+            let exceptions = code
+                .exception_table()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(exceptions, expected.exception_table);
+            assert_eq!(code.attributes().len(), expected.attributes.len());
+        }
+
+        assert_eq!(view.attributes().len(), class.attributes().len());
+    }
+}
+
+#[test]
+fn borrowed_strings() {
+    let bytes = include_bytes!("HelloWorld.class");
+    let view = ClassFileRef::read(bytes).unwrap();
+    assert!(matches!(
+        view.this_class().unwrap(),
+        Cow::Borrowed("HelloWorld")
+    ));
+    assert_eq!(view.super_class().unwrap(), "java/lang/Object");
+
+    let main = view
+        .methods()
+        .find(|x| x.name().unwrap() == "main")
+        .unwrap();
+    assert!(matches!(main.descriptor().unwrap(), Cow::Borrowed(_)));
+    let code = main.code().unwrap().unwrap();
+    let offsets = code
+        .instructions()
+        .map(|x| x.unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(offsets[0], 0);
+    assert_eq!(*offsets.last().unwrap() as usize, code.bytes().len() - 1);
+
+    // Only strings that differ from UTF-8 are copied.
+    let bytes = include_bytes!("Strings.class");
+    let view = ClassFileRef::read(bytes).unwrap();
+    let strings = (1..view.constant_pool_len() as u16)
+        .filter_map(|i| match view.get(i).unwrap() {
+            ConstantRef::String(s) => Some(s),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert!(matches!(&strings[..], [
+        Cow::Owned(nul),
+        Cow::Owned(emoji),
+        Cow::Borrowed("\u{3bb}"),
+    ] if nul == "a\0b" && emoji == "\u{1f600}"));
+
+    // Unpaired surrogates are kept as code units, not replaced.
+    let bytes = include_bytes!("Surrogates.class");
+    let view = ClassFileRef::read(bytes).unwrap();
+    let units = (1..view.constant_pool_len() as u16)
+        .filter(|&i| matches!(view.get(i).unwrap(), ConstantRef::Utf8Units(_)))
+        .collect::<Vec<_>>();
+    assert_eq!(units.len(), 3);
+    assert!(units.iter().all(|&i| view.get_utf8(i).is_err()));
+}
+
+#[test]
+fn invalid() {
+    let bytes = include_bytes!("HelloWorld.class");
+    assert!(ClassFileRef::read(include_bytes!("HelloWorld.java")).is_err());
+    assert!(ClassFileRef::read(&bytes[..bytes.len() - 1]).is_err());
+
+    let view = ClassFileRef::read(bytes).unwrap();
+    assert!(view.get(view.constant_pool_len() as u16).is_err());
+    let class = (1..view.constant_pool_len() as u16)
+        .find(|&i| matches!(view.get(i), Ok(ConstantRef::Class(_))))
+        .unwrap();
+    assert!(view.get_class(class).is_ok());
+    assert!(view.get_utf8(class).is_err());
+    assert_eq!(view.get(0).unwrap(), ConstantRef::Unusable);
+}
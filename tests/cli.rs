@@ -0,0 +1,90 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use jom::{jar::Jar, ClassFile};
+
+const HELLO_WORLD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/HelloWorld.class");
+const CALLS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/calls.jar");
+
+fn jom(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_jom"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// A path for the output of a test.
+fn output(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+#[test]
+fn inspect() {
+    assert_eq!(jom(&["version", HELLO_WORLD]), "61.0\n");
+    assert_eq!(jom(&["verify", HELLO_WORLD]), "HelloWorld: ok\n");
+    assert!(jom(&["dump", HELLO_WORLD]).contains("public class HelloWorld\n"));
+    assert!(jom(&["cp", HELLO_WORLD]).contains("= String             Hello World!\n"));
+}
+
+#[test]
+fn inspect_jar() {
+    let versions = jom(&["version", CALLS]);
+    assert!(versions.contains("calls/Main.class: 61.0\n"));
+    assert_eq!(versions.lines().count(), 5);
+    assert!(jom(&["verify", CALLS]).contains("calls/Main: ok\n"));
+    assert!(jom(&["dump", CALLS]).contains("calls/English.class:\n"));
+}
+
+#[test]
+fn edit() {
+    let stripped = output("Stripped.class");
+    jom(&["strip", HELLO_WORLD, "-o", stripped.to_str().unwrap()]);
+    let class = ClassFile::read(&fs::read(&stripped).unwrap()).unwrap();
+    assert!(class.attributes().iter().all(|x| x.name() != "SourceFile"));
+
+    let renamed = output("Renamed.class");
+    jom(&[
+        "rename",
+        HELLO_WORLD,
+        "HelloWorld=Greeting",
+        "-o",
+        renamed.to_str().unwrap(),
+    ]);
+    let class = ClassFile::read(&fs::read(&renamed).unwrap()).unwrap();
+    assert_eq!(class.this_class(), "Greeting");
+
+    let downgraded = output("Downgraded.class");
+    jom(&[
+        "version",
+        HELLO_WORLD,
+        "52",
+        "-o",
+        downgraded.to_str().unwrap(),
+    ]);
+    let class = ClassFile::read(&fs::read(&downgraded).unwrap()).unwrap();
+    assert_eq!((class.major(), class.minor()), (52, 0));
+}
+
+#[test]
+fn edit_jar() {
+    let jar = output("calls.jar");
+    let path = jar.to_str().unwrap();
+    jom(&["strip", CALLS, "-o", path]);
+    jom(&["rename", path, "calls/English=calls/British"]);
+    jom(&["version", path, "60"]);
+
+    let jar = Jar::read(&fs::read(&jar).unwrap()).unwrap();
+    assert!(jar.entry("calls/English.class").is_none());
+    for entry in jar.classes() {
+        let class = entry.class_file().unwrap();
+        assert_eq!(entry.class_name(), Some(class.this_class()));
+        assert_eq!(class.major(), 60);
+        assert!(class.attributes().iter().all(|x| x.name() != "SourceFile"));
+    }
+
+    let main = jar.entry("calls/Main.class").unwrap().class_file().unwrap();
+    assert!(main.to_string().contains("calls/British"));
+    assert!(jar.resources().any(|x| x.name() == "META-INF/MANIFEST.MF"));
+}
@@ -0,0 +1,81 @@
+use jom::{
+    attribute::ClassAttribute, call_graph::MethodRef, dependencies::FieldRef, error::JomError,
+    ClassFile,
+};
+
+#[test]
+fn dependencies() {
+    let class = ClassFile::read(include_bytes!("Dependencies.class")).unwrap();
+    let dependencies = class.dependencies().unwrap();
+
+    for name in [
+        // Only in generic signatures.
+        "java/lang/Comparable",
+        "java/util/regex/Pattern",
+        "Box",
+        "Box$Item",
+        // Only in annotations.
+        "Dependencies$Tag",
+        "java/math/BigDecimal",
+        "java/lang/Thread$State",
+        // Only in a type annotated signature.
+        "java/nio/file/Path",
+        // In the throws clause, exception table and `InnerClasses`.
+        "java/io/IOException",
+        "java/lang/IllegalStateException",
+        "Dependencies$Pair",
+        // In the bootstrap method of the method reference.
+        "java/lang/invoke/LambdaMetafactory",
+        "java/util/UUID",
+    ] {
+        assert!(dependencies.classes.contains(name), "{name}");
+    }
+    assert!(!dependencies.classes.contains("Dependencies"));
+    assert!(!dependencies.classes.contains("java/lang/Void"));
+
+    assert!(dependencies.methods.contains(&MethodRef::new(
+        "java/util/UUID",
+        "randomUUID",
+        "()Ljava/util/UUID;"
+    )));
+    assert!(dependencies.methods.contains(&MethodRef::new(
+        "java/util/Optional",
+        "of",
+        "(Ljava/lang/Object;)Ljava/util/Optional;"
+    )));
+    assert!(dependencies.fields.is_empty());
+}
+
+#[test]
+fn record_dependencies() {
+    let class = ClassFile::read(include_bytes!("Dependencies$Pair.class")).unwrap();
+    let dependencies = class.dependencies().unwrap();
+
+    for name in [
+        "Dependencies",
+        "java/lang/Record",
+        "java/lang/runtime/ObjectMethods",
+        "java/net/URI",
+        "java/time/Instant",
+        "java/util/List",
+    ] {
+        assert!(dependencies.classes.contains(name), "{name}");
+    }
+    assert!(dependencies.fields.contains(&FieldRef::new(
+        "Dependencies$Pair",
+        "first",
+        "Ljava/time/Instant;"
+    )));
+}
+
+#[test]
+fn unknown_target_type() {
+    let mut class = ClassFile::read(include_bytes!("HelloWorld.class")).unwrap();
+    class.attributes_mut().push(ClassAttribute::Unknown(
+        "RuntimeInvisibleTypeAnnotations".to_owned(),
+        vec![0, 1, 0x99, 0],
+    ));
+
+    let error = class.dependencies().unwrap_err();
+    assert!(matches!(error.root_cause(), JomError::InvalidClass(_)));
+}
@@ -0,0 +1,165 @@
+use jom::{
+    access::{ACC_PRIVATE, ACC_STATIC, ACC_SYNTHETIC},
+    downgrade::Downgrader,
+    error::ErrorPath,
+    method::code::instruction::Instruction,
+    version::{ClassVersion, Feature},
+    ClassFile,
+};
+
+fn nest() -> Vec<ClassFile> {
+    [
+        &include_bytes!("Nest.class")[..],
+        include_bytes!("Nest$Inner.class"),
+        include_bytes!("Nest$Builder.class"),
+    ]
+    .into_iter()
+    .map(|x| ClassFile::read(x).unwrap())
+    .collect()
+}
+
+/// Downgrades the classes, writing and reading them again.
+fn downgrade(classes: Vec<ClassFile>, target: ClassVersion) -> Vec<ClassFile> {
+    let mut downgrader = Downgrader::new(target);
+    for class in &classes {
+        downgrader.scan(class).unwrap();
+    }
+
+    classes
+        .into_iter()
+        .map(|mut class| {
+            assert_eq!(downgrader.downgrade(&mut class).unwrap(), []);
+            ClassFile::read(&class.write().unwrap()).unwrap()
+        })
+        .collect()
+}
+
+fn code_size(class: &ClassFile, name: &str) -> u32 {
+    let method = class.methods().iter().find(|x| x.name == name).unwrap();
+    let code = method.code().unwrap();
+    code.code.iter().fold(0, |pc, x| pc + x.size(pc))
+}
+
+#[test]
+fn nest_to_java8() {
+    let original = nest();
+    let classes = downgrade(nest(), ClassVersion::from_release(8).unwrap());
+    let [nest, inner, _] = &classes[..] else {
+        unreachable!()
+    };
+
+    for class in &classes {
+        assert_eq!(class.major(), 52);
+        assert!(class
+            .attributes()
+            .iter()
+            .all(|x| !x.name().starts_with("Nest")));
+        assert!(class
+            .methods()
+            .iter()
+            .filter_map(|x| x.code())
+            .flat_map(|x| &x.code)
+            .all(|x| !matches!(x, Instruction::InvokeDynamic(..))));
+    }
+
+    // Inner uses the private field, static field and methods of Nest through accessors.
+    let accessors = nest
+        .methods()
+        .iter()
+        .filter(|x| x.name.starts_with("access$"))
+        .collect::<Vec<_>>();
+    assert_eq!(accessors.len(), 5);
+    assert!(accessors
+        .iter()
+        .all(|x| x.access_flags == ACC_STATIC | ACC_SYNTHETIC));
+    assert!(accessors
+        .iter()
+        .any(|x| x.name == "access$000" && x.descriptor == "(LNest;)I"));
+    let constructor = nest.methods().iter().find(|x| x.name == "<init>").unwrap();
+    assert_eq!(constructor.access_flags & ACC_PRIVATE, 0);
+
+    let concat = nest
+        .methods()
+        .iter()
+        .find(|x| x.name == "concat$0")
+        .unwrap();
+    assert_eq!(
+        concat.descriptor,
+        "(Ljava/lang/String;II)Ljava/lang/String;"
+    );
+
+    // The code keeps its size, so its tables stay valid.
+    for (class, original) in classes.iter().zip(&original) {
+        for method in original.methods().iter().filter(|x| x.code().is_some()) {
+            assert_eq!(
+                code_size(class, &method.name),
+                code_size(original, &method.name)
+            );
+        }
+    }
+    let run = inner.methods().iter().find(|x| x.name == "run").unwrap();
+    assert!(run
+        .code()
+        .unwrap()
+        .attributes
+        .iter()
+        .any(|x| x.name() == "StackMapTable"));
+}
+
+#[test]
+fn nest_to_java1_4() {
+    let classes = downgrade(nest(), ClassVersion::new(48, 0));
+    for class in &classes {
+        assert_eq!(class.major(), 48);
+        assert!(class.version_issues().is_empty());
+        for method in class.methods() {
+            assert!(method.attributes.iter().all(|x| x.name() != "Signature"));
+            if let Some(code) = method.code() {
+                assert!(code.attributes.iter().all(|x| x.name() != "StackMapTable"));
+            }
+        }
+    }
+}
+
+#[test]
+fn remaining_issues() {
+    let mut class = ClassFile::read(include_bytes!("Dependencies.class")).unwrap();
+    let target = ClassVersion::from_release(6).unwrap();
+    let mut downgrader = Downgrader::new(target);
+    downgrader.scan(&class).unwrap();
+
+    // Lambdas are not rewritten.
+    let issues = downgrader.downgrade(&mut class).unwrap();
+    assert!(issues
+        .iter()
+        .any(|x| x.feature == Feature::Instruction("invokedynamic")));
+    assert_eq!(class.version(), target);
+
+    // Newer targets leave the class unchanged.
+    let mut class = ClassFile::read(include_bytes!("HelloWorld.class")).unwrap();
+    let issues = Downgrader::new(ClassVersion::LATEST)
+        .downgrade(&mut class)
+        .unwrap();
+    assert!(issues.is_empty());
+    assert_eq!(class.major(), 61);
+}
+
+#[test]
+fn interface_string_concat() {
+    let data = include_bytes!("Concat.class");
+
+    let mut class = ClassFile::read(data).unwrap();
+    let issues = Downgrader::new(ClassVersion::from_release(8).unwrap())
+        .downgrade(&mut class)
+        .unwrap();
+    assert_eq!(issues, []);
+
+    // Interfaces cannot have the helper methods before Java 8.
+    let mut class = ClassFile::read(data).unwrap();
+    let issues = Downgrader::new(ClassVersion::from_release(7).unwrap())
+        .downgrade(&mut class)
+        .unwrap();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].feature, Feature::StringConcat);
+    assert_eq!(issues[0].path.last(), Some(&ErrorPath::Pc(5)));
+}
@@ -0,0 +1,135 @@
+use jom::{
+    class_ref::ClassFileRef,
+    error::{ErrorPath, JomError},
+    ClassFile,
+};
+
+const HELLO_WORLD: &[u8] = include_bytes!("HelloWorld.class");
+
+fn read_error(bytes: &[u8]) -> JomError {
+    match ClassFile::read(bytes) {
+        Ok(_) => panic!("the class was read"),
+        Err(error) => error,
+    }
+}
+
+/// Returns the offset of the code of `main`.
+fn main_code(bytes: &[u8]) -> usize {
+    let view = ClassFileRef::read(bytes).unwrap();
+    let main = view
+        .methods()
+        .find(|x| x.name().unwrap() == "main")
+        .unwrap();
+    let code = main.code().unwrap().unwrap().bytes();
+
+    code.as_ptr() as usize - bytes.as_ptr() as usize
+}
+
+#[test]
+fn header() {
+    let error = read_error(include_bytes!("HelloWorld.java"));
+    assert!(matches!(error.root_cause(), JomError::BadMagic(0x7075626c)));
+    assert_eq!(error.offset(), Some(0));
+    assert_eq!(
+        error.to_string(),
+        "at offset 0: bad magic 0x7075626c, expected 0xcafebabe"
+    );
+
+    let mut bytes = HELLO_WORLD.to_vec();
+    bytes[6..8].copy_from_slice(&99u16.to_be_bytes());
+    let error = read_error(&bytes);
+    assert!(matches!(
+        error.root_cause(),
+        JomError::UnsupportedVersion(99, 0)
+    ));
+    assert!(ClassFileRef::read(&bytes).is_err());
+}
+
+#[test]
+fn truncated() {
+    for length in [6, 100, HELLO_WORLD.len() - 1] {
+        let error = read_error(&HELLO_WORLD[..length]);
+        assert!(matches!(error.root_cause(), JomError::Truncated));
+        assert_eq!(error.offset(), Some(length as u64));
+
+        let error = ClassFileRef::read(&HELLO_WORLD[..length]).err().unwrap();
+        assert!(matches!(error.root_cause(), JomError::Truncated));
+        assert_eq!(error.offset(), Some(length as u64));
+    }
+}
+
+#[test]
+fn invalid_opcode() {
+    let code = main_code(HELLO_WORLD);
+    let mut bytes = HELLO_WORLD.to_vec();
+    bytes[code] = 0xcb;
+
+    let error = read_error(&bytes);
+    assert!(matches!(error.root_cause(), JomError::InvalidOpcode(0xcb)));
+    assert_eq!(
+        error.path(),
+        [
+            ErrorPath::Class("HelloWorld".to_owned()),
+            ErrorPath::Method {
+                name: "main".to_owned(),
+                descriptor: "([Ljava/lang/String;)V".to_owned(),
+            },
+            ErrorPath::Attribute("Code".to_owned()),
+            ErrorPath::Pc(0),
+        ]
+    );
+    assert_eq!(error.offset(), Some(code as u64));
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "class HelloWorld > method main([Ljava/lang/String;)V > attribute Code > pc 0 \
+             at offset {code}: invalid opcode 0xcb"
+        )
+    );
+
+    // The operands of the last instruction run past the end of the code.
+    let view = ClassFileRef::read(HELLO_WORLD).unwrap();
+    let main = view
+        .methods()
+        .find(|x| x.name().unwrap() == "main")
+        .unwrap();
+    let length = main.code().unwrap().unwrap().bytes().len();
+    let mut bytes = HELLO_WORLD.to_vec();
+    bytes[code + length - 1] = 0x11;
+    let error = read_error(&bytes);
+    assert!(matches!(error.root_cause(), JomError::Truncated));
+    assert_eq!(error.path().last(), Some(&ErrorPath::Pc(length as u32 - 1)));
+}
+
+#[test]
+fn invalid_utf8() {
+    // The offset of the string's length.
+    let length = HELLO_WORLD
+        .windows(22)
+        .position(|x| x == b"\x01\x00\x13java/io/PrintStream")
+        .unwrap() as u64
+        + 1;
+    let mut bytes = HELLO_WORLD.to_vec();
+    bytes[length as usize + 2] = 0xff;
+
+    let error = read_error(&bytes);
+    assert!(matches!(error.root_cause(), JomError::InvalidUtf8));
+    assert_eq!(error.offset(), Some(length));
+
+    let view = ClassFileRef::read(&bytes).unwrap();
+    let error = (1..view.constant_pool_len() as u16)
+        .find_map(|i| view.get(i).err())
+        .unwrap();
+    assert!(matches!(error.root_cause(), JomError::InvalidUtf8));
+    assert_eq!(error.offset(), Some(length));
+}
+
+#[test]
+fn constant_pool() {
+    let view = ClassFileRef::read(HELLO_WORLD).unwrap();
+    let error = view.get_class(1).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "constant pool error: expected Class, found Methodref"
+    );
+}
@@ -0,0 +1,48 @@
+use jom::{attribute::CodeAttribute, hierarchy::ClassHierarchy, ClassFile};
+
+fn stack_map_tables(class: &ClassFile) -> Vec<Option<&[u8]>> {
+    class
+        .methods()
+        .iter()
+        .map(|x| {
+            x.code()?.attributes.iter().find_map(|x| match x {
+                CodeAttribute::Unknown(name, info) if name == "StackMapTable" => {
+                    Some(info.as_slice())
+                }
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+/// Recomputes the frames `javac` emitted, which only merge classes that are the same.
+#[test]
+fn javac_frames() {
+    for data in [
+        &include_bytes!("Analysis.class")[..],
+        include_bytes!("Dependencies.class"),
+        include_bytes!("Dependencies$Pair.class"),
+        include_bytes!("Nest.class"),
+    ] {
+        let expected = ClassFile::read(data).unwrap();
+        let mut class = ClassFile::read(data).unwrap();
+        for code in class.methods_mut().iter_mut().filter_map(|x| x.code_mut()) {
+            code.attributes.retain(|x| x.name() != "StackMapTable");
+        }
+
+        class.compute_frames(&ClassHierarchy::new()).unwrap();
+        assert_eq!(stack_map_tables(&class), stack_map_tables(&expected));
+    }
+}
+
+/// `describe` has a frame that drops a local `javac` considers out of scope, which computed
+/// frames would keep.
+#[test]
+fn existing_frames() {
+    let data = include_bytes!("Constants.class");
+    let expected = ClassFile::read(data).unwrap();
+    let mut class = ClassFile::read(data).unwrap();
+
+    class.compute_frames(&ClassHierarchy::new()).unwrap();
+    assert_eq!(stack_map_tables(&class), stack_map_tables(&expected));
+}
@@ -0,0 +1,3 @@
+module hello {
+    exports hello.greeting;
+}
@@ -0,0 +1,148 @@
+use std::{env, path::Path};
+
+use jom::{
+    class_path::{ClassPath, ClassPathEntry},
+    hierarchy::ClassHierarchy,
+    jar::Jar,
+};
+
+fn shapes() -> ClassHierarchy {
+    let jar = Jar::read(include_bytes!("shapes.jar")).unwrap();
+    let classes = jar
+        .classes()
+        .map(|x| x.class_file().unwrap())
+        .collect::<Vec<_>>();
+
+    ClassHierarchy::from_classes(&classes)
+}
+
+#[test]
+fn subtypes() {
+    let hierarchy = shapes();
+
+    assert!(hierarchy.is_interface("shapes/Polygon"));
+    assert!(hierarchy.is_subtype_of("shapes/Square", "shapes/Polygon"));
+    assert!(hierarchy.is_subtype_of("shapes/Square", "shapes/Shape"));
+    assert!(hierarchy.is_subtype_of("shapes/Circle", "java/lang/Object"));
+    assert!(!hierarchy.is_subtype_of("shapes/Circle", "shapes/Polygon"));
+    assert!(!hierarchy.is_subtype_of("shapes/Shape", "shapes/Circle"));
+
+    assert!(hierarchy.is_subtype_of("[Lshapes/Square;", "[Lshapes/Shape;"));
+    assert!(hierarchy.is_subtype_of("[[Lshapes/Square;", "[Ljava/lang/Object;"));
+    assert!(hierarchy.is_subtype_of("[I", "java/io/Serializable"));
+    assert!(!hierarchy.is_subtype_of("[I", "[J"));
+    assert!(!hierarchy.is_subtype_of("[Lshapes/Shape;", "[Lshapes/Square;"));
+
+    assert_eq!(
+        hierarchy.superclasses("shapes/Square"),
+        [
+            "shapes/Rectangle",
+            "shapes/AbstractShape",
+            "java/lang/Object"
+        ]
+    );
+    assert_eq!(
+        hierarchy.supertypes("shapes/Square"),
+        [
+            "shapes/Rectangle",
+            "shapes/AbstractShape",
+            "shapes/Polygon",
+            "java/lang/Object",
+            "shapes/Shape",
+        ]
+    );
+
+    let mut subtypes = hierarchy.subtypes("shapes/Shape");
+    subtypes.sort();
+    assert_eq!(
+        subtypes,
+        [
+            "shapes/AbstractShape",
+            "shapes/Circle",
+            "shapes/Polygon",
+            "shapes/Rectangle",
+            "shapes/Square",
+        ]
+    );
+}
+
+#[test]
+fn common_superclass() {
+    let hierarchy = shapes();
+
+    assert_eq!(
+        hierarchy.common_superclass("shapes/Square", "shapes/Circle"),
+        "shapes/AbstractShape"
+    );
+    assert_eq!(
+        hierarchy.common_superclass("shapes/Square", "shapes/Rectangle"),
+        "shapes/Rectangle"
+    );
+    assert_eq!(
+        hierarchy.common_superclass("shapes/Square", "shapes/Shape"),
+        "shapes/Shape"
+    );
+    assert_eq!(
+        hierarchy.common_superclass("shapes/Circle", "shapes/Polygon"),
+        "java/lang/Object"
+    );
+    assert_eq!(
+        hierarchy.common_superclass("[Lshapes/Square;", "[Lshapes/Circle;"),
+        "[Lshapes/AbstractShape;"
+    );
+    assert_eq!(
+        hierarchy.common_superclass("[I", "shapes/Circle"),
+        "java/lang/Object"
+    );
+}
+
+#[test]
+fn methods() {
+    let hierarchy = shapes();
+
+    assert_eq!(
+        hierarchy.resolve_method("shapes/Square", "area", "()D"),
+        Some("shapes/Rectangle")
+    );
+    assert_eq!(
+        hierarchy.resolve_method("shapes/Circle", "describe", "()Ljava/lang/String;"),
+        Some("shapes/Shape")
+    );
+    assert_eq!(
+        hierarchy.resolve_method("shapes/Circle", "sides", "()I"),
+        None
+    );
+
+    assert_eq!(
+        hierarchy.overridden_methods("shapes/Square", "describe", "()Ljava/lang/String;"),
+        ["shapes/Rectangle", "shapes/Shape"]
+    );
+
+    let mut implementations = hierarchy.implementations("shapes/Shape", "area", "()D");
+    implementations.sort();
+    assert_eq!(implementations, ["shapes/Circle", "shapes/Rectangle"]);
+    assert!(hierarchy
+        .implementations("shapes/Square", "<init>", "(D)V")
+        .is_empty());
+}
+
+/// Resolves the supertypes from the JDK at `JAVA_HOME`.
+#[test]
+#[ignore = "needs a JDK at JAVA_HOME"]
+fn add_from_class_path() {
+    let java_home = env::var_os("JAVA_HOME").expect("JAVA_HOME is not set");
+    let mut class_path = ClassPath::new();
+    class_path.push(ClassPathEntry::open(Path::new(&java_home)).unwrap());
+
+    let mut hierarchy = ClassHierarchy::new();
+    for class in ["java/util/ArrayList", "java/util/LinkedList"] {
+        hierarchy.add_from(&class_path, class).unwrap();
+    }
+
+    assert!(hierarchy.contains("java/util/AbstractList"));
+    assert!(hierarchy.is_subtype_of("java/util/ArrayList", "java/lang/Iterable"));
+    assert_eq!(
+        hierarchy.common_superclass("java/util/ArrayList", "java/util/LinkedList"),
+        "java/util/AbstractList"
+    );
+}
@@ -0,0 +1,48 @@
+use jom::{
+    analysis::{basic::BasicInterpreter, cfg::ControlFlowGraph, frame::Value, Analyzer},
+    method::code::instruction::{Instruction, StackEffect},
+    ClassFile,
+};
+
+#[test]
+fn metadata() {
+    let class = ClassFile::read(include_bytes!("HelloWorld.class")).unwrap();
+    let main = class.methods().iter().find(|x| x.name == "main").unwrap();
+    let code = &main.code().unwrap().code;
+
+    let mnemonics = code.iter().map(Instruction::mnemonic).collect::<Vec<_>>();
+    assert_eq!(mnemonics, ["getstatic", "ldc", "invokevirtual", "return"]);
+
+    let opcodes = code.iter().map(Instruction::opcode).collect::<Vec<_>>();
+    assert_eq!(opcodes, [0xb2, 0x12, 0xb6, 0xb1]);
+
+    let sizes = code.iter().map(|x| x.size(0)).collect::<Vec<_>>();
+    assert_eq!(sizes, [3, 2, 3, 1]);
+
+    let cp = class.constant_pool();
+    assert_eq!(code[0].stack_effect(cp).unwrap(), StackEffect { pops: 0, pushes: 1 });
+    assert_eq!(code[2].stack_effect(cp).unwrap(), StackEffect { pops: 2, pushes: 0 });
+    assert!(code[2].can_throw() && code[2].is_invoke());
+    assert!(code[3].is_return() && !code[3].falls_through());
+}
+
+#[test]
+fn stack_effects_match_analysis() {
+    let class = ClassFile::read(include_bytes!("Analysis.class")).unwrap();
+
+    for method in class.methods() {
+        let code = method.code().unwrap();
+        let cfg = ControlFlowGraph::new(code).unwrap();
+        let frames = Analyzer::new(BasicInterpreter).analyze(&class, method).unwrap();
+        let height = |i: usize| -> i32 {
+            frames[i].as_ref().unwrap().stack().iter().map(|x| x.size() as i32).sum()
+        };
+
+        for (i, instruction) in code.code.iter().enumerate() {
+            let effect = instruction.stack_effect(class.constant_pool()).unwrap();
+            for &successor in cfg.successors(i) {
+                assert_eq!(height(i) + effect.delta(), height(successor), "{}", instruction.mnemonic());
+            }
+        }
+    }
+}
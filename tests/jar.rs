@@ -0,0 +1,140 @@
+use jom::jar::{manifest::Manifest, Compression, DosDateTime, Jar, JarEntry};
+
+#[test]
+fn read() {
+    let jar = Jar::read(include_bytes!("hello.jar")).unwrap();
+
+    let names = jar.entries().iter().map(|x| x.name()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "META-INF/",
+            "META-INF/MANIFEST.MF",
+            "HelloWorld.class",
+            "hello.properties",
+            "META-INF/versions/11/HelloWorld.class",
+        ]
+    );
+
+    let manifest = jar.manifest_entry().unwrap().data().unwrap();
+    assert!(String::from_utf8(manifest)
+        .unwrap()
+        .contains("Multi-Release: true"));
+
+    let resources = jar.resources().map(|x| x.name()).collect::<Vec<_>>();
+    assert_eq!(resources, ["META-INF/MANIFEST.MF", "hello.properties"]);
+
+    let classes = jar.classes().collect::<Vec<_>>();
+    assert_eq!(classes.len(), 2);
+    assert_eq!(classes[0].release(), None);
+    assert_eq!(classes[1].release(), Some(11));
+    assert_eq!(classes[1].class_name(), Some("HelloWorld"));
+
+    let class = jar.find_class("HelloWorld", None).unwrap();
+    assert_eq!(class.name(), "HelloWorld.class");
+    assert_eq!(class.class_file().unwrap().this_class(), "HelloWorld");
+    let class = jar.find_class("HelloWorld", Some(17)).unwrap();
+    assert_eq!(class.release(), Some(11));
+    let class = jar.find_class("HelloWorld", Some(8)).unwrap();
+    assert_eq!(class.release(), None);
+}
+
+#[test]
+fn write() {
+    let mut jar = Jar::read(include_bytes!("hello.jar")).unwrap();
+    let modified = jar.entry("hello.properties").unwrap().modified();
+
+    let class = jar.entry("HelloWorld.class").unwrap().class_file().unwrap();
+    jar.entry_mut("HelloWorld.class")
+        .unwrap()
+        .set_class_file(&class)
+        .unwrap();
+    jar.remove("META-INF/versions/11/HelloWorld.class").unwrap();
+    jar.insert(JarEntry::new("notes.txt".to_owned(), b"notes"));
+
+    let jar = Jar::read(&jar.write().unwrap()).unwrap();
+
+    let names = jar.entries().iter().map(|x| x.name()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "META-INF/",
+            "META-INF/MANIFEST.MF",
+            "HelloWorld.class",
+            "hello.properties",
+            "notes.txt",
+        ]
+    );
+    assert_eq!(jar.entry("hello.properties").unwrap().modified(), modified);
+    assert_eq!(
+        jar.entry("HelloWorld.class").unwrap().data().unwrap(),
+        include_bytes!("HelloWorld.class")
+    );
+
+    let notes = jar.entry("notes.txt").unwrap();
+    assert_eq!(notes.compression(), Compression::Deflated);
+    assert_eq!(notes.modified(), DosDateTime::EPOCH);
+    assert_eq!(notes.data().unwrap(), b"notes");
+}
+
+#[test]
+fn manifest() {
+    let jar = Jar::read(include_bytes!("signed.jar")).unwrap();
+    let data = jar.manifest_entry().unwrap().data().unwrap();
+    let manifest = Manifest::parse(&data).unwrap();
+
+    assert_eq!(manifest.main_class(), Some("HelloWorld"));
+    assert!(!manifest.is_multi_release());
+    assert_eq!(
+        manifest.main_attributes().get("implementation-title"),
+        Some("A title long enough that the jar tool has to continue it onto the next line")
+    );
+    let sections = manifest.sections().map(|(x, _)| x).collect::<Vec<_>>();
+    assert_eq!(sections, ["HelloWorld.class", "hello.properties"]);
+    assert!(manifest
+        .section("HelloWorld.class")
+        .unwrap()
+        .get("SHA-256-Digest")
+        .is_some());
+
+    assert_eq!(manifest.write(), data);
+    assert!(Manifest::parse(b"Name: x\n continued\nbroken\n").is_err());
+}
+
+#[test]
+fn strip_signatures() {
+    let mut jar = Jar::read(include_bytes!("signed.jar")).unwrap();
+    assert!(jar.is_signed());
+    let signatures = jar.signature_files().map(|x| x.name()).collect::<Vec<_>>();
+    assert_eq!(signatures, ["META-INF/TEST.SF", "META-INF/TEST.RSA"]);
+
+    jar.strip_signatures().unwrap();
+
+    let jar = Jar::read(&jar.write().unwrap()).unwrap();
+    assert!(!jar.is_signed());
+    let manifest = jar.manifest().unwrap().unwrap();
+    assert_eq!(manifest.sections().count(), 0);
+    assert_eq!(manifest.main_class(), Some("HelloWorld"));
+}
+
+#[test]
+fn cp437_names() {
+    let mut jar = Jar::new();
+    jar.insert(JarEntry::new("café.txt".to_owned(), b"menu"));
+    let mut bytes = jar.write().unwrap();
+
+    // Clearing the UTF-8 flag of both headers makes the name CP437.
+    for (signature, offset) in [(b"PK\x03\x04", 7), (b"PK\x01\x02", 9)] {
+        let i = bytes.windows(4).position(|x| x == signature).unwrap();
+        assert_eq!(bytes[i + offset] & 0x08, 0x08);
+        bytes[i + offset] &= !0x08;
+    }
+    let jar = Jar::read(&bytes).unwrap();
+    assert_eq!(jar.entries()[0].name(), "caf├⌐.txt");
+    assert_eq!(jar.write().unwrap(), bytes);
+
+    let mut jar = jar;
+    jar.entries_mut()[0].set_name("café.txt".to_owned());
+    let jar = Jar::read(&jar.write().unwrap()).unwrap();
+    assert_eq!(jar.entries()[0].name(), "café.txt");
+}
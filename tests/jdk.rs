@@ -0,0 +1,48 @@
+use std::{env, fs, path::Path};
+
+use jom::{jimage::JImage, jmod::Jmod};
+
+#[test]
+fn jmod() {
+    let jmod = Jmod::read(include_bytes!("hello.jmod")).unwrap();
+
+    assert_eq!(
+        jmod.class_names().collect::<Vec<_>>(),
+        ["hello/greeting/Greeter"]
+    );
+    assert!(jmod.module_info().is_some());
+
+    let class = jmod.find_class("hello/greeting/Greeter").unwrap();
+    assert_eq!(
+        class.class_file().unwrap().this_class(),
+        "hello/greeting/Greeter"
+    );
+    assert!(jmod.find_class("hello/greeting/Missing").is_none());
+
+    assert!(Jmod::read(include_bytes!("hello.jar")).is_err());
+}
+
+/// Reads the platform classes of the JDK at `JAVA_HOME`.
+#[test]
+#[ignore = "needs a JDK at JAVA_HOME"]
+fn jimage() {
+    let java_home = env::var_os("JAVA_HOME").expect("JAVA_HOME is not set");
+    let image = JImage::read(fs::read(Path::new(&java_home).join("lib/modules")).unwrap()).unwrap();
+
+    let object = image.find_class("java/lang/Object").unwrap().unwrap();
+    assert_eq!(object.this_class(), "java/lang/Object");
+    assert_eq!(object.super_class(), "");
+    let string = image.find_class("java/lang/String").unwrap().unwrap();
+    assert_eq!(string.super_class(), "java/lang/Object");
+    assert!(image.find_class("java/lang/Missing").unwrap().is_none());
+    assert!(image.find_class("missing/Missing").unwrap().is_none());
+
+    assert!(image
+        .resource("/java.base/java/lang/Object.class")
+        .unwrap()
+        .is_some());
+    assert!(image
+        .class_names()
+        .unwrap()
+        .contains(&"java/util/ArrayList".to_owned()));
+}
@@ -0,0 +1,232 @@
+use jom::{
+    jar::Jar,
+    mapping::{LineMapping, Mappings},
+    ClassFile,
+};
+
+const PROGUARD: &str = "\
+# compiler: R8
+calls.Names -> a.a:
+    java.util.List ALL -> a
+calls.Greeter -> a.b:
+    java.lang.String greet(java.lang.String) -> a
+calls.English -> a.c:
+    java.lang.String greet(java.lang.String) -> a
+calls.Main -> a.d:
+    1:1:void calls.Main.log(java.lang.String):39:39 -> a
+    1:1:void greetAll(calls.Greeter):35 -> a
+    2:3:void greetAll(calls.Greeter):34:35 -> a
+    4:4:void log(java.lang.String):39:39 -> b
+    void main(java.lang.String[]) -> main
+";
+
+const TINY_V2: &str = "\
+tiny\t2\t0\tofficial\tnamed
+\tescaped-names
+c\ta\tcalls/Names
+\tf\tLjava/util/List;\ta\tALL
+\tc\ta comment
+c\tb\tcalls/Greeter
+\tm\t(Ljava/lang/String;)Ljava/lang/String;\ta\tgreet
+\t\tp\t1\t\tname
+c\td\tcalls/Main
+\tm\t(Lb;)V\ta\tgreetAll
+";
+
+#[test]
+fn proguard() {
+    let mappings = Mappings::parse_proguard(PROGUARD).unwrap();
+
+    let main = mappings.class("calls/Main").unwrap();
+    assert_eq!(main.target, "a/d");
+    assert_eq!(main.methods.len(), 5);
+    assert_eq!(main.methods[0].inlined_from.as_deref(), Some("calls/Main"));
+    assert_eq!(
+        main.methods[1].lines,
+        Some(LineMapping {
+            source: (35, 35),
+            target: (1, 1),
+        })
+    );
+    assert_eq!(
+        main.method("greetAll", "(Lcalls/Greeter;)V")
+            .unwrap()
+            .target,
+        "a"
+    );
+    assert_eq!(main.methods[4].lines, None);
+    assert_eq!(
+        mappings.class("calls/Names").unwrap().fields[0]
+            .descriptor
+            .as_deref(),
+        Some("Ljava/util/List;")
+    );
+
+    let written = mappings.write_proguard().unwrap();
+    assert_eq!(Mappings::parse_proguard(&written).unwrap(), mappings);
+}
+
+#[test]
+fn invalid() {
+    for text in [
+        "    int a -> b\n",
+        "calls.Main -> a.d\n",
+        "calls.Main -> a.d:\n    1:2:3:void a() -> b\n",
+        "calls.Main -> a.d:\n    void -> b\n",
+    ] {
+        let error = Mappings::parse_proguard(text).unwrap_err();
+        assert!(error.to_string().starts_with("invalid mapping: line "));
+    }
+    assert!(Mappings::parse_tiny(TINY_V2, "official", "intermediary").is_err());
+    assert!(Mappings::parse_srg("XX: a b\n").is_err());
+}
+
+#[test]
+fn tiny() {
+    let mappings = Mappings::parse_tiny(TINY_V2, "official", "named").unwrap();
+    assert_eq!(mappings.classes.len(), 3);
+    let main = mappings.class("d").unwrap();
+    assert_eq!(main.target, "calls/Main");
+    assert_eq!(main.method("a", "(Lb;)V").unwrap().target, "greetAll");
+
+    // Descriptors are given in the first namespace, and remapped to the source namespace.
+    let named = Mappings::parse_tiny(TINY_V2, "named", "official").unwrap();
+    let main = named.class("calls/Main").unwrap();
+    assert_eq!(
+        main.method("greetAll", "(Lcalls/Greeter;)V")
+            .unwrap()
+            .target,
+        "a"
+    );
+    assert_eq!(named, mappings.reverse().unwrap());
+
+    let v2 = mappings.write_tiny_v2("official", "named").unwrap();
+    assert_eq!(
+        Mappings::parse_tiny(&v2, "official", "named").unwrap(),
+        mappings
+    );
+    let v1 = mappings.write_tiny_v1("official", "named").unwrap();
+    assert!(v1.starts_with("v1\tofficial\tnamed\nCLASS\ta\tcalls/Names\n"));
+    assert_eq!(
+        Mappings::parse_tiny(&v1, "official", "named").unwrap(),
+        mappings
+    );
+}
+
+#[test]
+fn srg() {
+    let text = "\
+PK: . net/minecraft/src
+CL: a net/minecraft/Main
+CL: b net/minecraft/World
+FD: a/a net/minecraft/Main/world
+MD: a/a (Lb;)V net/minecraft/Main/tick (Lnet/minecraft/World;)V
+";
+    let mappings = Mappings::parse_srg(text).unwrap();
+    let main = mappings.class("a").unwrap();
+    assert_eq!(main.target, "net/minecraft/Main");
+    assert_eq!(main.field("a").unwrap().target, "world");
+    assert_eq!(main.field("a").unwrap().descriptor, None);
+    assert_eq!(main.method("a", "(Lb;)V").unwrap().target, "tick");
+
+    let written = mappings.write_srg().unwrap();
+    assert_eq!(
+        written,
+        text.lines().skip(1).collect::<Vec<_>>().join("\n") + "\n"
+    );
+    assert!(mappings.write_proguard().is_err());
+
+    let tsrg = mappings.write_tsrg();
+    assert_eq!(
+        tsrg,
+        "a net/minecraft/Main\n\ta world\n\ta (Lb;)V tick\nb net/minecraft/World\n"
+    );
+    assert_eq!(Mappings::parse_tsrg(&tsrg).unwrap(), mappings);
+
+    let tsrg2 = "\
+tsrg2 obf srg id
+a net/minecraft/Main 1
+\ta La; f_1 2
+\ta (Lb;)V m_1 3
+\t\tstatic
+\t\t0 o p_1 4
+";
+    let mappings = Mappings::parse_tsrg(tsrg2).unwrap();
+    let main = mappings.class("a").unwrap();
+    assert_eq!(main.field("a").unwrap().descriptor.as_deref(), Some("La;"));
+    assert_eq!(main.method("a", "(Lb;)V").unwrap().target, "m_1");
+}
+
+#[test]
+fn remap() {
+    let mappings = Mappings::parse_proguard(PROGUARD).unwrap();
+    let remapper = mappings.remapper();
+
+    let jar = Jar::read(include_bytes!("calls.jar")).unwrap();
+    let classes = jar
+        .classes()
+        .map(|x| {
+            let mut class = x.class_file().unwrap();
+            remapper.remap(&mut class).unwrap();
+            ClassFile::read(&class.write().unwrap()).unwrap()
+        })
+        .collect::<Vec<_>>();
+    let class = |name: &str| classes.iter().find(|x| x.this_class() == name).unwrap();
+
+    let main = class("a/d");
+    let names = main.methods().iter().map(|x| &x.name).collect::<Vec<_>>();
+    assert!(names.contains(&&"a".to_owned()));
+    assert!(names.contains(&&"b".to_owned()));
+    assert!(names.contains(&&"main".to_owned()));
+    assert_eq!(class("a/c").interfaces(), ["a/b"]);
+    assert_eq!(class("a/a").fields()[0].name, "a");
+}
+
+#[test]
+fn stack_trace() {
+    let mappings = Mappings::parse_proguard(PROGUARD).unwrap();
+
+    let trace = "\
+Exception in thread \"main\" a.a: boom
+\tat a.d.a(SourceFile:1)
+\tat a.d.a(SourceFile:3)
+\tat a.d.b(Unknown Source)
+\tat a.c.a(SourceFile)
+\tat app//a.d.main(SourceFile:7)
+\tat java.base/java.lang.Thread.run(Thread.java:833)
+Caused by: java.lang.IllegalStateException: a.d
+\t... 5 more
+";
+    let expected = "\
+Exception in thread \"main\" calls.Names: boom
+\tat calls.Main.log(Main.java:39)
+\tat calls.Main.greetAll(Main.java:35)
+\tat calls.Main.greetAll(Main.java:35)
+\tat calls.Main.log(Unknown Source)
+\tat calls.English.greet(English.java)
+\tat app//calls.Main.main(Main.java:7)
+\tat java.base/java.lang.Thread.run(Thread.java:833)
+Caused by: java.lang.IllegalStateException: a.d
+\t... 5 more
+";
+    assert_eq!(mappings.deobfuscate_stack_trace(trace), expected);
+
+    // Without a line, every method with the name is a candidate.
+    let mappings =
+        Mappings::parse_srg("CL: Main a\nMD: Main/run ()V a/a ()V\nMD: Main/stop (I)V a/a (I)V\n")
+            .unwrap();
+    assert_eq!(
+        mappings.deobfuscate_stack_trace("\tat a.a(Main.java)"),
+        "\tat Main.run|stop(Main.java)"
+    );
+}
+
+#[test]
+fn reversed_source_lines() {
+    let mappings = Mappings::parse_proguard("A -> b:\n    1:2:void a():5:3 -> a\n").unwrap();
+
+    assert_eq!(
+        mappings.deobfuscate_stack_trace("\tat b.a(SourceFile:2)"),
+        "\tat A.a(A.java:5)"
+    );
+}
@@ -0,0 +1,40 @@
+use jom::ClassFile;
+
+#[test]
+fn disassemble() {
+    let class = ClassFile::read(include_bytes!("HelloWorld.class")).unwrap();
+    let output = class.to_string();
+
+    let expected = "  public static void main(java.lang.String[]);
+    descriptor: ([Ljava/lang/String;)V
+    flags: (0x0009) ACC_PUBLIC, ACC_STATIC
+    Code:
+      stack=2, locals=1, args_size=1
+         0: getstatic     #7                 // Field java/lang/System.out:Ljava/io/PrintStream;
+         3: ldc           #13                // String Hello World!
+         5: invokevirtual #15                // Method java/io/PrintStream.println:(Ljava/lang/String;)V
+         8: return
+      LineNumberTable:
+        line 3: 0
+        line 4: 8
+";
+
+    assert!(output.starts_with("public class HelloWorld\n"));
+    assert!(output.contains("  flags: (0x0021) ACC_PUBLIC, ACC_SUPER\n"));
+    assert!(output.contains("    #1 = Methodref          java/lang/Object.\"<init>\":()V\n"));
+    assert!(output.contains(expected), "{output}");
+}
+
+#[test]
+fn exception_table() {
+    let class = ClassFile::read(include_bytes!("Analysis.class")).unwrap();
+    let output = class.to_string();
+
+    assert!(output.contains(
+        "      Exception table:
+         from    to  target type
+             0     5      14   Class java/lang/NullPointerException
+             0     5      27   any
+"
+    ));
+}
@@ -1,4 +1,8 @@
-use jom::ClassFile;
+use jom::{
+    attribute::{CodeAttribute, ConstantValue, FieldAttribute, MethodAttribute},
+    class_ref::ClassFileRef,
+    ClassFile, ReadOptions,
+};
 
 #[test]
 fn read() {
@@ -6,3 +10,125 @@ fn read() {
 
     ClassFile::read(file).unwrap();
 }
+
+#[test]
+fn modified_utf8() {
+    let file = include_bytes!("Strings.class");
+    let class = ClassFile::read(file).unwrap();
+
+    let constants = class
+        .fields()
+        .iter()
+        .flat_map(|x| &x.attributes)
+        .filter_map(|x| match x {
+            FieldAttribute::ConstantValue(ConstantValue::String(s)) => Some(s.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(constants, ["a\0b", "\u{1f600}", "\u{3bb}"]);
+
+    assert_eq!(&class.write().unwrap()[..], &file[..]);
+}
+
+#[test]
+fn skip_code() {
+    let file = include_bytes!("Analysis.class");
+    let full = ClassFile::read(file).unwrap();
+    let options = ReadOptions {
+        skip_code: true,
+        ..Default::default()
+    };
+    let mut class = ClassFile::read_with(file, options).unwrap();
+
+    assert!(class.methods().iter().all(|x| x.code().is_none()));
+    assert!(class.methods()[0]
+        .attributes
+        .iter()
+        .any(|x| matches!(x, MethodAttribute::Unknown(name, _) if name == "Code")));
+    assert_eq!(&class.write().unwrap()[..], &full.write().unwrap()[..]);
+
+    for i in 0..class.methods().len() {
+        let method = class.decode_method(i).unwrap();
+        let expected = full.methods()[i].code().unwrap();
+        assert_eq!(method.code().unwrap().code, expected.code);
+    }
+    assert_eq!(&class.write().unwrap()[..], &full.write().unwrap()[..]);
+}
+
+#[test]
+fn skip_code_attributes() {
+    let file = include_bytes!("HelloWorld.class");
+    let options = ReadOptions {
+        skip_code_attributes: true,
+        ..Default::default()
+    };
+    let mut class = ClassFile::read_with(file, options).unwrap();
+    let is_line_numbers = |x: &CodeAttribute| matches!(x, CodeAttribute::LineNumberTable(_));
+
+    let code = class.methods()[0].code().unwrap();
+    assert!(!code.code.is_empty());
+    assert!(!code.attributes.iter().any(is_line_numbers));
+    assert_eq!(&class.write().unwrap()[..], &file[..]);
+
+    let code = class.decode_method(0).unwrap().code().unwrap();
+    assert!(code.attributes.iter().any(is_line_numbers));
+}
+
+/// Returns the offset of the `Code` of `main` and of its `LineNumberTable` in the class.
+fn code_offsets(bytes: &[u8]) -> (usize, usize) {
+    let view = ClassFileRef::read(bytes).unwrap();
+    let main = view.methods().find(|x| x.name().unwrap() == "main").unwrap();
+    let code = main.code().unwrap().unwrap();
+    let line_numbers = code
+        .attributes()
+        .map(Result::unwrap)
+        .find(|x| x.name == "LineNumberTable")
+        .unwrap();
+    let offset = |x: &[u8]| x.as_ptr() as usize - bytes.as_ptr() as usize;
+
+    (offset(code.bytes()), offset(line_numbers.info))
+}
+
+#[test]
+fn lenient() {
+    let options = ReadOptions {
+        lenient: true,
+        ..Default::default()
+    };
+    let (code, line_numbers) = code_offsets(include_bytes!("HelloWorld.class"));
+
+    // A line number table longer than the attribute.
+    let mut bytes = include_bytes!("HelloWorld.class").to_vec();
+    bytes[line_numbers..line_numbers + 2].copy_from_slice(&[0xff, 0xff]);
+    assert!(ClassFile::read(&bytes).is_err());
+
+    let (class, diagnostics) = ClassFile::read_with_diagnostics(&bytes, options).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].attribute, "LineNumberTable");
+    assert_eq!(diagnostics[0].offset as usize, line_numbers - 6);
+    assert!(diagnostics[0]
+        .to_string()
+        .starts_with(&format!("LineNumberTable attribute at offset {}: ", line_numbers - 6)));
+    let main = class.methods().iter().find(|x| x.name == "main").unwrap();
+    assert!(main
+        .code()
+        .unwrap()
+        .attributes
+        .iter()
+        .any(|x| matches!(x, CodeAttribute::Unknown(name, _) if name == "LineNumberTable")));
+    assert_eq!(class.write().unwrap(), bytes);
+
+    // An unassigned opcode.
+    let mut bytes = include_bytes!("HelloWorld.class").to_vec();
+    bytes[code] = 0xcb;
+    assert!(ClassFile::read(&bytes).is_err());
+
+    let (class, diagnostics) = ClassFile::read_with_diagnostics(&bytes, options).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].attribute, "Code");
+    // The attribute header and `max_stack`, `max_locals` and `code_length` come first.
+    assert_eq!(diagnostics[0].offset as usize, code - 14);
+    let main = class.methods().iter().find(|x| x.name == "main").unwrap();
+    assert!(main.code().is_none());
+    assert_eq!(class.write().unwrap(), bytes);
+}
@@ -0,0 +1,142 @@
+use jom::{
+    attribute::ClassAttribute, constant_pool::ConstantPoolIndex, error::JomError,
+    hierarchy::ClassHierarchy, jar::Jar, remap::Remapper, ClassFile,
+};
+
+const GREET: &str = "(Ljava/lang/String;)Ljava/lang/String;";
+
+fn remap(remapper: &Remapper, bytes: &[u8]) -> ClassFile {
+    let mut class = ClassFile::read(bytes).unwrap();
+    remapper.remap(&mut class).unwrap();
+
+    ClassFile::read(&class.write().unwrap()).unwrap()
+}
+
+#[test]
+fn shade() {
+    let jar = Jar::read(include_bytes!("calls.jar")).unwrap();
+    let classes = jar
+        .classes()
+        .map(|x| x.class_file().unwrap())
+        .collect::<Vec<_>>();
+
+    let mut remapper = Remapper::new();
+    remapper.rename_package("calls", "shaded/calls");
+    remapper.rename_class("calls/Names", "shaded/calls/Roster");
+    remapper.rename_field("calls/Names", "ALL", None, "everyone");
+    remapper.rename_method("calls/Greeter", "greet", GREET, "salute");
+    remapper.set_hierarchy(ClassHierarchy::from_classes(&classes));
+
+    let remapped = jar
+        .classes()
+        .map(|x| remap(&remapper, &x.data().unwrap()))
+        .collect::<Vec<_>>();
+    let class = |name: &str| remapped.iter().find(|x| x.this_class() == name).unwrap();
+
+    let english = class("shaded/calls/English");
+    assert_eq!(english.interfaces(), ["shaded/calls/Greeter"]);
+    assert!(english.methods().iter().any(|x| x.name == "salute"));
+
+    let roster = class("shaded/calls/Roster");
+    assert_eq!(roster.fields()[0].name, "everyone");
+
+    let main = class("shaded/calls/Main");
+    assert!(main
+        .constant_pool()
+        .contains(&ConstantPoolIndex::InterfaceMethodref {
+            class: "shaded/calls/Greeter".to_owned(),
+            name: "salute".to_owned(),
+            descriptor: GREET.to_owned(),
+        }));
+    assert!(main.constant_pool().contains(&ConstantPoolIndex::Fieldref {
+        class: "shaded/calls/Roster".to_owned(),
+        name: "everyone".to_owned(),
+        descriptor: "Ljava/util/List;".to_owned(),
+    }));
+
+    for class in &remapped {
+        let dependencies = class.dependencies().unwrap();
+        assert!(
+            dependencies
+                .classes
+                .iter()
+                .all(|x| !x.starts_with("calls/")),
+            "{}",
+            class.this_class()
+        );
+    }
+}
+
+#[test]
+fn attributes() {
+    let mut remapper = Remapper::new();
+    remapper.rename_class("Box", "box/Crate");
+    remapper.rename_class("Box$Item", "box/Crate$Entry");
+    remapper.rename_class("Dependencies", "deps/Main");
+    remapper.rename_class("Dependencies$Tag", "deps/Main$Label");
+    remapper.rename_class("Dependencies$Pair", "deps/Main$Couple");
+    remapper.rename_field("Dependencies$Pair", "first", None, "start");
+
+    let class = remap(&remapper, include_bytes!("Dependencies.class"));
+    assert_eq!(class.this_class(), "deps/Main");
+    assert_eq!(
+        remapper
+            .map_signature("LBox<Ljava/lang/String;>.Item;")
+            .unwrap(),
+        "Lbox/Crate<Ljava/lang/String;>.Entry;"
+    );
+
+    let dependencies = class.dependencies().unwrap();
+    for name in [
+        "box/Crate",
+        "box/Crate$Entry",
+        "deps/Main$Label",
+        "deps/Main$Couple",
+    ] {
+        assert!(dependencies.classes.contains(name), "{name}");
+    }
+    for name in ["Box", "Box$Item", "Dependencies$Tag", "Dependencies$Pair"] {
+        assert!(!dependencies.classes.contains(name), "{name}");
+    }
+
+    let record = remap(&remapper, include_bytes!("Dependencies$Pair.class"));
+    assert_eq!(record.this_class(), "deps/Main$Couple");
+    assert_eq!(record.fields()[0].name, "start");
+    // The component names given to the `toString`, `equals` and `hashCode` bootstrap.
+    assert!(record
+        .constant_pool()
+        .contains(&ConstantPoolIndex::String("start;second".to_owned())));
+}
+
+#[test]
+fn strings() {
+    let mut remapper = Remapper::new();
+    remapper.rename_package("com/google", "shaded/com/google");
+    remapper.rename_class("org/example/Foo", "org/example/Bar");
+
+    assert_eq!(
+        remapper.map_string("com.google.common.Lists"),
+        "shaded.com.google.common.Lists"
+    );
+    assert_eq!(
+        remapper.map_string("com/google/common/version.properties"),
+        "shaded/com/google/common/version.properties"
+    );
+    assert_eq!(remapper.map_string("org.example.Foo"), "org.example.Bar");
+    assert_eq!(remapper.map_string("com.googlex.Foo"), "com.googlex.Foo");
+    assert_eq!(remapper.map_string("Hello World!"), "Hello World!");
+}
+
+#[test]
+fn unknown_target_type() {
+    let mut class = ClassFile::read(include_bytes!("HelloWorld.class")).unwrap();
+    class.attributes_mut().push(ClassAttribute::Unknown(
+        "RuntimeVisibleTypeAnnotations".to_owned(),
+        vec![0, 1, 0x99, 0],
+    ));
+
+    let mut remapper = Remapper::new();
+    remapper.rename_class("HelloWorld", "Greeting");
+    let error = remapper.remap(&mut class).unwrap_err();
+    assert!(matches!(error.root_cause(), JomError::InvalidClass(_)));
+}
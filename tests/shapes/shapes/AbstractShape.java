@@ -0,0 +1,8 @@
+package shapes;
+
+public abstract class AbstractShape implements Shape {
+    @Override
+    public String toString() {
+        return describe();
+    }
+}
@@ -0,0 +1,14 @@
+package shapes;
+
+public class Circle extends AbstractShape {
+    private final double radius;
+
+    public Circle(double radius) {
+        this.radius = radius;
+    }
+
+    @Override
+    public double area() {
+        return Math.PI * radius * radius;
+    }
+}
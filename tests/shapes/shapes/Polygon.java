@@ -0,0 +1,5 @@
+package shapes;
+
+public interface Polygon extends Shape {
+    int sides();
+}
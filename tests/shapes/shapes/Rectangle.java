@@ -0,0 +1,26 @@
+package shapes;
+
+public class Rectangle extends AbstractShape implements Polygon {
+    protected final double width;
+    protected final double height;
+
+    public Rectangle(double width, double height) {
+        this.width = width;
+        this.height = height;
+    }
+
+    @Override
+    public double area() {
+        return width * height;
+    }
+
+    @Override
+    public int sides() {
+        return 4;
+    }
+
+    @Override
+    public String describe() {
+        return "rectangle";
+    }
+}
@@ -0,0 +1,12 @@
+package shapes;
+
+public class Square extends Rectangle {
+    public Square(double side) {
+        super(side, side);
+    }
+
+    @Override
+    public String describe() {
+        return "square";
+    }
+}
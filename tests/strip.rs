@@ -0,0 +1,172 @@
+use jom::{
+    attribute::{ClassAttribute, CodeAttribute},
+    jar::Jar,
+    method::code::{instruction::Instruction, Code},
+    strip::Stripper,
+    ClassFile,
+};
+
+/// The index of the instruction at each offset in the code.
+fn instruction_at(code: &Code) -> impl Fn(i64) -> usize {
+    let mut pcs = vec![];
+    let mut pc = 0;
+    for instruction in &code.code {
+        pcs.push(pc);
+        pc += instruction.size(pc);
+    }
+    pcs.push(pc);
+
+    move |pc| pcs.binary_search(&(pc as u32)).unwrap()
+}
+
+/// Checks that every instruction refers to the same constant and branches to the same
+/// instruction in both classes.
+fn assert_same_code(class: &ClassFile, original: &ClassFile) {
+    for (method, original_method) in class.methods().iter().zip(original.methods()) {
+        assert_eq!(method.name, original_method.name);
+        let (Some(code), Some(original_code)) = (method.code(), original_method.code()) else {
+            assert!(method.code().is_none() && original_method.code().is_none());
+            continue;
+        };
+        assert_eq!(code.code.len(), original_code.code.len());
+        let (at, original_at) = (instruction_at(code), instruction_at(original_code));
+        let (mut pc, mut original_pc) = (0, 0);
+        for (x, y) in code.code.iter().zip(&original_code.code) {
+            let targets = x.branch_offsets();
+            let original_targets = y.branch_offsets();
+            match (x.constant_pool_index(), y.constant_pool_index()) {
+                (Some(a), Some(b)) => assert_eq!(
+                    class.constant_pool().get(a).unwrap(),
+                    original.constant_pool().get(b).unwrap()
+                ),
+                _ if !targets.is_empty() => assert_eq!(
+                    targets
+                        .iter()
+                        .map(|&x| at(pc + x as i64))
+                        .collect::<Vec<_>>(),
+                    original_targets
+                        .iter()
+                        .map(|&x| original_at(original_pc + x as i64))
+                        .collect::<Vec<_>>()
+                ),
+                _ => assert_eq!(x, y),
+            }
+            pc += x.size(pc as u32) as i64;
+            original_pc += y.size(original_pc as u32) as i64;
+        }
+        for (x, y) in code
+            .exception_table
+            .iter()
+            .zip(&original_code.exception_table)
+        {
+            assert_eq!(at(x.handler_pc as i64), original_at(y.handler_pc as i64));
+        }
+    }
+}
+
+#[test]
+fn strip() {
+    let data = include_bytes!("Analysis.class");
+    let original = ClassFile::read(data).unwrap();
+    let mut class = ClassFile::read(data).unwrap();
+    assert!(Stripper::new().strip(&mut class).unwrap());
+
+    let written = class.write().unwrap();
+    assert!(written.len() < data.len());
+    let class = ClassFile::read(&written).unwrap();
+    assert!(class.constant_pool().len() < original.constant_pool().len());
+    assert!(class.attributes().iter().all(|x| x.name() != "SourceFile"));
+    for method in class.methods() {
+        if let Some(code) = method.code() {
+            assert!(code
+                .attributes
+                .iter()
+                .all(|x| x.name() != "LineNumberTable"));
+        }
+    }
+    assert_same_code(&class, &original);
+}
+
+#[test]
+fn compact_constant_pool() {
+    let jar = Jar::read(include_bytes!("calls.jar")).unwrap();
+    for entry in jar
+        .entries()
+        .iter()
+        .filter(|x| x.name().ends_with(".class"))
+    {
+        let data = entry.data().unwrap();
+        let original = ClassFile::read(&data).unwrap();
+        let mut class = ClassFile::read(&data).unwrap();
+        assert!(class.compact_constant_pool().unwrap());
+
+        let class = ClassFile::read(&class.write().unwrap()).unwrap();
+        assert!(class.constant_pool().len() <= original.constant_pool().len());
+        assert_same_code(&class, &original);
+    }
+}
+
+#[test]
+fn unknown_attribute() {
+    let mut class = ClassFile::read(include_bytes!("HelloWorld.class")).unwrap();
+    class
+        .attributes_mut()
+        .push(ClassAttribute::Unknown("Custom".to_owned(), vec![0, 1]));
+    let len = class.constant_pool().len();
+
+    assert!(!class.compact_constant_pool().unwrap());
+    assert_eq!(class.constant_pool().len(), len);
+
+    // The debug attributes are still removed.
+    assert!(!Stripper::new().strip(&mut class).unwrap());
+    assert_eq!(class.constant_pool().len(), len);
+    assert!(class.attributes().iter().all(|x| x.name() != "SourceFile"));
+    assert!(class.attributes().iter().any(|x| x.name() == "Custom"));
+}
+
+#[test]
+fn shorten_loads() {
+    let data = include_bytes!("Constants.class");
+    let original = ClassFile::read(data).unwrap();
+    let mut class = ClassFile::read(data).unwrap();
+    assert!(class.compact_constant_pool().unwrap());
+    let class = ClassFile::read(&class.write().unwrap()).unwrap();
+    assert_same_code(&class, &original);
+
+    // The constants loaded by both methods come first.
+    let code = |class: &ClassFile, name: &str| {
+        let method = class.methods().iter().find(|x| x.name == name).unwrap();
+        let code = method.code().unwrap();
+        let loads = code
+            .code
+            .iter()
+            .filter(|x| matches!(x, Instruction::LdcW(_)))
+            .count();
+        let size = code.code.iter().fold(0, |pc, x| pc + x.size(pc));
+        (loads, size)
+    };
+    assert_eq!(code(&original, "describe").0, 7);
+    assert_eq!(code(&class, "describe").0, 0);
+    assert_eq!(code(&class, "last").0, 0);
+    // Switches may need more padding after moving.
+    assert!(code(&class, "describe").1 < code(&original, "describe").1);
+
+    // The offsets in the code attributes moved along.
+    let describe = class
+        .methods()
+        .iter()
+        .find(|x| x.name == "describe")
+        .unwrap();
+    let length = code(&class, "describe").1 as u16;
+    for attribute in &describe.code().unwrap().attributes {
+        match attribute {
+            CodeAttribute::LineNumberTable(table) => {
+                assert!(table.iter().all(|x| x.start_pc < length))
+            }
+            CodeAttribute::LocalVariableTable(table) => {
+                assert!(table.iter().all(|x| x.start_pc + x.length <= length))
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use jom::{
+    constant_pool::ConstantPoolIndex,
+    error::{ErrorPath, JomError},
+    version::{ClassVersion, Feature},
+    ClassFile, ReadOptions,
+};
+
+#[test]
+fn releases() {
+    let java8 = ClassVersion::from_release(8).unwrap();
+    assert_eq!(java8, ClassVersion::new(52, 0));
+    assert_eq!(java8.release(), 8);
+    assert_eq!(java8.to_string(), "Java 8 (52.0)");
+    assert_eq!(ClassVersion::new(48, 0).to_string(), "Java 1.4 (48.0)");
+    assert_eq!(
+        ClassVersion::from_release(1),
+        Some(ClassVersion::new(45, 3))
+    );
+    assert!(ClassVersion::new(51, 0) < ClassVersion::new(52, 0));
+
+    let preview = ClassVersion::from_release(21).unwrap().preview();
+    assert!(preview.is_preview());
+    assert_eq!(preview.to_string(), "Java 21 preview (65.65535)");
+    assert!(preview.is_supported());
+    assert!(!java8.preview().is_supported());
+    assert!(!ClassVersion::new(44, 0).is_supported());
+    assert!(ClassVersion::LATEST.is_supported());
+
+    assert!(java8.allows(&Feature::Constant("InvokeDynamic")));
+    assert!(!java8.allows(&Feature::Constant("Dynamic")));
+    assert!(!java8.allows(&Feature::Instruction("jsr")));
+    assert!(ClassVersion::new(50, 0).allows(&Feature::Instruction("jsr")));
+}
+
+#[test]
+fn retarget() {
+    let bytes = include_bytes!("Dependencies.class");
+    let class = ClassFile::read(bytes).unwrap();
+    assert!(class.version_issues().is_empty());
+
+    let issues = class.retarget_issues(ClassVersion::new(50, 0));
+    assert!(issues
+        .iter()
+        .all(|x| x.feature.since() > 50 && x.feature.until().is_none()));
+    let invokedynamic = issues
+        .iter()
+        .find(|x| x.feature == Feature::Instruction("invokedynamic"))
+        .unwrap();
+    assert_eq!(
+        invokedynamic.to_string(),
+        "method handle(Ljava/lang/Runnable;)Ljava/util/function/Supplier; > attribute Code > \
+         pc 12: invokedynamic instruction is only allowed from Java 7 (51.0)"
+    );
+    assert!(issues
+        .iter()
+        .any(|x| x.feature == Feature::Attribute("NestMembers".to_owned()) && x.path.is_empty()));
+
+    // The JVM rejects constants the version does not allow.
+    let mut bytes = bytes.to_vec();
+    bytes[6..8].copy_from_slice(&50u16.to_be_bytes());
+    let error = ClassFile::read(&bytes).err().unwrap();
+    assert!(matches!(
+        error.root_cause(),
+        JomError::NotAllowed(Feature::Constant("InvokeDynamic"), _)
+    ));
+    assert!(matches!(
+        error.path(),
+        [ErrorPath::Class(_), ErrorPath::Constant(_)]
+    ));
+
+    // A lenient read reports each of them instead.
+    let options = ReadOptions {
+        lenient: true,
+        ..Default::default()
+    };
+    let (class, diagnostics) = ClassFile::read_with_diagnostics(&bytes, options).unwrap();
+    assert!(!diagnostics.is_empty());
+    for diagnostic in &diagnostics {
+        let index = diagnostic.constant.unwrap();
+        let entry = &class.constant_pool()[index as usize];
+        assert!(matches!(
+            entry,
+            ConstantPoolIndex::MethodHandle { .. }
+                | ConstantPoolIndex::MethodType(_)
+                | ConstantPoolIndex::InvokeDynamic { .. }
+        ));
+        // The offset is that of the tag of the entry.
+        assert!([15, 16, 18].contains(&bytes[diagnostic.offset as usize]));
+    }
+    assert!(diagnostics[0]
+        .to_string()
+        .starts_with(&format!("constant #{}", diagnostics[0].constant.unwrap())));
+}
+
+#[test]
+fn stack_map_table() {
+    let mut class = ClassFile::read(include_bytes!("Analysis.class")).unwrap();
+    for method in class.methods_mut() {
+        if let Some(code) = method.code_mut() {
+            code.attributes.retain(|x| x.name() != "StackMapTable");
+        }
+    }
+
+    let issues = class.version_issues();
+    assert_eq!(issues.len(), 3);
+    assert!(issues
+        .iter()
+        .all(|x| x.feature == Feature::MissingStackMapTable));
+    assert!(class.retarget_issues(ClassVersion::new(50, 0)).is_empty());
+}
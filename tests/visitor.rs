@@ -0,0 +1,265 @@
+use jom::{
+    access::{ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC},
+    error::JomError,
+    jar::Jar,
+    method::code::instruction::Instruction,
+    visitor::{
+        AnnotationValue, AnnotationVisitor, ClassHeader, ClassReader, ClassVisitor, ClassWriter,
+        FieldVisitor, MethodVisitor,
+    },
+    ClassFile,
+};
+
+type Result<T> = std::result::Result<T, JomError>;
+
+fn round_trip(bytes: &[u8]) -> Vec<u8> {
+    let reader = ClassReader::new(bytes).unwrap();
+    let mut writer = ClassWriter::from_reader(&reader).unwrap();
+    reader.accept(&mut writer).unwrap();
+
+    writer.into_bytes().unwrap()
+}
+
+#[test]
+fn copy() {
+    let jar = Jar::read(include_bytes!("calls.jar")).unwrap();
+    for entry in jar.classes() {
+        let bytes = entry.data().unwrap();
+        assert_eq!(round_trip(&bytes), bytes);
+    }
+
+    // Annotations are decoded and encoded again.
+    for bytes in [
+        &include_bytes!("Dependencies.class")[..],
+        include_bytes!("Dependencies$Pair.class"),
+    ] {
+        let copy = ClassFile::read(&round_trip(bytes)).unwrap();
+        let original = ClassFile::read(bytes).unwrap();
+        assert_eq!(
+            copy.dependencies().unwrap(),
+            original.dependencies().unwrap()
+        );
+        assert_eq!(copy.methods().len(), original.methods().len());
+    }
+
+    assert!(ClassReader::new(include_bytes!("HelloWorld.java")).is_err());
+    assert!(ClassReader::new(&include_bytes!("HelloWorld.class")[..100]).is_err());
+}
+
+/// Records the annotations on methods.
+#[derive(Default)]
+struct Annotations(Vec<String>);
+
+struct Recorder<'a>(&'a mut Vec<String>);
+
+impl ClassVisitor for Annotations {
+    fn visit_method(
+        &mut self,
+        _access_flags: u16,
+        name: &str,
+        _descriptor: &str,
+    ) -> Result<Option<Box<dyn MethodVisitor + '_>>> {
+        self.0.push(name.to_owned());
+        Ok(Some(Box::new(Recorder(&mut self.0))))
+    }
+}
+
+impl MethodVisitor for Recorder<'_> {
+    fn visit_annotation(
+        &mut self,
+        descriptor: &str,
+        visible: bool,
+    ) -> Result<Option<Box<dyn AnnotationVisitor + '_>>> {
+        self.0.push(format!("@{descriptor} {visible}"));
+        Ok(Some(Box::new(Recorder(self.0))))
+    }
+}
+
+impl AnnotationVisitor for Recorder<'_> {
+    fn visit(&mut self, name: &str, value: &AnnotationValue) -> Result<()> {
+        self.0.push(format!("{name} = {value:?}"));
+        Ok(())
+    }
+
+    fn visit_enum(&mut self, name: &str, descriptor: &str, value: &str) -> Result<()> {
+        self.0.push(format!("{name} = {descriptor}.{value}"));
+        Ok(())
+    }
+}
+
+#[test]
+fn annotations() {
+    let reader = ClassReader::new(include_bytes!("Dependencies.class")).unwrap();
+    let mut annotations = Annotations::default();
+    reader.accept(&mut annotations).unwrap();
+
+    let first = annotations.0.iter().position(|x| x == "first").unwrap();
+    assert_eq!(
+        annotations.0[first..first + 4],
+        [
+            "first",
+            "@LDependencies$Tag; true",
+            "value = Class(\"Ljava/math/BigDecimal;\")",
+            "state = Ljava/lang/Thread$State;.BLOCKED",
+        ]
+    );
+}
+
+/// Counts the invocations in each method, skipping fields.
+#[derive(Default)]
+struct Invocations {
+    class: String,
+    counts: Vec<(String, usize)>,
+}
+
+struct Counter<'a>(&'a mut usize);
+
+impl ClassVisitor for Invocations {
+    fn visit(&mut self, header: &ClassHeader) -> Result<()> {
+        self.class = header.name.clone();
+        Ok(())
+    }
+
+    fn visit_method(
+        &mut self,
+        _access_flags: u16,
+        name: &str,
+        _descriptor: &str,
+    ) -> Result<Option<Box<dyn MethodVisitor + '_>>> {
+        self.counts.push((name.to_owned(), 0));
+        Ok(Some(Box::new(Counter(
+            &mut self.counts.last_mut().unwrap().1,
+        ))))
+    }
+}
+
+impl MethodVisitor for Counter<'_> {
+    fn visit_instruction(&mut self, _offset: u32, instruction: &Instruction) -> Result<()> {
+        *self.0 += instruction.is_invoke() as usize;
+        Ok(())
+    }
+}
+
+#[test]
+fn scan() {
+    let jar = Jar::read(include_bytes!("calls.jar")).unwrap();
+    let main = jar
+        .classes()
+        .map(|x| x.data().unwrap())
+        .find(|x| ClassReader::new(x).unwrap().header().unwrap().name == "calls/Main")
+        .unwrap();
+
+    let reader = ClassReader::new(&main).unwrap();
+    let header = reader.header().unwrap();
+    assert_eq!(header.super_class, "java/lang/Object");
+    assert!(header.interfaces.is_empty());
+
+    let mut invocations = Invocations::default();
+    reader.accept(&mut invocations).unwrap();
+    assert_eq!(invocations.class, "calls/Main");
+    let count = |name: &str| invocations.counts.iter().find(|x| x.0 == name).unwrap().1;
+    assert_eq!(count("<init>"), 1);
+    assert_eq!(count("greetAll"), 5);
+    assert_eq!(count("unused"), 1);
+}
+
+/// Makes fields private and renames a method, passing everything on to `next`.
+struct Transform<V> {
+    next: V,
+}
+
+struct PrivateField<'a>(Box<dyn FieldVisitor + 'a>);
+
+impl<V: ClassVisitor> ClassVisitor for Transform<V> {
+    fn visit(&mut self, header: &ClassHeader) -> Result<()> {
+        self.next.visit(header)
+    }
+
+    fn visit_attribute(&mut self, name: &str, info: &[u8]) -> Result<()> {
+        self.next.visit_attribute(name, info)
+    }
+
+    fn visit_field(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<Option<Box<dyn FieldVisitor + '_>>> {
+        let access_flags = access_flags & !(ACC_PUBLIC | ACC_PROTECTED) | ACC_PRIVATE;
+        let field = self.next.visit_field(access_flags, name, descriptor)?;
+        Ok(field.map(|x| Box::new(PrivateField(x)) as Box<dyn FieldVisitor + '_>))
+    }
+
+    fn visit_method(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<Option<Box<dyn MethodVisitor + '_>>> {
+        let name = match name {
+            "unused" => "removable",
+            x => x,
+        };
+        self.next.visit_method(access_flags, name, descriptor)
+    }
+
+    fn visit_end(&mut self) -> Result<()> {
+        self.next.visit_end()
+    }
+}
+
+impl FieldVisitor for PrivateField<'_> {
+    fn visit_attribute(&mut self, name: &str, info: &[u8]) -> Result<()> {
+        self.0.visit_attribute(name, info)
+    }
+
+    fn visit_end(&mut self) -> Result<()> {
+        self.0.visit_end()
+    }
+}
+
+#[test]
+fn transform() {
+    let jar = Jar::read(include_bytes!("calls.jar")).unwrap();
+    for entry in jar.classes() {
+        let bytes = entry.data().unwrap();
+        let reader = ClassReader::new(&bytes).unwrap();
+        let mut transform = Transform {
+            next: ClassWriter::from_reader(&reader).unwrap(),
+        };
+        reader.accept(&mut transform).unwrap();
+
+        let class = ClassFile::read(&transform.next.into_bytes().unwrap()).unwrap();
+        let original = entry.class_file().unwrap();
+        assert_eq!(class.this_class(), original.this_class());
+        assert!(class
+            .fields()
+            .iter()
+            .all(|x| x.access_flags & ACC_PRIVATE != 0));
+        assert!(!class.methods().iter().any(|x| x.name == "unused"));
+        if class.this_class() == "calls/Main" {
+            let method = class.methods().iter().find(|x| x.name == "removable");
+            assert!(method.unwrap().code().is_some());
+        }
+    }
+}
+
+#[test]
+fn new_writer() {
+    // The writer starts from the visited constant pool, like one made from the reader.
+    let jar = Jar::read(include_bytes!("calls.jar")).unwrap();
+    for entry in jar.classes() {
+        let bytes = entry.data().unwrap();
+        let mut writer = ClassWriter::new();
+        ClassReader::new(&bytes).unwrap().accept(&mut writer).unwrap();
+        assert_eq!(writer.into_bytes().unwrap(), bytes);
+    }
+
+    // Without it, the indices in the code and attributes would point nowhere.
+    let reader = ClassReader::new(include_bytes!("HelloWorld.class")).unwrap();
+    let mut transform = Transform {
+        next: ClassWriter::new(),
+    };
+    let error = reader.accept(&mut transform).unwrap_err();
+    assert!(matches!(error.root_cause(), JomError::InvalidClass(_)));
+}
@@ -0,0 +1,59 @@
+use jom::{
+    attribute::{ConstantValue, FieldAttribute},
+    constant_pool::ConstantPoolIndex,
+    ClassFile,
+};
+
+#[test]
+fn round_trip() {
+    let file = include_bytes!("HelloWorld.class");
+
+    let written = ClassFile::read(file).unwrap().write().unwrap();
+
+    assert_eq!(&written[..], &file[..]);
+}
+
+#[test]
+fn unpaired_surrogates() {
+    let file = include_bytes!("Surrogates.class");
+    let class = ClassFile::read(file).unwrap();
+
+    let constants = class
+        .fields()
+        .iter()
+        .flat_map(|x| &x.attributes)
+        .filter_map(|x| match x {
+            FieldAttribute::ConstantValue(ConstantValue::StringUnits(x)) => Some(&x[..]),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(constants, [&[0x61, 0xd800][..], &[0xdc00, 0x62]]);
+    assert!(class
+        .constant_pool()
+        .contains(&ConstantPoolIndex::StringUnits(vec![0xdc00, 0xd800])));
+
+    assert_eq!(&class.write().unwrap()[..], &file[..]);
+}
+
+#[test]
+fn nan_constants() {
+    let mut class = ClassFile::new("Example".to_owned());
+    let constant_pool = class.constant_pool_mut();
+
+    let nan = constant_pool
+        .insert(ConstantPoolIndex::Double(f64::NAN))
+        .unwrap();
+    assert_eq!(
+        constant_pool
+            .insert(ConstantPoolIndex::Double(f64::NAN))
+            .unwrap(),
+        nan
+    );
+    assert!(constant_pool.contains(&ConstantPoolIndex::Double(f64::NAN)));
+    assert_ne!(
+        constant_pool
+            .insert(ConstantPoolIndex::Float(-0.0))
+            .unwrap(),
+        constant_pool.insert(ConstantPoolIndex::Float(0.0)).unwrap()
+    );
+}